@@ -0,0 +1,234 @@
+//! Hot-path benchmarks for the gateway.
+//!
+//! The load profile is fixed so runs are comparable across machines and commits:
+//! payload sizes mirror the observed submission mix (small snippets, mid-sized
+//! modules, and occasional ~1MB uploads), and outputs are repetitive log text.
+//!
+//! Run `make bench-baseline` on main, then `make bench-compare` on a branch to
+//! report regressions against that baseline.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::Utc;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use prost::Message;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use syla_api_gateway::cache::CachedExecution;
+use syla_api_gateway::canary::Backend;
+use syla_api_gateway::config::StorageConfig;
+use syla_api_gateway::execution::{CreateExecutionRequest, ExecutionResponse, ExecutionResult, ExecutionStatus};
+use syla_api_gateway::grpc::{result_to_proto, status_to_proto, timestamp_to_proto};
+use syla_api_gateway::metrics::Metrics;
+use syla_api_gateway::proto;
+
+/// Submission sizes in bytes: typical snippet, module, and large upload
+const PAYLOAD_SIZES: &[usize] = &[256, 16 * 1024, 1024 * 1024];
+/// Concurrent callers sharing one upstream client
+const CLIENT_CONCURRENCY: usize = 64;
+
+fn code_of_len(len: usize) -> String {
+    "print('hello from the syla benchmark suite')\n"
+        .chars()
+        .cycle()
+        .take(len)
+        .collect()
+}
+
+fn request_json(len: usize) -> Vec<u8> {
+    serde_json::to_vec(&CreateExecutionRequest {
+        code: code_of_len(len).into(),
+        language: "python".to_string(),
+        language_version: None,
+        timeout_seconds: Some(30),
+        args: Some(vec!["--verbose".to_string(), "input.txt".to_string()]),
+        workspace_id: Some(Uuid::new_v4()),
+        env: None,
+        resources: None,
+        mode: None,
+        tty: None,
+        session_id: None,
+        upload_id: None,
+        result_destination: None,
+        callback_url: None,
+        files: Vec::new(),
+        metadata: HashMap::new(),
+        priority: None,
+        concurrency_group: None,
+        group_id: None,
+        stdin: None,
+        stdin_base64: None,
+    })
+    .expect("serialize request")
+}
+
+fn completed_execution(output_len: usize) -> ExecutionResponse {
+    ExecutionResponse {
+        id: Uuid::new_v4(),
+        status: ExecutionStatus::Completed,
+        created_at: Utc::now(),
+        started_at: Some(Utc::now()),
+        completed_at: Some(Utc::now()),
+        result: Some(ExecutionResult {
+            exit_code: 0,
+            stdout: code_of_len(output_len),
+            stderr: String::new(),
+            duration_ms: 1234,
+            ansi: false,
+            error: None,
+            files_created: Vec::new(),
+            stdout_sha256: None,
+            stderr_sha256: None,
+        }),
+        pinned: false,
+        output_limit_exceeded: false,
+        tty: false,
+        session_id: None,
+        group_id: None,
+        backend: Backend::Primary,
+        resubmitted_from: None,
+        retried_from: None,
+        cancellation: None,
+        annotations: Default::default(),
+        warnings: Vec::new(),
+        result_delivery: None,
+        trace_id: None,
+        trace_url: None,
+        code_sha256: None,
+        language_version: None,
+        metadata: HashMap::new(),
+        owner: None,
+    }
+}
+
+fn storage_config() -> StorageConfig {
+    StorageConfig {
+        compression_threshold_bytes: 64 * 1024,
+        compression_level: 3,
+    }
+}
+
+fn bench_json_deserialization(c: &mut Criterion) {
+    let mut group = c.benchmark_group("json_deserialize_create_request");
+    for &size in PAYLOAD_SIZES {
+        let body = request_json(size);
+        group.throughput(Throughput::Bytes(body.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &body, |b, body| {
+            b.iter(|| serde_json::from_slice::<CreateExecutionRequest>(black_box(body)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_proto_conversion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("proto_convert_execution");
+    for &size in PAYLOAD_SIZES {
+        let execution = completed_execution(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &execution, |b, execution| {
+            b.iter(|| {
+                let execution = black_box(execution).clone();
+                let proto = proto::Execution {
+                    id: execution.id.to_string(),
+                    user_id: "bench-user".to_string(),
+                    workspace_id: String::new(),
+                    status: status_to_proto(&execution.status),
+                    language: proto::Language::Python as i32,
+                    code: String::new(),
+                    args: vec![],
+                    result: execution.result.map(result_to_proto),
+                    resource_usage: None,
+                    created_at: Some(timestamp_to_proto(execution.created_at)),
+                    started_at: execution.started_at.map(timestamp_to_proto),
+                    completed_at: execution.completed_at.map(timestamp_to_proto),
+                    metadata: HashMap::new(),
+                    code_sha256: String::new(),
+                    language_version: String::new(),
+                };
+                proto.encode_to_vec()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_cache_access(c: &mut Criterion) {
+    let config = storage_config();
+    let metrics = Metrics::new();
+
+    let mut group = c.benchmark_group("cache");
+    for &size in PAYLOAD_SIZES {
+        let execution = completed_execution(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("pack", size), &execution, |b, execution| {
+            b.iter(|| CachedExecution::pack(black_box(execution).clone(), &config, &metrics))
+        });
+
+        let cached = CachedExecution::pack(execution, &config, &metrics);
+        group.bench_with_input(BenchmarkId::new("unpack", size), &cached, |b, cached| {
+            b.iter(|| black_box(cached).unpack())
+        });
+    }
+    group.finish();
+}
+
+/// Stand-in for a tonic client: cheap to clone, and each call awaits the network
+#[derive(Clone)]
+struct FakeClient;
+
+impl FakeClient {
+    async fn call(&mut self) {
+        tokio::task::yield_now().await;
+    }
+}
+
+fn bench_client_locking(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().expect("tokio runtime");
+    let mut group = c.benchmark_group("client_locking");
+
+    // Current strategy: hold the write lock for the duration of each call
+    group.bench_function("write_lock_per_call", |b| {
+        let client = Arc::new(RwLock::new(FakeClient));
+        b.to_async(&runtime).iter(|| {
+            let client = client.clone();
+            async move {
+                let calls = (0..CLIENT_CONCURRENCY).map(|_| {
+                    let client = client.clone();
+                    async move { client.write().await.call().await }
+                });
+                futures::future::join_all(calls).await
+            }
+        })
+    });
+
+    // Alternative: clone the client out from under a read lock
+    group.bench_function("clone_per_call", |b| {
+        let client = Arc::new(RwLock::new(FakeClient));
+        b.to_async(&runtime).iter(|| {
+            let client = client.clone();
+            async move {
+                let calls = (0..CLIENT_CONCURRENCY).map(|_| {
+                    let client = client.clone();
+                    async move {
+                        let mut client = client.read().await.clone();
+                        client.call().await
+                    }
+                });
+                futures::future::join_all(calls).await
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_json_deserialization,
+    bench_proto_conversion,
+    bench_cache_access,
+    bench_client_locking
+);
+criterion_main!(benches);
@@ -5,6 +5,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Print build info for debugging
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-changed=proto/syla.proto");
+    // Migrations are embedded by `sqlx::migrate!`
+    println!("cargo:rerun-if-changed=migrations");
+
+    emit_build_info();
     
     // Get OUT_DIR from cargo
     let out_dir = PathBuf::from(env::var("OUT_DIR")?);
@@ -70,4 +74,49 @@ pub use syla::v1::syla_gateway_server::{SylaGateway, SylaGatewayServer};
     println!("cargo:warning=Proto compilation completed successfully");
     
     Ok(())
+}
+
+/// Embed the git revision, build time and enabled features for `/v1/version`.
+/// CI builds without a checkout can pass `SYLA_GIT_SHA` directly, and
+/// `SOURCE_DATE_EPOCH` pins the build time for reproducible builds
+fn emit_build_info() {
+    println!("cargo:rerun-if-env-changed=SYLA_GIT_SHA");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    if std::path::Path::new(".git/HEAD").exists() {
+        println!("cargo:rerun-if-changed=.git/HEAD");
+    }
+
+    let git_sha = env::var("SYLA_GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.is_empty())
+        .or_else(|| {
+            let output = std::process::Command::new("git")
+                .args(["rev-parse", "HEAD"])
+                .output()
+                .ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=SYLA_GIT_SHA={}", git_sha);
+
+    let build_epoch = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default()
+        });
+    println!("cargo:rustc-env=SYLA_BUILD_EPOCH={}", build_epoch);
+
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(|f| f.to_lowercase().replace('_', "-")))
+        .filter(|feature| feature != "default")
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=SYLA_FEATURES={}", features.join(","));
 }
\ No newline at end of file
@@ -0,0 +1,18 @@
+-- Executions accepted through the gateway
+CREATE TABLE IF NOT EXISTS executions (
+    id           UUID PRIMARY KEY,
+    user_id      TEXT NOT NULL,
+    workspace_id UUID,
+    language     TEXT NOT NULL,
+    status       TEXT NOT NULL,
+    created_at   TIMESTAMPTZ NOT NULL,
+    started_at   TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    exit_code    INTEGER,
+    stdout       BYTEA,
+    stderr       BYTEA,
+    duration_ms  BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS executions_user_created_idx
+    ON executions (user_id, created_at DESC);
@@ -0,0 +1,3 @@
+-- Retention state, so the store can be swept like the gateway cache
+ALTER TABLE executions ADD COLUMN IF NOT EXISTS pinned BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE executions ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
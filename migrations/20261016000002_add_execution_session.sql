@@ -0,0 +1,6 @@
+-- IDE session an execution was submitted under, for per-session listing
+ALTER TABLE executions ADD COLUMN IF NOT EXISTS session_id TEXT;
+
+CREATE INDEX IF NOT EXISTS executions_user_session_idx
+    ON executions (user_id, session_id, created_at DESC)
+    WHERE session_id IS NOT NULL;
@@ -0,0 +1,23 @@
+-- Webhooks and metering records awaiting delivery, so they survive restarts
+CREATE TABLE IF NOT EXISTS delivery_outbox (
+    id              BIGSERIAL PRIMARY KEY,
+    kind            TEXT NOT NULL,
+    -- Sent with every attempt; also keeps an entry from being queued twice
+    idempotency_key TEXT NOT NULL UNIQUE,
+    execution_id    TEXT NOT NULL,
+    -- Webhook URL; unused for metering records
+    target          TEXT,
+    -- Metering record; webhooks are built once their execution finishes
+    payload         TEXT,
+    attempts        INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL,
+    expires_at      TIMESTAMPTZ NOT NULL,
+    last_error      TEXT,
+    created_at      TIMESTAMPTZ NOT NULL,
+    delivered_at    TIMESTAMPTZ,
+    failed_at       TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS delivery_outbox_due_idx
+    ON delivery_outbox (next_attempt_at)
+    WHERE delivered_at IS NULL AND failed_at IS NULL;
@@ -0,0 +1,8 @@
+-- Hex SHA-256 of submitted code and final output, for integrity checks and deduplication
+ALTER TABLE executions ADD COLUMN IF NOT EXISTS code_sha256 TEXT;
+ALTER TABLE executions ADD COLUMN IF NOT EXISTS stdout_sha256 TEXT;
+ALTER TABLE executions ADD COLUMN IF NOT EXISTS stderr_sha256 TEXT;
+
+CREATE INDEX IF NOT EXISTS executions_user_code_idx
+    ON executions (user_id, code_sha256)
+    WHERE code_sha256 IS NOT NULL;
@@ -0,0 +1,10 @@
+-- Status transitions of executions, for their events timeline
+CREATE TABLE IF NOT EXISTS execution_events (
+    id           BIGSERIAL PRIMARY KEY,
+    execution_id UUID NOT NULL,
+    status       TEXT NOT NULL,
+    -- As reported by the execution service when it reports one
+    occurred_at  TIMESTAMPTZ NOT NULL,
+    -- Each status is recorded once, whichever replica sees it first
+    UNIQUE (execution_id, status)
+);
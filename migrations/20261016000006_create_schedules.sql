@@ -0,0 +1,33 @@
+-- Executions submitted on a schedule, once at run_at or on every cron match
+CREATE TABLE IF NOT EXISTS schedules (
+    id          UUID PRIMARY KEY,
+    user_id     TEXT NOT NULL,
+    tenant_id   TEXT,
+    -- The execution request as JSON, with any upload inlined
+    request     TEXT NOT NULL,
+    run_at      TIMESTAMPTZ,
+    cron        TEXT,
+    -- Unset once a one-off schedule has run
+    next_run_at TIMESTAMPTZ,
+    last_run_at TIMESTAMPTZ,
+    last_error  TEXT,
+    created_at  TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS schedules_user_created_idx
+    ON schedules (user_id, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS schedules_due_idx
+    ON schedules (next_run_at)
+    WHERE next_run_at IS NOT NULL;
+
+-- Executions each schedule has submitted
+CREATE TABLE IF NOT EXISTS schedule_runs (
+    id           BIGSERIAL PRIMARY KEY,
+    schedule_id  UUID NOT NULL REFERENCES schedules (id) ON DELETE CASCADE,
+    execution_id UUID NOT NULL,
+    created_at   TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS schedule_runs_schedule_idx
+    ON schedule_runs (schedule_id, created_at DESC);
@@ -0,0 +1,2 @@
+-- Tenant of the submitting user, so replicas agree on per-tenant limits
+ALTER TABLE executions ADD COLUMN IF NOT EXISTS tenant_id TEXT;
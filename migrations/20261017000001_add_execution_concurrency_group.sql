@@ -0,0 +1,7 @@
+-- Concurrency group an execution holds while unfinished, so the lease holder
+-- can check on executions holding a group whichever replica submitted them
+ALTER TABLE executions ADD COLUMN IF NOT EXISTS concurrency_group TEXT;
+
+CREATE INDEX IF NOT EXISTS executions_unfinished_idx
+    ON executions (created_at)
+    WHERE status NOT IN ('completed', 'failed', 'cancelled', 'timeout');
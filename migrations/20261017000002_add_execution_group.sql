@@ -0,0 +1,6 @@
+-- Execution group an execution was submitted under, for group status
+ALTER TABLE executions ADD COLUMN IF NOT EXISTS group_id TEXT;
+
+CREATE INDEX IF NOT EXISTS executions_user_group_idx
+    ON executions (user_id, group_id, created_at DESC)
+    WHERE group_id IS NOT NULL;
@@ -0,0 +1,19 @@
+-- Tenants' CORS policies; their origins are in cors_origins
+CREATE TABLE IF NOT EXISTS cors_policies (
+    tenant_id         TEXT PRIMARY KEY,
+    allow_credentials BOOLEAN NOT NULL,
+    max_age_seconds   BIGINT,
+    updated_at        TIMESTAMPTZ,
+    updated_by        TEXT
+);
+
+-- Browser origins by the tenant that registered them; each has at most one
+CREATE TABLE IF NOT EXISTS cors_origins (
+    origin    TEXT PRIMARY KEY,
+    tenant_id TEXT NOT NULL REFERENCES cors_policies (tenant_id) ON DELETE CASCADE,
+    -- Order within the tenant's allowed_origins
+    position  INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS cors_origins_tenant_idx
+    ON cors_origins (tenant_id, position);
@@ -0,0 +1,35 @@
+-- Executions run one after another, each started once the one before it completed
+CREATE TABLE IF NOT EXISTS pipelines (
+    id               UUID PRIMARY KEY,
+    user_id          TEXT NOT NULL,
+    tenant_id        TEXT,
+    -- Granted to the creator's token, which steps are submitted with
+    scopes           TEXT[] NOT NULL,
+    impersonated_by  TEXT,
+    status           TEXT NOT NULL,
+    cancel_requested BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at       TIMESTAMPTZ NOT NULL,
+    completed_at     TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS pipelines_user_created_idx
+    ON pipelines (user_id, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS pipelines_running_idx
+    ON pipelines (created_at)
+    WHERE status = 'running';
+
+-- Each pipeline's steps and where they've got to
+CREATE TABLE IF NOT EXISTS pipeline_steps (
+    pipeline_id  UUID NOT NULL REFERENCES pipelines (id) ON DELETE CASCADE,
+    position     INTEGER NOT NULL,
+    language     TEXT NOT NULL,
+    status       TEXT NOT NULL,
+    execution_id UUID,
+    error        TEXT,
+    -- When the step was claimed for submission
+    started_at   TIMESTAMPTZ,
+    -- The execution request and what it takes from the step before, as JSON
+    request      TEXT NOT NULL,
+    PRIMARY KEY (pipeline_id, position)
+);
@@ -0,0 +1,11 @@
+-- Notes and scores attached to finished executions, one per author
+CREATE TABLE IF NOT EXISTS execution_annotations (
+    execution_id UUID NOT NULL REFERENCES executions (id) ON DELETE CASCADE,
+    author       TEXT NOT NULL,
+    note         TEXT,
+    score        DOUBLE PRECISION,
+    -- Structured feedback, as JSON
+    data         TEXT,
+    updated_at   TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (execution_id, author)
+);
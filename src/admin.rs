@@ -0,0 +1,175 @@
+use axum::{
+    extract::{Path, Request, State},
+    middleware::{self, Next},
+    response::Response,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::archive::ArchivedExchange;
+use crate::audit::{self, AuditEvent, AuditOutcome};
+use crate::auth::{self, AuthContext, AuthInterceptor, ADMIN_SCOPE};
+use crate::bulk::{BulkReport, BulkRequest};
+use crate::clients::ChannelStats;
+use crate::error::ApiError;
+use crate::execution::ExecutionResponse;
+use crate::inflight::InflightSnapshot;
+use crate::read_only::ReadOnlyStatus;
+use crate::shadow::Divergence;
+use crate::slo::SloStatus;
+use crate::state::AppState;
+use crate::watchdog::StuckExecution;
+
+/// Operator-only routes, authenticated and gated on the admin scope
+pub fn routes(auth_interceptor: AuthInterceptor) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/admin/v1/archive/:request_id", get(get_archived_exchange))
+        .route("/admin/v1/inflight", get(get_inflight))
+        .route("/admin/v1/upstreams", get(get_upstreams))
+        .route("/admin/v1/shadow/divergences", get(get_shadow_divergences))
+        .route("/admin/v1/slos", get(get_slos))
+        .route("/admin/v1/read-only", get(get_read_only).put(put_read_only))
+        .route("/admin/v1/executions/stuck", get(get_stuck_executions))
+        .route("/admin/v1/executions/bulk-cancel", post(bulk_cancel_executions))
+        .route("/admin/v1/executions/bulk-requeue", post(bulk_requeue_executions))
+        .route("/admin/v1/executions/:id/undelete", post(undelete_execution))
+        .route_layer(middleware::from_fn(require_admin))
+        .route_layer(middleware::from_fn_with_state(auth_interceptor, auth::require_auth))
+}
+
+async fn require_admin(request: Request, next: Next) -> Result<Response, ApiError> {
+    let is_admin = request
+        .extensions()
+        .get::<AuthContext>()
+        .is_some_and(|ctx| ctx.has_scope(ADMIN_SCOPE));
+
+    if !is_admin {
+        return Err(ApiError::Forbidden(format!("Requires the {} scope", ADMIN_SCOPE)));
+    }
+
+    Ok(next.run(request).await)
+}
+
+async fn get_archived_exchange(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(request_id): Path<String>,
+) -> Result<Json<ArchivedExchange>, ApiError> {
+    audit::record(
+        AuditEvent::new("archive.read", &auth_context.user_id, AuditOutcome::Allowed)
+            .subject(&request_id),
+    );
+
+    let exchange = state
+        .payload_archive()
+        .get(&request_id)
+        .await
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(exchange))
+}
+
+async fn get_inflight(State(state): State<Arc<AppState>>) -> Json<InflightSnapshot> {
+    Json(state.inflight().snapshot())
+}
+
+async fn get_upstreams(State(state): State<Arc<AppState>>) -> Json<Vec<ChannelStats>> {
+    Json(state.upstream_channel_stats().await)
+}
+
+/// Burn rates and remaining error budget of every configured objective
+async fn get_slos(State(state): State<Arc<AppState>>) -> Json<Vec<SloStatus>> {
+    Json(state.slo_tracker().report())
+}
+
+/// Recent mirrored executions whose results differed, newest first; not
+/// found when shadow traffic is off
+async fn get_shadow_divergences(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<Divergence>>, ApiError> {
+    let shadow = state.shadow().ok_or(ApiError::NotFound)?;
+    Ok(Json(shadow.divergences()))
+}
+
+async fn get_read_only(State(state): State<Arc<AppState>>) -> Json<ReadOnlyStatus> {
+    Json(state.read_only().status())
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadOnlyChange {
+    read_only: bool,
+    reason: Option<String>,
+}
+
+/// Turn this replica's read-only mode on or off
+async fn put_read_only(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Json(change): Json<ReadOnlyChange>,
+) -> Json<ReadOnlyStatus> {
+    let action = if change.read_only { "gateway.read_only.enable" } else { "gateway.read_only.disable" };
+    let mut event = AuditEvent::new(action, &auth_context.user_id, AuditOutcome::Allowed);
+    event.subject = change.reason.as_deref();
+    audit::record(event);
+    Json(state.read_only().set(change.read_only, change.reason, &auth_context.user_id))
+}
+
+/// Executions the watchdog has flagged as stuck, longest stuck first
+async fn get_stuck_executions(State(state): State<Arc<AppState>>) -> Json<Vec<StuckExecution>> {
+    Json(state.watchdog().stuck())
+}
+
+/// Cancel every execution matching a filter, e.g. zombies left by an executor outage
+async fn bulk_cancel_executions(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Json(request): Json<BulkRequest>,
+) -> Json<BulkReport> {
+    let report = state.bulk_cancel(&auth_context, &request).await;
+    record_bulk("executions.bulk_cancel", &auth_context, &request, &report);
+    Json(report)
+}
+
+/// Resubmit every execution matching a filter from its original request
+async fn bulk_requeue_executions(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Json(request): Json<BulkRequest>,
+) -> Json<BulkReport> {
+    let report = state.bulk_requeue(&auth_context, &request).await;
+    record_bulk("executions.bulk_requeue", &auth_context, &request, &report);
+    Json(report)
+}
+
+/// Audit a bulk operation as a whole; dry runs are recorded too, under their own action
+fn record_bulk(action: &str, auth_context: &AuthContext, request: &BulkRequest, report: &BulkReport) {
+    let action = if report.dry_run {
+        format!("{}.dry_run", action)
+    } else {
+        action.to_string()
+    };
+    let summary = format!(
+        "matched={} succeeded={} failed={}",
+        report.matched, report.succeeded, report.failed
+    );
+    audit::record(
+        AuditEvent::new(&action, &auth_context.user_id, AuditOutcome::Allowed)
+            .subject(&summary)
+            .tenant(request.filter.tenant_id.as_deref()),
+    );
+}
+
+async fn undelete_execution(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ExecutionResponse>, ApiError> {
+    let execution = state.undelete_execution(id).await?;
+    audit::record(
+        AuditEvent::new("execution.undelete", &auth_context.user_id, AuditOutcome::Allowed)
+            .subject(&id.to_string()),
+    );
+    Ok(Json(execution))
+}
@@ -0,0 +1,268 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use axum::{
+    extract::{Request, State},
+    http,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use tower::{Layer, Service};
+use tracing::debug;
+
+use crate::config::AdmissionConfig;
+use crate::error::ApiError;
+use crate::execution::Priority;
+use crate::inflight::Listener;
+use crate::state::AppState;
+
+/// REST paths that never count against the budget, so probes and scrapes
+/// keep working while the gateway is saturated
+const EXEMPT_PATHS: &[&str] = &["/health", "/ready", "/metrics"];
+
+/// Slots in use: each listener's reservation, then the pool they share
+#[derive(Default)]
+struct Usage {
+    rest: usize,
+    grpc: usize,
+    shared: usize,
+}
+
+/// Where an admitted request's slot came from
+#[derive(Clone, Copy)]
+enum Slot {
+    Reserved,
+    Shared,
+    /// The budget is unlimited
+    Unmetered,
+}
+
+/// Concurrency budget shared by the REST and gRPC listeners.
+///
+/// Each listener has slots reserved for it and takes from a shared pool once
+/// those are in use, so a flood on one protocol can exhaust the shared pool
+/// but never the other's reservation. Requests over budget are rejected
+/// rather than queued. Slots are held until the response starts, so open
+/// streams don't count against it.
+///
+/// Submissions are also shed by priority as the budget fills, so what's
+/// left of it goes to high-priority work.
+pub struct AdmissionBudget {
+    config: AdmissionConfig,
+    usage: Mutex<Usage>,
+    rejected_rest: AtomicU64,
+    rejected_grpc: AtomicU64,
+    shed_low: AtomicU64,
+    shed_normal: AtomicU64,
+}
+
+impl AdmissionBudget {
+    pub fn new(config: AdmissionConfig) -> Self {
+        Self {
+            config,
+            usage: Mutex::new(Usage::default()),
+            rejected_rest: AtomicU64::new(0),
+            rejected_grpc: AtomicU64::new(0),
+            shed_low: AtomicU64::new(0),
+            shed_normal: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether a submission of `priority`, whose request already holds a
+    /// slot, may go ahead at the current load
+    pub fn admits_priority(&self, priority: Priority) -> bool {
+        if self.config.max_inflight == 0 {
+            return true;
+        }
+        let (limit, shed) = match priority {
+            Priority::High => return true,
+            Priority::Normal => (self.config.normal_priority_limit, &self.shed_normal),
+            Priority::Low => (self.config.low_priority_limit, &self.shed_low),
+        };
+        let in_use = {
+            let usage = self.usage.lock().unwrap();
+            usage.rest + usage.grpc + usage.shared
+        };
+        if in_use as f64 <= self.config.max_inflight as f64 * limit {
+            return true;
+        }
+        shed.fetch_add(1, Ordering::Relaxed);
+        debug!(priority = priority.as_str(), in_use, "Shed submission under load");
+        false
+    }
+
+    /// Take a slot for a request on `listener`, or `None` if both its
+    /// reservation and the shared pool are in use
+    pub fn try_admit(self: &Arc<Self>, listener: Listener) -> Option<AdmissionPermit> {
+        if self.config.max_inflight == 0 {
+            return Some(self.permit(listener, Slot::Unmetered));
+        }
+        let (reserved, rejected) = match listener {
+            Listener::Rest => (self.config.rest_reserved, &self.rejected_rest),
+            Listener::Grpc => (self.config.grpc_reserved, &self.rejected_grpc),
+        };
+        let mut usage = self.usage.lock().unwrap();
+        let own = match listener {
+            Listener::Rest => &mut usage.rest,
+            Listener::Grpc => &mut usage.grpc,
+        };
+        if *own < reserved {
+            *own += 1;
+            return Some(self.permit(listener, Slot::Reserved));
+        }
+        if usage.shared < self.config.shared() {
+            usage.shared += 1;
+            return Some(self.permit(listener, Slot::Shared));
+        }
+        drop(usage);
+        rejected.fetch_add(1, Ordering::Relaxed);
+        debug!(?listener, "Rejected request over the admission budget");
+        None
+    }
+
+    fn permit(self: &Arc<Self>, listener: Listener, slot: Slot) -> AdmissionPermit {
+        AdmissionPermit {
+            budget: self.clone(),
+            listener,
+            slot,
+        }
+    }
+
+    fn release(&self, listener: Listener, slot: Slot) {
+        let mut usage = self.usage.lock().unwrap();
+        match (slot, listener) {
+            (Slot::Reserved, Listener::Rest) => usage.rest = usage.rest.saturating_sub(1),
+            (Slot::Reserved, Listener::Grpc) => usage.grpc = usage.grpc.saturating_sub(1),
+            (Slot::Shared, _) => usage.shared = usage.shared.saturating_sub(1),
+            (Slot::Unmetered, _) => {}
+        }
+    }
+
+    /// Budget usage and rejections in Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        if self.config.max_inflight == 0 {
+            return out;
+        }
+        let (rest, grpc, shared) = {
+            let usage = self.usage.lock().unwrap();
+            (usage.rest, usage.grpc, usage.shared)
+        };
+        let _ = writeln!(out, "# TYPE syla_gateway_admission_slots gauge");
+        let pools = [
+            ("rest", rest, self.config.rest_reserved),
+            ("grpc", grpc, self.config.grpc_reserved),
+            ("shared", shared, self.config.shared()),
+        ];
+        for (pool, in_use, size) in pools {
+            let _ = writeln!(out, "syla_gateway_admission_slots{{pool=\"{}\",state=\"in_use\"}} {}", pool, in_use);
+            let _ = writeln!(out, "syla_gateway_admission_slots{{pool=\"{}\",state=\"total\"}} {}", pool, size);
+        }
+        let _ = writeln!(out, "# TYPE syla_gateway_admission_rejected_total counter");
+        let rejected = [("rest", &self.rejected_rest), ("grpc", &self.rejected_grpc)];
+        for (listener, counter) in rejected {
+            let _ = writeln!(
+                out,
+                "syla_gateway_admission_rejected_total{{listener=\"{}\"}} {}",
+                listener,
+                counter.load(Ordering::Relaxed)
+            );
+        }
+        let _ = writeln!(out, "# TYPE syla_gateway_admission_shed_total counter");
+        let shed = [("low", &self.shed_low), ("normal", &self.shed_normal)];
+        for (priority, counter) in shed {
+            let _ = writeln!(
+                out,
+                "syla_gateway_admission_shed_total{{priority=\"{}\"}} {}",
+                priority,
+                counter.load(Ordering::Relaxed)
+            );
+        }
+        out
+    }
+}
+
+/// Returns its slot to the budget when dropped
+pub struct AdmissionPermit {
+    budget: Arc<AdmissionBudget>,
+    listener: Listener,
+    slot: Slot,
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        self.budget.release(self.listener, self.slot);
+    }
+}
+
+/// Admit a REST request against the shared budget
+pub async fn admit_rest(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    if EXEMPT_PATHS.contains(&request.uri().path()) {
+        return next.run(request).await;
+    }
+    let Some(permit) = state.admission().try_admit(Listener::Rest) else {
+        return ApiError::ServiceUnavailable.into_response();
+    };
+    let response = next.run(request).await;
+    drop(permit);
+    response
+}
+
+/// Tower layer admitting gRPC requests against the shared budget
+#[derive(Clone)]
+pub struct AdmissionLayer {
+    budget: Arc<AdmissionBudget>,
+}
+
+impl AdmissionLayer {
+    pub fn new(budget: Arc<AdmissionBudget>) -> Self {
+        Self { budget }
+    }
+}
+
+impl<S> Layer<S> for AdmissionLayer {
+    type Service = AdmissionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AdmissionService {
+            inner,
+            budget: self.budget.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AdmissionService<S> {
+    inner: S,
+    budget: Arc<AdmissionBudget>,
+}
+
+impl<S, B> Service<http::Request<B>> for AdmissionService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<tonic::body::BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let Some(permit) = self.budget.try_admit(Listener::Grpc) else {
+            let status = tonic::Status::unavailable("Gateway is at capacity; retry shortly");
+            return Box::pin(async move { Ok(status.into_http()) });
+        };
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let result = future.await;
+            drop(permit);
+            result
+        })
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::Utc;
+use reqwest::header;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::{AlertConfig, AlertFormat};
+
+/// Token validations needed in an interval before its mean latency is trusted
+const MIN_AUTH_SAMPLES: u64 = 10;
+
+/// A condition the gateway alerts operators on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// The execution service failed several probes in a row, so requests to
+    /// it are failing
+    UpstreamDown,
+    /// The gRPC response cache is evicting entries faster than they can be reused
+    CacheEvictionStorm,
+    /// Token validation has slowed down
+    AuthLatency,
+}
+
+impl AlertKind {
+    const ALL: [AlertKind; 3] = [
+        AlertKind::UpstreamDown,
+        AlertKind::CacheEvictionStorm,
+        AlertKind::AuthLatency,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertKind::UpstreamDown => "upstream_down",
+            AlertKind::CacheEvictionStorm => "cache_eviction_storm",
+            AlertKind::AuthLatency => "auth_latency",
+        }
+    }
+
+    /// Severity in PagerDuty's terms
+    fn severity(&self) -> &'static str {
+        match self {
+            AlertKind::UpstreamDown => "critical",
+            AlertKind::CacheEvictionStorm | AlertKind::AuthLatency => "warning",
+        }
+    }
+
+    /// Shared by every replica, so receivers that deduplicate open one
+    /// incident for the fleet
+    fn dedup_key(&self) -> String {
+        format!("syla-api-gateway/{}", self.as_str())
+    }
+}
+
+/// A condition found firing by one check
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub summary: String,
+    pub details: Value,
+}
+
+/// Token validation latency since the last check
+#[derive(Default)]
+pub struct LatencyRecorder {
+    total_micros: AtomicU64,
+    samples: AtomicU64,
+}
+
+impl LatencyRecorder {
+    pub fn record(&self, latency: Duration) {
+        self.total_micros.fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+        self.samples.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Mean latency and number of samples since the last call, starting over
+    fn take(&self) -> (Duration, u64) {
+        let samples = self.samples.swap(0, Ordering::Relaxed);
+        let total_micros = self.total_micros.swap(0, Ordering::Relaxed);
+        (Duration::from_micros(total_micros.checked_div(samples).unwrap_or(0)), samples)
+    }
+}
+
+#[derive(Default)]
+struct AlertCounters {
+    sent: AtomicU64,
+    failed: AtomicU64,
+}
+
+/// Posts operator alerts to the configured webhook or PagerDuty. A condition
+/// is sent once when it starts firing, again only every renotify interval
+/// while it lasts, and once more when it clears
+pub struct Alerter {
+    http: reqwest::Client,
+    config: AlertConfig,
+    /// Replica the alerts come from
+    source: String,
+    auth_latency: Arc<LatencyRecorder>,
+    upstream_failures: AtomicU32,
+    /// Cache evictions as of the previous check
+    evictions_seen: AtomicU64,
+    /// Conditions sent as firing, and when they were last sent
+    firing: Mutex<HashMap<AlertKind, Instant>>,
+    counters: AlertCounters,
+}
+
+impl Alerter {
+    /// `None` when alerts have nowhere to go
+    pub fn new(config: &AlertConfig, instance_id: Uuid) -> Result<Option<Self>> {
+        if !config.enabled() {
+            return Ok(None);
+        }
+        let http = reqwest::Client::builder()
+            .timeout(config.request_timeout)
+            .build()?;
+        Ok(Some(Self {
+            http,
+            config: config.clone(),
+            source: format!("syla-api-gateway/{}", instance_id),
+            auth_latency: Arc::new(LatencyRecorder::default()),
+            upstream_failures: AtomicU32::new(0),
+            evictions_seen: AtomicU64::new(0),
+            firing: Mutex::new(HashMap::new()),
+            counters: AlertCounters::default(),
+        }))
+    }
+
+    pub fn config(&self) -> &AlertConfig {
+        &self.config
+    }
+
+    /// Where the auth interceptor reports token validation latency
+    pub fn auth_latency(&self) -> Arc<LatencyRecorder> {
+        self.auth_latency.clone()
+    }
+
+    /// Conditions firing given this check's upstream probe outcome and the
+    /// gRPC cache's eviction total, if the cache is enabled
+    pub fn evaluate(&self, upstream: Result<(), String>, cache_evictions: Option<u64>) -> Vec<Alert> {
+        let mut firing = Vec::new();
+
+        match upstream {
+            Ok(()) => self.upstream_failures.store(0, Ordering::Relaxed),
+            Err(error) => {
+                let failures = self.upstream_failures.fetch_add(1, Ordering::Relaxed) + 1;
+                if failures >= self.config.upstream_failures {
+                    firing.push(Alert {
+                        kind: AlertKind::UpstreamDown,
+                        summary: format!("Execution service failed {} consecutive probes", failures),
+                        details: json!({"consecutive_failures": failures, "last_error": error}),
+                    });
+                }
+            }
+        }
+
+        if let Some(total) = cache_evictions {
+            let evicted = total.saturating_sub(self.evictions_seen.swap(total, Ordering::Relaxed));
+            if self.config.cache_evictions > 0 && evicted >= self.config.cache_evictions {
+                firing.push(Alert {
+                    kind: AlertKind::CacheEvictionStorm,
+                    summary: format!(
+                        "gRPC response cache evicted {} entries in {}s",
+                        evicted,
+                        self.config.interval.as_secs()
+                    ),
+                    details: json!({"evictions": evicted, "interval_secs": self.config.interval.as_secs()}),
+                });
+            }
+        }
+
+        let (mean, samples) = self.auth_latency.take();
+        let threshold = self.config.auth_latency;
+        if !threshold.is_zero() && samples >= MIN_AUTH_SAMPLES && mean > threshold {
+            firing.push(Alert {
+                kind: AlertKind::AuthLatency,
+                summary: format!(
+                    "Token validation averaged {}ms over {} requests, above {}ms",
+                    mean.as_millis(),
+                    samples,
+                    threshold.as_millis()
+                ),
+                details: json!({
+                    "mean_latency_ms": mean.as_millis() as u64,
+                    "samples": samples,
+                    "threshold_ms": threshold.as_millis() as u64,
+                }),
+            });
+        }
+
+        firing
+    }
+
+    /// Send `firing` conditions that are new or due a reminder, and resolve
+    /// those sent earlier that are no longer firing. Failed sends are retried
+    /// on the next check
+    pub async fn notify(&self, firing: Vec<Alert>) {
+        let now = Instant::now();
+        let (due, cleared): (Vec<Alert>, Vec<AlertKind>) = {
+            let sent = self.firing.lock().unwrap();
+            let due = firing
+                .iter()
+                .filter(|alert| {
+                    sent.get(&alert.kind)
+                        .is_none_or(|at| now.duration_since(*at) >= self.config.renotify_interval)
+                })
+                .cloned()
+                .collect();
+            let cleared = sent
+                .keys()
+                .filter(|kind| !firing.iter().any(|alert| alert.kind == **kind))
+                .copied()
+                .collect();
+            (due, cleared)
+        };
+
+        for alert in due {
+            warn!(alert = alert.kind.as_str(), "{}", alert.summary);
+            if self.send(alert.kind, true, &alert.summary, alert.details).await {
+                self.firing.lock().unwrap().insert(alert.kind, now);
+            }
+        }
+        for kind in cleared {
+            let summary = format!("{} cleared", kind.as_str());
+            info!(alert = kind.as_str(), "Alert cleared");
+            if self.send(kind, false, &summary, Value::Null).await {
+                self.firing.lock().unwrap().remove(&kind);
+            }
+        }
+    }
+
+    /// POST one trigger or resolve event, returning whether it was accepted
+    async fn send(&self, kind: AlertKind, trigger: bool, summary: &str, details: Value) -> bool {
+        let Some(url) = &self.config.webhook_url else {
+            return false;
+        };
+        let body = match self.config.format {
+            AlertFormat::Generic => json!({
+                "event": if trigger { "trigger" } else { "resolve" },
+                "alert": kind,
+                "severity": kind.severity(),
+                "summary": summary,
+                "dedup_key": kind.dedup_key(),
+                "source": self.source,
+                "details": details,
+                "at": Utc::now(),
+            }),
+            AlertFormat::PagerDuty => json!({
+                "routing_key": self.config.pagerduty_routing_key,
+                "event_action": if trigger { "trigger" } else { "resolve" },
+                "dedup_key": kind.dedup_key(),
+                "payload": {
+                    "summary": summary,
+                    "source": self.source,
+                    "severity": kind.severity(),
+                    "component": "syla-api-gateway",
+                    "class": kind.as_str(),
+                    "timestamp": Utc::now(),
+                    "custom_details": details,
+                },
+            }),
+        };
+        let response = self
+            .http
+            .post(url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .send()
+            .await;
+        // Errors carry the URL, which may hold a token; leave it out
+        let outcome = match response {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response) => Err(format!("alert webhook responded with {}", response.status())),
+            Err(e) => Err(e.without_url().to_string()),
+        };
+        match outcome {
+            Ok(()) => {
+                self.counters.sent.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(e) => {
+                self.counters.failed.fetch_add(1, Ordering::Relaxed);
+                warn!(alert = kind.as_str(), "Failed to send alert: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Alert counters and firing conditions in Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            ("syla_gateway_alerts_sent_total", &self.counters.sent),
+            ("syla_gateway_alert_failures_total", &self.counters.failed),
+        ];
+        for (name, counter) in counters {
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, counter.load(Ordering::Relaxed));
+        }
+        let firing = self.firing.lock().unwrap();
+        let _ = writeln!(out, "# TYPE syla_gateway_alert_firing gauge");
+        for kind in AlertKind::ALL {
+            let _ = writeln!(
+                out,
+                "syla_gateway_alert_firing{{alert=\"{}\"}} {}",
+                kind.as_str(),
+                u8::from(firing.contains_key(&kind))
+            );
+        }
+        out
+    }
+}
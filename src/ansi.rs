@@ -0,0 +1,63 @@
+//! Detection and removal of ANSI escape sequences in execution output.
+
+const ESC: char = '\u{1b}';
+const BEL: char = '\u{7}';
+
+/// Whether `text` contains any escape sequence
+pub fn contains_ansi(text: &str) -> bool {
+    text.contains(ESC)
+}
+
+/// `text` with escape sequences removed: CSI sequences such as colours and
+/// cursor movement, OSC sequences such as window titles and hyperlinks, and
+/// two-character escapes
+pub fn strip_ansi(text: &str) -> String {
+    if !contains_ansi(text) {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != ESC {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters and intermediates, ended by a byte in @..~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: ended by BEL or ST (ESC \)
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == BEL {
+                        break;
+                    }
+                    if c == ESC && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // Any other escape is a single following character
+            Some(_) | None => {}
+        }
+    }
+    out
+}
+
+/// How escape sequences in output are rendered for a client
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnsiMode {
+    /// Pass output through as the program wrote it
+    #[default]
+    Preserve,
+    /// Remove escape sequences, for clients that render plain text
+    Strip,
+}
@@ -0,0 +1,248 @@
+use axum::{
+    body::{Body, HttpBody},
+    extract::{Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::config::ArchiveConfig;
+use crate::error::ApiError;
+use crate::state::AppState;
+
+/// Header carrying the per-request correlation ID
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const REDACTED: &str = "[REDACTED]";
+/// Header names and JSON keys containing any of these, ignoring case, have
+/// their values redacted
+const SENSITIVE_KEY_PARTS: &[&str] = &[
+    "token",
+    "password",
+    "passwd",
+    "secret",
+    "api_key",
+    "api-key",
+    "apikey",
+    "authorization",
+    "credential",
+    "private_key",
+    "cookie",
+];
+/// JSON keys whose values are redacted throughout, as anything can be a secret there
+const REDACTED_OBJECTS: &[&str] = &["env", "environment"];
+
+fn is_sensitive(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SENSITIVE_KEY_PARTS.iter().any(|part| key.contains(part))
+}
+
+/// A captured request/response pair with secrets redacted
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchivedExchange {
+    pub request_id: String,
+    pub method: String,
+    pub uri: String,
+    pub request_headers: HashMap<String, String>,
+    pub request_body: Option<Value>,
+    pub status: u16,
+    pub response_headers: HashMap<String, String>,
+    pub response_body: Option<Value>,
+    pub captured_at: DateTime<Utc>,
+    #[serde(skip)]
+    expires_at: Instant,
+}
+
+/// Sampled, TTL-bounded store of request/response pairs for debugging
+pub struct PayloadArchive {
+    config: ArchiveConfig,
+    entries: RwLock<HashMap<String, ArchivedExchange>>,
+}
+
+impl PayloadArchive {
+    pub fn new(config: ArchiveConfig) -> Self {
+        Self {
+            config,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Decide whether the current request should be captured
+    pub fn should_sample(&self) -> bool {
+        if self.config.sample_rate <= 0.0 {
+            return false;
+        }
+        let roll = (Uuid::new_v4().as_u128() % 1_000_000) as f64 / 1_000_000.0;
+        roll < self.config.sample_rate
+    }
+
+    pub async fn insert(&self, exchange: ArchivedExchange) {
+        let mut entries = self.entries.write().await;
+        let now = Instant::now();
+        entries.retain(|_, e| e.expires_at > now);
+
+        if entries.len() >= self.config.max_entries {
+            // Drop the oldest capture to make room
+            if let Some(oldest) = entries
+                .values()
+                .min_by_key(|e| e.captured_at)
+                .map(|e| e.request_id.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+
+        entries.insert(exchange.request_id.clone(), exchange);
+    }
+
+    /// Drop every capture that mentions execution `id`, so purging an
+    /// execution leaves no copy of its code or output here
+    pub async fn forget_execution(&self, id: Uuid) {
+        let id = id.to_string();
+        let mentions = |body: &Option<Value>| body.as_ref().is_some_and(|body| body.to_string().contains(&id));
+        self.entries.write().await.retain(|_, e| {
+            !(e.uri.contains(&id) || mentions(&e.request_body) || mentions(&e.response_body))
+        });
+    }
+
+    pub async fn get(&self, request_id: &str) -> Option<ArchivedExchange> {
+        let entries = self.entries.read().await;
+        entries
+            .get(request_id)
+            .filter(|e| e.expires_at > Instant::now())
+            .cloned()
+    }
+}
+
+/// Axum middleware capturing a sample of requests into the payload archive
+pub async fn capture(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let archive = state.payload_archive();
+    if !archive.should_sample() {
+        return next.run(request).await;
+    }
+
+    let Some(request_id) = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+    else {
+        return next.run(request).await;
+    };
+
+    let max_body_bytes = archive.config.max_body_bytes;
+    let method = request.method().to_string();
+    let uri = request.uri().to_string();
+    let request_headers = redact_headers(request.headers());
+
+    let (parts, body) = request.into_parts();
+    let (body, request_body) = match buffer_body(body, max_body_bytes).await {
+        Ok(buffered) => buffered,
+        Err(_) => {
+            return ApiError::BadRequest("Failed to read request body".to_string()).into_response()
+        }
+    };
+    let response = next.run(Request::from_parts(parts, body)).await;
+
+    let status = response.status().as_u16();
+    let response_headers = redact_headers(response.headers());
+    let (parts, body) = response.into_parts();
+    let (body, response_body) = match buffer_body(body, max_body_bytes).await {
+        Ok(buffered) => buffered,
+        Err(e) => return ApiError::Internal(e.into()).into_response(),
+    };
+
+    archive
+        .insert(ArchivedExchange {
+            request_id,
+            method,
+            uri,
+            request_headers,
+            request_body,
+            status,
+            response_headers,
+            response_body,
+            captured_at: Utc::now(),
+            expires_at: Instant::now() + archive.config.ttl,
+        })
+        .await;
+
+    Response::from_parts(parts, body)
+}
+
+/// Buffer a body if it is small enough, returning a replacement body and its redacted capture
+async fn buffer_body(body: Body, max_bytes: usize) -> Result<(Body, Option<Value>), axum::Error> {
+    let fits = body
+        .size_hint()
+        .upper()
+        .is_some_and(|n| n <= max_bytes as u64);
+    if !fits {
+        return Ok((body, None));
+    }
+
+    let bytes = axum::body::to_bytes(body, max_bytes).await?;
+
+    let captured = if bytes.is_empty() {
+        None
+    } else {
+        Some(match serde_json::from_slice::<Value>(&bytes) {
+            Ok(mut json) => {
+                redact_json(&mut json);
+                json
+            }
+            Err(_) => Value::String(String::from_utf8_lossy(&bytes).into_owned()),
+        })
+    };
+
+    Ok((Body::from(bytes), captured))
+}
+
+fn redact_headers(headers: &HeaderMap) -> HashMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if is_sensitive(name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                value.to_str().unwrap_or(REDACTED).to_string()
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if is_sensitive(key) {
+                    *v = Value::String(REDACTED.to_string());
+                } else if REDACTED_OBJECTS.iter().any(|name| key.eq_ignore_ascii_case(name)) {
+                    redact_all(v);
+                } else {
+                    redact_json(v);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+/// Redact every value under `value`, keeping object keys so what was set stays visible
+fn redact_all(value: &mut Value) {
+    match value {
+        Value::Object(map) => map.values_mut().for_each(redact_all),
+        Value::Array(items) => items.iter_mut().for_each(redact_all),
+        Value::Null => {}
+        _ => *value = Value::String(REDACTED.to_string()),
+    }
+}
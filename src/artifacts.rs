@@ -0,0 +1,226 @@
+use axum::{
+    extract::{Path, State},
+    middleware,
+    routing::get,
+    Extension, Json, Router,
+};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::{self, AuthContext, AuthInterceptor, ADMIN_SCOPE};
+use crate::config::{ArtifactProvider, ArtifactStoreConfig};
+use crate::error::ApiError;
+use crate::execution::CreatedFile;
+use crate::state::AppState;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Artifact routes, authenticated
+pub fn routes(auth_interceptor: AuthInterceptor) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/executions/:id/artifacts", get(list_artifacts))
+        .route_layer(middleware::from_fn_with_state(auth_interceptor, auth::require_auth))
+}
+
+/// Signs download URLs for files executions created, using query-string
+/// SigV4 as both S3 and GCS (with HMAC keys) accept it
+pub struct ArtifactStore {
+    config: ArtifactStoreConfig,
+    base: Url,
+    bucket: String,
+    access_key_id: String,
+    secret_access_key: String,
+    http: reqwest::Client,
+}
+
+impl ArtifactStore {
+    /// The configured store, or `None` when artifact storage isn't set up
+    pub fn new(config: &ArtifactStoreConfig) -> anyhow::Result<Option<Self>> {
+        let (Some(bucket), Some(access_key_id), Some(secret_access_key)) = (
+            config.bucket.clone(),
+            config.access_key_id.clone(),
+            config.secret_access_key.clone(),
+        ) else {
+            return Ok(None);
+        };
+        let endpoint = match (&config.endpoint, config.provider) {
+            (Some(endpoint), _) => endpoint.clone(),
+            (None, ArtifactProvider::S3) => format!("https://s3.{}.amazonaws.com", config.region),
+            (None, ArtifactProvider::Gcs) => "https://storage.googleapis.com".to_string(),
+        };
+        let base = Url::parse(&endpoint)
+            .map_err(|e| anyhow::anyhow!("Invalid ARTIFACT_ENDPOINT '{}': {}", endpoint, e))?;
+        if base.host_str().is_none() {
+            anyhow::bail!("ARTIFACT_ENDPOINT '{}' has no host", endpoint);
+        }
+        Ok(Some(Self {
+            config: config.clone(),
+            base,
+            bucket,
+            access_key_id,
+            secret_access_key,
+            http: reqwest::Client::new(),
+        }))
+    }
+
+    /// Object key of file `path` created by execution `id`
+    fn key(&self, id: Uuid, path: &str) -> String {
+        format!("{}{}/{}", self.config.key_prefix, id, path.trim_start_matches('/'))
+    }
+
+    /// Delete the stored copies of `files` created by execution `id`;
+    /// objects already gone count as deleted
+    pub async fn delete(&self, id: Uuid, files: &[CreatedFile]) -> anyhow::Result<()> {
+        let now = Utc::now();
+        for file in files {
+            let (url, _) = self.presign("DELETE", &self.key(id, &file.path), now);
+            let status = self.http.delete(url).send().await?.status();
+            if !status.is_success() && status != reqwest::StatusCode::NOT_FOUND {
+                anyhow::bail!("Deleting artifact '{}' failed with {}", file.path, status);
+            }
+        }
+        Ok(())
+    }
+
+    /// Content of file `path` created by execution `id`
+    pub async fn read(&self, id: Uuid, path: &str) -> anyhow::Result<Vec<u8>> {
+        let (url, _) = self.presign("GET", &self.key(id, path), Utc::now());
+        let response = self.http.get(url).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("Reading artifact '{}' failed with {}", path, status);
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// A URL that sends `method` to `key` until `expires_at`, signed as of `now`
+    fn presign(&self, method: &str, key: &str, now: DateTime<Utc>) -> (String, DateTime<Utc>) {
+        let (algorithm, param_prefix, service, terminator, key_prefix) = match self.config.provider {
+            ArtifactProvider::S3 => ("AWS4-HMAC-SHA256", "X-Amz", "s3", "aws4_request", "AWS4"),
+            ArtifactProvider::Gcs => ("GOOG4-HMAC-SHA256", "X-Goog", "storage", "goog4_request", "GOOG4"),
+        };
+        let date = now.format("%Y%m%d").to_string();
+        let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let scope = format!("{}/{}/{}/{}", date, self.config.region, service, terminator);
+        let ttl = self.config.url_ttl.as_secs();
+
+        // Path-style addressing, under any path the endpoint already has
+        let path = format!(
+            "{}/{}/{}",
+            self.base.path().trim_end_matches('/'),
+            uri_encode(&self.bucket, false),
+            uri_encode(key, true)
+        );
+        let host = match self.base.port() {
+            Some(port) => format!("{}:{}", self.base.host_str().unwrap_or_default(), port),
+            None => self.base.host_str().unwrap_or_default().to_string(),
+        };
+        // Parameter names are already in sorted order
+        let query = format!(
+            "{p}-Algorithm={}&{p}-Credential={}&{p}-Date={}&{p}-Expires={}&{p}-SignedHeaders=host",
+            algorithm,
+            uri_encode(&format!("{}/{}", self.access_key_id, scope), false),
+            timestamp,
+            ttl,
+            p = param_prefix,
+        );
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD",
+            method, path, query, host
+        );
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            algorithm,
+            timestamp,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let mut signing_key = format!("{}{}", key_prefix, self.secret_access_key).into_bytes();
+        for part in [date.as_str(), self.config.region.as_str(), service, terminator] {
+            signing_key = hmac(&signing_key, part.as_bytes());
+        }
+        let signature = hex::encode(hmac(&signing_key, string_to_sign.as_bytes()));
+
+        let url = format!(
+            "{}://{}{}?{}&{}-Signature={}",
+            self.base.scheme(),
+            host,
+            path,
+            query,
+            param_prefix,
+            signature
+        );
+        (url, now + chrono::Duration::seconds(ttl as i64))
+    }
+}
+
+fn hmac(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode all but RFC 3986 unreserved characters, and `/` when
+/// encoding a path
+fn uri_encode(value: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => out.push(byte as char),
+            b'/' if keep_slash => out.push('/'),
+            _ => {
+                let _ = write!(out, "%{:02X}", byte);
+            }
+        }
+    }
+    out
+}
+
+#[derive(Debug, Serialize)]
+struct Artifact {
+    #[serde(flatten)]
+    file: CreatedFile,
+    /// Pre-signed download URL; absent when no artifact store is configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+struct ArtifactList {
+    artifacts: Vec<Artifact>,
+}
+
+/// Files a finished execution created, each with a short-lived download URL.
+/// URLs are only signed for the execution's owner or an admin
+async fn list_artifacts(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ArtifactList>, ApiError> {
+    let execution = state.get_owned_execution(&auth_context, id, ADMIN_SCOPE).await?;
+    let files = execution.result.map(|result| result.files_created).unwrap_or_default();
+    let now = Utc::now();
+    let artifacts = files
+        .into_iter()
+        .map(|file| {
+            let signed = state
+                .artifact_store()
+                .map(|store| store.presign("GET", &store.key(id, &file.path), now));
+            Artifact {
+                url: signed.as_ref().map(|(url, _)| url.clone()),
+                expires_at: signed.map(|(_, expires_at)| expires_at),
+                file,
+            }
+        })
+        .collect();
+    Ok(Json(ArtifactList { artifacts }))
+}
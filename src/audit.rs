@@ -0,0 +1,75 @@
+use serde::Serialize;
+use tracing::info;
+
+use crate::client_ip::ClientIp;
+use crate::client_version::ClientVersion;
+
+/// Tracing target used for audit records so they can be routed separately
+pub const AUDIT_TARGET: &str = "audit";
+
+/// Outcome of an audited action
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Allowed,
+    Denied,
+}
+
+/// A single entry in the audit trail
+#[derive(Debug, Serialize)]
+pub struct AuditEvent<'a> {
+    pub action: &'a str,
+    pub actor: &'a str,
+    pub subject: Option<&'a str>,
+    pub tenant_id: Option<&'a str>,
+    pub outcome: AuditOutcome,
+    /// Why the action was taken, when the actor gave a reason
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<&'a str>,
+    /// SDK and version the request came from, when it identified itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    /// Address of the caller behind any trusted proxies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_ip: Option<std::net::IpAddr>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> AuditEvent<'a> {
+    pub fn new(action: &'a str, actor: &'a str, outcome: AuditOutcome) -> Self {
+        Self {
+            action,
+            actor,
+            subject: None,
+            tenant_id: None,
+            outcome,
+            reason: None,
+            client: ClientVersion::current().map(|client| client.to_string()),
+            client_ip: ClientIp::current(),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    pub fn subject(mut self, subject: &'a str) -> Self {
+        self.subject = Some(subject);
+        self
+    }
+
+    pub fn tenant(mut self, tenant_id: Option<&'a str>) -> Self {
+        self.tenant_id = tenant_id;
+        self
+    }
+
+    pub fn reason(mut self, reason: &'a str) -> Self {
+        self.reason = Some(reason);
+        self
+    }
+}
+
+/// Write an event to the audit trail
+pub fn record(event: AuditEvent<'_>) {
+    match serde_json::to_string(&event) {
+        Ok(json) => info!(target: AUDIT_TARGET, "{}", json),
+        Err(e) => info!(target: AUDIT_TARGET, "failed to serialize audit event {:?}: {}", event, e),
+    }
+}
@@ -1,12 +1,39 @@
-use tonic::{Request, Status};
+use axum::{
+    extract::{Request as HttpRequest, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response as HttpResponse,
+};
+use std::sync::Arc;
+use std::time::Instant;
+
+use tonic::{metadata::MetadataMap, Request, Response, Status};
 use tracing::{debug, warn};
 
+use crate::alerts::LatencyRecorder;
+use crate::audit::{self, AuditEvent, AuditOutcome};
+use crate::error::ApiError;
+use crate::server_timing::{self, Phase};
+
 /// Metadata key for user ID
 pub const USER_ID_KEY: &str = "x-user-id";
 /// Metadata key for tenant ID
 pub const TENANT_ID_KEY: &str = "x-tenant-id";
 /// Metadata key for authorization header
 pub const AUTH_HEADER_KEY: &str = "authorization";
+/// Metadata key naming the user an admin wants to act on behalf of
+pub const IMPERSONATE_USER_KEY: &str = "x-impersonate-user";
+/// Metadata key set on responses served under impersonation
+pub const IMPERSONATED_BY_KEY: &str = "x-impersonated-by";
+
+/// Scope required to use the impersonation header
+pub const IMPERSONATE_SCOPE: &str = "admin:impersonate";
+/// Scope required for operator-only admin routes
+pub const ADMIN_SCOPE: &str = "admin";
+/// Scope allowing annotation of other users' executions
+pub const GRADER_SCOPE: &str = "executions:grade";
+/// Scope allowing changes to the caller's tenant execution defaults
+pub const SETTINGS_SCOPE: &str = "settings:write";
 
 /// Authentication context extracted from request
 #[derive(Debug, Clone)]
@@ -14,6 +41,25 @@ pub struct AuthContext {
     pub user_id: String,
     pub tenant_id: Option<String>,
     pub token: String,
+    /// Scopes granted to the token
+    pub scopes: Vec<String>,
+    /// Admin user acting on behalf of `user_id`, when impersonating
+    pub impersonated_by: Option<String>,
+}
+
+impl AuthContext {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    /// Who the caller acts for: its tenant, or its user outside tenants.
+    /// The two are prefixed apart, so a user ID can't name a tenant
+    pub fn principal(&self) -> String {
+        match &self.tenant_id {
+            Some(tenant_id) => format!("tenant:{}", tenant_id),
+            None => format!("user:{}", self.user_id),
+        }
+    }
 }
 
 /// Authentication interceptor for gRPC requests
@@ -23,6 +69,8 @@ pub struct AuthInterceptor {
     auth_service_url: String,
     /// Whether to skip auth in development mode
     skip_auth: bool,
+    /// Receives how long each token validation took
+    latency: Option<Arc<LatencyRecorder>>,
 }
 
 impl AuthInterceptor {
@@ -30,41 +78,110 @@ impl AuthInterceptor {
         Self {
             auth_service_url,
             skip_auth,
+            latency: None,
         }
     }
 
-    /// Extract and validate authentication from request
+    /// Report token validation latency to `recorder`
+    pub fn with_latency_recorder(mut self, recorder: Arc<LatencyRecorder>) -> Self {
+        self.latency = Some(recorder);
+        self
+    }
+
+    /// Extract and validate authentication from request, unless the rate
+    /// limiter already did
     pub async fn authenticate<T>(&self, request: &Request<T>) -> Result<AuthContext, Status> {
+        if let Some(auth_context) = request.extensions().get::<AuthContext>() {
+            return Ok(auth_context.clone());
+        }
+        self.authenticate_metadata(request.metadata()).await
+    }
+
+    /// Extract and validate authentication from request metadata
+    pub async fn authenticate_metadata(&self, metadata: &MetadataMap) -> Result<AuthContext, Status> {
         // In development mode, optionally skip authentication
-        if self.skip_auth {
+        let auth_context = if self.skip_auth {
             debug!("Skipping authentication in development mode");
-            return Ok(AuthContext {
+            AuthContext {
                 user_id: "dev-user".to_string(),
                 tenant_id: Some("dev-tenant".to_string()),
                 token: "dev-token".to_string(),
-            });
-        }
+                scopes: vec![ADMIN_SCOPE.to_string(), IMPERSONATE_SCOPE.to_string()],
+                impersonated_by: None,
+            }
+        } else {
+            // Extract authorization header
+            let auth_header = metadata
+                .get(AUTH_HEADER_KEY)
+                .ok_or_else(|| Status::unauthenticated("Missing authorization header"))?;
+
+            let auth_str = auth_header
+                .to_str()
+                .map_err(|_| Status::unauthenticated("Invalid authorization header"))?;
+
+            // Extract bearer token
+            let token = auth_str
+                .strip_prefix("Bearer ")
+                .ok_or_else(|| Status::unauthenticated("Invalid authorization format"))?;
+
+            // Validate with external auth service
+            let started = Instant::now();
+            let validated = self.validate_token(token).await;
+            server_timing::record(Phase::Auth, started.elapsed());
+            if let Some(latency) = &self.latency {
+                latency.record(started.elapsed());
+            }
+            validated?
+        };
 
-        // Extract authorization header
-        let auth_header = request
-            .metadata()
-            .get(AUTH_HEADER_KEY)
-            .ok_or_else(|| Status::unauthenticated("Missing authorization header"))?;
+        self.apply_impersonation(metadata, auth_context).map_err(|s| *s)
+    }
 
-        let auth_str = auth_header
+    /// Swap the effective identity when an authorized admin sends `x-impersonate-user`
+    fn apply_impersonation(
+        &self,
+        metadata: &MetadataMap,
+        mut auth_context: AuthContext,
+    ) -> Result<AuthContext, Box<Status>> {
+        let Some(target) = metadata.get(IMPERSONATE_USER_KEY) else {
+            return Ok(auth_context);
+        };
+
+        let target = target
             .to_str()
-            .map_err(|_| Status::unauthenticated("Invalid authorization header"))?;
+            .map(str::trim)
+            .map_err(|_| Status::invalid_argument("Invalid impersonation header"))?;
+        if target.is_empty() {
+            return Err(Box::new(Status::invalid_argument("Invalid impersonation header")));
+        }
 
-        // Extract bearer token
-        let token = if auth_str.starts_with("Bearer ") {
-            &auth_str[7..]
-        } else {
-            return Err(Status::unauthenticated("Invalid authorization format"));
-        };
+        let tenant_id = metadata
+            .get(TENANT_ID_KEY)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .or_else(|| auth_context.tenant_id.clone());
+
+        if !auth_context.has_scope(IMPERSONATE_SCOPE) {
+            audit::record(
+                AuditEvent::new("impersonate", &auth_context.user_id, AuditOutcome::Denied)
+                    .subject(target)
+                    .tenant(tenant_id.as_deref()),
+            );
+            return Err(Box::new(Status::permission_denied(format!(
+                "Impersonation requires the {} scope",
+                IMPERSONATE_SCOPE
+            ))));
+        }
 
-        // Validate with external auth service
-        let auth_context = self.validate_token(token).await?;
+        audit::record(
+            AuditEvent::new("impersonate", &auth_context.user_id, AuditOutcome::Allowed)
+                .subject(target)
+                .tenant(tenant_id.as_deref()),
+        );
 
+        let admin = std::mem::replace(&mut auth_context.user_id, target.to_string());
+        auth_context.impersonated_by = Some(admin);
+        auth_context.tenant_id = tenant_id;
         Ok(auth_context)
     }
 
@@ -75,10 +192,13 @@ impl AuthInterceptor {
         
         // In a real implementation, this would:
         // 1. Call the external auth service to validate the token
-        // 2. Extract user_id and tenant_id from the response
+        // 2. Extract user_id, tenant_id and scopes from the response
         // 3. Cache the result for performance
         
-        warn!("Token validation not yet implemented - using placeholder");
+        warn!(
+            auth_service = %self.auth_service_url,
+            "Token validation not yet implemented - using placeholder"
+        );
         
         // Placeholder implementation
         if token == "invalid" {
@@ -89,34 +209,43 @@ impl AuthInterceptor {
             user_id: "placeholder-user".to_string(),
             tenant_id: Some("placeholder-tenant".to_string()),
             token: token.to_string(),
+            scopes: vec![],
+            impersonated_by: None,
         })
     }
 }
 
-/// Extension trait to inject auth context into requests
-pub trait RequestExt {
-    fn auth_context(&self) -> Result<&AuthContext, Status>;
-}
-
-impl<T> RequestExt for Request<T> {
-    fn auth_context(&self) -> Result<&AuthContext, Status> {
-        self.extensions()
-            .get::<AuthContext>()
-            .ok_or_else(|| Status::internal("Missing authentication context"))
+/// Mark a gRPC response as served under impersonation
+pub fn annotate_response<T>(auth_context: &AuthContext, response: &mut Response<T>) {
+    if let Some(admin) = &auth_context.impersonated_by {
+        if let Ok(value) = admin.parse() {
+            response.metadata_mut().insert(IMPERSONATED_BY_KEY, value);
+        }
     }
 }
 
-/// Tower service for authentication
-#[derive(Clone)]
-pub struct AuthService<S> {
-    inner: S,
-    interceptor: AuthInterceptor,
-}
-
-impl<S> AuthService<S> {
-    pub fn new(inner: S, interceptor: AuthInterceptor) -> Self {
-        Self { inner, interceptor }
+/// Axum middleware authenticating REST requests with the same rules as gRPC
+pub async fn require_auth(
+    State(interceptor): State<AuthInterceptor>,
+    mut request: HttpRequest,
+    next: Next,
+) -> Result<HttpResponse, ApiError> {
+    // The rate limiter authenticates requests carrying credentials first
+    let auth_context = match request.extensions().get::<AuthContext>() {
+        Some(auth_context) => auth_context.clone(),
+        None => {
+            let metadata = MetadataMap::from_headers(request.headers().clone());
+            interceptor.authenticate_metadata(&metadata).await?
+        }
+    };
+    let impersonated_by = auth_context.impersonated_by.clone();
+    request.extensions_mut().insert(auth_context);
+
+    let mut response = next.run(request).await;
+    if let Some(admin) = impersonated_by {
+        if let Ok(value) = HeaderValue::from_str(&admin) {
+            response.headers_mut().insert(IMPERSONATED_BY_KEY, value);
+        }
     }
+    Ok(response)
 }
-
-// We'll implement the Tower service trait for AuthService in the main grpc module
\ No newline at end of file
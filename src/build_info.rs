@@ -0,0 +1,45 @@
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Compiled descriptor set of the public API, as generated by `build.rs`
+static PROTO_DESCRIPTOR: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/descriptor.bin"));
+
+/// The public API's compiled proto descriptor set
+pub fn proto_descriptor() -> &'static [u8] {
+    PROTO_DESCRIPTOR
+}
+
+/// What this binary was built from, embedded at build time
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub git_sha: &'static str,
+    pub build_time: DateTime<Utc>,
+    /// Cargo features compiled in
+    pub features: Vec<&'static str>,
+    /// SHA-256 of the public API's proto descriptor set, so SDKs can check
+    /// they were generated from the same protos
+    pub proto_descriptor_sha256: String,
+}
+
+/// Build information for the running binary
+pub fn build_info() -> &'static BuildInfo {
+    static INFO: OnceLock<BuildInfo> = OnceLock::new();
+    INFO.get_or_init(|| BuildInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        git_sha: env!("SYLA_GIT_SHA"),
+        build_time: env!("SYLA_BUILD_EPOCH")
+            .parse()
+            .ok()
+            .and_then(|epoch| DateTime::from_timestamp(epoch, 0))
+            .unwrap_or_default(),
+        features: env!("SYLA_FEATURES")
+            .split(',')
+            .filter(|feature| !feature.is_empty())
+            .collect(),
+        proto_descriptor_sha256: hex::encode(Sha256::digest(PROTO_DESCRIPTOR)),
+    })
+}
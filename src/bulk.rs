@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::cache::ExecutionMeta;
+use crate::execution::ExecutionStatus;
+
+/// Most executions a single bulk operation acts on
+pub const MAX_BULK_EXECUTIONS: usize = 10_000;
+
+/// Executions acted on at once by a bulk operation
+pub const BULK_CONCURRENCY: usize = 16;
+
+/// Executions an admin bulk operation applies to; unset criteria match everything
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BulkFilter {
+    pub tenant_id: Option<String>,
+    /// Statuses to include; every unfinished status when empty
+    #[serde(default)]
+    pub statuses: Vec<ExecutionStatus>,
+    /// Only executions created at least this long ago
+    pub older_than_secs: Option<u64>,
+}
+
+impl BulkFilter {
+    /// Whether an execution in `status`, created at `created_at`, matches
+    pub fn matches(&self, meta: &ExecutionMeta, status: &ExecutionStatus, created_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        if meta.is_deleted() {
+            return false;
+        }
+        if self.tenant_id.is_some() && meta.tenant_id != self.tenant_id {
+            return false;
+        }
+        let status_matches = if self.statuses.is_empty() {
+            !status.is_terminal()
+        } else {
+            self.statuses.contains(status)
+        };
+        let old_enough = self.older_than_secs.is_none_or(|secs| {
+            (now - created_at).to_std().is_ok_and(|age| age.as_secs() >= secs)
+        });
+        status_matches && old_enough
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BulkRequest {
+    #[serde(flatten)]
+    pub filter: BulkFilter,
+    /// Report what would be affected without changing anything
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Most executions acted on, oldest first; capped at `MAX_BULK_EXECUTIONS`
+    pub limit: Option<usize>,
+}
+
+impl BulkRequest {
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(MAX_BULK_EXECUTIONS).min(MAX_BULK_EXECUTIONS)
+    }
+}
+
+/// What a bulk operation did, or would do on a dry run
+#[derive(Debug, Serialize)]
+pub struct BulkReport {
+    pub dry_run: bool,
+    /// Executions matching the filter, including ones past the limit or not eligible
+    pub matched: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub executions: Vec<BulkOutcome>,
+}
+
+impl BulkReport {
+    pub fn new(dry_run: bool, matched: usize, executions: Vec<BulkOutcome>) -> Self {
+        let failed = executions.iter().filter(|outcome| outcome.error.is_some()).count();
+        Self {
+            dry_run,
+            matched,
+            succeeded: if dry_run { 0 } else { executions.len() - failed },
+            failed,
+            executions,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkOutcome {
+    pub execution_id: Uuid,
+    /// Status when the operation started
+    pub status: ExecutionStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    /// New execution created by a requeue
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requeued_as: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
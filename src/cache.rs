@@ -0,0 +1,290 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use tracing::warn;
+
+use crate::canary::Backend;
+use crate::config::StorageConfig;
+use crate::error::ApiError;
+use crate::execution::{
+    Annotation, Cancellation, CreateExecutionRequest, DeliveryState, ExecutionResponse, ExecutionStatus,
+    ResultDelivery,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use crate::metrics::Metrics;
+use crate::trace::TraceContext;
+
+/// Set to `true` on execution reads answered from the cache because upstream
+/// was too slow; REST header and gRPC metadata key alike
+pub const STALE_HEADER: &str = "x-stale";
+
+/// stdout/stderr as held in the cache, compressed when large
+#[derive(Debug, Clone)]
+enum StoredOutput {
+    Plain(String),
+    Zstd(Vec<u8>),
+}
+
+impl StoredOutput {
+    fn pack(output: String, config: &StorageConfig, metrics: &Metrics) -> Self {
+        if output.len() < config.compression_threshold_bytes {
+            return StoredOutput::Plain(output);
+        }
+
+        match zstd::bulk::compress(output.as_bytes(), config.compression_level) {
+            Ok(data) if data.len() < output.len() => {
+                metrics.record_compression(output.len(), data.len());
+                StoredOutput::Zstd(data)
+            }
+            Ok(_) => StoredOutput::Plain(output),
+            Err(e) => {
+                warn!("Failed to compress output, storing uncompressed: {}", e);
+                StoredOutput::Plain(output)
+            }
+        }
+    }
+
+    fn unpack(&self) -> std::io::Result<String> {
+        match self {
+            StoredOutput::Plain(output) => Ok(output.clone()),
+            StoredOutput::Zstd(data) => zstd::stream::decode_all(data.as_slice())
+                .map(|bytes| String::from_utf8_lossy(&bytes).into_owned()),
+        }
+    }
+}
+
+/// Gateway-owned state for an execution, kept across upstream refreshes
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionMeta {
+    /// Submitting user, as recorded when the execution was created through
+    /// the gateway or else as the execution service reports it
+    pub owner: Option<String>,
+    pub tenant_id: Option<String>,
+    /// Set by a soft delete; the entry is purged once the purge window passes
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Pinned executions are kept past the retention period
+    pub pinned: bool,
+    pub annotations: BTreeMap<String, Annotation>,
+    /// Request the execution was created from, kept for resubmission
+    pub request: Option<CreateExecutionRequest>,
+    pub resubmitted_from: Option<Uuid>,
+    pub retried_from: Option<Uuid>,
+    /// Output passed the tenant's cap
+    pub output_limit_exceeded: bool,
+    /// Execution backend the execution was routed to
+    pub backend: Backend,
+    /// Writing the result to a requested destination
+    pub result_delivery: Option<ResultDelivery>,
+    /// A cancellation was sent upstream
+    pub cancel_requested: bool,
+    /// Who or what first cancelled the execution
+    pub cancellation: Option<Cancellation>,
+    /// Trace of the submitting request
+    pub trace: Option<TraceContext>,
+    /// Hex SHA-256 of the submitted code
+    pub code_sha256: Option<String>,
+}
+
+impl ExecutionMeta {
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// Overlay the gateway-owned fields onto an upstream response
+    pub fn apply_to(&self, execution: &mut ExecutionResponse) {
+        execution.status = self.reported_status(&execution.status);
+        execution.pinned = self.pinned;
+        execution.annotations = self.annotations.clone();
+        execution.resubmitted_from = self.resubmitted_from;
+        execution.retried_from = self.retried_from;
+        execution.cancellation = self.cancellation.clone();
+        execution.output_limit_exceeded = self.output_limit_exceeded;
+        execution.tty = self.requested_tty();
+        execution.session_id = self.session_id().map(str::to_string);
+        execution.group_id = self.group_id().map(str::to_string);
+        execution.language_version = self.language_version().map(str::to_string);
+        execution.metadata = self.metadata().cloned().unwrap_or_default();
+        execution.backend = self.backend;
+        execution.result_delivery = self.result_delivery.clone();
+        execution.trace_id = self.trace.as_ref().map(|trace| trace.trace_id.clone());
+        execution.trace_url = self.trace.as_ref().and_then(|trace| trace.trace_url.clone());
+        execution.code_sha256 = self.code_sha256.clone();
+    }
+
+    /// Leave output out of `execution` when it goes to a result destination
+    /// instead; if delivery failed, output is served inline after all
+    pub fn withhold_output(&self, execution: &mut ExecutionResponse) {
+        let withheld = self
+            .result_delivery
+            .as_ref()
+            .is_some_and(|delivery| delivery.state != DeliveryState::Failed);
+        if let (true, Some(result)) = (withheld, execution.result.as_mut()) {
+            result.stdout.clear();
+            result.stderr.clear();
+        }
+    }
+
+    /// Status to report for an execution upstream has in `status`; one with a
+    /// cancellation pending is cancelling until upstream reports a final status
+    pub fn reported_status(&self, status: &ExecutionStatus) -> ExecutionStatus {
+        if self.cancel_requested && !status.is_terminal() {
+            return ExecutionStatus::Cancelling;
+        }
+        status.clone()
+    }
+
+    /// Whether the execution was requested with a terminal attached
+    pub fn requested_tty(&self) -> bool {
+        self.request.as_ref().and_then(|r| r.tty).unwrap_or(false)
+    }
+
+    /// Session the execution was submitted under
+    pub fn session_id(&self) -> Option<&str> {
+        self.request.as_ref().and_then(|r| r.session_id.as_deref())
+    }
+
+    /// Execution group the execution was submitted under
+    pub fn group_id(&self) -> Option<&str> {
+        self.request.as_ref().and_then(|r| r.group_id.as_deref())
+    }
+
+    /// Runtime version the execution was pinned to
+    pub fn language_version(&self) -> Option<&str> {
+        self.request.as_ref().and_then(|r| r.language_version.as_deref())
+    }
+
+    /// Caller-defined labels the execution was submitted with
+    pub fn metadata(&self) -> Option<&HashMap<String, String>> {
+        self.request.as_ref().map(|r| &r.metadata)
+    }
+
+    /// Whether `user_id` is the execution's known owner. Executions whose
+    /// owner isn't known belong to no one, so only scoped callers reach them
+    pub fn is_owned_by(&self, user_id: &str) -> bool {
+        self.owner.as_deref() == Some(user_id)
+    }
+}
+
+/// Source of entry versions; each change to an entry takes a fresh one, so
+/// versions never repeat even across entries
+static NEXT_VERSION: AtomicU64 = AtomicU64::new(1);
+
+fn next_version() -> u64 {
+    NEXT_VERSION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Cache entry for an execution, with large outputs stored compressed
+#[derive(Debug, Clone)]
+pub struct CachedExecution {
+    execution: ExecutionResponse,
+    stdout: StoredOutput,
+    stderr: StoredOutput,
+    meta: ExecutionMeta,
+    /// When the upstream snapshot was last replaced
+    refreshed_at: Instant,
+    /// Another replica saw a newer upstream state
+    stale: bool,
+    /// Changes whenever the entry does
+    version: u64,
+}
+
+impl CachedExecution {
+    pub fn pack(mut execution: ExecutionResponse, config: &StorageConfig, metrics: &Metrics) -> Self {
+        let (stdout, stderr) = match execution.result.as_mut() {
+            Some(result) => (
+                std::mem::take(&mut result.stdout),
+                std::mem::take(&mut result.stderr),
+            ),
+            None => (String::new(), String::new()),
+        };
+
+        Self {
+            execution,
+            stdout: StoredOutput::pack(stdout, config, metrics),
+            stderr: StoredOutput::pack(stderr, config, metrics),
+            meta: ExecutionMeta::default(),
+            refreshed_at: Instant::now(),
+            stale: false,
+            version: next_version(),
+        }
+    }
+
+    /// Replace the upstream snapshot, keeping gateway-owned metadata
+    pub fn refresh(&mut self, fresh: CachedExecution) {
+        self.execution = fresh.execution;
+        self.stdout = fresh.stdout;
+        self.stderr = fresh.stderr;
+        self.refreshed_at = fresh.refreshed_at;
+        self.stale = false;
+        self.version = next_version();
+    }
+
+    /// Force the next read to go upstream, keeping gateway-owned metadata
+    pub fn mark_stale(&mut self) {
+        self.stale = true;
+        self.version = next_version();
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Time since the upstream snapshot was last replaced
+    pub fn age(&self) -> Duration {
+        self.refreshed_at.elapsed()
+    }
+
+    pub fn meta(&self) -> &ExecutionMeta {
+        &self.meta
+    }
+
+    pub fn meta_mut(&mut self) -> &mut ExecutionMeta {
+        self.version = next_version();
+        &mut self.meta
+    }
+
+    /// Identifies the entry's current contents, so views derived from it can
+    /// tell whether they are still current
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn status(&self) -> &ExecutionStatus {
+        &self.execution.status
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.execution.created_at
+    }
+
+    pub fn started_at(&self) -> Option<DateTime<Utc>> {
+        self.execution.started_at
+    }
+
+    /// Whether the execution has reached a final status upstream
+    pub fn is_terminal(&self) -> bool {
+        self.execution.status.is_terminal()
+    }
+
+    /// Rebuild the full response, decompressing outputs transparently. Output
+    /// that won't decompress is an error rather than served empty; refetching
+    /// the execution upstream replaces it
+    pub fn unpack(&self) -> Result<ExecutionResponse, ApiError> {
+        let mut execution = self.execution.clone();
+        self.meta.apply_to(&mut execution);
+        if let Some(result) = execution.result.as_mut() {
+            let corrupt = |e: std::io::Error| {
+                ApiError::Internal(anyhow::anyhow!(
+                    "Cached output of execution {} failed to decompress: {}",
+                    self.execution.id,
+                    e
+                ))
+            };
+            result.stdout = self.stdout.unpack().map_err(corrupt)?;
+            result.stderr = self.stderr.unpack().map_err(corrupt)?;
+        }
+        self.meta.withhold_output(&mut execution);
+        Ok(execution)
+    }
+}
@@ -0,0 +1,116 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Path, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::Response,
+    routing::post,
+    Json, Router,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::CallbackConfig;
+use crate::error::ApiError;
+use crate::execution::ExecutionUpdate;
+use crate::state::AppState;
+
+/// Header carrying `sha256=<hex HMAC>` over `<timestamp>.<body>`
+pub const SIGNATURE_HEADER: &str = "x-syla-signature";
+/// Header carrying the Unix time, in seconds, the callback was signed at
+pub const TIMESTAMP_HEADER: &str = "x-syla-timestamp";
+
+pub(crate) const SIGNATURE_PREFIX: &str = "sha256=";
+/// Completions carry execution output, bounded like execution responses
+const MAX_CALLBACK_BODY_BYTES: usize = 8 * 1024 * 1024;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Routes the execution service calls back into, authenticated by request signature
+pub fn routes(config: CallbackConfig) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/internal/v1/executions/:id/status", post(update_execution))
+        .route("/internal/v1/executions/:id/complete", post(complete_execution))
+        .route_layer(middleware::from_fn_with_state(config, verify_signature))
+}
+
+/// Reject callbacks that aren't signed with the shared secret, or whose
+/// timestamp is outside the allowed skew, before they can touch state
+async fn verify_signature(
+    State(config): State<CallbackConfig>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let Some(secret) = config.secret.as_deref() else {
+        return Err(ApiError::Unauthorized("Callbacks are not enabled".to_string()));
+    };
+
+    let (parts, body) = request.into_parts();
+    let timestamp = check_timestamp(&parts.headers, &config)?;
+    let signature = signature(&parts.headers)?;
+
+    let body = to_bytes(body, MAX_CALLBACK_BODY_BYTES)
+        .await
+        .map_err(|_| ApiError::BadRequest("Callback body too large or unreadable".to_string()))?;
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Invalid callback secret: {}", e)))?;
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(&body);
+    mac.verify_slice(&signature)
+        .map_err(|_| ApiError::Unauthorized("Invalid callback signature".to_string()))?;
+
+    Ok(next.run(Request::from_parts(parts, Body::from(body))).await)
+}
+
+fn check_timestamp<'a>(headers: &'a HeaderMap, config: &CallbackConfig) -> Result<&'a str, ApiError> {
+    let raw = headers
+        .get(TIMESTAMP_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized(format!("Missing {} header", TIMESTAMP_HEADER)))?;
+    let signed_at: i64 = raw
+        .parse()
+        .map_err(|_| ApiError::Unauthorized(format!("Invalid {} header", TIMESTAMP_HEADER)))?;
+
+    let skew = (chrono::Utc::now().timestamp() - signed_at).unsigned_abs();
+    if skew > config.max_skew.as_secs() {
+        return Err(ApiError::Unauthorized("Callback timestamp outside allowed window".to_string()));
+    }
+    Ok(raw)
+}
+
+fn signature(headers: &HeaderMap) -> Result<Vec<u8>, ApiError> {
+    headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix(SIGNATURE_PREFIX))
+        .and_then(|hex_digest| hex::decode(hex_digest).ok())
+        .ok_or_else(|| ApiError::Unauthorized(format!("Missing or malformed {} header", SIGNATURE_HEADER)))
+}
+
+/// Any status change, e.g. an execution starting to run
+async fn update_execution(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Json(update): Json<ExecutionUpdate>,
+) -> Result<StatusCode, ApiError> {
+    state.apply_update(id, update).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn complete_execution(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Json(update): Json<ExecutionUpdate>,
+) -> Result<StatusCode, ApiError> {
+    if !update.status.is_terminal() {
+        return Err(ApiError::BadRequest(
+            "Completion callbacks must carry a final status".to_string(),
+        ));
+    }
+    state.apply_update(id, update).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
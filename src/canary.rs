@@ -0,0 +1,40 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::config::CanaryConfig;
+
+/// Execution backend an execution was routed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Primary,
+    Canary,
+}
+
+impl Backend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Backend::Primary => "primary",
+            Backend::Canary => "canary",
+        }
+    }
+
+    pub fn is_primary(&self) -> bool {
+        *self == Backend::Primary
+    }
+}
+
+/// Pick the backend for a new execution from its tenant's canary share
+pub fn route(config: &CanaryConfig, tenant_id: Option<&str>) -> Backend {
+    let percent = config.percent_for(tenant_id);
+    if percent <= 0.0 {
+        return Backend::Primary;
+    }
+    let roll = (Uuid::new_v4().as_u128() % 10_000) as f64 / 100.0;
+    if roll < percent {
+        Backend::Canary
+    } else {
+        Backend::Primary
+    }
+}
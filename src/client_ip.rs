@@ -0,0 +1,161 @@
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::{
+    extract::ConnectInfo,
+    http::{self, header, HeaderMap},
+};
+use futures::future::BoxFuture;
+use ipnet::IpNet;
+use tower::{Layer, Service};
+
+use crate::config::TrustedProxyConfig;
+
+tokio::task_local! {
+    static REQUEST_CLIENT_IP: Option<IpAddr>;
+}
+
+/// Address of the client behind any trusted proxies, added to request extensions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+impl ClientIp {
+    /// Client address for the request being handled
+    pub fn current() -> Option<IpAddr> {
+        REQUEST_CLIENT_IP.try_with(|ip| *ip).ok().flatten()
+    }
+}
+
+/// Resolve the client address for a connection from `peer`. Forwarding headers
+/// are only believed when `peer` is a trusted proxy, and only back to the first
+/// hop that isn't; `Forwarded` is preferred over `X-Forwarded-For`
+pub fn resolve(peer: IpAddr, headers: &HeaderMap, trusted: &[IpNet]) -> IpAddr {
+    let is_trusted = |ip: &IpAddr| trusted.iter().any(|range| range.contains(ip));
+    if !is_trusted(&peer) {
+        return peer;
+    }
+
+    let chain = if headers.contains_key(header::FORWARDED) {
+        forwarded_chain(headers)
+    } else {
+        x_forwarded_for_chain(headers)
+    };
+
+    let mut client = peer;
+    for hop in chain.iter().rev() {
+        // An unparsable or obfuscated hop can't be checked, so nothing before it is believed
+        let Some(ip) = hop else { break };
+        client = *ip;
+        if !is_trusted(ip) {
+            break;
+        }
+    }
+    client
+}
+
+/// `for=` addresses from `Forwarded`, nearest the client first
+fn forwarded_chain(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    header_elements(headers, header::FORWARDED.as_str())
+        .map(|element| {
+            element
+                .split(';')
+                .find_map(|pair| {
+                    let (key, value) = pair.split_once('=')?;
+                    key.trim().eq_ignore_ascii_case("for").then_some(value)
+                })
+                .and_then(parse_node)
+        })
+        .collect()
+}
+
+/// Addresses from `X-Forwarded-For`, nearest the client first
+fn x_forwarded_for_chain(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    header_elements(headers, "x-forwarded-for").map(parse_node).collect()
+}
+
+/// Comma-separated elements across every instance of a header, in order
+fn header_elements<'a>(headers: &'a HeaderMap, name: &str) -> impl Iterator<Item = &'a str> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+}
+
+/// Parse a node as written in either header: a bare or quoted address,
+/// optionally bracketed and with a port
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    node.parse::<IpAddr>()
+        .ok()
+        .or_else(|| node.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .or_else(|| node.strip_prefix('[')?.strip_suffix(']')?.parse().ok())
+}
+
+/// Tower layer resolving the client address on both listeners, for handlers
+/// via the `ClientIp` extension and for audit records
+#[derive(Clone)]
+pub struct ClientIpLayer {
+    config: Arc<TrustedProxyConfig>,
+}
+
+impl ClientIpLayer {
+    pub fn new(config: TrustedProxyConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+        }
+    }
+}
+
+impl<S> Layer<S> for ClientIpLayer {
+    type Service = ClientIpService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ClientIpService {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ClientIpService<S> {
+    inner: S,
+    config: Arc<TrustedProxyConfig>,
+}
+
+impl<S, B> Service<http::Request<B>> for ClientIpService<S>
+where
+    S: Service<http::Request<B>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        // REST connections carry axum's connect info, gRPC ones tonic's
+        let extensions = request.extensions();
+        let peer = extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip())
+            .or_else(|| {
+                extensions
+                    .get::<tonic::transport::server::TcpConnectInfo>()
+                    .and_then(|info| info.remote_addr())
+                    .map(|addr| addr.ip())
+            });
+
+        let client_ip = peer.map(|peer| resolve(peer, request.headers(), &self.config.ranges));
+        if let Some(ip) = client_ip {
+            request.extensions_mut().insert(ClientIp(ip));
+        }
+        Box::pin(REQUEST_CLIENT_IP.scope(client_ip, self.inner.call(request)))
+    }
+}
@@ -0,0 +1,175 @@
+use std::fmt;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::{
+    extract::{Request, State},
+    http::{self, header, HeaderMap},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use tower::{Layer, Service};
+use tracing::info;
+
+use crate::{config::ClientVersionConfig, error::ApiError, state::AppState};
+
+tokio::task_local! {
+    static REQUEST_CLIENT: Option<ClientVersion>;
+}
+
+/// Header SDKs set to identify themselves, as `<name>/<version>`
+pub const CLIENT_VERSION_HEADER: &str = "x-client-version";
+
+/// The SDK or tool behind a request and the version it reported
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientVersion {
+    /// Lowercased product name, e.g. `syla-python`
+    pub name: String,
+    pub version: semver::Version,
+}
+
+impl ClientVersion {
+    /// Parse a `<name>/<version>` product token
+    pub fn parse(token: &str) -> Option<ClientVersion> {
+        let (name, version) = token.trim().split_once('/')?;
+        let valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid_name {
+            return None;
+        }
+        Some(ClientVersion {
+            name: name.to_ascii_lowercase(),
+            version: parse_version(version)?,
+        })
+    }
+
+    /// Identify the client from `x-client-version`, falling back to the first
+    /// product in `User-Agent`
+    pub fn from_headers(headers: &HeaderMap) -> Option<ClientVersion> {
+        let value = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        value(CLIENT_VERSION_HEADER)
+            .and_then(ClientVersion::parse)
+            .or_else(|| {
+                value(header::USER_AGENT.as_str())
+                    .and_then(|agent| agent.split_whitespace().next())
+                    .and_then(ClientVersion::parse)
+            })
+    }
+
+    /// Client identified for the request being handled
+    pub fn current() -> Option<ClientVersion> {
+        REQUEST_CLIENT.try_with(Clone::clone).ok().flatten()
+    }
+}
+
+impl fmt::Display for ClientVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.name, self.version)
+    }
+}
+
+/// Parse a version leniently: a leading `v` is dropped and missing minor or
+/// patch components count as zero, so `v1.4` reads as `1.4.0`
+pub fn parse_version(raw: &str) -> Option<semver::Version> {
+    let raw = raw.trim();
+    let raw = raw.strip_prefix('v').unwrap_or(raw);
+    if let Ok(version) = semver::Version::parse(raw) {
+        return Some(version);
+    }
+    let (core, suffix) = raw.split_at(raw.find(['-', '+']).unwrap_or(raw.len()));
+    let components = core.split('.').count();
+    if components >= 3 {
+        return None;
+    }
+    semver::Version::parse(&format!("{}{}{}", core, ".0".repeat(3 - components), suffix)).ok()
+}
+
+/// Record the client in metrics and reject it if it's older than the
+/// minimum configured for its name. Unidentified clients are always admitted
+pub fn admit(state: &AppState, client: Option<&ClientVersion>) -> Result<(), ApiError> {
+    let minimum = client.and_then(|client| minimum_for(&state.config().client_versions, client));
+    state.metrics().record_client(client, minimum.is_some());
+    match (client, minimum) {
+        (Some(client), Some(minimum)) => {
+            info!(client = %client, minimum = %minimum, "Rejected outdated client");
+            Err(ApiError::UpgradeRequired {
+                client: client.to_string(),
+                minimum: minimum.to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The minimum `client` falls short of, if any
+fn minimum_for<'a>(config: &'a ClientVersionConfig, client: &ClientVersion) -> Option<&'a semver::Version> {
+    config
+        .minimums
+        .get(&client.name)
+        .filter(|minimum| client.version < **minimum)
+}
+
+/// Identify the REST client, enforcing minimum versions
+pub async fn track(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let client = ClientVersion::from_headers(request.headers());
+    if let Err(e) = admit(&state, client.as_ref()) {
+        return e.into_response();
+    }
+    REQUEST_CLIENT.scope(client, next.run(request)).await
+}
+
+/// Tower layer identifying gRPC clients from request metadata, enforcing
+/// minimum versions
+#[derive(Clone)]
+pub struct ClientVersionLayer {
+    state: Arc<AppState>,
+}
+
+impl ClientVersionLayer {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+}
+
+impl<S> Layer<S> for ClientVersionLayer {
+    type Service = ClientVersionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ClientVersionService {
+            inner,
+            state: self.state.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ClientVersionService<S> {
+    inner: S,
+    state: Arc<AppState>,
+}
+
+impl<S, B> Service<http::Request<B>> for ClientVersionService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<tonic::body::BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let client = ClientVersion::from_headers(request.headers());
+        if let Err(e) = admit(&self.state, client.as_ref()) {
+            let status = tonic::Status::failed_precondition(e.to_string());
+            return Box::pin(async move { Ok(status.into_http()) });
+        }
+        Box::pin(REQUEST_CLIENT.scope(client, self.inner.call(request)))
+    }
+}
@@ -0,0 +1,114 @@
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpStream;
+use tonic::codegen::http::Uri;
+use tower::Service;
+
+/// Connects to whichever of an upstream's addresses answers first, in the
+/// manner of Happy Eyeballs (RFC 8305): addresses are tried in resolver
+/// order alternating between IPv6 and IPv4, a new attempt starting every
+/// `attempt_delay` or as soon as the previous one fails, so a blackholed
+/// address or address family costs one delay rather than a connect timeout
+#[derive(Debug, Clone)]
+pub struct HappyEyeballsConnector {
+    attempt_delay: Duration,
+}
+
+impl HappyEyeballsConnector {
+    pub fn new(attempt_delay: Duration) -> Self {
+        Self { attempt_delay }
+    }
+}
+
+impl Service<Uri> for HappyEyeballsConnector {
+    type Response = TokioIo<TcpStream>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let attempt_delay = self.attempt_delay;
+        Box::pin(async move {
+            let addrs = resolve(&uri).await?;
+            let stream = race(addrs, attempt_delay).await?;
+            stream.set_nodelay(true)?;
+            Ok(TokioIo::new(stream))
+        })
+    }
+}
+
+/// Addresses of `uri`'s host, alternating address families starting with
+/// the resolver's first choice
+async fn resolve(uri: &Uri) -> io::Result<Vec<SocketAddr>> {
+    let host = uri
+        .host()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "upstream URL has no host"))?
+        .trim_matches(['[', ']']);
+    let port = uri
+        .port_u16()
+        .unwrap_or(if uri.scheme_str() == Some("https") { 443 } else { 80 });
+    let resolved: Vec<SocketAddr> = tokio::net::lookup_host((host, port)).await?.collect();
+    let Some(first) = resolved.first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} resolved to no addresses", host),
+        ));
+    };
+    let preferred_v6 = first.is_ipv6();
+    let (preferred, fallback): (Vec<_>, Vec<_>) =
+        resolved.into_iter().partition(|addr| addr.is_ipv6() == preferred_v6);
+    let mut addrs = Vec::with_capacity(preferred.len() + fallback.len());
+    let mut fallback = fallback.into_iter();
+    for addr in preferred {
+        addrs.push(addr);
+        addrs.extend(fallback.next());
+    }
+    addrs.extend(fallback);
+    Ok(addrs)
+}
+
+/// Staggered connection attempts to `addrs`; the first to connect wins and
+/// the rest are dropped
+async fn race(addrs: Vec<SocketAddr>, attempt_delay: Duration) -> io::Result<TcpStream> {
+    let mut pending = addrs.into_iter();
+    let mut attempts = FuturesUnordered::new();
+    let mut last_error = None;
+    loop {
+        if attempts.is_empty() {
+            match pending.next() {
+                Some(addr) => attempts.push(TcpStream::connect(addr)),
+                None => {
+                    return Err(last_error
+                        .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no addresses to connect to")))
+                }
+            }
+        }
+        tokio::select! {
+            Some(result) = attempts.next() => match result {
+                Ok(stream) => return Ok(stream),
+                Err(e) => {
+                    last_error = Some(e);
+                    // Start the next attempt now rather than after the delay
+                    if let Some(addr) = pending.next() {
+                        attempts.push(TcpStream::connect(addr));
+                    }
+                }
+            },
+            _ = tokio::time::sleep(attempt_delay), if pending.len() > 0 => {
+                if let Some(addr) = pending.next() {
+                    attempts.push(TcpStream::connect(addr));
+                }
+            }
+        }
+    }
+}
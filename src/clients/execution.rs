@@ -1,63 +1,470 @@
-use crate::execution::{CreateExecutionRequest, ExecutionResponse, ExecutionResult, ExecutionStatus};
+use crate::canary::Backend;
+use crate::config::{UpstreamConfig, UpstreamProtocol};
+use crate::execution::{
+    CreateExecutionRequest, CreatedFile, ExecutionError, ExecutionErrorKind, ExecutionResponse, ExecutionResult,
+    ExecutionStatus, IsolationMode,
+};
 use crate::error::ApiError;
+use crate::ids;
+use crate::languages::{CatalogSource, LanguageCatalog};
+use crate::output::OutputStream;
 use anyhow::Result;
-use tonic::{Request, Status};
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tonic::transport::Channel;
+use tonic::{Request, Streaming};
 use uuid::Uuid;
 
 // Import the generated proto types
 use crate::proto::execution::v1::{
     execution_service_client::ExecutionServiceClient,
-    SubmitExecutionRequest, GetExecutionRequest, ExecutionRequest,
-    Language, ExecutionMode, ExecutionStatus as ProtoExecutionStatus,
+    SubmitExecutionRequest, GetExecutionRequest, ExecutionRequest, ExecutionEvent,
+    StreamExecutionRequest, OutputType, execution_event, CancelExecutionRequest, DeleteExecutionRequest,
+    Language, ExecutionMode, ExecutionStatus as ProtoExecutionStatus, InputFile, OutputFile, ResourceRequirements,
+    Execution, ListExecutionsRequest, ListLanguagesRequest, ExecutionError as ProtoExecutionError, WriteStdinRequest,
+};
+use crate::proto::common::v1::{
+    ExecutionContext, HealthCheckRequest, HealthStatus, PageRequest,
 };
-use crate::proto::common::v1::ExecutionContext;
+use super::http_execution::HttpExecutionBackend;
+use super::{CallGuard, ChannelCounters, ChannelStats};
+
+const SERVICE_NAME: &str = "execution";
+
+/// Execution context metadata key carrying the priority the execution
+/// service schedules by; also read from gRPC create requests' metadata
+pub const PRIORITY_KEY: &str = "priority";
+
+/// Execution context metadata key carrying the ID the gateway proposes for a
+/// new execution; the ID the execution service returns is the one used
+pub const EXECUTION_ID_KEY: &str = "execution_id";
+
+/// Filters and cursor for one upstream list call
+#[derive(Debug, Clone, Default)]
+pub struct UpstreamListQuery {
+    pub page_size: u32,
+    pub page_token: Option<String>,
+    pub status: Option<ExecutionStatus>,
+    pub language: Option<String>,
+    pub created_after: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_before: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// A page of executions from the execution service
+#[derive(Debug)]
+pub struct ExecutionPage {
+    pub executions: Vec<ExecutionResponse>,
+    /// Cursor for the next page, if there is one
+    pub next_page_token: Option<String>,
+}
+
+/// Upstream stream events the gateway acts on
+#[derive(Debug, Clone)]
+pub enum UpstreamEvent {
+    Output(OutputStream, String),
+    Status(ExecutionStatus),
+}
+
+fn upstream_event(event: ExecutionEvent) -> Option<UpstreamEvent> {
+    match event.event? {
+        execution_event::Event::Output(output) => {
+            let stream = match OutputType::try_from(output.r#type) {
+                Ok(OutputType::Stdout) => OutputStream::Stdout,
+                Ok(OutputType::Stderr) => OutputStream::Stderr,
+                _ => return None,
+            };
+            Some(UpstreamEvent::Output(stream, output.data))
+        }
+        execution_event::Event::StatusChange(change) => {
+            Some(UpstreamEvent::Status(proto_to_status(change.new_status)))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Clone)]
+struct PooledClient {
+    client: ExecutionServiceClient<Channel>,
+    counters: Arc<ChannelCounters>,
+}
+
+impl PooledClient {
+    async fn connect(config: &UpstreamConfig) -> Result<Self> {
+        let channel = super::create_channel(&config.execution_service_url, config).await?;
+        Ok(Self {
+            client: ExecutionServiceClient::new(channel),
+            counters: Arc::new(ChannelCounters::default()),
+        })
+    }
+}
+
+/// Outcome of one adaptive pool sizing pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolResize {
+    Unchanged,
+    Grew(usize),
+    Shrank(usize),
+}
+
+/// An execution service the gateway submits to, in whichever protocol it
+/// speaks, with executions in the gateway's own types
+#[async_trait]
+pub trait ExecutionBackend: Send + Sync {
+    /// Connections currently pooled to the service
+    fn pool_size(&self) -> usize {
+        1
+    }
+
+    /// Per-connection call accounting, where the transport keeps any
+    fn channel_stats(&self) -> Vec<ChannelStats> {
+        Vec::new()
+    }
+
+    /// Resize the connection pool to the load since the previous pass
+    async fn autoscale(&self) -> Result<PoolResize> {
+        Ok(PoolResize::Unchanged)
+    }
+
+    /// Output and status changes of an execution, from its first event
+    async fn stream_execution(
+        &self,
+        id: Uuid,
+    ) -> Result<BoxStream<'static, Result<UpstreamEvent, ApiError>>, ApiError>;
+
+    /// Fail unless the service answers and reports itself healthy
+    async fn probe(&self) -> Result<(), ApiError>;
+
+    async fn create_execution(
+        &self,
+        user_id: String,
+        workspace_id: Option<String>,
+        request: CreateExecutionRequest,
+    ) -> Result<ExecutionResponse, ApiError>;
+
+    async fn get_execution(&self, id: Uuid) -> Result<ExecutionResponse, ApiError>;
+
+    /// One page of `user_id`'s executions, newest first
+    async fn list_executions(&self, user_id: &str, query: &UpstreamListQuery) -> Result<ExecutionPage, ApiError>;
+
+    /// Whether `lang` is a language the service knows
+    fn recognizes_language(&self, lang: &str) -> bool;
+
+    /// Languages the service supports, as last discovered
+    fn languages(&self) -> Arc<LanguageCatalog>;
 
+    /// Ask the service which languages it supports
+    async fn discover_languages(&self) -> Result<CatalogSource, ApiError>;
+
+    /// Stop an execution at once, returning the status it ended in
+    async fn cancel_execution(&self, id: Uuid, reason: &str) -> Result<ExecutionStatus, ApiError>;
+
+    /// Delete everything stored for a finished execution; `false` when the
+    /// service can't delete executions
+    async fn delete_execution(&self, id: Uuid, reason: &str) -> Result<bool, ApiError>;
+
+    /// Write `data` to a running execution's stdin, closing it afterwards if `close`
+    async fn write_stdin(&self, id: Uuid, data: Vec<u8>, close: bool) -> Result<(), ApiError>;
+}
+
+/// Client for the execution service, over the protocol configured for it.
+/// Derefs to the backend, so callers don't depend on the protocol
 pub struct ExecutionClient {
-    client: ExecutionServiceClient<tonic::transport::Channel>,
+    backend: Box<dyn ExecutionBackend>,
 }
 
 impl ExecutionClient {
-    pub async fn new(url: &str) -> Result<Self> {
-        let channel = super::create_channel(url).await?;
-        let client = ExecutionServiceClient::new(channel);
-        Ok(Self { client })
+    /// Connect to the execution service `config` points at
+    pub async fn new(config: &UpstreamConfig) -> Result<Self> {
+        let backend: Box<dyn ExecutionBackend> = match config.protocol {
+            UpstreamProtocol::Grpc => Box::new(GrpcExecutionBackend::new(config).await?),
+            UpstreamProtocol::Http => Box::new(HttpExecutionBackend::new(config)?),
+        };
+        Ok(Self { backend })
+    }
+}
+
+impl Deref for ExecutionClient {
+    type Target = dyn ExecutionBackend;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.backend
+    }
+}
+
+/// Poll `client` every `poll_interval` until execution `id` finishes,
+/// giving up once `deadline` would pass
+pub async fn wait_for_result(
+    client: &tokio::sync::RwLock<ExecutionClient>,
+    id: Uuid,
+    poll_interval: Duration,
+    deadline: tokio::time::Instant,
+) -> Result<ExecutionResponse> {
+    loop {
+        let execution = client.read().await.get_execution(id).await?;
+        if execution.status.is_terminal() {
+            return Ok(execution);
+        }
+        if tokio::time::Instant::now() + poll_interval > deadline {
+            anyhow::bail!("execution {} did not finish in time", id);
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// Execution service speaking the gRPC proto, spreading calls over a pool of channels
+pub struct GrpcExecutionBackend {
+    // Only held long enough to pick or resize, never across an RPC
+    clients: RwLock<Vec<PooledClient>>,
+    next: AtomicUsize,
+    config: UpstreamConfig,
+    languages: RwLock<Arc<LanguageCatalog>>,
+}
+
+impl GrpcExecutionBackend {
+    /// Connect the configured number of channels up front so the first
+    /// requests after startup don't pay connection-establishment latency
+    pub async fn new(config: &UpstreamConfig) -> Result<Self> {
+        let pool_size = config.pool_size.max(1);
+        let mut clients = Vec::with_capacity(pool_size);
+        for _ in 0..pool_size {
+            clients.push(PooledClient::connect(config).await?);
+        }
+        Ok(Self {
+            clients: RwLock::new(clients),
+            next: AtomicUsize::new(0),
+            config: config.clone(),
+            languages: RwLock::new(Arc::new(LanguageCatalog::builtin())),
+        })
+    }
+
+    /// Pick the next pooled channel round-robin for a unary call; tonic clients are cheap to clone
+    fn client(&self) -> (ExecutionServiceClient<Channel>, CallGuard) {
+        let clients = self.clients.read().unwrap();
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % clients.len();
+        let pooled = &clients[index];
+        (pooled.client.clone(), pooled.counters.begin_call())
+    }
+
+    /// Pick the next pooled channel for a server stream, counted as an open stream
+    /// for as long as the guard lives
+    fn stream_client(&self) -> (ExecutionServiceClient<Channel>, CallGuard) {
+        let clients = self.clients.read().unwrap();
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % clients.len();
+        let pooled = &clients[index];
+        (pooled.client.clone(), pooled.counters.begin_stream())
+    }
+
+    fn language_to_proto(&self, lang: &str) -> Language {
+        self.languages().to_proto(lang)
     }
     
-    pub async fn create_execution(
-        &mut self,
+    fn proto_to_status(&self, status: i32) -> ExecutionStatus {
+        proto_to_status(status)
+    }
+}
+
+#[async_trait]
+impl ExecutionBackend for GrpcExecutionBackend {
+    fn pool_size(&self) -> usize {
+        self.clients.read().unwrap().len()
+    }
+
+    /// Per-channel call accounting, for spotting a saturated channel
+    fn channel_stats(&self) -> Vec<ChannelStats> {
+        self.clients
+            .read()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .map(|(i, pooled)| ChannelStats::new(SERVICE_NAME, i, &pooled.counters, &self.config))
+            .collect()
+    }
+
+    /// Grow or shrink the pool by one channel, within the configured bounds,
+    /// based on in-flight load and unary latency since the previous pass
+    async fn autoscale(&self) -> Result<PoolResize> {
+        let (size, load, mean_latency) = {
+            let clients = self.clients.read().unwrap();
+            let mut load = 0;
+            let mut latency = Duration::ZERO;
+            let mut completed = 0;
+            for pooled in clients.iter() {
+                load += pooled.counters.load();
+                let (total, count) = pooled.counters.take_latency();
+                latency += total;
+                completed += count;
+            }
+            let mean = if completed > 0 {
+                latency / completed as u32
+            } else {
+                Duration::ZERO
+            };
+            (clients.len(), load, mean)
+        };
+
+        let target = self.config.pool_target_inflight.max(1);
+        let saturated = load > target * size as u64;
+        // Slow responses only justify another channel while every channel is busy;
+        // an idle pool with a slow upstream won't be helped by more connections
+        let slow = mean_latency > self.config.pool_latency_target && load >= size as u64;
+
+        if (saturated || slow) && size < self.config.pool_max {
+            let pooled = PooledClient::connect(&self.config).await?;
+            let mut clients = self.clients.write().unwrap();
+            clients.push(pooled);
+            return Ok(PoolResize::Grew(clients.len()));
+        }
+
+        // Shrink only when the remaining channels would still sit under half the target
+        let underused = load * 2 < target * (size as u64 - 1);
+        if underused && !slow && size > self.config.pool_min {
+            let mut clients = self.clients.write().unwrap();
+            // In-flight calls keep their own handle to the dropped channel
+            clients.pop();
+            return Ok(PoolResize::Shrank(clients.len()));
+        }
+
+        Ok(PoolResize::Unchanged)
+    }
+
+
+    /// Open the upstream event stream for an execution from its first event,
+    /// keeping only output and status changes
+    async fn stream_execution(
+        &self,
+        id: Uuid,
+    ) -> Result<BoxStream<'static, Result<UpstreamEvent, ApiError>>, ApiError> {
+        let (request, correlation_id) = super::correlated(StreamExecutionRequest {
+            execution_id: id.to_string(),
+            from_start: true,
+            last_sequence: 0,
+        });
+        let (mut client, guard) = self.stream_client();
+        let stream: Streaming<ExecutionEvent> = client
+            .stream_execution(request)
+            .await
+            .map_err(|e| ApiError::upstream(correlation_id.clone(), e))?
+            .into_inner();
+
+        Ok(stream
+            .filter_map(move |event| {
+                // The stream counts as open upstream until it's dropped
+                let _open = &guard;
+                let event = match event {
+                    Ok(event) => upstream_event(event).map(Ok),
+                    Err(e) => Some(Err(ApiError::upstream(correlation_id.clone(), e))),
+                };
+                futures::future::ready(event)
+            })
+            .boxed())
+    }
+
+    /// Run a health-check RPC over every pooled channel
+    async fn probe(&self) -> Result<(), ApiError> {
+        let clients = self.clients.read().unwrap().clone();
+        for mut pooled in clients {
+            let (request, correlation_id) = super::correlated(HealthCheckRequest::default());
+            let response = pooled
+                .client
+                .health_check(request)
+                .await
+                .map_err(|e| ApiError::upstream(correlation_id, e))?
+                .into_inner();
+
+            if HealthStatus::try_from(response.status) == Ok(HealthStatus::Unhealthy) {
+                return Err(ApiError::ServiceUnavailable);
+            }
+        }
+        Ok(())
+    }
+    
+    async fn create_execution(
+        &self,
         user_id: String,
         workspace_id: Option<String>,
         request: CreateExecutionRequest,
     ) -> Result<ExecutionResponse, ApiError> {
+        let correlation_id = ids::generate().to_string();
+        let session_id = request.session_id.clone().unwrap_or_default();
+        let stdin = request
+            .stdin_bytes()
+            .map_err(ApiError::BadRequest)?
+            .unwrap_or_default();
+        let mut metadata = request.metadata;
+        metadata.insert(
+            PRIORITY_KEY.to_string(),
+            request.priority.unwrap_or_default().as_str().to_string(),
+        );
+        if let Some(id) = ids::execution_id() {
+            metadata.insert(EXECUTION_ID_KEY.to_string(), id.to_string());
+        }
         let proto_request = SubmitExecutionRequest {
             context: Some(ExecutionContext {
                 user_id,
                 workspace_id: workspace_id.unwrap_or_default(),
-                request_id: Uuid::new_v4().to_string(),
-                session_id: String::new(),
-                metadata: std::collections::HashMap::new(),
+                request_id: correlation_id.clone(),
+                session_id,
+                // Caller labels, for the execution service's logs, plus the
+                // priority it schedules by and any proposed ID; the gateway
+                // keeps its own copy of the labels for responses and filtering
+                metadata,
             }),
             request: Some(ExecutionRequest {
-                code: request.code,
+                code: request.code.to_string(),
                 language: self.language_to_proto(&request.language) as i32,
+                language_version: request.language_version.unwrap_or_default(),
                 args: request.args.unwrap_or_default(),
-                environment: std::collections::HashMap::new(),
-                resources: None,
+                environment: request.env.unwrap_or_default(),
+                resources: request.resources.map(|r| ResourceRequirements {
+                    memory_mb: r.memory_mb.unwrap_or_default(),
+                    cpu_cores: r.cpus().unwrap_or_default(),
+                    disk_mb: r.disk_mb.unwrap_or_default(),
+                    enable_network: r.enable_network.unwrap_or_default(),
+                    enable_gpu: false,
+                }),
                 timeout: request.timeout_seconds.map(|s| prost_types::Duration {
                     seconds: s as i64,
                     nanos: 0,
                 }),
-                files: vec![],
-                mode: ExecutionMode::Sandbox as i32,
-                metadata: std::collections::HashMap::new(),
+                files: request
+                    .files
+                    .into_iter()
+                    .map(|file| InputFile {
+                        path: file.path,
+                        content: file.content.into_bytes(),
+                    })
+                    .collect(),
+                stdin,
+                mode: match request.mode.unwrap_or(IsolationMode::Sandbox) {
+                    IsolationMode::Sandbox => ExecutionMode::Sandbox,
+                    IsolationMode::Container => ExecutionMode::Container,
+                    IsolationMode::Process => ExecutionMode::Process,
+                } as i32,
+                // The execution service reads terminal allocation from request metadata
+                metadata: request
+                    .tty
+                    .filter(|&tty| tty)
+                    .map(|_| ("tty".to_string(), "true".to_string()))
+                    .into_iter()
+                    .collect(),
             }),
             r#async: true,
         };
         
-        let response = self.client
-            .submit_execution(Request::new(proto_request))
+        let mut request = Request::new(proto_request);
+        if let Ok(value) = correlation_id.parse() {
+            request.metadata_mut().insert(super::CORRELATION_ID_KEY, value);
+        }
+
+        let (mut client, _call) = self.client();
+        let response = client
+            .submit_execution(request)
             .await
-            .map_err(|e| ApiError::Internal(e.into()))?
+            .map_err(|e| ApiError::upstream(correlation_id, e))?
             .into_inner();
         
         // Convert to ExecutionResponse
@@ -73,78 +480,296 @@ impl ExecutionClient {
                 stdout: r.stdout,
                 stderr: r.stderr,
                 duration_ms: 0, // TODO: Calculate from timestamps
+                ansi: false,
+                error: r.error.map(error_from_proto),
+                files_created: r.files.into_iter().map(created_file_from_proto).collect(),
+                stdout_sha256: None,
+                stderr_sha256: None,
             }),
+            pinned: false,
+            output_limit_exceeded: false,
+            tty: false,
+            session_id: None,
+            group_id: None,
+            backend: Backend::Primary,
+            resubmitted_from: None,
+            retried_from: None,
+            cancellation: None,
+            annotations: Default::default(),
+            warnings: Vec::new(),
+            result_delivery: None,
+            trace_id: None,
+            trace_url: None,
+            code_sha256: None,
+            language_version: None,
+            metadata: Default::default(),
+            owner: None,
         })
     }
     
-    pub async fn get_execution(&mut self, id: Uuid) -> Result<ExecutionResponse, ApiError> {
+    async fn get_execution(&self, id: Uuid) -> Result<ExecutionResponse, ApiError> {
         let request = GetExecutionRequest {
             execution_id: id.to_string(),
             include_output: true,
             include_metrics: false,
         };
         
-        let response = self.client
-            .get_execution(Request::new(request))
+        let (request, correlation_id) = super::correlated(request);
+        let (mut client, _call) = self.client();
+        let response = client
+            .get_execution(request)
             .await
-            .map_err(|e| match e.code() {
-                tonic::Code::NotFound => ApiError::NotFound,
-                _ => ApiError::Internal(e.into()),
-            })?
+            .map_err(|e| ApiError::upstream(correlation_id, e))?
             .into_inner();
         
         let execution = response.execution
             .ok_or_else(|| ApiError::Internal(anyhow::anyhow!("Missing execution data")))?;
-        
-        // Convert to ExecutionResponse
-        Ok(ExecutionResponse {
-            id: Uuid::parse_str(&execution.id)
-                .map_err(|e| ApiError::Internal(e.into()))?,
-            status: self.proto_to_status(execution.status),
-            created_at: execution.created_at
-                .map(|t| chrono::DateTime::from_timestamp(t.seconds, t.nanos as u32))
-                .flatten()
-                .unwrap_or_else(chrono::Utc::now),
-            started_at: execution.started_at
-                .map(|t| chrono::DateTime::from_timestamp(t.seconds, t.nanos as u32))
-                .flatten(),
-            completed_at: execution.completed_at
-                .map(|t| chrono::DateTime::from_timestamp(t.seconds, t.nanos as u32))
-                .flatten(),
-            result: execution.result.map(|r| ExecutionResult {
-                exit_code: r.exit_code,
-                stdout: r.stdout,
-                stderr: r.stderr,
-                duration_ms: 0, // TODO: Calculate from timestamps
+        execution_from_proto(execution)
+    }
+
+    /// One page of `user_id`'s executions, newest first as the execution
+    /// service orders them
+    async fn list_executions(
+        &self,
+        user_id: &str,
+        query: &UpstreamListQuery,
+    ) -> Result<ExecutionPage, ApiError> {
+        let language = match &query.language {
+            Some(lang) if !self.recognizes_language(lang) => {
+                return Err(ApiError::BadRequest(format!("Unknown language '{}'", lang)));
+            }
+            Some(lang) => Some(self.language_to_proto(lang) as i32),
+            None => None,
+        };
+        let request = ListExecutionsRequest {
+            user_id: user_id.to_string(),
+            workspace_id: String::new(),
+            status: query
+                .status
+                .as_ref()
+                .map_or(ProtoExecutionStatus::Unspecified, status_to_proto) as i32,
+            created_after: query.created_after.map(timestamp_to_proto),
+            created_before: query.created_before.map(timestamp_to_proto),
+            page: Some(PageRequest {
+                page_size: query.page_size,
+                page_token: query.page_token.clone().unwrap_or_default(),
             }),
-        })
+        };
+
+        let (request, correlation_id) = super::correlated(request);
+        let (mut client, _call) = self.client();
+        let response = client
+            .list_executions(request)
+            .await
+            .map_err(|e| ApiError::upstream(correlation_id, e))?
+            .into_inner();
+
+        // The list RPC has no language filter, so that is applied here and a
+        // page may come back short
+        let executions = response
+            .executions
+            .into_iter()
+            .filter(|execution| {
+                language.is_none_or(|language| execution.request.as_ref().is_some_and(|r| r.language == language))
+            })
+            .map(execution_from_proto)
+            .collect::<Result<_, _>>()?;
+        let next_page_token = response
+            .page
+            .map(|page| page.next_page_token)
+            .filter(|token| !token.is_empty());
+        Ok(ExecutionPage { executions, next_page_token })
     }
     
-    fn language_to_proto(&self, lang: &str) -> Language {
-        match lang.to_lowercase().as_str() {
-            "python" => Language::Python,
-            "javascript" => Language::Javascript,
-            "typescript" => Language::Typescript,
-            "rust" => Language::Rust,
-            "go" => Language::Go,
-            "java" => Language::Java,
-            "cpp" | "c++" => Language::Cpp,
-            "csharp" | "c#" => Language::Csharp,
-            "ruby" => Language::Ruby,
-            "php" => Language::Php,
-            "shell" | "bash" | "sh" => Language::Shell,
-            _ => Language::Unspecified,
+    /// Whether `lang` maps to a language the execution service knows; others
+    /// are sent unspecified and left to the service's default
+    fn recognizes_language(&self, lang: &str) -> bool {
+        self.language_to_proto(lang) != Language::Unspecified
+    }
+
+    /// Languages the execution service supports, as last discovered
+    fn languages(&self) -> Arc<LanguageCatalog> {
+        self.languages.read().unwrap().clone()
+    }
+
+    /// Replace the built-in language list with the one the execution service
+    /// reports; services without the RPC keep the built-in list
+    async fn discover_languages(&self) -> Result<CatalogSource, ApiError> {
+        let (request, correlation_id) = super::correlated(ListLanguagesRequest::default());
+        let (mut client, _call) = self.client();
+        let response = match client.list_languages(request).await {
+            Ok(response) => response.into_inner(),
+            Err(status) if status.code() == tonic::Code::Unimplemented => return Ok(CatalogSource::Builtin),
+            Err(e) => return Err(ApiError::upstream(correlation_id, e)),
+        };
+        let catalog = LanguageCatalog::from_runtimes(response.languages);
+        if catalog.languages.is_empty() {
+            return Ok(CatalogSource::Builtin);
         }
+        *self.languages.write().unwrap() = Arc::new(catalog);
+        Ok(CatalogSource::Backend)
     }
-    
-    fn proto_to_status(&self, status: i32) -> ExecutionStatus {
-        match ProtoExecutionStatus::try_from(status).unwrap_or(ProtoExecutionStatus::Unspecified) {
-            ProtoExecutionStatus::Pending | ProtoExecutionStatus::Queued | ProtoExecutionStatus::Preparing => ExecutionStatus::Pending,
-            ProtoExecutionStatus::Running => ExecutionStatus::Running,
-            ProtoExecutionStatus::Completed => ExecutionStatus::Completed,
-            ProtoExecutionStatus::Failed | ProtoExecutionStatus::Cancelled => ExecutionStatus::Failed,
-            ProtoExecutionStatus::Timeout => ExecutionStatus::Timeout,
-            _ => ExecutionStatus::Pending,
+
+    /// Ask the execution service to stop an execution at once
+    async fn cancel_execution(&self, id: Uuid, reason: &str) -> Result<ExecutionStatus, ApiError> {
+        let (request, correlation_id) = super::correlated(CancelExecutionRequest {
+            execution_id: id.to_string(),
+            force: true,
+            reason: reason.to_string(),
+        });
+        let (mut client, _call) = self.client();
+        let response = client
+            .cancel_execution(request)
+            .await
+            .map_err(|e| ApiError::upstream(correlation_id, e))?
+            .into_inner();
+        Ok(proto_to_status(response.final_status))
+    }
+
+    /// Ask the execution service to delete everything it stores for a
+    /// finished execution; `false` when it can't delete executions
+    async fn delete_execution(&self, id: Uuid, reason: &str) -> Result<bool, ApiError> {
+        let (request, correlation_id) = super::correlated(DeleteExecutionRequest {
+            execution_id: id.to_string(),
+            reason: reason.to_string(),
+        });
+        let (mut client, _call) = self.client();
+        match client.delete_execution(request).await {
+            Ok(response) => Ok(response.into_inner().deleted),
+            // Nothing left upstream to delete
+            Err(status) if status.code() == tonic::Code::NotFound => Ok(true),
+            Err(status) if status.code() == tonic::Code::Unimplemented => Ok(false),
+            Err(e) => Err(ApiError::upstream(correlation_id, e)),
         }
     }
-}
\ No newline at end of file
+
+    /// Write `data` to a running execution's stdin, closing it afterwards if `close`
+    async fn write_stdin(&self, id: Uuid, data: Vec<u8>, close: bool) -> Result<(), ApiError> {
+        let (request, correlation_id) = super::correlated(WriteStdinRequest {
+            execution_id: id.to_string(),
+            data,
+            close,
+        });
+        let (mut client, _call) = self.client();
+        let response = client
+            .write_stdin(request)
+            .await
+            .map_err(|e| ApiError::upstream(correlation_id.clone(), e))?
+            .into_inner();
+        if !response.accepted {
+            return Err(ApiError::Upstream {
+                correlation_id,
+                code: tonic::Code::FailedPrecondition,
+                message: format!("Execution {} is not accepting input", id),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn proto_to_status(status: i32) -> ExecutionStatus {
+    match ProtoExecutionStatus::try_from(status).unwrap_or(ProtoExecutionStatus::Unspecified) {
+        ProtoExecutionStatus::Pending => ExecutionStatus::Pending,
+        ProtoExecutionStatus::Queued => ExecutionStatus::Queued,
+        ProtoExecutionStatus::Preparing => ExecutionStatus::Preparing,
+        ProtoExecutionStatus::Running => ExecutionStatus::Running,
+        ProtoExecutionStatus::Completed => ExecutionStatus::Completed,
+        ProtoExecutionStatus::Failed => ExecutionStatus::Failed,
+        ProtoExecutionStatus::Cancelled => ExecutionStatus::Cancelled,
+        ProtoExecutionStatus::Timeout => ExecutionStatus::Timeout,
+        _ => ExecutionStatus::Pending,
+    }
+}
+
+/// The upstream status a gateway status is stored as; cancelling is only
+/// tracked by the gateway, so upstream still reports those as running
+fn status_to_proto(status: &ExecutionStatus) -> ProtoExecutionStatus {
+    match status {
+        ExecutionStatus::Pending => ProtoExecutionStatus::Pending,
+        ExecutionStatus::Queued => ProtoExecutionStatus::Queued,
+        ExecutionStatus::Preparing => ProtoExecutionStatus::Preparing,
+        ExecutionStatus::Running | ExecutionStatus::Cancelling => ProtoExecutionStatus::Running,
+        ExecutionStatus::Completed => ProtoExecutionStatus::Completed,
+        ExecutionStatus::Failed => ProtoExecutionStatus::Failed,
+        ExecutionStatus::Cancelled => ProtoExecutionStatus::Cancelled,
+        ExecutionStatus::Timeout => ProtoExecutionStatus::Timeout,
+    }
+}
+
+/// Type an executor diagnostic, taking its position from the details when present
+fn error_from_proto(error: ProtoExecutionError) -> ExecutionError {
+    let (line, column) = ExecutionError::position(&error.details);
+    ExecutionError {
+        kind: ExecutionErrorKind::from_code(&error.code),
+        code: error.code,
+        message: error.message,
+        line,
+        column,
+        details: Some(error.details).filter(|details| !details.is_empty()),
+        stack_trace: Some(error.stack_trace).filter(|trace| !trace.is_empty()),
+    }
+}
+
+/// Created files are stored by the execution service; any inline content
+/// only counts towards the size
+fn created_file_from_proto(file: OutputFile) -> CreatedFile {
+    CreatedFile {
+        size_bytes: if file.size_bytes > 0 { file.size_bytes } else { file.content.len() as u64 },
+        path: file.path,
+        mime_type: Some(file.mime_type).filter(|mime_type| !mime_type.is_empty()),
+    }
+}
+
+fn timestamp_to_proto(time: chrono::DateTime<chrono::Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: time.timestamp(),
+        nanos: time.timestamp_subsec_nanos() as i32,
+    }
+}
+
+fn execution_from_proto(execution: Execution) -> Result<ExecutionResponse, ApiError> {
+    Ok(ExecutionResponse {
+        id: Uuid::parse_str(&execution.id)
+            .map_err(|e| ApiError::Internal(e.into()))?,
+        status: proto_to_status(execution.status),
+        created_at: execution.created_at
+            .and_then(|t| chrono::DateTime::from_timestamp(t.seconds, t.nanos as u32))
+            .unwrap_or_else(chrono::Utc::now),
+        started_at: execution.started_at
+            .and_then(|t| chrono::DateTime::from_timestamp(t.seconds, t.nanos as u32)),
+        completed_at: execution.completed_at
+            .and_then(|t| chrono::DateTime::from_timestamp(t.seconds, t.nanos as u32)),
+        result: execution.result.map(|r| ExecutionResult {
+            exit_code: r.exit_code,
+            stdout: r.stdout,
+            stderr: r.stderr,
+            duration_ms: 0, // TODO: Calculate from timestamps
+            ansi: false,
+            error: r.error.map(error_from_proto),
+            files_created: r.files.into_iter().map(created_file_from_proto).collect(),
+            stdout_sha256: None,
+            stderr_sha256: None,
+        }),
+        pinned: false,
+        output_limit_exceeded: false,
+        tty: false,
+        session_id: None,
+        group_id: None,
+        backend: Backend::Primary,
+        resubmitted_from: None,
+        retried_from: None,
+        cancellation: None,
+        annotations: Default::default(),
+        warnings: Vec::new(),
+        result_delivery: None,
+        trace_id: None,
+        trace_url: None,
+        code_sha256: None,
+        language_version: execution
+            .request
+            .map(|request| request.language_version)
+            .filter(|version| !version.is_empty()),
+        metadata: Default::default(),
+        owner: Some(execution.user_id).filter(|user_id| !user_id.is_empty()),
+    })
+}
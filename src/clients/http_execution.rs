@@ -0,0 +1,418 @@
+//! Execution services speaking plain HTTP/JSON rather than the gRPC proto,
+//! for third-party executors. Paths are relative to the configured URL:
+//!
+//! - `POST /executions` submits an execution, answering with it
+//! - `GET /executions/{id}` reads one, `GET /executions?user_id=…` lists a user's
+//! - `POST /executions/{id}/cancel` stops one, answering `{"status": …}`
+//! - `DELETE /executions/{id}` deletes a finished one
+//! - `POST /executions/{id}/stdin` writes `{"data_base64": …, "close": …}` to its stdin
+//! - `GET /health` answers 2xx while the service can take work
+//!
+//! Executions are objects with `id`, `status` and the `created_at`,
+//! `started_at` and `completed_at` timestamps, the submitting `user_id`, plus
+//! a `result` once finished.
+//! Statuses and errors are mapped from the HTTP status code; 405 and 501 mean
+//! the executor doesn't support the call. Executors can't stream, so output
+//! is polled for.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use futures::stream::{BoxStream, StreamExt};
+use reqwest::{RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use uuid::Uuid;
+
+use super::execution::{ExecutionBackend, ExecutionPage, UpstreamEvent, UpstreamListQuery};
+use super::CORRELATION_ID_KEY;
+use crate::config::UpstreamConfig;
+use crate::error::ApiError;
+use crate::execution::{
+    CreateExecutionRequest, CreatedFile, ExecutionError, ExecutionErrorKind, ExecutionFile, ExecutionResponse,
+    ExecutionResult, ExecutionStatus, IsolationMode, Priority, ResourceLimits,
+};
+use crate::ids;
+use crate::languages::{CatalogSource, LanguageCatalog};
+use crate::output::OutputStream;
+use crate::server_timing::{self, Phase};
+use crate::trace::{TraceContext, TRACEPARENT_HEADER};
+
+/// Events buffered between the poller and a slow reader
+const POLL_CHANNEL_DEPTH: usize = 16;
+
+/// Execution service speaking HTTP/JSON
+#[derive(Clone)]
+pub struct HttpExecutionBackend {
+    http: reqwest::Client,
+    base_url: String,
+    poll_interval: Duration,
+    languages: Arc<LanguageCatalog>,
+}
+
+/// Submission body; the language is passed by name
+#[derive(Serialize)]
+struct SubmitBody {
+    /// Proposed execution ID; the one answered with is used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<Uuid>,
+    user_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    workspace_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session_id: Option<String>,
+    priority: Priority,
+    metadata: HashMap<String, String>,
+    code: Arc<str>,
+    language: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language_version: Option<String>,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resources: Option<ResourceLimits>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout_seconds: Option<u64>,
+    files: Vec<ExecutionFile>,
+    mode: IsolationMode,
+    tty: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stdin_base64: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WireExecution {
+    id: Uuid,
+    status: ExecutionStatus,
+    created_at: Option<DateTime<Utc>>,
+    started_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+    result: Option<WireResult>,
+    language_version: Option<String>,
+    /// Submitting user
+    user_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WireResult {
+    exit_code: i32,
+    #[serde(default)]
+    stdout: String,
+    #[serde(default)]
+    stderr: String,
+    #[serde(default)]
+    duration_ms: u64,
+    error: Option<WireError>,
+    #[serde(default)]
+    files_created: Vec<CreatedFile>,
+}
+
+#[derive(Deserialize)]
+struct WireError {
+    code: String,
+    message: String,
+    #[serde(default)]
+    details: String,
+    #[serde(default)]
+    stack_trace: String,
+}
+
+#[derive(Deserialize)]
+struct WirePage {
+    executions: Vec<WireExecution>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WireCancelled {
+    status: ExecutionStatus,
+}
+
+impl HttpExecutionBackend {
+    pub fn new(config: &UpstreamConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(5))
+            .timeout(Duration::from_secs(30))
+            .build()?;
+        Ok(Self {
+            http,
+            base_url: config.execution_service_url.trim_end_matches('/').to_string(),
+            poll_interval: config.http_poll_interval,
+            languages: Arc::new(LanguageCatalog::builtin()),
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Send `request` with a fresh correlation ID and the current trace,
+    /// turning failures and non-2xx answers into upstream errors
+    async fn send(&self, request: RequestBuilder) -> Result<reqwest::Response, ApiError> {
+        let correlation_id = ids::generate().to_string();
+        let mut request = request.header(CORRELATION_ID_KEY, &correlation_id);
+        if let Some(trace) = TraceContext::current() {
+            request = request.header(TRACEPARENT_HEADER, trace.child_traceparent());
+        }
+        let response = server_timing::time(Phase::Upstream, request.send())
+            .await
+            .map_err(|e| {
+                let code = if e.is_timeout() {
+                    tonic::Code::DeadlineExceeded
+                } else {
+                    tonic::Code::Unavailable
+                };
+                ApiError::upstream(correlation_id.clone(), tonic::Status::new(code, e.to_string()))
+            })?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let message = response.text().await.unwrap_or_default();
+        Err(ApiError::upstream(correlation_id, tonic::Status::new(code_for(status), message)))
+    }
+
+    async fn fetch(&self, id: Uuid) -> Result<ExecutionResponse, ApiError> {
+        let response = self.send(self.http.get(self.url(&format!("/executions/{}", id)))).await?;
+        Ok(decode::<WireExecution>(response).await?.into())
+    }
+}
+
+#[async_trait]
+impl ExecutionBackend for HttpExecutionBackend {
+    /// Poll the execution, reporting status changes and output as it grows;
+    /// executors that only return output once finished deliver it then
+    async fn stream_execution(
+        &self,
+        id: Uuid,
+    ) -> Result<BoxStream<'static, Result<UpstreamEvent, ApiError>>, ApiError> {
+        // Unknown executions fail here, as opening a stream would
+        let mut execution = self.fetch(id).await?;
+        let backend = self.clone();
+        let (tx, rx) = mpsc::channel(POLL_CHANNEL_DEPTH);
+        tokio::spawn(async move {
+            let mut reported = None;
+            let (mut stdout_sent, mut stderr_sent) = (0, 0);
+            loop {
+                let mut events = Vec::new();
+                if let Some(result) = &execution.result {
+                    let stdout = unsent(&result.stdout, &mut stdout_sent);
+                    let stderr = unsent(&result.stderr, &mut stderr_sent);
+                    events.extend(stdout.map(|data| UpstreamEvent::Output(OutputStream::Stdout, data)));
+                    events.extend(stderr.map(|data| UpstreamEvent::Output(OutputStream::Stderr, data)));
+                }
+                // Output comes before the final status, as on a stream
+                if reported.as_ref() != Some(&execution.status) {
+                    reported = Some(execution.status.clone());
+                    events.push(UpstreamEvent::Status(execution.status.clone()));
+                }
+                for event in events {
+                    if tx.send(Ok(event)).await.is_err() {
+                        return;
+                    }
+                }
+                if execution.status.is_terminal() {
+                    return;
+                }
+
+                tokio::time::sleep(backend.poll_interval).await;
+                execution = match backend.fetch(id).await {
+                    Ok(execution) => execution,
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                };
+            }
+        });
+        Ok(ReceiverStream::new(rx).boxed())
+    }
+
+    async fn probe(&self) -> Result<(), ApiError> {
+        self.send(self.http.get(self.url("/health"))).await.map(|_| ())
+    }
+
+    async fn create_execution(
+        &self,
+        user_id: String,
+        workspace_id: Option<String>,
+        request: CreateExecutionRequest,
+    ) -> Result<ExecutionResponse, ApiError> {
+        let stdin_base64 = request
+            .stdin_bytes()
+            .map_err(ApiError::BadRequest)?
+            .map(|stdin| base64::engine::general_purpose::STANDARD.encode(stdin));
+        let body = SubmitBody {
+            id: ids::execution_id(),
+            user_id,
+            workspace_id,
+            session_id: request.session_id,
+            priority: request.priority.unwrap_or_default(),
+            metadata: request.metadata,
+            code: request.code,
+            language: request.language,
+            language_version: request.language_version,
+            args: request.args.unwrap_or_default(),
+            env: request.env.unwrap_or_default(),
+            // CPU as fractional cores, however it was requested
+            resources: request.resources.map(|r| ResourceLimits {
+                cpu_cores: r.cpus(),
+                cpu_millis: None,
+                ..r
+            }),
+            timeout_seconds: request.timeout_seconds,
+            files: request.files,
+            mode: request.mode.unwrap_or(IsolationMode::Sandbox),
+            tty: request.tty.unwrap_or_default(),
+            stdin_base64,
+        };
+        let response = self.send(self.http.post(self.url("/executions")).json(&body)).await?;
+        Ok(decode::<WireExecution>(response).await?.into())
+    }
+
+    async fn get_execution(&self, id: Uuid) -> Result<ExecutionResponse, ApiError> {
+        self.fetch(id).await
+    }
+
+    async fn list_executions(&self, user_id: &str, query: &UpstreamListQuery) -> Result<ExecutionPage, ApiError> {
+        let mut params = vec![
+            ("user_id", user_id.to_string()),
+            ("page_size", query.page_size.to_string()),
+        ];
+        params.extend(query.page_token.clone().map(|token| ("page_token", token)));
+        params.extend(query.status.as_ref().map(|status| ("status", status.as_str().to_string())));
+        params.extend(query.language.clone().map(|language| ("language", language)));
+        params.extend(query.created_after.map(|time| ("created_after", time.to_rfc3339())));
+        params.extend(query.created_before.map(|time| ("created_before", time.to_rfc3339())));
+
+        let response = self.send(self.http.get(self.url("/executions")).query(&params)).await?;
+        let page: WirePage = decode(response).await?;
+        Ok(ExecutionPage {
+            executions: page.executions.into_iter().map(Into::into).collect(),
+            next_page_token: page.next_page_token.filter(|token| !token.is_empty()),
+        })
+    }
+
+    /// Languages are passed by name, so any the built-in list knows is sent
+    fn recognizes_language(&self, lang: &str) -> bool {
+        self.languages.resolve(lang).is_some()
+    }
+
+    fn languages(&self) -> Arc<LanguageCatalog> {
+        self.languages.clone()
+    }
+
+    /// The HTTP API has no language listing; the built-in list is kept
+    async fn discover_languages(&self) -> Result<CatalogSource, ApiError> {
+        Ok(CatalogSource::Builtin)
+    }
+
+    async fn cancel_execution(&self, id: Uuid, reason: &str) -> Result<ExecutionStatus, ApiError> {
+        let response = self
+            .send(
+                self.http
+                    .post(self.url(&format!("/executions/{}/cancel", id)))
+                    .json(&serde_json::json!({ "reason": reason })),
+            )
+            .await?;
+        Ok(decode::<WireCancelled>(response).await?.status)
+    }
+
+    async fn delete_execution(&self, id: Uuid, reason: &str) -> Result<bool, ApiError> {
+        let request = self
+            .http
+            .delete(self.url(&format!("/executions/{}", id)))
+            .query(&[("reason", reason)]);
+        match self.send(request).await {
+            Ok(_) => Ok(true),
+            // Nothing left upstream to delete
+            Err(ApiError::NotFound) => Ok(true),
+            Err(ApiError::Upstream { code: tonic::Code::Unimplemented, .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// A 409 means the execution isn't accepting input
+    async fn write_stdin(&self, id: Uuid, data: Vec<u8>, close: bool) -> Result<(), ApiError> {
+        let body = serde_json::json!({
+            "data_base64": base64::engine::general_purpose::STANDARD.encode(data),
+            "close": close,
+        });
+        self.send(self.http.post(self.url(&format!("/executions/{}/stdin", id))).json(&body))
+            .await
+            .map(|_| ())
+    }
+}
+
+impl From<WireExecution> for ExecutionResponse {
+    fn from(execution: WireExecution) -> Self {
+        let mut response = ExecutionResponse::new_pending();
+        response.id = execution.id;
+        response.status = execution.status;
+        response.created_at = execution.created_at.unwrap_or_else(Utc::now);
+        response.started_at = execution.started_at;
+        response.completed_at = execution.completed_at;
+        response.result = execution.result.map(|result| ExecutionResult {
+            exit_code: result.exit_code,
+            stdout: result.stdout,
+            stderr: result.stderr,
+            duration_ms: result.duration_ms,
+            ansi: false,
+            error: result.error.map(|error| {
+                let (line, column) = ExecutionError::position(&error.details);
+                ExecutionError {
+                    kind: ExecutionErrorKind::from_code(&error.code),
+                    code: error.code,
+                    message: error.message,
+                    line,
+                    column,
+                    details: Some(error.details).filter(|details| !details.is_empty()),
+                    stack_trace: Some(error.stack_trace).filter(|trace| !trace.is_empty()),
+                }
+            }),
+            files_created: result.files_created,
+            stdout_sha256: None,
+            stderr_sha256: None,
+        });
+        response.language_version = execution.language_version.filter(|version| !version.is_empty());
+        response.owner = execution.user_id.filter(|user_id| !user_id.is_empty());
+        response
+    }
+}
+
+/// The gRPC code an executor's HTTP status stands for
+fn code_for(status: StatusCode) -> tonic::Code {
+    match status {
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => tonic::Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED => tonic::Code::Unauthenticated,
+        StatusCode::FORBIDDEN => tonic::Code::PermissionDenied,
+        StatusCode::NOT_FOUND => tonic::Code::NotFound,
+        StatusCode::CONFLICT => tonic::Code::FailedPrecondition,
+        StatusCode::TOO_MANY_REQUESTS => tonic::Code::ResourceExhausted,
+        StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED => tonic::Code::Unimplemented,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => tonic::Code::Unavailable,
+        StatusCode::GATEWAY_TIMEOUT => tonic::Code::DeadlineExceeded,
+        _ => tonic::Code::Internal,
+    }
+}
+
+async fn decode<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, ApiError> {
+    response
+        .json()
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Malformed execution service response: {}", e)))
+}
+
+/// Output past the `sent` bytes already delivered, advancing `sent`
+fn unsent(output: &str, sent: &mut usize) -> Option<String> {
+    let data = output.get(*sent..).filter(|data| !data.is_empty())?.to_string();
+    *sent = output.len();
+    Some(data)
+}
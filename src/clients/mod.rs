@@ -1,14 +1,161 @@
+mod connector;
 pub mod execution;
+pub mod http_execution;
+pub mod workspace;
 
+use crate::config::UpstreamConfig;
+use crate::server_timing::{self, Phase};
+use crate::trace::{TraceContext, TRACEPARENT_HEADER};
+use connector::HappyEyeballsConnector;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tonic::transport::{Channel, Endpoint};
 use anyhow::Result;
 
-// Create a shared channel for a service
-pub async fn create_channel(url: &str) -> Result<Channel> {
-    let endpoint = Endpoint::from_shared(url.to_string())?
+/// Metadata key carrying the ID the gateway assigned to an upstream call
+pub const CORRELATION_ID_KEY: &str = "x-correlation-id";
+
+/// Wrap an upstream request with a fresh correlation ID, returning both
+pub fn correlated<T>(message: T) -> (tonic::Request<T>, String) {
+    let correlation_id = crate::ids::generate().to_string();
+    let mut request = tonic::Request::new(message);
+    if let Ok(value) = correlation_id.parse() {
+        request.metadata_mut().insert(CORRELATION_ID_KEY, value);
+    }
+    if let Some(value) = TraceContext::current().and_then(|trace| trace.child_traceparent().parse().ok()) {
+        request.metadata_mut().insert(TRACEPARENT_HEADER, value);
+    }
+    (request, correlation_id)
+}
+
+fn endpoint(url: &str, config: &UpstreamConfig) -> Result<Endpoint> {
+    Ok(Endpoint::from_shared(url.to_string())?
         .connect_timeout(std::time::Duration::from_secs(5))
-        .timeout(std::time::Duration::from_secs(30));
-    
-    let channel = endpoint.connect().await?;
+        .timeout(std::time::Duration::from_secs(30))
+        .initial_stream_window_size(config.stream_window_bytes)
+        .initial_connection_window_size(config.connection_window_bytes))
+}
+
+// Create a shared channel for a service
+pub async fn create_channel(url: &str, config: &UpstreamConfig) -> Result<Channel> {
+    let connector = HappyEyeballsConnector::new(config.connect_attempt_delay);
+    let channel = endpoint(url, config)?.connect_with_connector(connector).await?;
     Ok(channel)
-}
\ No newline at end of file
+}
+
+/// A channel that connects on first use, for services the gateway can start without
+pub fn lazy_channel(url: &str, config: &UpstreamConfig) -> Result<Channel> {
+    let connector = HappyEyeballsConnector::new(config.connect_attempt_delay);
+    Ok(endpoint(url, config)?.connect_with_connector_lazy(connector))
+}
+
+/// Call accounting for one pooled channel
+#[derive(Default)]
+pub struct ChannelCounters {
+    active_calls: AtomicU64,
+    active_streams: AtomicU64,
+    total_calls: AtomicU64,
+    // Latency of calls completed since the last `take_latency`
+    window_latency_micros: AtomicU64,
+    window_completed: AtomicU64,
+}
+
+impl ChannelCounters {
+    /// In-flight calls and streams right now
+    pub fn load(&self) -> u64 {
+        self.active_calls.load(Ordering::Relaxed) + self.active_streams.load(Ordering::Relaxed)
+    }
+
+    /// Drain the latency window, returning (total latency, completed calls)
+    pub fn take_latency(&self) -> (Duration, u64) {
+        let micros = self.window_latency_micros.swap(0, Ordering::Relaxed);
+        let completed = self.window_completed.swap(0, Ordering::Relaxed);
+        (Duration::from_micros(micros), completed)
+    }
+
+    /// Count a unary call; hold the guard until the response arrives
+    pub fn begin_call(self: &Arc<Self>) -> CallGuard {
+        self.active_calls.fetch_add(1, Ordering::Relaxed);
+        self.total_calls.fetch_add(1, Ordering::Relaxed);
+        CallGuard {
+            counters: self.clone(),
+            stream: false,
+            started: Instant::now(),
+        }
+    }
+
+    /// Count a streaming call; hold the guard for the lifetime of the stream
+    pub fn begin_stream(self: &Arc<Self>) -> CallGuard {
+        self.active_streams.fetch_add(1, Ordering::Relaxed);
+        self.total_calls.fetch_add(1, Ordering::Relaxed);
+        CallGuard {
+            counters: self.clone(),
+            stream: true,
+            started: Instant::now(),
+        }
+    }
+}
+
+/// Releases an active call or stream on its channel when dropped
+pub struct CallGuard {
+    counters: Arc<ChannelCounters>,
+    stream: bool,
+    started: Instant,
+}
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        let counter = if self.stream {
+            &self.counters.active_streams
+        } else {
+            &self.counters.active_calls
+        };
+        counter.fetch_sub(1, Ordering::Relaxed);
+
+        // Streams stay open for as long as the client reads, so only unary calls
+        // say anything about upstream latency
+        if !self.stream {
+            let elapsed = self.started.elapsed();
+            server_timing::record(Phase::Upstream, elapsed);
+            let micros = elapsed.as_micros() as u64;
+            self.counters.window_latency_micros.fetch_add(micros, Ordering::Relaxed);
+            self.counters.window_completed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Point-in-time usage of one pooled upstream channel.
+///
+/// Live HTTP/2 flow-control windows aren't exposed by the transport, so the
+/// configured initial window sizes are reported alongside the stream counts.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelStats {
+    pub service: &'static str,
+    pub channel: usize,
+    pub active_calls: u64,
+    pub active_streams: u64,
+    pub total_calls: u64,
+    pub stream_window_bytes: Option<u32>,
+    pub connection_window_bytes: Option<u32>,
+}
+
+impl ChannelStats {
+    pub fn new(
+        service: &'static str,
+        channel: usize,
+        counters: &ChannelCounters,
+        config: &UpstreamConfig,
+    ) -> Self {
+        Self {
+            service,
+            channel,
+            active_calls: counters.active_calls.load(Ordering::Relaxed),
+            active_streams: counters.active_streams.load(Ordering::Relaxed),
+            total_calls: counters.total_calls.load(Ordering::Relaxed),
+            stream_window_bytes: config.stream_window_bytes,
+            connection_window_bytes: config.connection_window_bytes,
+        }
+    }
+}
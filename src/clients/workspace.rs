@@ -0,0 +1,239 @@
+use anyhow::Result;
+use tonic::transport::Channel;
+
+use crate::auth::{AuthContext, TENANT_ID_KEY, USER_ID_KEY};
+use crate::config::{UpstreamConfig, WorkspaceServiceConfig};
+use crate::error::ApiError;
+use crate::proto::{
+    self, syla_gateway_client::SylaGatewayClient, DeleteWorkspaceRequest, GetWorkspaceRequest,
+    ListWorkspacesRequest, WorkspaceStatus as ProtoWorkspaceStatus, WorkspaceType as ProtoWorkspaceType,
+};
+use crate::workspace::{
+    CreateWorkspaceRequest, UpdateWorkspaceRequest, Workspace, WorkspacePage, WorkspaceStatus,
+    WorkspaceType,
+};
+
+/// Client for the workspace service, which serves the workspace RPCs of the
+/// public gateway API. The caller's identity travels in request metadata
+#[derive(Clone)]
+pub struct WorkspaceClient {
+    client: SylaGatewayClient<Channel>,
+}
+
+impl WorkspaceClient {
+    /// Set up a client; the channel connects on first use, so the gateway
+    /// starts even while the workspace service is down
+    pub fn new(config: &WorkspaceServiceConfig, upstream: &UpstreamConfig) -> Result<Self> {
+        let channel = super::lazy_channel(&config.url, upstream)?;
+        Ok(Self {
+            client: SylaGatewayClient::new(channel),
+        })
+    }
+
+    /// Wrap `message` with a correlation ID and the caller's identity
+    fn request<T>(&self, auth_context: &AuthContext, message: T) -> (tonic::Request<T>, String) {
+        let (mut request, correlation_id) = super::correlated(message);
+        let metadata = request.metadata_mut();
+        if let Ok(value) = auth_context.user_id.parse() {
+            metadata.insert(USER_ID_KEY, value);
+        }
+        if let Some(value) = auth_context.tenant_id.as_deref().and_then(|t| t.parse().ok()) {
+            metadata.insert(TENANT_ID_KEY, value);
+        }
+        (request, correlation_id)
+    }
+
+    pub async fn create_workspace(
+        &self,
+        auth_context: &AuthContext,
+        request: CreateWorkspaceRequest,
+    ) -> Result<Workspace, ApiError> {
+        let (request, correlation_id) = self.request(
+            auth_context,
+            proto::CreateWorkspaceRequest {
+                name: request.name,
+                description: request.description,
+                r#type: type_to_proto(request.kind) as i32,
+                config: Some(proto::WorkspaceConfig {
+                    allowed_packages: request.allowed_packages,
+                    environment: request.environment,
+                    ..Default::default()
+                }),
+                ttl: request.ttl_seconds.map(|s| prost_types::Duration {
+                    seconds: s as i64,
+                    nanos: 0,
+                }),
+                metadata: request.metadata,
+            },
+        );
+        let response = self
+            .client
+            .clone()
+            .create_workspace(request)
+            .await
+            .map_err(|e| ApiError::upstream(correlation_id, e))?
+            .into_inner();
+        workspace_from_proto(response.workspace)
+    }
+
+    pub async fn get_workspace(&self, auth_context: &AuthContext, id: &str) -> Result<Workspace, ApiError> {
+        let (request, correlation_id) = self.request(auth_context, GetWorkspaceRequest { id: id.to_string() });
+        let response = self
+            .client
+            .clone()
+            .get_workspace(request)
+            .await
+            .map_err(|e| ApiError::upstream(correlation_id, e))?
+            .into_inner();
+        workspace_from_proto(response.workspace)
+    }
+
+    pub async fn list_workspaces(
+        &self,
+        auth_context: &AuthContext,
+        kind: Option<WorkspaceType>,
+        status: Option<WorkspaceStatus>,
+        page_size: u32,
+        page_token: Option<String>,
+    ) -> Result<WorkspacePage, ApiError> {
+        let (request, correlation_id) = self.request(
+            auth_context,
+            ListWorkspacesRequest {
+                r#type: kind.map_or(ProtoWorkspaceType::Unspecified, type_to_proto) as i32,
+                status: status.map_or(ProtoWorkspaceStatus::Unspecified, status_to_proto) as i32,
+                page_size,
+                page_token: page_token.unwrap_or_default(),
+            },
+        );
+        let response = self
+            .client
+            .clone()
+            .list_workspaces(request)
+            .await
+            .map_err(|e| ApiError::upstream(correlation_id, e))?
+            .into_inner();
+        Ok(WorkspacePage {
+            workspaces: response
+                .workspaces
+                .into_iter()
+                .map(|workspace| workspace_from_proto(Some(workspace)))
+                .collect::<Result<_, _>>()?,
+            next_page_token: Some(response.next_page_token).filter(|token| !token.is_empty()),
+        })
+    }
+
+    /// Apply `update` on top of `current`, sending every field so ones left
+    /// unset keep their values
+    pub async fn update_workspace(
+        &self,
+        auth_context: &AuthContext,
+        current: Workspace,
+        update: UpdateWorkspaceRequest,
+    ) -> Result<Workspace, ApiError> {
+        let mut config = current.config;
+        if let Some(environment) = update.environment {
+            config.environment = environment;
+        }
+        if let Some(allowed_packages) = update.allowed_packages {
+            config.allowed_packages = allowed_packages;
+        }
+        let (request, correlation_id) = self.request(
+            auth_context,
+            proto::UpdateWorkspaceRequest {
+                id: current.id,
+                name: update.name.unwrap_or(current.name),
+                description: update.description.unwrap_or(current.description),
+                config: Some(config),
+                metadata: update.metadata.unwrap_or(current.metadata),
+            },
+        );
+        let response = self
+            .client
+            .clone()
+            .update_workspace(request)
+            .await
+            .map_err(|e| ApiError::upstream(correlation_id, e))?
+            .into_inner();
+        workspace_from_proto(response.workspace)
+    }
+
+    pub async fn delete_workspace(&self, auth_context: &AuthContext, id: &str, force: bool) -> Result<(), ApiError> {
+        let (request, correlation_id) = self.request(
+            auth_context,
+            DeleteWorkspaceRequest {
+                id: id.to_string(),
+                force,
+            },
+        );
+        let response = self
+            .client
+            .clone()
+            .delete_workspace(request)
+            .await
+            .map_err(|e| ApiError::upstream(correlation_id.clone(), e))?
+            .into_inner();
+        if !response.success {
+            return Err(ApiError::Upstream {
+                correlation_id,
+                code: tonic::Code::FailedPrecondition,
+                message: format!("Workspace service declined to delete workspace {}", id),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn type_to_proto(kind: WorkspaceType) -> ProtoWorkspaceType {
+    match kind {
+        WorkspaceType::Ephemeral => ProtoWorkspaceType::Ephemeral,
+        WorkspaceType::Session => ProtoWorkspaceType::Session,
+        WorkspaceType::Persistent => ProtoWorkspaceType::Persistent,
+        WorkspaceType::Collaborative => ProtoWorkspaceType::Collaborative,
+    }
+}
+
+fn status_to_proto(status: WorkspaceStatus) -> ProtoWorkspaceStatus {
+    match status {
+        WorkspaceStatus::Pending => ProtoWorkspaceStatus::Pending,
+        WorkspaceStatus::Active => ProtoWorkspaceStatus::Active,
+        WorkspaceStatus::Suspended => ProtoWorkspaceStatus::Suspended,
+        WorkspaceStatus::Terminated => ProtoWorkspaceStatus::Terminated,
+        WorkspaceStatus::Error => ProtoWorkspaceStatus::Error,
+    }
+}
+
+fn workspace_from_proto(workspace: Option<proto::Workspace>) -> Result<Workspace, ApiError> {
+    let workspace = workspace.ok_or_else(|| ApiError::Internal(anyhow::anyhow!("Missing workspace data")))?;
+    let timestamp = |t: Option<prost_types::Timestamp>| {
+        t.and_then(|t| chrono::DateTime::from_timestamp(t.seconds, t.nanos as u32))
+    };
+    let kind = match ProtoWorkspaceType::try_from(workspace.r#type).unwrap_or(ProtoWorkspaceType::Unspecified) {
+        ProtoWorkspaceType::Session => WorkspaceType::Session,
+        ProtoWorkspaceType::Persistent => WorkspaceType::Persistent,
+        ProtoWorkspaceType::Collaborative => WorkspaceType::Collaborative,
+        _ => WorkspaceType::Ephemeral,
+    };
+    let status = match ProtoWorkspaceStatus::try_from(workspace.status).unwrap_or(ProtoWorkspaceStatus::Unspecified) {
+        ProtoWorkspaceStatus::Active => WorkspaceStatus::Active,
+        ProtoWorkspaceStatus::Suspended => WorkspaceStatus::Suspended,
+        ProtoWorkspaceStatus::Terminated => WorkspaceStatus::Terminated,
+        ProtoWorkspaceStatus::Error => WorkspaceStatus::Error,
+        _ => WorkspaceStatus::Pending,
+    };
+    let config = workspace.config.unwrap_or_default();
+    Ok(Workspace {
+        id: workspace.id,
+        user_id: workspace.user_id,
+        name: workspace.name,
+        description: workspace.description,
+        kind,
+        status,
+        environment: config.environment.clone(),
+        allowed_packages: config.allowed_packages.clone(),
+        created_at: timestamp(workspace.created_at),
+        updated_at: timestamp(workspace.updated_at),
+        expires_at: timestamp(workspace.expires_at),
+        metadata: workspace.metadata,
+        config,
+    })
+}
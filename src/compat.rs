@@ -0,0 +1,369 @@
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, FromRequestParts, Request},
+    http::request::Parts,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::client_version::ClientVersion;
+use crate::error::ApiError;
+use crate::execution::{
+    CreateExecutionRequest, ExecutionResponse, ExecutionResult, ExecutionStatus, ResubmitOverrides,
+};
+use crate::state::AppState;
+
+/// Header clients use to opt into a response schema version
+pub const SCHEMA_VERSION_HEADER: &str = "x-schema-version";
+
+/// Original response shape, served to clients that don't send a version
+pub const LEGACY_SCHEMA_VERSION: u32 = 1;
+/// Newest response shape this gateway can produce
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// Response schema version negotiated from the request headers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SchemaVersion(pub u32);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for SchemaVersion {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(SCHEMA_VERSION_HEADER) else {
+            return Ok(SchemaVersion(LEGACY_SCHEMA_VERSION));
+        };
+
+        let version = value
+            .to_str()
+            .ok()
+            .and_then(|v| v.trim().parse::<u32>().ok())
+            .ok_or_else(|| ApiError::BadRequest(format!("Invalid {} header", SCHEMA_VERSION_HEADER)))?;
+
+        if !(LEGACY_SCHEMA_VERSION..=CURRENT_SCHEMA_VERSION).contains(&version) {
+            return Err(ApiError::BadRequest(format!(
+                "Unsupported schema version {}; supported versions are {}-{}",
+                version, LEGACY_SCHEMA_VERSION, CURRENT_SCHEMA_VERSION
+            )));
+        }
+
+        Ok(SchemaVersion(version))
+    }
+}
+
+/// An execution rendered in the schema version the client asked for
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum VersionedExecution {
+    V1(ExecutionResponseV1),
+    Current(Box<ExecutionEnvelope>),
+}
+
+impl VersionedExecution {
+    pub fn id(&self) -> Uuid {
+        match self {
+            VersionedExecution::V1(e) => e.id,
+            VersionedExecution::Current(e) => e.execution.id,
+        }
+    }
+
+    /// Bytes of stdout/stderr carried, which dominate the serialized size
+    pub fn output_len(&self) -> usize {
+        match self {
+            VersionedExecution::V1(e) => e
+                .result
+                .as_ref()
+                .map_or(0, |r| r.stdout.len() + r.stderr.len()),
+            VersionedExecution::Current(e) => e
+                .execution
+                .result
+                .as_ref()
+                .map_or(0, |r| r.stdout.len() + r.stderr.len()),
+        }
+    }
+
+    pub fn new(execution: ExecutionResponse, version: SchemaVersion) -> Self {
+        if version.0 == LEGACY_SCHEMA_VERSION {
+            VersionedExecution::V1(execution.into())
+        } else {
+            VersionedExecution::Current(Box::new(ExecutionEnvelope {
+                schema_version: CURRENT_SCHEMA_VERSION,
+                execution,
+            }))
+        }
+    }
+}
+
+/// Current response shape; new fields are added to `ExecutionResponse` and surface here
+#[derive(Debug, Serialize)]
+pub struct ExecutionEnvelope {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub execution: ExecutionResponse,
+}
+
+/// Frozen v1 shape for SDKs that deserialize strictly. Do not add fields.
+#[derive(Debug, Serialize)]
+pub struct ExecutionResponseV1 {
+    pub id: Uuid,
+    pub status: ExecutionStatus,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub result: Option<ExecutionResultV1>,
+}
+
+/// Frozen v1 result shape. Do not add fields.
+#[derive(Debug, Serialize)]
+pub struct ExecutionResultV1 {
+    pub exit_code: i32,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration_ms: u64,
+}
+
+impl From<ExecutionResponse> for ExecutionResponseV1 {
+    fn from(execution: ExecutionResponse) -> Self {
+        Self {
+            id: execution.id,
+            status: legacy_status(execution.status),
+            created_at: execution.created_at,
+            started_at: execution.started_at,
+            completed_at: execution.completed_at,
+            result: execution.result.map(ExecutionResultV1::from),
+        }
+    }
+}
+
+/// The v1 status closest to `status`; v1 clients only know the original five
+fn legacy_status(status: ExecutionStatus) -> ExecutionStatus {
+    match status {
+        ExecutionStatus::Queued | ExecutionStatus::Preparing => ExecutionStatus::Pending,
+        ExecutionStatus::Cancelling => ExecutionStatus::Running,
+        ExecutionStatus::Cancelled => ExecutionStatus::Failed,
+        status => status,
+    }
+}
+
+impl From<ExecutionResult> for ExecutionResultV1 {
+    fn from(result: ExecutionResult) -> Self {
+        Self {
+            exit_code: result.exit_code,
+            stdout: result.stdout,
+            stderr: result.stderr,
+            duration_ms: result.duration_ms,
+        }
+    }
+}
+
+/// Request bodies that still accept field names and shapes older clients send
+pub trait LegacyShapes {
+    /// Rewrite legacy fields in `body` to the current shape, returning the name
+    /// of each legacy shape found
+    fn upgrade(body: &mut Map<String, Value>) -> Vec<&'static str>;
+}
+
+/// JSON body extractor that upgrades legacy shapes before deserializing,
+/// counting each one seen so we know when it can be dropped
+pub struct CompatJson<T>(pub T);
+
+#[async_trait]
+impl<T: LegacyShapes + DeserializeOwned> FromRequest<Arc<AppState>> for CompatJson<T> {
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let Json(mut body) = Json::<Value>::from_request(request, state)
+            .await
+            .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+
+        if let Value::Object(fields) = &mut body {
+            for shape in T::upgrade(fields) {
+                state.metrics().record_legacy_shape(shape);
+                debug!(
+                    shape,
+                    client = %ClientVersion::current().map_or_else(|| "unknown".to_string(), |c| c.to_string()),
+                    "Upgraded legacy request shape"
+                );
+            }
+        }
+
+        serde_json::from_value(body)
+            .map(CompatJson)
+            .map_err(|e| ApiError::BadRequest(format!("Invalid request body: {}", e)))
+    }
+}
+
+/// Move `legacy` to `current` unless the body already has `current`; either
+/// way the legacy field is dropped. Returns whether `legacy` was present
+fn rename(body: &mut Map<String, Value>, legacy: &str, current: &str) -> bool {
+    let Some(value) = body.remove(legacy) else {
+        return false;
+    };
+    body.entry(current).or_insert(value);
+    true
+}
+
+/// Legacy spellings shared by create and resubmit bodies
+fn upgrade_execution_fields(body: &mut Map<String, Value>) -> Vec<&'static str> {
+    let mut shapes = Vec::new();
+    for (legacy, current, shape) in [
+        ("timeout", "timeout_seconds", "timeout"),
+        ("lang", "language", "lang"),
+        ("source", "code", "source"),
+        ("environment", "env", "environment"),
+        ("workspace", "workspace_id", "workspace"),
+    ] {
+        if rename(body, legacy, current) {
+            shapes.push(shape);
+        }
+    }
+
+    // Millisecond timeouts, rounded up to whole seconds
+    if let Some(ms) = body.remove("timeout_ms") {
+        shapes.push("timeout_ms");
+        if let Some(ms) = ms.as_u64() {
+            body.entry("timeout_seconds").or_insert(Value::from(ms.div_ceil(1000)));
+        }
+    }
+
+    // Arguments as one space-separated string
+    if let Some(Value::String(args)) = body.get("args") {
+        let args = args.split_whitespace().map(Value::from).collect();
+        body.insert("args".to_string(), Value::Array(args));
+        shapes.push("args_string");
+    }
+
+    shapes
+}
+
+impl LegacyShapes for CreateExecutionRequest {
+    fn upgrade(body: &mut Map<String, Value>) -> Vec<&'static str> {
+        upgrade_execution_fields(body)
+    }
+}
+
+impl LegacyShapes for ResubmitOverrides {
+    fn upgrade(body: &mut Map<String, Value>) -> Vec<&'static str> {
+        upgrade_execution_fields(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn object(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(fields) => fields,
+            _ => panic!("not an object"),
+        }
+    }
+
+    #[test]
+    fn rename_moves_legacy_fields() {
+        let mut body = object(json!({ "lang": "python" }));
+        assert!(rename(&mut body, "lang", "language"));
+        assert_eq!(Value::Object(body), json!({ "language": "python" }));
+    }
+
+    #[test]
+    fn rename_keeps_current_fields_over_legacy_ones() {
+        let mut body = object(json!({ "lang": "ruby", "language": "python" }));
+        assert!(rename(&mut body, "lang", "language"));
+        assert_eq!(Value::Object(body), json!({ "language": "python" }));
+
+        let mut body = object(json!({ "language": "python" }));
+        assert!(!rename(&mut body, "lang", "language"));
+        assert_eq!(Value::Object(body), json!({ "language": "python" }));
+    }
+
+    #[test]
+    fn current_bodies_are_left_alone() {
+        let current = json!({
+            "language": "python",
+            "code": "print(1)",
+            "timeout_seconds": 30,
+            "args": ["-v"],
+        });
+        let mut body = object(current.clone());
+        assert!(upgrade_execution_fields(&mut body).is_empty());
+        assert_eq!(Value::Object(body), current);
+    }
+
+    #[test]
+    fn legacy_spellings_are_upgraded() {
+        let mut body = object(json!({
+            "lang": "python",
+            "source": "print(1)",
+            "timeout": 30,
+            "environment": { "DEBUG": "1" },
+            "workspace": "7f9c3a52-1d2b-4c1e-9a55-0f4d8b2e6a10",
+        }));
+        let shapes = upgrade_execution_fields(&mut body);
+        assert_eq!(shapes, ["timeout", "lang", "source", "environment", "workspace"]);
+        assert_eq!(
+            Value::Object(body),
+            json!({
+                "language": "python",
+                "code": "print(1)",
+                "timeout_seconds": 30,
+                "env": { "DEBUG": "1" },
+                "workspace_id": "7f9c3a52-1d2b-4c1e-9a55-0f4d8b2e6a10",
+            })
+        );
+    }
+
+    #[test]
+    fn millisecond_timeouts_round_up_to_seconds() {
+        let mut body = object(json!({ "language": "python", "timeout_ms": 1500 }));
+        assert_eq!(upgrade_execution_fields(&mut body), ["timeout_ms"]);
+        assert_eq!(body["timeout_seconds"], json!(2));
+
+        let mut body = object(json!({ "timeout_ms": 3000 }));
+        upgrade_execution_fields(&mut body);
+        assert_eq!(body["timeout_seconds"], json!(3));
+
+        // Seconds sent alongside win, and unusable values are dropped
+        let mut body = object(json!({ "timeout_seconds": 10, "timeout_ms": 1500 }));
+        upgrade_execution_fields(&mut body);
+        assert_eq!(Value::Object(body), json!({ "timeout_seconds": 10 }));
+        let mut body = object(json!({ "timeout_ms": "soon" }));
+        assert_eq!(upgrade_execution_fields(&mut body), ["timeout_ms"]);
+        assert!(body.is_empty());
+    }
+
+    #[test]
+    fn argument_strings_are_split_on_whitespace() {
+        let mut body = object(json!({ "args": "-v  --name world" }));
+        assert_eq!(upgrade_execution_fields(&mut body), ["args_string"]);
+        assert_eq!(body["args"], json!(["-v", "--name", "world"]));
+    }
+
+    #[test]
+    fn upgraded_bodies_deserialize() {
+        let mut body = object(json!({ "lang": "python", "source": "print(1)", "timeout_ms": 2500, "args": "a b" }));
+        CreateExecutionRequest::upgrade(&mut body);
+        let request: CreateExecutionRequest = serde_json::from_value(Value::Object(body)).unwrap();
+        assert_eq!(request.language, "python");
+        assert_eq!(&*request.code, "print(1)");
+        assert_eq!(request.timeout_seconds, Some(3));
+        assert_eq!(request.args, Some(vec!["a".to_string(), "b".to_string()]));
+    }
+
+    #[test]
+    fn new_statuses_map_to_their_closest_legacy_status() {
+        assert_eq!(legacy_status(ExecutionStatus::Queued), ExecutionStatus::Pending);
+        assert_eq!(legacy_status(ExecutionStatus::Preparing), ExecutionStatus::Pending);
+        assert_eq!(legacy_status(ExecutionStatus::Cancelling), ExecutionStatus::Running);
+        assert_eq!(legacy_status(ExecutionStatus::Cancelled), ExecutionStatus::Failed);
+        assert_eq!(legacy_status(ExecutionStatus::Completed), ExecutionStatus::Completed);
+        assert_eq!(legacy_status(ExecutionStatus::Running), ExecutionStatus::Running);
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::config::{ConcurrencyGroupConfig, LeaderElectionConfig};
+use crate::error::ApiError;
+use crate::leader::LeaseStore;
+
+/// gRPC create request metadata key naming the execution's concurrency group
+pub const CONCURRENCY_GROUP_KEY: &str = "concurrency_group";
+
+/// A group's lease in the shared lease store, renewed until dropped
+struct GroupLease {
+    store: Arc<dyn LeaseStore>,
+    name: String,
+    holder: String,
+    renewal: JoinHandle<()>,
+}
+
+impl Drop for GroupLease {
+    fn drop(&mut self) {
+        self.renewal.abort();
+        let (store, name, holder) = (self.store.clone(), std::mem::take(&mut self.name), self.holder.clone());
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                if let Err(e) = store.release(&name, &holder).await {
+                    warn!("Failed to release the {} lease: {}", name, e);
+                }
+            });
+        }
+    }
+}
+
+/// A submission's turn to run in its group, held until its execution finishes
+pub struct GroupTurn {
+    _lease: GroupLease,
+    _permit: OwnedSemaphorePermit,
+}
+
+/// Executions that share a concurrency group run one at a time: submissions
+/// wait, in arrival order, until the execution holding the group finishes.
+///
+/// Groups are scoped to a tenant, or to a user outside tenants. Submissions on
+/// one replica queue locally, and the group is then held across replicas
+/// through a lease in the leader election store; with the local store that
+/// lease is always free, which suits single-replica deployments.
+pub struct ConcurrencyGroups {
+    config: ConcurrencyGroupConfig,
+    leases: Arc<dyn LeaseStore>,
+    /// Identifies this replica in the holders of its group leases
+    holder: String,
+    lease_ttl: Duration,
+    renew_interval: Duration,
+    /// One permit per group, keyed by scope and group name
+    groups: Mutex<HashMap<(String, String), Arc<Semaphore>>>,
+    /// Executions holding their group's turn
+    running: Mutex<HashMap<Uuid, GroupTurn>>,
+}
+
+impl ConcurrencyGroups {
+    pub fn new(
+        config: ConcurrencyGroupConfig,
+        leases: Arc<dyn LeaseStore>,
+        holder: String,
+        lease_config: &LeaderElectionConfig,
+    ) -> Self {
+        Self {
+            config,
+            leases,
+            holder,
+            lease_ttl: lease_config.lease_ttl,
+            renew_interval: lease_config.renew_interval,
+            groups: Mutex::new(HashMap::new()),
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &ConcurrencyGroupConfig {
+        &self.config
+    }
+
+    /// Wait for `group`'s turn within `scope`, rejecting the submission when
+    /// the group already has a full queue here or stays busy past the wait limit
+    pub async fn acquire(&self, scope: &str, group: &str) -> Result<GroupTurn, ApiError> {
+        let deadline = tokio::time::Instant::now() + self.config.max_wait;
+        let turn = {
+            let mut groups = self.groups.lock().unwrap();
+            // Besides the map's own, every reference to a group's semaphore
+            // is a submission holding or waiting for its turn
+            groups.retain(|_, turn| Arc::strong_count(turn) > 1);
+            let turn = groups
+                .entry((scope.to_string(), group.to_string()))
+                .or_insert_with(|| Arc::new(Semaphore::new(1)))
+                .clone();
+            let held = usize::from(turn.available_permits() == 0);
+            if Arc::strong_count(&turn) - 2 - held >= self.config.max_queued {
+                return Err(ApiError::Conflict(format!(
+                    "Concurrency group '{}' already has {} submissions waiting",
+                    group, self.config.max_queued
+                )));
+            }
+            turn
+        };
+
+        let busy = || {
+            ApiError::Conflict(format!(
+                "Concurrency group '{}' stayed busy for {}s",
+                group,
+                self.config.max_wait.as_secs()
+            ))
+        };
+        let permit = match tokio::time::timeout_at(deadline, turn.acquire_owned()).await {
+            Ok(Ok(permit)) => permit,
+            // The semaphore is never closed
+            Ok(Err(_)) => return Err(ApiError::ServiceUnavailable),
+            Err(_) => return Err(busy()),
+        };
+
+        // Another replica may hold the group; its lease lapses if that replica dies
+        let name = format!("concurrency-group:{}:{}", scope, group);
+        // One holder per turn, so a release still in flight for the previous
+        // turn here can't give up this one's lease
+        let holder = format!("{}:{}", self.holder, Uuid::new_v4());
+        loop {
+            let held = self
+                .leases
+                .try_acquire(&name, &holder, self.lease_ttl)
+                .await
+                .map_err(|e| ApiError::Internal(e.context("Failed to take the concurrency group lease")))?;
+            if held {
+                break;
+            }
+            if tokio::time::Instant::now() + self.config.poll_interval > deadline {
+                return Err(busy());
+            }
+            tokio::time::sleep(self.config.poll_interval).await;
+        }
+        Ok(GroupTurn {
+            _lease: self.renew(name, holder),
+            _permit: permit,
+        })
+    }
+
+    /// Keep `holder`'s lease `name` renewed until the returned handle is dropped
+    fn renew(&self, name: String, holder: String) -> GroupLease {
+        let store = self.leases.clone();
+        let (lease_name, lease_holder) = (name.clone(), holder.clone());
+        let (ttl, renew_interval) = (self.lease_ttl, self.renew_interval);
+        let renewal = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(renew_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                match store.try_acquire(&lease_name, &lease_holder, ttl).await {
+                    Ok(true) => {}
+                    Ok(false) => warn!("Lost the {} lease while an execution held it", lease_name),
+                    Err(e) => warn!("Failed to renew the {} lease: {}", lease_name, e),
+                }
+            }
+        });
+        GroupLease {
+            store: self.leases.clone(),
+            name,
+            holder,
+            renewal,
+        }
+    }
+
+    /// Keep `turn` until execution `id` finishes
+    pub fn hold(&self, id: Uuid, turn: GroupTurn) {
+        self.running.lock().unwrap().insert(id, turn);
+    }
+
+    /// Pass execution `id`'s turn, if it holds one, to the next submission
+    /// waiting in its group
+    pub fn release(&self, id: Uuid) {
+        self.running.lock().unwrap().remove(&id);
+    }
+
+    /// Executions holding their group's turn
+    pub fn running(&self) -> Vec<Uuid> {
+        self.running.lock().unwrap().keys().copied().collect()
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let (busy, waiting) = self
+            .groups
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|turn| turn.available_permits() == 0)
+            .fold((0, 0), |(busy, waiting), turn| {
+                (busy + 1, waiting + Arc::strong_count(turn).saturating_sub(2))
+            });
+        let _ = writeln!(out, "# HELP syla_gateway_concurrency_groups_busy Concurrency groups with an execution running");
+        let _ = writeln!(out, "# TYPE syla_gateway_concurrency_groups_busy gauge");
+        let _ = writeln!(out, "syla_gateway_concurrency_groups_busy {}", busy);
+        let _ = writeln!(
+            out,
+            "# HELP syla_gateway_concurrency_group_waiting Submissions waiting for their concurrency group"
+        );
+        let _ = writeln!(out, "# TYPE syla_gateway_concurrency_group_waiting gauge");
+        let _ = writeln!(out, "syla_gateway_concurrency_group_waiting {}", waiting);
+        out
+    }
+}
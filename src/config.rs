@@ -0,0 +1,1399 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Largest request body the REST API accepts
+pub const MAX_REQUEST_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// Read an environment variable, falling back to `default` when unset or unparsable
+pub fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.parse::<T>().ok())
+        .unwrap_or(default)
+}
+
+/// Read an optional environment variable, treating unparsable values as unset
+pub fn env_opt<T: FromStr>(key: &str) -> Option<T> {
+    std::env::var(key).ok().and_then(|v| v.parse::<T>().ok())
+}
+
+/// Read comma-separated `<key>=<value>` pairs, skipping malformed entries
+fn env_pairs(key: &str) -> Vec<(String, String)> {
+    std::env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|entry| {
+            let (key, value) = entry.split_once('=')?;
+            Some((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Gateway configuration loaded from the environment
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub archive: ArchiveConfig,
+    pub surface: SurfaceConfig,
+    pub storage: StorageConfig,
+    pub database: DatabaseConfig,
+    pub retention: RetentionConfig,
+    pub export: ExportConfig,
+    pub uploads: UploadConfig,
+    pub callbacks: CallbackConfig,
+    pub event_bus: EventBusConfig,
+    pub leader_election: LeaderElectionConfig,
+    pub sync_wait: SyncWaitConfig,
+    pub stale_reads: StaleReadConfig,
+    pub output_buffer: OutputBufferConfig,
+    pub health: HealthConfig,
+    pub upstream: UpstreamConfig,
+    pub workspace_service: WorkspaceServiceConfig,
+    pub response: ResponseConfig,
+    pub client_versions: ClientVersionConfig,
+    pub canary: CanaryConfig,
+    pub shadow: ShadowConfig,
+    pub trusted_proxies: TrustedProxyConfig,
+    pub cors: CorsConfig,
+    pub watchdog: WatchdogConfig,
+    pub alerts: AlertConfig,
+    pub result_delivery: ResultDeliveryConfig,
+    pub admission: AdmissionConfig,
+    pub resource_caps: ResourceCapsConfig,
+    pub proxy: ProxyConfig,
+    pub rate_limit: RateLimitConfig,
+    pub webhooks: WebhookConfig,
+    pub artifacts: ArtifactStoreConfig,
+    pub outbox: OutboxConfig,
+    pub schedules: ScheduleConfig,
+    pub pipelines: PipelineConfig,
+    pub concurrency_groups: ConcurrencyGroupConfig,
+    pub tracing: TracingConfig,
+    pub slo: SloConfig,
+    pub grpc_cache: GrpcCacheConfig,
+    pub stream_compression: StreamCompressionConfig,
+    /// Start with mutations rejected, e.g. for replicas serving dashboards
+    /// through a backend maintenance window; toggled at runtime via the admin API
+    pub read_only: bool,
+    /// How the gateway assigns request, job and execution IDs
+    pub id_format: IdFormat,
+    /// Upper bound on waiting for in-flight requests during shutdown
+    pub drain_timeout: Duration,
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        Self {
+            archive: ArchiveConfig::from_env(),
+            surface: SurfaceConfig::from_env(),
+            storage: StorageConfig::from_env(),
+            database: DatabaseConfig::from_env(),
+            retention: RetentionConfig::from_env(),
+            export: ExportConfig::from_env(),
+            uploads: UploadConfig::from_env(),
+            callbacks: CallbackConfig::from_env(),
+            event_bus: EventBusConfig::from_env(),
+            leader_election: LeaderElectionConfig::from_env(),
+            sync_wait: SyncWaitConfig::from_env(),
+            stale_reads: StaleReadConfig::from_env(),
+            output_buffer: OutputBufferConfig::from_env(),
+            health: HealthConfig::from_env(),
+            upstream: UpstreamConfig::from_env(),
+            workspace_service: WorkspaceServiceConfig::from_env(),
+            response: ResponseConfig::from_env(),
+            client_versions: ClientVersionConfig::from_env(),
+            canary: CanaryConfig::from_env(),
+            shadow: ShadowConfig::from_env(),
+            trusted_proxies: TrustedProxyConfig::from_env(),
+            cors: CorsConfig::from_env(),
+            watchdog: WatchdogConfig::from_env(),
+            alerts: AlertConfig::from_env(),
+            result_delivery: ResultDeliveryConfig::from_env(),
+            admission: AdmissionConfig::from_env(),
+            resource_caps: ResourceCapsConfig::from_env(),
+            proxy: ProxyConfig::from_env(),
+            rate_limit: RateLimitConfig::from_env(),
+            webhooks: WebhookConfig::from_env(),
+            artifacts: ArtifactStoreConfig::from_env(),
+            outbox: OutboxConfig::from_env(),
+            schedules: ScheduleConfig::from_env(),
+            pipelines: PipelineConfig::from_env(),
+            concurrency_groups: ConcurrencyGroupConfig::from_env(),
+            tracing: TracingConfig::from_env(),
+            slo: SloConfig::from_env(),
+            grpc_cache: GrpcCacheConfig::from_env(),
+            stream_compression: StreamCompressionConfig::from_env(),
+            read_only: env_or("READ_ONLY", false),
+            id_format: env_or("ID_FORMAT", IdFormat::Upstream),
+            drain_timeout: Duration::from_secs(env_or("SHUTDOWN_DRAIN_TIMEOUT_SECS", 30)),
+        }
+    }
+}
+
+/// Format of the IDs the gateway assigns, all written in UUID form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdFormat {
+    /// Random UUIDv4s, proposed to the execution service for executions too
+    UuidV4,
+    /// Time-ordered UUIDv7s
+    UuidV7,
+    /// Time-ordered ULIDs
+    Ulid,
+    /// Execution IDs are left to the execution service; the rest are UUIDv4s
+    Upstream,
+}
+
+impl FromStr for IdFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "uuid4" | "uuidv4" => Ok(Self::UuidV4),
+            "uuid7" | "uuidv7" => Ok(Self::UuidV7),
+            "ulid" => Ok(Self::Ulid),
+            "upstream" => Ok(Self::Upstream),
+            other => Err(format!("unknown ID format {}", other)),
+        }
+    }
+}
+
+/// Protocol an execution service speaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpstreamProtocol {
+    /// The gateway's execution service proto over gRPC
+    Grpc,
+    /// Plain HTTP/JSON, for executors that don't implement the proto
+    Http,
+}
+
+impl FromStr for UpstreamProtocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "grpc" => Ok(Self::Grpc),
+            "http" | "json" => Ok(Self::Http),
+            other => Err(format!("unknown upstream protocol {}", other)),
+        }
+    }
+}
+
+/// Route groups exposed by this deployment, so minimal deployments can ship a reduced surface
+#[derive(Debug, Clone)]
+pub struct SurfaceConfig {
+    pub executions: bool,
+    pub workspaces: bool,
+    pub admin: bool,
+    /// Completion webhooks to clients' `callback_url`s, and the execution
+    /// service's signed callbacks into the gateway
+    pub webhooks: bool,
+    /// Tenants registering the browser origins they embed playgrounds on
+    pub cors: bool,
+}
+
+impl SurfaceConfig {
+    fn from_env() -> Self {
+        Self {
+            executions: env_or("ENABLE_EXECUTIONS_API", true),
+            workspaces: env_or("ENABLE_WORKSPACES_API", true),
+            admin: env_or("ENABLE_ADMIN_API", true),
+            webhooks: env_or("ENABLE_WEBHOOKS_API", true),
+            cors: env_or("ENABLE_CORS_API", true),
+        }
+    }
+}
+
+/// Sampled request/response archiving for debugging
+#[derive(Debug, Clone)]
+pub struct ArchiveConfig {
+    /// Fraction of requests captured, from 0.0 (disabled) to 1.0
+    pub sample_rate: f64,
+    /// How long captured exchanges are retained
+    pub ttl: Duration,
+    /// Upper bound on retained exchanges
+    pub max_entries: usize,
+    /// Bodies larger than this are not captured
+    pub max_body_bytes: usize,
+}
+
+impl ArchiveConfig {
+    fn from_env() -> Self {
+        Self {
+            sample_rate: env_or("PAYLOAD_ARCHIVE_SAMPLE_RATE", 0.0_f64).clamp(0.0, 1.0),
+            ttl: Duration::from_secs(env_or("PAYLOAD_ARCHIVE_TTL_SECS", 3600)),
+            max_entries: env_or("PAYLOAD_ARCHIVE_MAX_ENTRIES", 1000),
+            max_body_bytes: env_or("PAYLOAD_ARCHIVE_MAX_BODY_BYTES", 256 * 1024),
+        }
+    }
+}
+
+/// How execution outputs are held in the gateway's store
+#[derive(Debug, Clone)]
+pub struct StorageConfig {
+    /// stdout/stderr at or above this size are stored zstd-compressed
+    pub compression_threshold_bytes: usize,
+    pub compression_level: i32,
+}
+
+impl StorageConfig {
+    fn from_env() -> Self {
+        Self {
+            compression_threshold_bytes: env_or("OUTPUT_COMPRESSION_THRESHOLD_BYTES", 64 * 1024),
+            compression_level: env_or("OUTPUT_COMPRESSION_LEVEL", 3),
+        }
+    }
+}
+
+/// How long executions are kept, and how long removed ones stay recoverable
+#[derive(Debug, Clone)]
+pub struct RetentionConfig {
+    /// Finished, unpinned executions are dropped after this; kept indefinitely when unset
+    pub execution_ttl: Option<Duration>,
+    /// Soft-deleted executions can be undeleted until this has elapsed
+    pub purge_window: Duration,
+    /// How often expired executions are purged
+    pub purge_interval: Duration,
+}
+
+impl RetentionConfig {
+    fn from_env() -> Self {
+        Self {
+            execution_ttl: env_opt("EXECUTION_RETENTION_SECS").map(Duration::from_secs),
+            purge_window: Duration::from_secs(env_or("EXECUTION_PURGE_WINDOW_SECS", 7 * 24 * 60 * 60)),
+            purge_interval: Duration::from_secs(env_or("EXECUTION_PURGE_INTERVAL_SECS", 60 * 60)),
+        }
+    }
+}
+
+/// Transport used to distribute events between features and replicas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventBusBackend {
+    InProcess,
+    Redis,
+    Nats,
+    Kafka,
+}
+
+impl FromStr for EventBusBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "inprocess" | "in-process" | "memory" => Ok(Self::InProcess),
+            "redis" => Ok(Self::Redis),
+            "nats" => Ok(Self::Nats),
+            "kafka" => Ok(Self::Kafka),
+            other => Err(format!("unknown event bus backend {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EventBusConfig {
+    pub backend: EventBusBackend,
+    /// Broker address; required for every backend but the in-process one
+    pub url: Option<String>,
+    /// Messages an in-process subscriber may fall behind before missing some
+    pub buffer: usize,
+}
+
+impl EventBusConfig {
+    fn from_env() -> Self {
+        Self {
+            backend: env_or("EVENT_BUS", EventBusBackend::InProcess),
+            url: std::env::var("EVENT_BUS_URL").ok().filter(|url| !url.is_empty()),
+            buffer: env_or("EVENT_BUS_BUFFER", 1024),
+        }
+    }
+}
+
+/// Where cluster-wide background task leases are held
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaseBackend {
+    /// Every replica leads; for single-replica deployments
+    Local,
+    Redis,
+}
+
+impl FromStr for LeaseBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "local" | "none" => Ok(Self::Local),
+            "redis" => Ok(Self::Redis),
+            other => Err(format!("unknown leader election backend {}", other)),
+        }
+    }
+}
+
+/// Leader election for background tasks that must run once cluster-wide
+#[derive(Debug, Clone)]
+pub struct LeaderElectionConfig {
+    pub backend: LeaseBackend,
+    /// Lease store address; required for every backend but the local one
+    pub url: Option<String>,
+    /// How long a lease outlives its holder's last renewal
+    pub lease_ttl: Duration,
+    /// How often held leases are renewed and free ones contended for
+    pub renew_interval: Duration,
+}
+
+impl LeaderElectionConfig {
+    fn from_env() -> Self {
+        Self {
+            backend: env_or("LEADER_ELECTION", LeaseBackend::Local),
+            url: std::env::var("LEADER_ELECTION_URL").ok().filter(|url| !url.is_empty()),
+            lease_ttl: Duration::from_secs(env_or("LEADER_LEASE_TTL_SECS", 15)),
+            renew_interval: Duration::from_secs(env_or("LEADER_RENEW_INTERVAL_SECS", 5)),
+        }
+    }
+}
+
+/// Signed callbacks from the execution service; disabled unless a secret is set
+#[derive(Clone)]
+pub struct CallbackConfig {
+    /// Shared HMAC-SHA256 key the execution service signs callbacks with
+    pub secret: Option<String>,
+    /// Callbacks timestamped further than this from now are rejected
+    pub max_skew: Duration,
+    /// With callbacks enabled, unfinished executions are only polled upstream
+    /// once no update has arrived for this long, in case a callback was lost
+    pub poll_fallback_after: Duration,
+}
+
+impl std::fmt::Debug for CallbackConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallbackConfig")
+            .field("secret", &self.secret.as_ref().map(|_| "[REDACTED]"))
+            .field("max_skew", &self.max_skew)
+            .field("poll_fallback_after", &self.poll_fallback_after)
+            .finish()
+    }
+}
+
+impl CallbackConfig {
+    fn from_env() -> Self {
+        Self {
+            secret: std::env::var("EXECUTOR_CALLBACK_SECRET").ok().filter(|s| !s.is_empty()),
+            max_skew: Duration::from_secs(env_or("EXECUTOR_CALLBACK_MAX_SKEW_SECS", 300)),
+            poll_fallback_after: Duration::from_secs(env_or("EXECUTOR_CALLBACK_POLL_FALLBACK_SECS", 60)),
+        }
+    }
+}
+
+/// JSONL exports of execution history
+#[derive(Debug, Clone)]
+pub struct ExportConfig {
+    /// Exports matching more executions than this run as background jobs
+    pub sync_max_rows: usize,
+    /// Default stdout/stderr bytes kept per execution
+    pub output_limit_bytes: usize,
+    /// Upper bound on the per-request output limit
+    pub max_output_limit_bytes: usize,
+    /// Where background export files are written
+    pub dir: PathBuf,
+    /// How long finished export jobs and their files are kept
+    pub job_ttl: Duration,
+}
+
+impl ExportConfig {
+    fn from_env() -> Self {
+        Self {
+            sync_max_rows: env_or("EXPORT_SYNC_MAX_ROWS", 10_000),
+            output_limit_bytes: env_or("EXPORT_OUTPUT_LIMIT_BYTES", 4 * 1024),
+            max_output_limit_bytes: env_or("EXPORT_MAX_OUTPUT_LIMIT_BYTES", 64 * 1024),
+            dir: std::env::var("EXPORT_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| std::env::temp_dir().join("syla-exports")),
+            job_ttl: Duration::from_secs(env_or("EXPORT_JOB_TTL_SECS", 60 * 60)),
+        }
+    }
+}
+
+/// Optional SQL store; disabled unless `DATABASE_URL` is set
+#[derive(Debug, Clone)]
+pub struct DatabaseConfig {
+    pub url: Option<String>,
+    pub max_connections: u32,
+    pub acquire_timeout: Duration,
+    /// Apply pending migrations during startup
+    pub migrate_on_startup: bool,
+}
+
+/// Payloads uploaded ahead of the executions that use them
+#[derive(Debug, Clone)]
+pub struct UploadConfig {
+    /// Where upload content is written
+    pub dir: PathBuf,
+    /// Largest upload accepted
+    pub max_bytes: u64,
+    /// How long an upload can be used before it's removed
+    pub ttl: Duration,
+}
+
+impl UploadConfig {
+    fn from_env() -> Self {
+        Self {
+            dir: std::env::var("UPLOAD_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| std::env::temp_dir().join("syla-uploads")),
+            max_bytes: env_or("UPLOAD_MAX_BYTES", 64 * 1024 * 1024),
+            ttl: Duration::from_secs(env_or("UPLOAD_TTL_SECS", 60 * 60)),
+        }
+    }
+}
+
+impl DatabaseConfig {
+    fn from_env() -> Self {
+        Self {
+            url: std::env::var("DATABASE_URL").ok().filter(|url| !url.is_empty()),
+            max_connections: env_or("DATABASE_MAX_CONNECTIONS", 10),
+            acquire_timeout: Duration::from_secs(env_or("DATABASE_ACQUIRE_TIMEOUT_SECS", 5)),
+            migrate_on_startup: env_or("DATABASE_MIGRATE_ON_STARTUP", true),
+        }
+    }
+}
+
+/// Connections to the execution service
+#[derive(Debug, Clone)]
+pub struct UpstreamConfig {
+    pub execution_service_url: String,
+    pub protocol: UpstreamProtocol,
+    /// Number of channels established at startup
+    pub pool_size: usize,
+    /// Bounds for adaptive pool sizing; the pool stays fixed when they're equal
+    pub pool_min: usize,
+    pub pool_max: usize,
+    /// How often pool size is re-evaluated
+    pub pool_scale_interval: Duration,
+    /// In-flight calls per channel above which the pool grows
+    pub pool_target_inflight: u64,
+    /// Mean unary call latency above which a loaded pool grows
+    pub pool_latency_target: Duration,
+    /// Probe attempts before startup gives up on the execution service
+    pub warmup_attempts: u32,
+    pub warmup_retry_delay: Duration,
+    /// HTTP/2 initial flow-control windows; transport defaults when unset
+    pub stream_window_bytes: Option<u32>,
+    pub connection_window_bytes: Option<u32>,
+    /// When an upstream resolves to several addresses, how long a connection
+    /// attempt gets before the next address is tried alongside it
+    pub connect_attempt_delay: Duration,
+    /// How often HTTP executors, which can't stream, are polled for output
+    pub http_poll_interval: Duration,
+}
+
+impl UpstreamConfig {
+    fn from_env() -> Self {
+        let pool_size = env_or("UPSTREAM_POOL_SIZE", 4usize).max(1);
+        let pool_min = env_or("UPSTREAM_POOL_MIN", pool_size).clamp(1, pool_size);
+        let pool_max = env_or("UPSTREAM_POOL_MAX", pool_size).max(pool_size);
+
+        Self {
+            execution_service_url: std::env::var("EXECUTION_SERVICE_URL")
+                .unwrap_or_else(|_| "http://localhost:8081".to_string()),
+            protocol: env_or("EXECUTION_SERVICE_PROTOCOL", UpstreamProtocol::Grpc),
+            pool_size,
+            pool_min,
+            pool_max,
+            pool_scale_interval: Duration::from_secs(env_or("UPSTREAM_POOL_SCALE_INTERVAL_SECS", 10)),
+            pool_target_inflight: env_or("UPSTREAM_POOL_TARGET_INFLIGHT", 64),
+            pool_latency_target: Duration::from_millis(env_or("UPSTREAM_POOL_LATENCY_TARGET_MS", 250)),
+            warmup_attempts: env_or("WARMUP_ATTEMPTS", 5),
+            warmup_retry_delay: Duration::from_millis(env_or("WARMUP_RETRY_DELAY_MS", 1000)),
+            stream_window_bytes: env_opt("UPSTREAM_STREAM_WINDOW_BYTES"),
+            connection_window_bytes: env_opt("UPSTREAM_CONNECTION_WINDOW_BYTES"),
+            connect_attempt_delay: Duration::from_millis(env_or("UPSTREAM_CONNECT_ATTEMPT_DELAY_MS", 250)),
+            http_poll_interval: Duration::from_millis(env_or("UPSTREAM_HTTP_POLL_INTERVAL_MS", 500).max(50)),
+        }
+    }
+
+    /// The same channel settings pointed at another execution service,
+    /// speaking `protocol` or else the primary's
+    pub fn with_url(&self, url: &str, protocol: Option<UpstreamProtocol>) -> Self {
+        Self {
+            execution_service_url: url.to_string(),
+            protocol: protocol.unwrap_or(self.protocol),
+            ..self.clone()
+        }
+    }
+}
+
+/// The workspace service behind the workspace routes
+#[derive(Debug, Clone)]
+pub struct WorkspaceServiceConfig {
+    pub url: String,
+}
+
+impl WorkspaceServiceConfig {
+    fn from_env() -> Self {
+        Self {
+            url: std::env::var("WORKSPACE_SERVICE_URL")
+                .unwrap_or_else(|_| "http://localhost:8083".to_string()),
+        }
+    }
+}
+
+/// Per-caller token buckets shared by the REST and gRPC listeners
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// Tokens refilled per second; rate limiting is off when 0
+    pub per_second: f64,
+    /// Bucket size, i.e. the most a caller can spend at once after idling
+    pub burst: f64,
+    /// Cost of an operation, keyed by REST route (`POST /v1/executions`) or
+    /// gRPC method (`/syla.gateway.v1.SylaGateway/CreateExecution`); others
+    /// cost 1. Batch operations pay it once per item
+    pub weights: HashMap<String, f64>,
+}
+
+impl RateLimitConfig {
+    /// Reads `RATE_LIMIT_WEIGHTS` as `<route>=<cost>` pairs, e.g.
+    /// `POST /v1/executions=5,GET /v1/executions/:id/status=0.1`
+    fn from_env() -> Self {
+        let per_second = env_or("RATE_LIMIT_PER_SEC", 0.0_f64).max(0.0);
+        let weights = env_pairs("RATE_LIMIT_WEIGHTS")
+            .into_iter()
+            .filter_map(|(route, cost)| Some((route, cost.parse::<f64>().ok().filter(|c| *c >= 0.0)?)))
+            .collect();
+        Self {
+            per_second,
+            burst: env_or("RATE_LIMIT_BURST", per_second.max(1.0)).max(1.0),
+            weights,
+        }
+    }
+
+    /// Cost of one call to `route`
+    pub fn weight(&self, route: &str) -> f64 {
+        self.weights.get(route).copied().unwrap_or(1.0)
+    }
+}
+
+/// An RPC of another internal service exposed through the gateway's gRPC listener
+#[derive(Debug, Clone)]
+pub struct ProxyRoute {
+    /// `<package.Service>/<Method>`, or `<package.Service>/*` for every method
+    pub rpc: String,
+    pub url: String,
+    /// Scope callers need, beyond being authenticated
+    pub scope: Option<String>,
+}
+
+impl ProxyRoute {
+    /// Whether this route serves the gRPC request path `path` (`/<service>/<method>`)
+    pub fn matches(&self, path: &str) -> bool {
+        let Some(path) = path.strip_prefix('/') else {
+            return false;
+        };
+        match self.rpc.strip_suffix("/*") {
+            Some(service) => path
+                .split_once('/')
+                .is_some_and(|(s, method)| s == service && !method.is_empty() && !method.contains('/')),
+            None => path == self.rpc,
+        }
+    }
+}
+
+/// Pass-through of selected RPCs to other internal services
+#[derive(Debug, Clone)]
+pub struct ProxyConfig {
+    pub routes: Vec<ProxyRoute>,
+    /// Deadline for a proxied call
+    pub timeout: Duration,
+}
+
+impl ProxyConfig {
+    /// Reads `PROXY_RPCS` as `<package.Service>/<Method>=<url>` pairs and
+    /// `PROXY_RPC_SCOPES` as `<package.Service>/<Method>=<scope>` pairs, e.g.
+    /// `syla.analysis.v1.AnalysisService/*=http://analysis:8090`
+    fn from_env() -> Self {
+        let scopes: HashMap<String, String> = env_pairs("PROXY_RPC_SCOPES").into_iter().collect();
+        let routes = env_pairs("PROXY_RPCS")
+            .into_iter()
+            .filter(|(rpc, url)| rpc.contains('/') && !url.is_empty())
+            .map(|(rpc, url)| ProxyRoute {
+                scope: scopes.get(&rpc).cloned(),
+                rpc,
+                url,
+            })
+            .collect();
+        Self {
+            routes,
+            timeout: Duration::from_secs(env_or("PROXY_TIMEOUT_SECS", 30)),
+        }
+    }
+}
+
+/// Waiting for an execution to finish within the create request
+#[derive(Debug, Clone)]
+pub struct SyncWaitConfig {
+    /// Longest wait a client may ask for
+    pub max_wait: Duration,
+    /// How often upstream is polled while no status event arrives
+    pub poll_interval: Duration,
+}
+
+impl SyncWaitConfig {
+    fn from_env() -> Self {
+        Self {
+            max_wait: Duration::from_secs(env_or("SYNC_WAIT_MAX_SECS", 60)),
+            poll_interval: Duration::from_millis(env_or("SYNC_WAIT_POLL_INTERVAL_MS", 500)),
+        }
+    }
+}
+
+/// Serving execution reads from the cache while upstream catches up
+#[derive(Debug, Clone)]
+pub struct StaleReadConfig {
+    /// Finished executions cached for longer than this are refreshed in the
+    /// background after being served; 0 turns this off
+    pub revalidate_after: Option<Duration>,
+    /// How long a read of an unfinished execution waits on upstream before
+    /// the cached copy is served marked stale; 0 makes reads always wait
+    pub upstream_budget: Option<Duration>,
+}
+
+impl StaleReadConfig {
+    fn from_env() -> Self {
+        Self {
+            revalidate_after: Some(Duration::from_secs(env_or("STALE_REVALIDATE_AFTER_SECS", 300)))
+                .filter(|after| !after.is_zero()),
+            upstream_budget: Some(Duration::from_millis(env_or("STALE_READ_BUDGET_MS", 250)))
+                .filter(|budget| !budget.is_zero()),
+        }
+    }
+}
+
+/// Output held at the gateway for executions streaming through it
+#[derive(Debug, Clone)]
+pub struct OutputBufferConfig {
+    /// Output bytes held per execution; clients resuming from before the
+    /// oldest held byte must restart their stream
+    pub max_bytes: usize,
+    /// How long a finished execution's buffer stays for reconnecting clients
+    pub linger: Duration,
+    /// Most recent output bytes replayed to a subscriber joining late
+    pub replay_bytes: usize,
+    /// Only output younger than this is replayed to a subscriber joining late
+    pub replay_window: Duration,
+}
+
+impl OutputBufferConfig {
+    fn from_env() -> Self {
+        Self {
+            max_bytes: env_or("OUTPUT_BUFFER_MAX_BYTES", 1024 * 1024),
+            linger: Duration::from_secs(env_or("OUTPUT_BUFFER_LINGER_SECS", 60)),
+            replay_bytes: env_or("OUTPUT_REPLAY_BYTES", 16 * 1024),
+            replay_window: Duration::from_secs(env_or("OUTPUT_REPLAY_WINDOW_SECS", 30)),
+        }
+    }
+}
+
+/// Time allowed for health checks to probe dependencies
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+    /// Longest any one dependency probe may take
+    pub probe_timeout: Duration,
+    /// Longest the whole health check may take
+    pub budget: Duration,
+}
+
+impl HealthConfig {
+    fn from_env() -> Self {
+        Self {
+            probe_timeout: Duration::from_millis(env_or("HEALTH_PROBE_TIMEOUT_MS", 2000)),
+            budget: Duration::from_millis(env_or("HEALTH_CHECK_BUDGET_MS", 3000)),
+        }
+    }
+}
+
+/// Bounds on JSON response bodies
+#[derive(Debug, Clone)]
+pub struct ResponseConfig {
+    /// Responses estimated above this size are rejected in favour of the logs endpoint
+    pub max_body_bytes: usize,
+    /// Responses estimated above this size are serialized incrementally
+    pub streaming_threshold_bytes: usize,
+    /// Break down where request time went in a `Server-Timing` header and gRPC trailer
+    pub server_timing: bool,
+}
+
+impl ResponseConfig {
+    fn from_env() -> Self {
+        Self {
+            max_body_bytes: env_or("RESPONSE_MAX_BODY_BYTES", 8 * 1024 * 1024),
+            streaming_threshold_bytes: env_or("RESPONSE_STREAMING_THRESHOLD_BYTES", 256 * 1024),
+            server_timing: env_or("SERVER_TIMING_ENABLED", true),
+        }
+    }
+}
+
+/// Oldest SDK versions still accepted, keyed by client name
+#[derive(Debug, Clone, Default)]
+pub struct ClientVersionConfig {
+    pub minimums: HashMap<String, semver::Version>,
+}
+
+impl ClientVersionConfig {
+    /// Reads `CLIENT_MIN_VERSIONS` as comma-separated `<name>=<version>` pairs,
+    /// e.g. `syla-python=1.4.0,syla-js=2.1`; malformed entries are ignored
+    fn from_env() -> Self {
+        let minimums = env_pairs("CLIENT_MIN_VERSIONS")
+            .into_iter()
+            .filter_map(|(name, version)| {
+                let version = crate::client_version::parse_version(&version)?;
+                Some((name.to_ascii_lowercase(), version))
+            })
+            .collect();
+        Self { minimums }
+    }
+}
+
+/// Share of executions sent to an alternate execution backend while it's validated
+#[derive(Debug, Clone, Default)]
+pub struct CanaryConfig {
+    /// Canary execution service; canary routing is off when unset
+    pub url: Option<String>,
+    /// Protocol the canary speaks, when it differs from the primary's
+    pub protocol: Option<UpstreamProtocol>,
+    /// Percentage of executions routed to the canary, 0-100
+    pub percent: f64,
+    /// Per-tenant percentages overriding `percent`, read from
+    /// `CANARY_TENANT_PERCENTS` as `<tenant>=<percent>` pairs
+    pub tenant_percents: HashMap<String, f64>,
+}
+
+impl CanaryConfig {
+    fn from_env() -> Self {
+        let tenant_percents = env_pairs("CANARY_TENANT_PERCENTS")
+            .into_iter()
+            .filter_map(|(tenant, percent)| Some((tenant, percent.parse::<f64>().ok()?.clamp(0.0, 100.0))))
+            .collect();
+        Self {
+            url: std::env::var("CANARY_EXECUTION_SERVICE_URL").ok().filter(|url| !url.is_empty()),
+            protocol: env_opt("CANARY_EXECUTION_SERVICE_PROTOCOL"),
+            percent: env_or("CANARY_PERCENT", 0.0_f64).clamp(0.0, 100.0),
+            tenant_percents,
+        }
+    }
+
+    /// Percentage of `tenant_id`'s executions routed to the canary
+    pub fn percent_for(&self, tenant_id: Option<&str>) -> f64 {
+        if self.url.is_none() {
+            return 0.0;
+        }
+        tenant_id
+            .and_then(|tenant| self.tenant_percents.get(tenant))
+            .copied()
+            .unwrap_or(self.percent)
+    }
+}
+
+/// Flagging executions that stay in a non-final status for too long
+#[derive(Debug, Clone)]
+pub struct WatchdogConfig {
+    /// How often cached executions are checked
+    pub interval: Duration,
+    /// Pending, queued or preparing for longer than this counts as stuck
+    pub pending_threshold: Duration,
+    /// Running or cancelling for longer than this counts as stuck
+    pub running_threshold: Duration,
+    /// Cancel stuck executions upstream rather than only flagging them
+    pub auto_cancel: bool,
+}
+
+impl WatchdogConfig {
+    fn from_env() -> Self {
+        Self {
+            interval: Duration::from_secs(env_or("WATCHDOG_INTERVAL_SECS", 60)),
+            pending_threshold: Duration::from_secs(env_or("WATCHDOG_PENDING_THRESHOLD_SECS", 10 * 60)),
+            running_threshold: Duration::from_secs(env_or("WATCHDOG_RUNNING_THRESHOLD_SECS", 60 * 60)),
+            auto_cancel: env_or("WATCHDOG_AUTO_CANCEL", false),
+        }
+    }
+}
+
+/// Writing finished results to client-provided object storage
+#[derive(Debug, Clone)]
+pub struct ResultDeliveryConfig {
+    /// Accept plain-HTTP destinations; only HTTPS ones otherwise
+    pub allow_http: bool,
+    /// Timeout for a single write to a destination
+    pub request_timeout: Duration,
+    /// Writes attempted before delivery is given up on
+    pub attempts: u32,
+    /// How long to wait for an execution to finish before giving up on its delivery
+    pub result_timeout: Duration,
+    /// How often executions awaiting delivery are polled for their results
+    pub poll_interval: Duration,
+}
+
+impl ResultDeliveryConfig {
+    fn from_env() -> Self {
+        Self {
+            allow_http: env_or("RESULT_DELIVERY_ALLOW_HTTP", false),
+            request_timeout: Duration::from_secs(env_or("RESULT_DELIVERY_TIMEOUT_SECS", 60)),
+            attempts: env_or("RESULT_DELIVERY_ATTEMPTS", 3_u32).max(1),
+            result_timeout: Duration::from_secs(env_or("RESULT_DELIVERY_WAIT_SECS", 2 * 60 * 60)),
+            poll_interval: Duration::from_millis(env_or("RESULT_DELIVERY_POLL_INTERVAL_MS", 1000)),
+        }
+    }
+}
+
+/// Signed completion webhooks to clients' `callback_url`s; disabled unless a
+/// signing secret is set
+#[derive(Clone)]
+pub struct WebhookConfig {
+    /// Master key each tenant's, or tenant-less user's, webhook signing
+    /// secret is derived from; never used to sign directly
+    pub secret: Option<String>,
+    /// Accept plain-HTTP callback URLs; only HTTPS ones otherwise
+    pub allow_http: bool,
+    /// Timeout for a single webhook request
+    pub request_timeout: Duration,
+    /// Requests attempted before a webhook is given up on
+    pub attempts: u32,
+    /// How long to wait for an execution to finish before giving up on its webhook
+    pub result_timeout: Duration,
+    /// How often executions awaiting a webhook are polled for their results
+    pub poll_interval: Duration,
+}
+
+impl std::fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookConfig")
+            .field("secret", &self.secret.as_ref().map(|_| "[REDACTED]"))
+            .field("allow_http", &self.allow_http)
+            .field("request_timeout", &self.request_timeout)
+            .field("attempts", &self.attempts)
+            .field("result_timeout", &self.result_timeout)
+            .field("poll_interval", &self.poll_interval)
+            .finish()
+    }
+}
+
+impl WebhookConfig {
+    fn from_env() -> Self {
+        Self {
+            secret: std::env::var("WEBHOOK_SIGNING_SECRET").ok().filter(|s| !s.is_empty()),
+            allow_http: env_or("WEBHOOK_ALLOW_HTTP", false),
+            request_timeout: Duration::from_secs(env_or("WEBHOOK_TIMEOUT_SECS", 10)),
+            attempts: env_or("WEBHOOK_ATTEMPTS", 5_u32).max(1),
+            result_timeout: Duration::from_secs(env_or("WEBHOOK_WAIT_SECS", 2 * 60 * 60)),
+            poll_interval: Duration::from_millis(env_or("WEBHOOK_POLL_INTERVAL_MS", 1000)),
+        }
+    }
+}
+
+/// W3C trace context propagation, and trace links in REST responses
+#[derive(Debug, Clone)]
+pub struct TracingConfig {
+    pub enabled: bool,
+    /// Trace viewer URL with `{trace_id}` in place of the trace ID, e.g.
+    /// `https://jaeger.example.com/trace/{trace_id}`
+    pub viewer_url_template: Option<String>,
+}
+
+impl TracingConfig {
+    fn from_env() -> Self {
+        Self {
+            enabled: env_or("TRACING_ENABLED", false),
+            viewer_url_template: env_opt::<String>("TRACE_VIEWER_URL_TEMPLATE").filter(|s| !s.is_empty()),
+        }
+    }
+}
+
+/// Per-route service level objectives, evaluated by the gateway itself
+#[derive(Debug, Clone)]
+pub struct SloConfig {
+    /// REST route (`POST /v1/executions`) to the fraction of its requests that
+    /// must not fail with a server error, e.g. 0.999
+    pub availability: HashMap<String, f64>,
+    /// REST route to a latency threshold and the fraction of its requests that
+    /// must complete within it
+    pub latency: HashMap<String, (Duration, f64)>,
+    /// Burn rate over both the 1h and 5m windows that warrants paging someone
+    pub page_burn_rate: f64,
+    /// Burn rate over both the 6h and 30m windows that warrants a ticket
+    pub ticket_burn_rate: f64,
+}
+
+impl SloConfig {
+    /// Reads `SLO_AVAILABILITY` as `<route>=<target %>` pairs, e.g.
+    /// `POST /v1/executions=99.9`, and `SLO_LATENCY` as
+    /// `<route>=<threshold ms>:<target %>` pairs, e.g. `GET /v1/executions/:id=250:99`
+    fn from_env() -> Self {
+        // A 100% target leaves no error budget to burn
+        let target = |percent: &str| {
+            percent
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|p| *p > 0.0 && *p < 100.0)
+                .map(|p| p / 100.0)
+        };
+        let availability = env_pairs("SLO_AVAILABILITY")
+            .into_iter()
+            .filter_map(|(route, percent)| Some((route, target(&percent)?)))
+            .collect();
+        let latency = env_pairs("SLO_LATENCY")
+            .into_iter()
+            .filter_map(|(route, objective)| {
+                let (threshold_ms, percent) = objective.split_once(':')?;
+                let threshold = Duration::from_millis(threshold_ms.trim().parse().ok()?);
+                Some((route, (threshold, target(percent)?)))
+            })
+            .collect();
+        Self {
+            availability,
+            latency,
+            page_burn_rate: env_or("SLO_PAGE_BURN_RATE", 14.4),
+            ticket_burn_rate: env_or("SLO_TICKET_BURN_RATE", 6.0),
+        }
+    }
+}
+
+/// Cache of converted gRPC `GetExecution` responses for finished executions
+#[derive(Debug, Clone)]
+pub struct GrpcCacheConfig {
+    /// Responses kept, oldest evicted first; the cache is off when 0
+    pub max_entries: usize,
+}
+
+impl GrpcCacheConfig {
+    fn from_env() -> Self {
+        Self {
+            max_entries: env_or("GRPC_CACHE_MAX_ENTRIES", 0),
+        }
+    }
+}
+
+/// Compression of execution output streamed over SSE and WebSocket
+#[derive(Debug, Clone)]
+pub struct StreamCompressionConfig {
+    /// Gzip SSE streams for clients that accept it
+    pub sse_gzip: bool,
+    /// Negotiate permessage-deflate on interactive WebSocket sessions
+    pub websocket_deflate: bool,
+    /// Compression level, 0 (none) to 9 (smallest)
+    pub level: u32,
+}
+
+impl StreamCompressionConfig {
+    fn from_env() -> Self {
+        Self {
+            sse_gzip: env_or("STREAM_SSE_GZIP", false),
+            websocket_deflate: env_or("STREAM_WS_DEFLATE", false),
+            level: env_or("STREAM_COMPRESSION_LEVEL", 6u32).min(9),
+        }
+    }
+}
+
+/// Durable delivery of webhooks and metering records through the SQL store
+#[derive(Debug, Clone)]
+pub struct OutboxConfig {
+    /// Queue deliveries in the SQL store when one is configured
+    pub enabled: bool,
+    /// How often due entries are picked up
+    pub poll_interval: Duration,
+    /// Entries attempted per poll
+    pub batch_size: usize,
+    /// Attempts before an entry is given up on
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubling after each failure up to the maximum
+    pub retry_backoff: Duration,
+    pub max_retry_backoff: Duration,
+    /// How long delivered and abandoned entries are kept
+    pub retention: Duration,
+}
+
+impl OutboxConfig {
+    fn from_env() -> Self {
+        Self {
+            enabled: env_or("OUTBOX_ENABLED", true),
+            poll_interval: Duration::from_millis(env_or("OUTBOX_POLL_INTERVAL_MS", 1000)),
+            batch_size: env_or("OUTBOX_BATCH_SIZE", 100_usize).max(1),
+            max_attempts: env_or("OUTBOX_MAX_ATTEMPTS", 10_u32).max(1),
+            retry_backoff: Duration::from_secs(env_or("OUTBOX_RETRY_BACKOFF_SECS", 1)),
+            max_retry_backoff: Duration::from_secs(env_or("OUTBOX_MAX_RETRY_BACKOFF_SECS", 60 * 60)),
+            retention: Duration::from_secs(env_or("OUTBOX_RETENTION_SECS", 7 * 24 * 60 * 60)),
+        }
+    }
+}
+
+/// Executions submitted later, once or on a cron expression
+#[derive(Debug, Clone)]
+pub struct ScheduleConfig {
+    /// How often due schedules are picked up
+    pub poll_interval: Duration,
+    /// Schedules one user can keep at once
+    pub max_per_user: usize,
+}
+
+impl ScheduleConfig {
+    fn from_env() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(env_or("SCHEDULE_POLL_INTERVAL_SECS", 5_u64).max(1)),
+            max_per_user: env_or("SCHEDULE_MAX_PER_USER", 100),
+        }
+    }
+}
+
+/// Executions sharing a concurrency group, which run one at a time
+#[derive(Debug, Clone)]
+pub struct ConcurrencyGroupConfig {
+    /// Longest a submission waits for its group before being rejected
+    pub max_wait: Duration,
+    /// Submissions one group can have waiting at once
+    pub max_queued: usize,
+    /// How often executions holding a group are checked on, so the next
+    /// submission goes ahead even when no read sees them finish
+    pub poll_interval: Duration,
+}
+
+impl ConcurrencyGroupConfig {
+    fn from_env() -> Self {
+        Self {
+            max_wait: Duration::from_secs(env_or("CONCURRENCY_GROUP_MAX_WAIT_SECS", 300)),
+            max_queued: env_or("CONCURRENCY_GROUP_MAX_QUEUED", 100),
+            poll_interval: Duration::from_secs(env_or("CONCURRENCY_GROUP_POLL_INTERVAL_SECS", 2_u64).max(1)),
+        }
+    }
+}
+
+/// Executions chained into pipelines
+#[derive(Debug, Clone)]
+pub struct PipelineConfig {
+    /// Most steps one pipeline can have
+    pub max_steps: usize,
+    /// How long a step may run before the pipeline gives up on it
+    pub max_step_wait: Duration,
+    /// Largest artifact a step can pass on to the next
+    pub max_artifact_bytes: usize,
+    /// How long finished pipelines are kept
+    pub retention: Duration,
+    /// How often running pipelines are picked up, e.g. after the replica
+    /// driving one stopped
+    pub poll_interval: Duration,
+}
+
+impl PipelineConfig {
+    fn from_env() -> Self {
+        Self {
+            max_steps: env_or("PIPELINE_MAX_STEPS", 16),
+            max_step_wait: Duration::from_secs(env_or("PIPELINE_MAX_STEP_WAIT_SECS", 60 * 60)),
+            max_artifact_bytes: env_or("PIPELINE_MAX_ARTIFACT_BYTES", 1024 * 1024),
+            retention: Duration::from_secs(env_or("PIPELINE_RETENTION_SECS", 24 * 60 * 60)),
+            poll_interval: Duration::from_secs(env_or("PIPELINE_POLL_INTERVAL_SECS", 1_u64).max(1)),
+        }
+    }
+}
+
+/// Object store flavour artifact URLs are signed for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactProvider {
+    S3,
+    /// Google Cloud Storage's XML API, with HMAC keys
+    Gcs,
+}
+
+impl FromStr for ArtifactProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "s3" => Ok(Self::S3),
+            "gcs" => Ok(Self::Gcs),
+            other => Err(format!("unknown artifact store {}", other)),
+        }
+    }
+}
+
+/// Object storage the execution service writes created files to, under
+/// `<key_prefix><execution ID>/<path>`; the gateway hands out pre-signed
+/// download URLs for them. Disabled unless a bucket and credentials are set
+#[derive(Clone)]
+pub struct ArtifactStoreConfig {
+    pub provider: ArtifactProvider,
+    pub bucket: Option<String>,
+    /// Base URL of the store's API; the provider's public endpoint when unset
+    pub endpoint: Option<String>,
+    pub region: String,
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    pub key_prefix: String,
+    /// How long handed-out URLs stay valid
+    pub url_ttl: Duration,
+}
+
+impl std::fmt::Debug for ArtifactStoreConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ArtifactStoreConfig")
+            .field("provider", &self.provider)
+            .field("bucket", &self.bucket)
+            .field("endpoint", &self.endpoint)
+            .field("region", &self.region)
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &self.secret_access_key.as_ref().map(|_| "[REDACTED]"))
+            .field("key_prefix", &self.key_prefix)
+            .field("url_ttl", &self.url_ttl)
+            .finish()
+    }
+}
+
+impl ArtifactStoreConfig {
+    fn from_env() -> Self {
+        let provider = env_or("ARTIFACT_STORE", ArtifactProvider::S3);
+        let default_region = match provider {
+            ArtifactProvider::S3 => "us-east-1",
+            ArtifactProvider::Gcs => "auto",
+        };
+        Self {
+            provider,
+            bucket: env_opt::<String>("ARTIFACT_BUCKET").filter(|s| !s.is_empty()),
+            endpoint: env_opt::<String>("ARTIFACT_ENDPOINT").filter(|s| !s.is_empty()),
+            region: env_or("ARTIFACT_REGION", default_region.to_string()),
+            access_key_id: env_opt::<String>("ARTIFACT_ACCESS_KEY_ID").filter(|s| !s.is_empty()),
+            secret_access_key: env_opt::<String>("ARTIFACT_SECRET_ACCESS_KEY").filter(|s| !s.is_empty()),
+            key_prefix: env_or("ARTIFACT_KEY_PREFIX", "artifacts/".to_string()),
+            // Signed URLs can't outlive seven days on either provider
+            url_ttl: Duration::from_secs(env_or("ARTIFACT_URL_TTL_SECS", 900_u64).clamp(1, 7 * 24 * 60 * 60)),
+        }
+    }
+}
+
+/// Largest resources, code and timeout a single execution may ask for;
+/// unlimited where unset
+#[derive(Debug, Clone, Default)]
+pub struct ResourceCapsConfig {
+    pub max_memory_mb: Option<u64>,
+    pub max_cpu_millis: Option<u64>,
+    pub max_disk_mb: Option<u64>,
+    pub max_code_bytes: Option<u64>,
+    pub max_timeout_seconds: Option<u64>,
+}
+
+impl ResourceCapsConfig {
+    fn from_env() -> Self {
+        Self {
+            max_memory_mb: env_opt("EXECUTION_MAX_MEMORY_MB"),
+            max_cpu_millis: env_opt("EXECUTION_MAX_CPU_MILLIS"),
+            max_disk_mb: env_opt("EXECUTION_MAX_DISK_MB"),
+            max_code_bytes: env_opt("EXECUTION_MAX_CODE_BYTES"),
+            max_timeout_seconds: env_opt("EXECUTION_MAX_TIMEOUT_SECS"),
+        }
+    }
+}
+
+/// Concurrency shared between the REST and gRPC listeners
+#[derive(Debug, Clone)]
+pub struct AdmissionConfig {
+    /// Requests admitted at once across both listeners; unlimited when 0
+    pub max_inflight: usize,
+    /// Slots only REST requests may use
+    pub rest_reserved: usize,
+    /// Slots only gRPC requests may use
+    pub grpc_reserved: usize,
+    /// Share of the budget in use past which low-priority submissions are
+    /// shed, 0.0-1.0
+    pub low_priority_limit: f64,
+    /// Share of the budget in use past which normal-priority submissions are
+    /// shed; high-priority ones are only limited by the budget itself
+    pub normal_priority_limit: f64,
+}
+
+impl AdmissionConfig {
+    fn from_env() -> Self {
+        let max_inflight = env_or("ADMISSION_MAX_INFLIGHT", 0usize);
+        let rest_reserved = env_or("ADMISSION_REST_RESERVED", 0usize).min(max_inflight);
+        let grpc_reserved = env_or("ADMISSION_GRPC_RESERVED", 0usize).min(max_inflight - rest_reserved);
+        let normal_priority_limit = env_or("ADMISSION_NORMAL_PRIORITY_LIMIT", 0.9f64).clamp(0.0, 1.0);
+        Self {
+            max_inflight,
+            rest_reserved,
+            grpc_reserved,
+            low_priority_limit: env_or("ADMISSION_LOW_PRIORITY_LIMIT", 0.7f64).clamp(0.0, normal_priority_limit),
+            normal_priority_limit,
+        }
+    }
+
+    /// Slots either listener may use once its reservation is taken
+    pub fn shared(&self) -> usize {
+        self.max_inflight - self.rest_reserved - self.grpc_reserved
+    }
+}
+
+/// Mirroring a sample of submissions to a secondary execution backend
+#[derive(Debug, Clone)]
+pub struct ShadowConfig {
+    /// Shadow execution service; mirroring is off when unset
+    pub url: Option<String>,
+    /// Protocol the shadow speaks, when it differs from the primary's
+    pub protocol: Option<UpstreamProtocol>,
+    /// Fraction of submissions mirrored, 0.0-1.0
+    pub sample_rate: f64,
+    /// How long to wait for both executions to finish before giving up on a comparison
+    pub result_timeout: Duration,
+    /// How often mirrored executions are polled for their results
+    pub poll_interval: Duration,
+}
+
+impl ShadowConfig {
+    fn from_env() -> Self {
+        Self {
+            url: std::env::var("SHADOW_EXECUTION_SERVICE_URL").ok().filter(|url| !url.is_empty()),
+            protocol: env_opt("SHADOW_EXECUTION_SERVICE_PROTOCOL"),
+            sample_rate: env_or("SHADOW_SAMPLE_RATE", 0.0_f64).clamp(0.0, 1.0),
+            result_timeout: Duration::from_secs(env_or("SHADOW_RESULT_TIMEOUT_SECS", 300)),
+            poll_interval: Duration::from_millis(env_or("SHADOW_POLL_INTERVAL_MS", 1000)),
+        }
+    }
+}
+
+/// Proxies whose forwarding headers are believed when resolving client addresses
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxyConfig {
+    /// Read from `TRUSTED_PROXIES` as comma-separated CIDR ranges or single
+    /// addresses; unparsable entries are ignored. Empty trusts no proxy, so
+    /// clients are identified by the connecting address
+    pub ranges: Vec<ipnet::IpNet>,
+}
+
+impl TrustedProxyConfig {
+    fn from_env() -> Self {
+        let ranges = std::env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter_map(|range| {
+                range
+                    .parse::<ipnet::IpNet>()
+                    .ok()
+                    .or_else(|| range.parse::<std::net::IpAddr>().ok().map(ipnet::IpNet::from))
+            })
+            .collect();
+        Self { ranges }
+    }
+}
+
+/// Browser origins allowed to call the REST API beyond those tenants register
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// Read from `CORS_ALLOWED_ORIGINS` as comma-separated origins; unset or
+    /// `*` allows any origin, without credentials. Malformed entries are ignored
+    pub allowed_origins: Option<Vec<String>>,
+    /// How long browsers may cache a preflight, unless a tenant's policy says otherwise
+    pub max_age: Duration,
+    /// Upper bound on fetching an origin's verification token when a tenant
+    /// registers it
+    pub verification_timeout: Duration,
+}
+
+impl CorsConfig {
+    fn from_env() -> Self {
+        let raw = std::env::var("CORS_ALLOWED_ORIGINS").unwrap_or_default();
+        let allowed_origins = (!raw.trim().is_empty() && raw.trim() != "*").then(|| {
+            raw.split(',')
+                .filter_map(crate::cors::normalize_origin)
+                .collect()
+        });
+        Self {
+            allowed_origins,
+            max_age: Duration::from_secs(env_or("CORS_MAX_AGE_SECS", 600)),
+            verification_timeout: Duration::from_secs(env_or("CORS_VERIFICATION_TIMEOUT_SECS", 10)),
+        }
+    }
+}
+
+/// Payload shape alerts are posted in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertFormat {
+    /// The gateway's own JSON document
+    Generic,
+    /// PagerDuty Events API v2
+    PagerDuty,
+}
+
+impl FromStr for AlertFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "generic" | "json" => Ok(Self::Generic),
+            "pagerduty" => Ok(Self::PagerDuty),
+            other => Err(format!("unknown alert format {}", other)),
+        }
+    }
+}
+
+/// Operator alerts on conditions the gateway detects itself; off unless a
+/// webhook URL, or a PagerDuty routing key, is set
+#[derive(Clone)]
+pub struct AlertConfig {
+    pub webhook_url: Option<String>,
+    pub format: AlertFormat,
+    /// Integration key events are sent with in the PagerDuty format
+    pub pagerduty_routing_key: Option<String>,
+    /// Timeout for a single alert request
+    pub request_timeout: Duration,
+    /// How often conditions are checked
+    pub interval: Duration,
+    /// A condition still firing is re-sent this often; alerts are otherwise
+    /// sent once when they start and once when they clear
+    pub renotify_interval: Duration,
+    /// Consecutive failed execution service probes before the upstream counts as down
+    pub upstream_failures: u32,
+    /// gRPC response cache evictions within one interval that count as a
+    /// storm; 0 turns the check off
+    pub cache_evictions: u64,
+    /// Mean token validation latency over an interval that counts as a
+    /// spike; 0 turns the check off
+    pub auth_latency: Duration,
+}
+
+impl std::fmt::Debug for AlertConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AlertConfig")
+            .field("webhook_url", &self.webhook_url.as_ref().map(|_| "[REDACTED]"))
+            .field("format", &self.format)
+            .field("pagerduty_routing_key", &self.pagerduty_routing_key.as_ref().map(|_| "[REDACTED]"))
+            .field("request_timeout", &self.request_timeout)
+            .field("interval", &self.interval)
+            .field("renotify_interval", &self.renotify_interval)
+            .field("upstream_failures", &self.upstream_failures)
+            .field("cache_evictions", &self.cache_evictions)
+            .field("auth_latency", &self.auth_latency)
+            .finish()
+    }
+}
+
+impl AlertConfig {
+    fn from_env() -> Self {
+        let format = env_or("ALERT_FORMAT", AlertFormat::Generic);
+        let default_url = match format {
+            AlertFormat::Generic => None,
+            AlertFormat::PagerDuty => Some("https://events.pagerduty.com/v2/enqueue".to_string()),
+        };
+        Self {
+            webhook_url: env_opt::<String>("ALERT_WEBHOOK_URL").filter(|s| !s.is_empty()).or(default_url),
+            format,
+            pagerduty_routing_key: env_opt::<String>("ALERT_PAGERDUTY_ROUTING_KEY").filter(|s| !s.is_empty()),
+            request_timeout: Duration::from_secs(env_or("ALERT_TIMEOUT_SECS", 10)),
+            interval: Duration::from_secs(env_or("ALERT_CHECK_INTERVAL_SECS", 30_u64).max(1)),
+            renotify_interval: Duration::from_secs(env_or("ALERT_RENOTIFY_SECS", 60 * 60)),
+            upstream_failures: env_or("ALERT_UPSTREAM_FAILURES", 3_u32).max(1),
+            cache_evictions: env_or("ALERT_CACHE_EVICTIONS", 1000),
+            auth_latency: Duration::from_millis(env_or("ALERT_AUTH_LATENCY_MS", 500)),
+        }
+    }
+
+    /// Whether alerts have somewhere to go
+    pub fn enabled(&self) -> bool {
+        match self.format {
+            AlertFormat::Generic => self.webhook_url.is_some(),
+            AlertFormat::PagerDuty => self.webhook_url.is_some() && self.pagerduty_routing_key.is_some(),
+        }
+    }
+}
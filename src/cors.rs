@@ -0,0 +1,508 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Query, Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Extension, Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::postgres::PgPool;
+use sqlx::Row;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::audit::{self, AuditEvent, AuditOutcome};
+use crate::auth::{self, AuthContext, AuthInterceptor, ADMIN_SCOPE, SETTINGS_SCOPE};
+use crate::config::CorsConfig;
+use crate::delivery;
+use crate::error::ApiError;
+use crate::state::AppState;
+
+/// Methods preflights are answered for
+const ALLOWED_METHODS: &str = "GET, POST, PUT, PATCH, DELETE";
+
+/// Response headers browsers may read cross-origin
+const EXPOSED_HEADERS: &str = "x-request-id, traceparent, retry-after";
+
+/// Per-tenant CORS routes, authenticated and scoped to the caller's tenant
+pub fn routes(auth_interceptor: AuthInterceptor) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/settings/cors", get(get_policy).put(put_policy))
+        .route("/v1/settings/cors/verification", get(get_verification))
+        .route_layer(middleware::from_fn_with_state(auth_interceptor, auth::require_auth))
+}
+
+/// Browser origins a tenant serves its embedded playgrounds from
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CorsPolicy {
+    /// Origins such as `https://play.example.com`; each belongs to at most one tenant
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// Let browsers send cookies and credentials along with requests
+    #[serde(default)]
+    pub allow_credentials: bool,
+    /// How long browsers may cache a preflight; the gateway default when unset
+    pub max_age_seconds: Option<u64>,
+    /// Set by the gateway on every change
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_by: Option<String>,
+}
+
+impl CorsPolicy {
+    /// Reduce each origin to its `scheme://host[:port]` form, rejecting
+    /// anything that isn't a single HTTP(S) origin
+    fn normalize(&mut self) -> Result<(), ApiError> {
+        let mut origins = Vec::with_capacity(self.allowed_origins.len());
+        for origin in &self.allowed_origins {
+            let normalized = normalize_origin(origin).ok_or_else(|| {
+                ApiError::BadRequest(format!(
+                    "'{}' is not an origin; use the form https://host[:port]",
+                    origin
+                ))
+            })?;
+            if !origins.contains(&normalized) {
+                origins.push(normalized);
+            }
+        }
+        self.allowed_origins = origins;
+        Ok(())
+    }
+}
+
+/// `origin` as browsers send it in `Origin`, or `None` if it has a path,
+/// query, credentials or a non-HTTP(S) scheme
+pub fn normalize_origin(origin: &str) -> Option<String> {
+    let url = reqwest::Url::parse(origin.trim()).ok()?;
+    let bare = matches!(url.scheme(), "http" | "https")
+        && url.host().is_some()
+        && url.path() == "/"
+        && url.query().is_none()
+        && url.fragment().is_none()
+        && url.username().is_empty()
+        && url.password().is_none();
+    bare.then(|| url.origin().ascii_serialization())
+}
+
+/// How a request's origin may use the API
+#[derive(Debug, Clone, PartialEq)]
+enum Grant {
+    /// Any origin, without credentials
+    Any,
+    /// The origin itself, echoed back
+    Origin {
+        allow_credentials: bool,
+        max_age_seconds: u64,
+    },
+}
+
+#[derive(Default)]
+struct Registry {
+    policies: HashMap<String, CorsPolicy>,
+    /// Tenant that registered each origin
+    owners: HashMap<String, String>,
+}
+
+impl Registry {
+    /// One of `policy`'s origins another tenant already registered
+    fn claimed_elsewhere<'a>(&self, tenant_id: &str, policy: &'a CorsPolicy) -> Option<&'a String> {
+        policy
+            .allowed_origins
+            .iter()
+            .find(|origin| self.owners.get(*origin).is_some_and(|owner| owner != tenant_id))
+    }
+
+    /// Make `policy` `tenant_id`'s, taking its origins from any tenant that
+    /// held them
+    fn replace(&mut self, tenant_id: &str, policy: CorsPolicy) {
+        self.owners.retain(|_, owner| owner != tenant_id);
+        for origin in &policy.allowed_origins {
+            if let Some(previous) = self.owners.insert(origin.clone(), tenant_id.to_string()) {
+                if let Some(held) = self.policies.get_mut(&previous) {
+                    held.allowed_origins.retain(|allowed| allowed != origin);
+                }
+            }
+        }
+        self.policies.insert(tenant_id.to_string(), policy);
+    }
+}
+
+/// A tenant's policy changed on the replica that published this
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorsPolicyChange {
+    pub tenant_id: String,
+    pub policy: CorsPolicy,
+    /// Publishing replica, which ignores its own changes
+    pub replica: Uuid,
+}
+
+/// CORS policies by tenant ID, indexed by the origins they allow. With the
+/// SQL store, policies survive restarts and the store decides which tenant
+/// an origin belongs to; other replicas hear of changes over the event bus.
+/// Without one, each replica only knows the changes made while it runs
+pub struct TenantOrigins {
+    registry: RwLock<Registry>,
+    pool: Option<PgPool>,
+}
+
+impl TenantOrigins {
+    pub fn new(pool: Option<PgPool>) -> Self {
+        Self {
+            registry: RwLock::new(Registry::default()),
+            pool,
+        }
+    }
+
+    /// Replace the registry with the stored policies
+    pub async fn load(&self) -> anyhow::Result<()> {
+        let Some(pool) = &self.pool else {
+            return Ok(());
+        };
+        let rows = sqlx::query(
+            "SELECT tenant_id, allow_credentials, max_age_seconds, updated_at, updated_by FROM cors_policies",
+        )
+        .fetch_all(pool)
+        .await?;
+        let mut policies = HashMap::with_capacity(rows.len());
+        for row in rows {
+            let tenant_id: String = row.try_get("tenant_id")?;
+            let max_age_seconds: Option<i64> = row.try_get("max_age_seconds")?;
+            let policy = CorsPolicy {
+                allowed_origins: Vec::new(),
+                allow_credentials: row.try_get("allow_credentials")?,
+                max_age_seconds: max_age_seconds.and_then(|seconds| u64::try_from(seconds).ok()),
+                updated_at: row.try_get("updated_at")?,
+                updated_by: row.try_get("updated_by")?,
+            };
+            policies.insert(tenant_id, policy);
+        }
+        let origins = sqlx::query("SELECT origin, tenant_id FROM cors_origins ORDER BY tenant_id, position")
+            .fetch_all(pool)
+            .await?;
+        let mut owners = HashMap::with_capacity(origins.len());
+        for row in origins {
+            let origin: String = row.try_get("origin")?;
+            let tenant_id: String = row.try_get("tenant_id")?;
+            if let Some(policy) = policies.get_mut(&tenant_id) {
+                policy.allowed_origins.push(origin.clone());
+            }
+            owners.insert(origin, tenant_id);
+        }
+        *self.registry.write().await = Registry { policies, owners };
+        Ok(())
+    }
+
+    pub async fn get(&self, tenant_id: &str) -> Option<CorsPolicy> {
+        self.registry.read().await.policies.get(tenant_id).cloned()
+    }
+
+    /// Origins of `policy` that `tenant_id` hasn't registered yet, so must
+    /// prove it controls
+    pub async fn unregistered(&self, tenant_id: &str, policy: &CorsPolicy) -> Vec<String> {
+        let registry = self.registry.read().await;
+        policy
+            .allowed_origins
+            .iter()
+            .filter(|origin| registry.owners.get(*origin).map(String::as_str) != Some(tenant_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Replace `tenant_id`'s policy; fails without changes if another tenant
+    /// already registered one of its origins
+    pub async fn put(&self, tenant_id: &str, policy: CorsPolicy) -> Result<(), ApiError> {
+        let Some(pool) = &self.pool else {
+            let mut registry = self.registry.write().await;
+            if let Some(origin) = registry.claimed_elsewhere(tenant_id, &policy) {
+                return Err(claimed(origin));
+            }
+            registry.replace(tenant_id, policy);
+            return Ok(());
+        };
+        if let Some(origin) = self.registry.read().await.claimed_elsewhere(tenant_id, &policy) {
+            return Err(claimed(origin));
+        }
+        store(pool, tenant_id, &policy).await?;
+        self.adopt(tenant_id, policy).await;
+        Ok(())
+    }
+
+    /// Take on a policy another replica stored, or this one just did
+    pub async fn adopt(&self, tenant_id: &str, policy: CorsPolicy) {
+        self.registry.write().await.replace(tenant_id, policy);
+    }
+
+    /// The tenant that registered `origin`, and its policy
+    pub async fn resolve(&self, origin: &str) -> Option<(String, CorsPolicy)> {
+        let registry = self.registry.read().await;
+        let tenant_id = registry.owners.get(origin)?;
+        let policy = registry.policies.get(tenant_id)?;
+        Some((tenant_id.clone(), policy.clone()))
+    }
+
+    /// What `origin` may do: a tenant's registration wins, then the
+    /// gateway-wide list
+    async fn grant(&self, origin: &str, config: &CorsConfig) -> Option<Grant> {
+        if let Some((tenant_id, policy)) = self.resolve(origin).await {
+            tracing::trace!(tenant_id = %tenant_id, origin, "Applying tenant CORS policy");
+            return Some(Grant::Origin {
+                allow_credentials: policy.allow_credentials,
+                max_age_seconds: policy.max_age_seconds.unwrap_or(config.max_age.as_secs()),
+            });
+        }
+        match &config.allowed_origins {
+            None => Some(Grant::Any),
+            Some(origins) if origins.iter().any(|allowed| allowed == origin) => Some(Grant::Origin {
+                allow_credentials: false,
+                max_age_seconds: config.max_age.as_secs(),
+            }),
+            Some(_) => None,
+        }
+    }
+}
+
+fn claimed(origin: &str) -> ApiError {
+    ApiError::Conflict(format!("Origin {} is registered by another tenant", origin))
+}
+
+/// Write `policy` as `tenant_id`'s in one transaction; the store's unique
+/// origins settle races between replicas
+async fn store(pool: &PgPool, tenant_id: &str, policy: &CorsPolicy) -> Result<(), ApiError> {
+    let stored: anyhow::Result<Option<String>> = async {
+        let mut tx = pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO cors_policies (tenant_id, allow_credentials, max_age_seconds, updated_at, updated_by) \
+             VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (tenant_id) DO UPDATE SET allow_credentials = $2, max_age_seconds = $3, \
+                                                   updated_at = $4, updated_by = $5",
+        )
+        .bind(tenant_id)
+        .bind(policy.allow_credentials)
+        .bind(policy.max_age_seconds.map(|seconds| i64::try_from(seconds).unwrap_or(i64::MAX)))
+        .bind(policy.updated_at)
+        .bind(&policy.updated_by)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM cors_origins WHERE tenant_id = $1")
+            .bind(tenant_id)
+            .execute(&mut *tx)
+            .await?;
+        for (position, origin) in policy.allowed_origins.iter().enumerate() {
+            let inserted = sqlx::query(
+                "INSERT INTO cors_origins (origin, tenant_id, position) VALUES ($1, $2, $3) \
+                 ON CONFLICT (origin) DO NOTHING",
+            )
+            .bind(origin)
+            .bind(tenant_id)
+            .bind(i32::try_from(position).unwrap_or(i32::MAX))
+            .execute(&mut *tx)
+            .await?;
+            if inserted.rows_affected() == 0 {
+                // Dropping the transaction rolls it back
+                return Ok(Some(origin.clone()));
+            }
+        }
+        tx.commit().await?;
+        Ok(None)
+    }
+    .await;
+    match stored? {
+        Some(origin) => Err(claimed(&origin)),
+        None => Ok(()),
+    }
+}
+
+/// Where an origin serves the token proving a tenant controls it
+pub const VERIFICATION_PATH: &str = "/.well-known/syla-cors-verification";
+
+/// Token `tenant_id` serves at [`VERIFICATION_PATH`] on `origin` to register
+/// it. Only whoever controls the origin can serve it, so it needn't be secret
+pub fn verification_token(tenant_id: &str, origin: &str) -> String {
+    hex::encode(Sha256::digest(format!("syla-cors-verification:{}:{}", tenant_id, origin)))
+}
+
+/// How a tenant proves it controls an origin
+#[derive(Debug, Serialize)]
+pub struct OriginVerification {
+    pub origin: String,
+    /// Served as a line of the body at `url`
+    pub token: String,
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerificationQuery {
+    origin: String,
+}
+
+/// Check that `origin` serves `tenant_id`'s verification token
+async fn verify_origin(tenant_id: &str, origin: &str, timeout: Duration) -> Result<(), ApiError> {
+    let token = verification_token(tenant_id, origin);
+    let unverified = || {
+        ApiError::Forbidden(format!(
+            "Could not verify {}; serve {} at {}{} to prove you control it, or ask an administrator to register it",
+            origin, token, origin, VERIFICATION_PATH
+        ))
+    };
+    let url = delivery::parse_external_url(&format!("{}{}", origin, VERIFICATION_PATH), true, "origin")
+        .map_err(|_| unverified())?;
+    let served = async {
+        let client = delivery::pinned_client(&url, timeout).await?;
+        let response = client.get(url.clone()).send().await?.error_for_status()?;
+        anyhow::Ok(response.text().await?)
+    }
+    .await;
+    match served {
+        Ok(body) if body.lines().any(|line| line.trim() == token) => Ok(()),
+        Ok(_) => Err(unverified()),
+        Err(e) => {
+            tracing::debug!(tenant_id, origin, "Origin verification failed: {}", e);
+            Err(unverified())
+        }
+    }
+}
+
+/// Answer preflights and add CORS headers to responses, with the policy
+/// resolved from the request's `Origin`
+pub async fn apply(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let Some(origin) = request.headers().get(header::ORIGIN).cloned() else {
+        return next.run(request).await;
+    };
+    let grant = match origin.to_str() {
+        Ok(raw) => state.tenant_origins().grant(raw, &state.config().cors).await,
+        Err(_) => None,
+    };
+    let preflight = request.method() == Method::OPTIONS
+        && request.headers().contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+
+    if preflight {
+        let mut response = StatusCode::NO_CONTENT.into_response();
+        if let Some(grant) = &grant {
+            let requested_headers = request.headers().get(header::ACCESS_CONTROL_REQUEST_HEADERS).cloned();
+            let headers = response.headers_mut();
+            allow(headers, &origin, grant);
+            headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, HeaderValue::from_static(ALLOWED_METHODS));
+            if let Some(requested_headers) = requested_headers {
+                headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, requested_headers);
+            }
+            let max_age = match grant {
+                Grant::Any => state.config().cors.max_age.as_secs(),
+                Grant::Origin { max_age_seconds, .. } => *max_age_seconds,
+            };
+            headers.insert(header::ACCESS_CONTROL_MAX_AGE, HeaderValue::from(max_age));
+        }
+        vary_on_origin(response.headers_mut());
+        return response;
+    }
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    if let Some(grant) = &grant {
+        allow(headers, &origin, grant);
+        headers.insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, HeaderValue::from_static(EXPOSED_HEADERS));
+    }
+    vary_on_origin(headers);
+    response
+}
+
+fn allow(headers: &mut axum::http::HeaderMap, origin: &HeaderValue, grant: &Grant) {
+    match grant {
+        Grant::Any => {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+        }
+        Grant::Origin { allow_credentials, .. } => {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+            if *allow_credentials {
+                headers.insert(header::ACCESS_CONTROL_ALLOW_CREDENTIALS, HeaderValue::from_static("true"));
+            }
+        }
+    }
+}
+
+/// Policies differ by origin, so caches must key on it
+fn vary_on_origin(headers: &mut axum::http::HeaderMap) {
+    headers.append(header::VARY, HeaderValue::from_static("origin"));
+}
+
+fn caller_tenant(auth_context: &AuthContext) -> Result<&str, ApiError> {
+    auth_context
+        .tenant_id
+        .as_deref()
+        .ok_or_else(|| ApiError::BadRequest("No tenant is associated with this caller".to_string()))
+}
+
+async fn get_policy(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<CorsPolicy>, ApiError> {
+    let tenant_id = caller_tenant(&auth_context)?;
+    Ok(Json(state.tenant_origins().get(tenant_id).await.unwrap_or_default()))
+}
+
+async fn put_policy(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Json(mut policy): Json<CorsPolicy>,
+) -> Result<Json<CorsPolicy>, ApiError> {
+    let tenant_id = caller_tenant(&auth_context)?;
+    let event = AuditEvent::new("settings.cors.update", &auth_context.user_id, AuditOutcome::Allowed)
+        .tenant(Some(tenant_id));
+
+    if !auth_context.has_scope(SETTINGS_SCOPE) && !auth_context.has_scope(ADMIN_SCOPE) {
+        audit::record(AuditEvent {
+            outcome: AuditOutcome::Denied,
+            ..event
+        });
+        return Err(ApiError::Forbidden(format!("Requires the {} scope", SETTINGS_SCOPE)));
+    }
+    policy.normalize()?;
+
+    // Administrators may register origins outright; anyone else proves
+    // control of each origin new to the tenant
+    if !auth_context.has_scope(ADMIN_SCOPE) {
+        let timeout = state.config().cors.verification_timeout;
+        for origin in state.tenant_origins().unregistered(tenant_id, &policy).await {
+            if let Err(e) = verify_origin(tenant_id, &origin, timeout).await {
+                audit::record(AuditEvent {
+                    outcome: AuditOutcome::Denied,
+                    ..event
+                });
+                return Err(e);
+            }
+        }
+    }
+
+    policy.updated_at = Some(Utc::now());
+    policy.updated_by = Some(auth_context.user_id.clone());
+    state.put_cors_policy(tenant_id, policy.clone()).await?;
+    audit::record(event);
+
+    Ok(Json(policy))
+}
+
+/// The token the caller's tenant serves to register an origin
+async fn get_verification(
+    Extension(auth_context): Extension<AuthContext>,
+    Query(query): Query<VerificationQuery>,
+) -> Result<Json<OriginVerification>, ApiError> {
+    let tenant_id = caller_tenant(&auth_context)?;
+    let origin = normalize_origin(&query.origin).ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "'{}' is not an origin; use the form https://host[:port]",
+            query.origin
+        ))
+    })?;
+    Ok(Json(OriginVerification {
+        token: verification_token(tenant_id, &origin),
+        url: format!("{}{}", origin, VERIFICATION_PATH),
+        origin,
+    }))
+}
@@ -0,0 +1,38 @@
+use crate::config::DatabaseConfig;
+use anyhow::{Context, Result};
+use sqlx::migrate::Migrator;
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use tracing::info;
+
+/// Schema migrations embedded from `migrations/` at build time
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Connect to the SQL store, if one is configured
+pub async fn connect(config: &DatabaseConfig) -> Result<Option<PgPool>> {
+    let Some(url) = &config.url else {
+        return Ok(None);
+    };
+
+    let pool = PgPoolOptions::new()
+        .max_connections(config.max_connections)
+        .acquire_timeout(config.acquire_timeout)
+        .connect(url)
+        .await
+        .context("Failed to connect to the SQL store")?;
+    Ok(Some(pool))
+}
+
+/// Apply pending migrations.
+///
+/// The embedded migrator holds a Postgres advisory lock for the duration of the
+/// run, so replicas starting together apply each migration exactly once and the
+/// rest wait for it to finish.
+pub async fn migrate(pool: &PgPool) -> Result<()> {
+    debug_assert!(MIGRATOR.locking);
+    MIGRATOR
+        .run(pool)
+        .await
+        .context("Failed to apply SQL store migrations")?;
+    info!("SQL store schema is up to date ({} migrations)", MIGRATOR.iter().count());
+    Ok(())
+}
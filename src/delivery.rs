@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use reqwest::{header, redirect, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::cache::CachedExecution;
+use crate::clients::execution::{self, ExecutionClient};
+use crate::config::ResultDeliveryConfig;
+use crate::error::ApiError;
+use crate::execution::{DeliveryState, ExecutionResponse, ResultDelivery, ResultDestination};
+
+/// A result destination checked at submission, before the execution ID is known
+#[derive(Debug, Clone)]
+pub enum ResultTarget {
+    Url(Url),
+    Bucket { base: Url, prefix: String },
+}
+
+impl ResultTarget {
+    /// Where results go, without any signature; bucket targets show the
+    /// execution ID as `{id}`
+    pub fn location(&self) -> String {
+        match self {
+            ResultTarget::Url(url) => location(url),
+            ResultTarget::Bucket { base, prefix } => {
+                format!("{}/{}{{id}}.json", location(base).trim_end_matches('/'), prefix)
+            }
+        }
+    }
+
+    /// URL the result of execution `id` is written to
+    pub fn url_for(&self, id: Uuid) -> Url {
+        match self {
+            ResultTarget::Url(url) => url.clone(),
+            ResultTarget::Bucket { base, prefix } => {
+                let mut url = base.clone();
+                let path = format!("{}/{}{}.json", base.path().trim_end_matches('/'), prefix, id);
+                url.set_path(&path);
+                url
+            }
+        }
+    }
+}
+
+/// Delivery state for a result about to be written to `url`
+pub fn pending(url: &Url) -> ResultDelivery {
+    ResultDelivery {
+        state: DeliveryState::Pending,
+        location: location(url),
+        size_bytes: None,
+        sha256: None,
+        delivered_at: None,
+        error: None,
+    }
+}
+
+/// `url` without its query string, which for pre-signed URLs carries the signature
+pub(crate) fn location(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_query(None);
+    url.set_fragment(None);
+    url.to_string()
+}
+
+/// Parse a URL the gateway will send to, refusing ones it shouldn't reach;
+/// `what` names it in errors, e.g. "Result destination"
+pub(crate) fn parse_external_url(url: &str, allow_http: bool, what: &str) -> Result<Url, ApiError> {
+    let url = Url::parse(url).map_err(|_| ApiError::BadRequest(format!("{} is not a valid URL", what)))?;
+    match url.scheme() {
+        "https" => {}
+        "http" if allow_http => {}
+        _ => return Err(ApiError::BadRequest(format!("{} must use HTTPS", what))),
+    }
+    // Requests are sent from inside the deployment, so targets must not
+    // reach the gateway's own network. Host names are checked again when
+    // resolved, by `pinned_client`
+    let internal = match url.host_str() {
+        Some(host) => match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+            Ok(ip) => !is_public(ip),
+            Err(_) => host.eq_ignore_ascii_case("localhost"),
+        },
+        None => true,
+    };
+    if internal {
+        return Err(ApiError::BadRequest(format!("{} must be publicly reachable", what)));
+    }
+    Ok(url)
+}
+
+/// A host that resolved to an address the gateway mustn't send to
+#[derive(Debug, thiserror::Error)]
+#[error("{0} resolves to an address that isn't publicly reachable")]
+pub(crate) struct NonPublicAddress(String);
+
+/// Client for one request to `url`, a URL `parse_external_url` accepted.
+/// Its host is resolved now and refused if any address isn't public, and
+/// the connection is pinned to the checked addresses, so a second lookup
+/// can't be pointed elsewhere. Redirects aren't followed, as their targets
+/// are unchecked, and proxies aren't used
+pub(crate) async fn pinned_client(url: &Url, timeout: Duration) -> Result<reqwest::Client> {
+    let builder = reqwest::Client::builder()
+        .timeout(timeout)
+        .redirect(redirect::Policy::none())
+        .no_proxy();
+    let host = url.host_str().context("URL has no host")?;
+    let builder = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) if is_public(ip) => builder,
+        Ok(_) => return Err(NonPublicAddress(host.to_string()).into()),
+        Err(_) => {
+            let port = url.port_or_known_default().context("URL has no port")?;
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+                .await
+                .with_context(|| format!("failed to resolve {}", host))?
+                .collect();
+            if addrs.is_empty() {
+                anyhow::bail!("{} did not resolve", host);
+            }
+            if addrs.iter().any(|addr| !is_public(addr.ip())) {
+                return Err(NonPublicAddress(host.to_string()).into());
+            }
+            builder.resolve_to_addrs(host, &addrs)
+        }
+    };
+    Ok(builder.build()?)
+}
+
+/// Whether `ip` is on the public internet, rather than a private, local or
+/// otherwise special-purpose range. IPv6 addresses embedding an IPv4 one
+/// are judged by it
+pub(crate) fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_v4(ip);
+            }
+            // NAT64, 64:ff9b::/96
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                let [.., high, low] = segments;
+                return is_public_v4(Ipv4Addr::from((u32::from(high) << 16) | u32::from(low)));
+            }
+            !(ip.is_multicast()
+                // Unspecified, loopback and the deprecated IPv4-compatible range, ::/96
+                || segments[..6] == [0; 6]
+                // Unique local, fc00::/7
+                || segments[0] & 0xfe00 == 0xfc00
+                // Link-local, fe80::/10, and deprecated site-local, fec0::/10
+                || segments[0] & 0xffc0 == 0xfe80
+                || segments[0] & 0xffc0 == 0xfec0
+                // Documentation, 2001:db8::/32
+                || segments[..2] == [0x2001, 0xdb8])
+        }
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        // "This network", 0.0.0.0/8
+        || a == 0
+        // Shared address space for carrier-grade NAT, 100.64.0.0/10
+        || (a == 100 && b & 0xc0 == 64)
+        // IETF protocol assignments, 192.0.0.0/24
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking, 198.18.0.0/15
+        || (a == 198 && b & 0xfe == 18)
+        // Reserved, 240.0.0.0/4
+        || a >= 240)
+}
+
+#[derive(Default)]
+struct DeliveryCounters {
+    delivered: AtomicU64,
+    failed: AtomicU64,
+    bytes: AtomicU64,
+}
+
+/// Writes finished executions' full results to client-provided object storage
+pub struct ResultDeliveries {
+    config: ResultDeliveryConfig,
+    counters: DeliveryCounters,
+}
+
+impl ResultDeliveries {
+    pub fn new(config: &ResultDeliveryConfig) -> Self {
+        Self {
+            config: config.clone(),
+            counters: DeliveryCounters::default(),
+        }
+    }
+
+    /// Check `destination` before an execution is submitted; `bucket_url` is
+    /// the caller's tenant's linked bucket, if any
+    pub fn target(&self, destination: &ResultDestination, bucket_url: Option<&str>) -> Result<ResultTarget, ApiError> {
+        match destination {
+            ResultDestination::PresignedUrl { url } => Ok(ResultTarget::Url(self.parse(url)?)),
+            ResultDestination::TenantBucket { prefix } => {
+                let bucket_url = bucket_url.ok_or_else(|| {
+                    ApiError::BadRequest("No result bucket is linked to your tenant".to_string())
+                })?;
+                let escapes = prefix.starts_with('/')
+                    || prefix.contains(['?', '#', '\\'])
+                    || prefix.split('/').any(|segment| segment == "." || segment == "..");
+                if escapes {
+                    return Err(ApiError::BadRequest(
+                        "Result prefix must be a relative path without '.' or '..' segments".to_string(),
+                    ));
+                }
+                Ok(ResultTarget::Bucket {
+                    base: self.parse(bucket_url)?,
+                    prefix: prefix.clone(),
+                })
+            }
+        }
+    }
+
+    fn parse(&self, url: &str) -> Result<Url, ApiError> {
+        parse_external_url(url, self.config.allow_http, "Result destination")
+    }
+
+    /// Wait in the background for execution `id` to finish on `client`, write
+    /// its full result to `url` and record the outcome on its cache entry
+    pub fn deliver(
+        self: &Arc<Self>,
+        client: Arc<RwLock<ExecutionClient>>,
+        executions: Arc<RwLock<HashMap<Uuid, CachedExecution>>>,
+        id: Uuid,
+        url: Url,
+    ) {
+        let deliveries = self.clone();
+        tokio::spawn(async move {
+            let outcome = match deliveries.wait_for_result(&client, id).await {
+                Ok(execution) => deliveries.upload(&url, &execution).await,
+                Err(e) => Err(e),
+            };
+            let mut delivery = pending(&url);
+            match outcome {
+                Ok((size_bytes, sha256)) => {
+                    deliveries.counters.delivered.fetch_add(1, Ordering::Relaxed);
+                    deliveries.counters.bytes.fetch_add(size_bytes, Ordering::Relaxed);
+                    info!("Delivered result of execution {} to {}", id, delivery.location);
+                    delivery.state = DeliveryState::Delivered;
+                    delivery.size_bytes = Some(size_bytes);
+                    delivery.sha256 = Some(sha256);
+                    delivery.delivered_at = Some(Utc::now());
+                }
+                Err(e) => {
+                    deliveries.counters.failed.fetch_add(1, Ordering::Relaxed);
+                    warn!("Failed to deliver result of execution {} to {}: {}", id, delivery.location, e);
+                    delivery.state = DeliveryState::Failed;
+                    // How the destination responded stays in the logs, so
+                    // deliveries can't be used to probe what a URL serves
+                    delivery.error = Some("the result could not be delivered".to_string());
+                }
+            }
+            if let Some(cached) = executions.write().await.get_mut(&id) {
+                cached.meta_mut().result_delivery = Some(delivery);
+            }
+        });
+    }
+
+    async fn wait_for_result(&self, client: &RwLock<ExecutionClient>, id: Uuid) -> Result<ExecutionResponse> {
+        let deadline = Instant::now() + self.config.result_timeout;
+        execution::wait_for_result(client, id, self.config.poll_interval, deadline).await
+    }
+
+    /// `PUT` `execution` to `url` as JSON, retrying transient failures; returns
+    /// the document's size and hex SHA-256
+    async fn upload(&self, url: &Url, execution: &ExecutionResponse) -> Result<(u64, String)> {
+        let body = serde_json::to_vec(execution)?;
+        let sha256 = hex::encode(Sha256::digest(&body));
+        let mut backoff = Duration::from_secs(1);
+        let mut attempt = 1;
+        loop {
+            let failure = match pinned_client(url, self.config.request_timeout).await {
+                Err(e) if e.is::<NonPublicAddress>() => return Err(e),
+                Err(e) => e.to_string(),
+                Ok(http) => {
+                    let response = http
+                        .put(url.clone())
+                        .header(header::CONTENT_TYPE, "application/json")
+                        .body(body.clone())
+                        .send()
+                        .await;
+                    // Errors carry the URL, whose query may hold a signature; leave it out
+                    match response {
+                        Ok(response) if response.status().is_success() => return Ok((body.len() as u64, sha256)),
+                        Ok(response)
+                            if !response.status().is_server_error()
+                                && response.status() != StatusCode::TOO_MANY_REQUESTS =>
+                        {
+                            anyhow::bail!("destination rejected the result with {}", response.status())
+                        }
+                        Ok(response) => format!("destination responded with {}", response.status()),
+                        Err(e) => e.without_url().to_string(),
+                    }
+                }
+            };
+            if attempt >= self.config.attempts {
+                anyhow::bail!("{} after {} attempts", failure, attempt);
+            }
+            warn!("Result delivery attempt {} to {} failed: {}", attempt, location(url), failure);
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+
+    /// Delivery outcomes in Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            ("syla_gateway_result_deliveries_total", &self.counters.delivered),
+            ("syla_gateway_result_delivery_failures_total", &self.counters.failed),
+            ("syla_gateway_result_delivery_bytes_total", &self.counters.bytes),
+        ];
+        for (name, counter) in counters {
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, counter.load(Ordering::Relaxed));
+        }
+        out
+    }
+}
@@ -1,11 +1,14 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::i18n::{self, Locale};
+use crate::trace::TraceContext;
+
 #[derive(Error, Debug)]
 pub enum ApiError {
     #[error("Not found")]
@@ -20,31 +23,195 @@ pub enum ApiError {
     #[error("Service unavailable")]
     ServiceUnavailable,
 
+    #[error("The gateway is read-only for maintenance; retry later")]
+    ReadOnly,
+
+    /// With how long until the caller may retry
     #[error("Too many requests")]
-    RateLimited,
+    RateLimited(std::time::Duration),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    /// A call to a backend service failed; the correlation ID was sent with it
+    #[error("Internal server error")]
+    Upstream {
+        correlation_id: String,
+        code: tonic::Code,
+        message: String,
+    },
+
+    #[error("Response of {size} bytes exceeds the {limit} byte limit; fetch output via {logs_url}")]
+    ResponseTooLarge {
+        size: usize,
+        limit: usize,
+        logs_url: String,
+    },
+
+    #[error("Client {client} is no longer supported; upgrade to {minimum} or later")]
+    UpgradeRequired {
+        client: String,
+        minimum: String,
+    },
+}
+
+/// Every `error` code REST responses use, with its HTTP status and meaning
+pub const ERROR_CATALOG: &[(&str, StatusCode, &str)] = &[
+    ("not_found", StatusCode::NOT_FOUND, "The resource does not exist or isn't visible to the caller"),
+    ("bad_request", StatusCode::BAD_REQUEST, "The request was malformed or failed validation"),
+    ("internal_error", StatusCode::INTERNAL_SERVER_ERROR, "The gateway or a backend service failed"),
+    ("service_unavailable", StatusCode::SERVICE_UNAVAILABLE, "A backend service is unavailable; retry later"),
+    ("read_only", StatusCode::SERVICE_UNAVAILABLE, "The gateway only serves reads during maintenance; retry the change later"),
+    ("rate_limited", StatusCode::TOO_MANY_REQUESTS, "Too many requests; retry later"),
+    ("unauthorized", StatusCode::UNAUTHORIZED, "Credentials are missing or invalid"),
+    ("forbidden", StatusCode::FORBIDDEN, "The caller lacks a required scope"),
+    ("conflict", StatusCode::CONFLICT, "The resource isn't in a state that allows the request, e.g. still running"),
+    ("response_too_large", StatusCode::PAYLOAD_TOO_LARGE, "The response exceeds the body limit; fetch output from the logs URL"),
+    ("upgrade_required", StatusCode::UPGRADE_REQUIRED, "The client is older than the minimum supported version"),
+];
+
+impl ApiError {
+    /// Rebuild an equivalent error for another waiter on a shared result
+    pub fn duplicate(&self) -> ApiError {
+        match self {
+            ApiError::NotFound => ApiError::NotFound,
+            ApiError::BadRequest(msg) => ApiError::BadRequest(msg.clone()),
+            ApiError::Internal(e) => ApiError::Internal(anyhow::anyhow!("{:#}", e)),
+            ApiError::ServiceUnavailable => ApiError::ServiceUnavailable,
+            ApiError::ReadOnly => ApiError::ReadOnly,
+            ApiError::RateLimited(retry_after) => ApiError::RateLimited(*retry_after),
+            ApiError::Unauthorized(msg) => ApiError::Unauthorized(msg.clone()),
+            ApiError::Forbidden(msg) => ApiError::Forbidden(msg.clone()),
+            ApiError::Conflict(msg) => ApiError::Conflict(msg.clone()),
+            ApiError::Upstream { correlation_id, code, message } => ApiError::Upstream {
+                correlation_id: correlation_id.clone(),
+                code: *code,
+                message: message.clone(),
+            },
+            ApiError::ResponseTooLarge { size, limit, logs_url } => ApiError::ResponseTooLarge {
+                size: *size,
+                limit: *limit,
+                logs_url: logs_url.clone(),
+            },
+            ApiError::UpgradeRequired { client, minimum } => ApiError::UpgradeRequired {
+                client: client.clone(),
+                minimum: minimum.clone(),
+            },
+        }
+    }
+
+    /// Map a failed upstream call, keeping the correlation ID it was sent with
+    pub fn upstream(correlation_id: impl Into<String>, status: tonic::Status) -> ApiError {
+        match status.code() {
+            tonic::Code::NotFound => ApiError::NotFound,
+            code => ApiError::Upstream {
+                correlation_id: correlation_id.into(),
+                code,
+                message: status.message().to_string(),
+            },
+        }
+    }
+
+    /// Correlation ID of the failed upstream call behind this error, if any
+    pub fn upstream_correlation_id(&self) -> Option<&str> {
+        match self {
+            ApiError::Upstream { correlation_id, .. } => Some(correlation_id),
+            _ => None,
+        }
+    }
+
+    /// Machine-readable context included alongside the message
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            ApiError::ResponseTooLarge { size, limit, logs_url } => Some(serde_json::json!({
+                "size": size,
+                "limit": limit,
+                "logs_url": logs_url,
+            })),
+            ApiError::Upstream { correlation_id, .. } => Some(serde_json::json!({
+                "upstream_correlation_id": correlation_id,
+            })),
+            ApiError::UpgradeRequired { client, minimum } => Some(serde_json::json!({
+                "client": client,
+                "minimum_version": minimum,
+            })),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Serialize)]
 struct ErrorResponse {
     error: String,
     message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trace_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trace_url: Option<String>,
 }
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
-        let (status, error) = match self {
+impl ApiError {
+    /// HTTP status and catalog code REST responses report this error with
+    pub fn status_and_code(&self) -> (StatusCode, &'static str) {
+        match self {
             ApiError::NotFound => (StatusCode::NOT_FOUND, "not_found"),
             ApiError::BadRequest(_) => (StatusCode::BAD_REQUEST, "bad_request"),
-            ApiError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+            ApiError::Internal(_) | ApiError::Upstream { .. } => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal_error")
+            }
             ApiError::ServiceUnavailable => (StatusCode::SERVICE_UNAVAILABLE, "service_unavailable"),
-            ApiError::RateLimited => (StatusCode::TOO_MANY_REQUESTS, "rate_limited"),
-        };
+            ApiError::ReadOnly => (StatusCode::SERVICE_UNAVAILABLE, "read_only"),
+            ApiError::RateLimited(_) => (StatusCode::TOO_MANY_REQUESTS, "rate_limited"),
+            ApiError::Unauthorized(_) => (StatusCode::UNAUTHORIZED, "unauthorized"),
+            ApiError::Forbidden(_) => (StatusCode::FORBIDDEN, "forbidden"),
+            ApiError::Conflict(_) => (StatusCode::CONFLICT, "conflict"),
+            ApiError::ResponseTooLarge { .. } => (StatusCode::PAYLOAD_TOO_LARGE, "response_too_large"),
+            ApiError::UpgradeRequired { .. } => (StatusCode::UPGRADE_REQUIRED, "upgrade_required"),
+        }
+    }
+}
 
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, error) = self.status_and_code();
+
+        let locale = Locale::current();
+        let trace = TraceContext::current();
         let body = Json(ErrorResponse {
             error: error.to_string(),
-            message: self.to_string(),
+            message: i18n::error_message(locale, &self),
+            details: self.details(),
+            trace_id: trace.as_ref().map(|trace| trace.trace_id.clone()),
+            trace_url: trace.and_then(|trace| trace.trace_url),
         });
 
-        (status, body).into_response()
+        let mut response = (status, [(header::CONTENT_LANGUAGE, locale.tag())], body).into_response();
+        if let ApiError::RateLimited(retry_after) = self {
+            if let Ok(value) = HeaderValue::from_str(&retry_after.as_secs_f64().ceil().max(1.0).to_string()) {
+                response.headers_mut().insert(header::RETRY_AFTER, value);
+            }
+        }
+        response
     }
-}
\ No newline at end of file
+}
+
+impl From<tonic::Status> for ApiError {
+    fn from(status: tonic::Status) -> Self {
+        match status.code() {
+            tonic::Code::Unauthenticated => ApiError::Unauthorized(status.message().to_string()),
+            tonic::Code::PermissionDenied => ApiError::Forbidden(status.message().to_string()),
+            tonic::Code::InvalidArgument => ApiError::BadRequest(status.message().to_string()),
+            tonic::Code::NotFound => ApiError::NotFound,
+            tonic::Code::Unavailable => ApiError::ServiceUnavailable,
+            _ => ApiError::Internal(status.into()),
+        }
+    }
+}
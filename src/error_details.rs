@@ -0,0 +1,133 @@
+use axum::body::Bytes;
+use prost::Message;
+use tonic::{Request, Status};
+
+use crate::archive::REQUEST_ID_HEADER;
+use crate::error::ApiError;
+use crate::i18n::{self, Locale};
+use crate::ids;
+
+const REQUEST_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.RequestInfo";
+const LOCALIZED_MESSAGE_TYPE_URL: &str = "type.googleapis.com/google.rpc.LocalizedMessage";
+
+/// `google.rpc.Status`, as carried in the `grpc-status-details-bin` trailer
+#[derive(Clone, PartialEq, Message)]
+struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    code: i32,
+    #[prost(string, tag = "2")]
+    message: String,
+    #[prost(message, repeated, tag = "3")]
+    details: Vec<prost_types::Any>,
+}
+
+/// `google.rpc.RequestInfo`
+#[derive(Clone, PartialEq, Message)]
+struct RequestInfo {
+    #[prost(string, tag = "1")]
+    request_id: String,
+    #[prost(string, tag = "2")]
+    serving_data: String,
+}
+
+/// `google.rpc.LocalizedMessage`
+#[derive(Clone, PartialEq, Message)]
+struct LocalizedMessage {
+    #[prost(string, tag = "1")]
+    locale: String,
+    #[prost(string, tag = "2")]
+    message: String,
+}
+
+/// IDs quoted back to callers on gRPC errors, so a support ticket can be
+/// traced through gateway and upstream logs
+pub struct RequestIds {
+    pub request_id: String,
+    /// Negotiated from `accept-language` metadata
+    pub locale: Locale,
+}
+
+impl RequestIds {
+    /// Use the caller's request ID when it sent one, otherwise assign one
+    pub fn from_request<T>(request: &Request<T>) -> Self {
+        let request_id = request
+            .metadata()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| ids::generate().to_string());
+        let locale = request
+            .metadata()
+            .get("accept-language")
+            .and_then(|v| v.to_str().ok())
+            .map(Locale::negotiate)
+            .unwrap_or_default();
+        Self { request_id, locale }
+    }
+
+    /// Attach a `RequestInfo` detail to an error status that has none yet
+    pub fn attach(&self, status: Status) -> Status {
+        if !status.details().is_empty() {
+            return status;
+        }
+        self.with_info(status, None, None)
+    }
+
+    /// Convert a gateway error to a status with `message`, logging the cause
+    /// and attaching the upstream correlation ID when an upstream call failed.
+    /// The status message stays English; callers that asked for another
+    /// language also get a `LocalizedMessage` detail
+    pub fn error_status(&self, error: ApiError, message: &str) -> Status {
+        let status = match &error {
+            ApiError::NotFound => Status::not_found("Execution not found"),
+            ApiError::ServiceUnavailable | ApiError::ReadOnly => Status::unavailable(message),
+            ApiError::Conflict(_) => Status::failed_precondition(message),
+            ApiError::Upstream { code: tonic::Code::Unavailable, .. } => Status::unavailable(message),
+            _ => Status::internal(message),
+        };
+        if !matches!(error, ApiError::NotFound) {
+            tracing::error!(request_id = %self.request_id, "{}: {}", message, error);
+        }
+        let localized = (self.locale != Locale::En).then(|| LocalizedMessage {
+            locale: self.locale.tag().to_string(),
+            message: i18n::error_message(self.locale, &error),
+        });
+        self.with_info(status, error.upstream_correlation_id(), localized)
+    }
+
+    fn with_info(
+        &self,
+        status: Status,
+        upstream_correlation_id: Option<&str>,
+        localized: Option<LocalizedMessage>,
+    ) -> Status {
+        let info = RequestInfo {
+            request_id: self.request_id.clone(),
+            serving_data: upstream_correlation_id
+                .map(|id| format!("upstream_correlation_id={}", id))
+                .unwrap_or_default(),
+        };
+        let mut details = vec![prost_types::Any {
+            type_url: REQUEST_INFO_TYPE_URL.to_string(),
+            value: info.encode_to_vec(),
+        }];
+        if let Some(localized) = localized {
+            details.push(prost_types::Any {
+                type_url: LOCALIZED_MESSAGE_TYPE_URL.to_string(),
+                value: localized.encode_to_vec(),
+            });
+        }
+        let details = RpcStatus {
+            code: status.code() as i32,
+            message: status.message().to_string(),
+            details,
+        };
+        Status::with_details_and_metadata(
+            status.code(),
+            status.message(),
+            Bytes::from(details.encode_to_vec()),
+            status.metadata().clone(),
+        )
+    }
+}
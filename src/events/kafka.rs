@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use axum::body::Bytes;
+use futures::StreamExt;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::message::Message;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use std::time::Duration;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::{EventBus, EventStream};
+
+/// How long a publish waits for room in the producer queue
+const ENQUEUE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Event bus over Kafka topics
+pub struct KafkaBus {
+    brokers: String,
+    producer: FutureProducer,
+}
+
+impl KafkaBus {
+    pub fn connect(brokers: &str) -> Result<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .create()
+            .context("Failed to create Kafka event bus producer")?;
+        Ok(Self {
+            brokers: brokers.to_string(),
+            producer,
+        })
+    }
+}
+
+#[async_trait]
+impl EventBus for KafkaBus {
+    async fn publish(&self, topic: &str, payload: Bytes) -> Result<()> {
+        let record: FutureRecord<'_, (), [u8]> = FutureRecord::to(topic).payload(payload.as_ref());
+        self.producer
+            .send(record, ENQUEUE_TIMEOUT)
+            .await
+            .map_err(|(e, _)| e)?;
+        Ok(())
+    }
+
+    async fn subscribe(&self, topic: &str) -> Result<EventStream> {
+        // A consumer group per subscription gives every subscriber every
+        // message, matching pub/sub semantics of the other buses
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &self.brokers)
+            .set("group.id", format!("syla-gateway-{}", Uuid::new_v4()))
+            .set("auto.offset.reset", "latest")
+            .set("enable.auto.commit", "false")
+            .create()
+            .context("Failed to create Kafka event bus consumer")?;
+        consumer.subscribe(&[topic])?;
+
+        Ok(futures::stream::unfold(consumer, |consumer| async move {
+            loop {
+                let payload = match consumer.recv().await {
+                    Ok(message) => Bytes::copy_from_slice(message.payload().unwrap_or_default()),
+                    Err(e) => {
+                        warn!("Kafka event bus receive failed: {}", e);
+                        continue;
+                    }
+                };
+                return Some((payload, consumer));
+            }
+        })
+        .boxed())
+    }
+}
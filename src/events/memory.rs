@@ -0,0 +1,50 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::body::Bytes;
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+
+use super::{EventBus, EventStream};
+
+/// Event bus within this process only; the default for single-replica deployments
+pub struct InProcessBus {
+    buffer: usize,
+    topics: Mutex<HashMap<String, broadcast::Sender<Bytes>>>,
+}
+
+impl InProcessBus {
+    /// `buffer` is how many messages a subscriber may fall behind before it misses some
+    pub fn new(buffer: usize) -> Self {
+        Self {
+            buffer: buffer.max(1),
+            topics: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn sender(&self, topic: &str) -> broadcast::Sender<Bytes> {
+        let mut topics = self.topics.lock().unwrap();
+        topics
+            .entry(topic.to_string())
+            .or_insert_with(|| broadcast::channel(self.buffer).0)
+            .clone()
+    }
+}
+
+#[async_trait]
+impl EventBus for InProcessBus {
+    async fn publish(&self, topic: &str, payload: Bytes) -> Result<()> {
+        // No subscribers is fine; messages are only for whoever is listening now
+        let _ = self.sender(topic).send(payload);
+        Ok(())
+    }
+
+    async fn subscribe(&self, topic: &str) -> Result<EventStream> {
+        let receiver = self.sender(topic).subscribe();
+        Ok(BroadcastStream::new(receiver)
+            .filter_map(|message| futures::future::ready(message.ok()))
+            .boxed())
+    }
+}
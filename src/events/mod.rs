@@ -0,0 +1,124 @@
+//! Event distribution between gateway features and replicas.
+//!
+//! Streaming, webhooks, cache invalidation and metering publish and consume
+//! through one [`EventBus`], so the transport is chosen once in config rather
+//! than per feature. The in-process bus is always available; the Redis, NATS
+//! and Kafka buses are compiled in with the `redis-bus`, `nats-bus` and
+//! `kafka-bus` features.
+
+mod memory;
+#[cfg(feature = "kafka-bus")]
+mod kafka;
+#[cfg(feature = "nats-bus")]
+mod nats;
+#[cfg(feature = "redis-bus")]
+mod redis;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::body::Bytes;
+use chrono::{DateTime, Utc};
+use futures::stream::{BoxStream, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::config::{EventBusBackend, EventBusConfig};
+use crate::execution::ExecutionStatus;
+
+pub use memory::InProcessBus;
+
+/// Execution status transitions, as [`ExecutionEvent`]s
+pub const EXECUTION_EVENTS_TOPIC: &str = "syla.gateway.execution-events";
+/// Upstream state changes other replicas should drop from their caches
+pub const CACHE_INVALIDATION_TOPIC: &str = "syla.gateway.cache-invalidation";
+/// Usage records for billing
+pub const METERING_TOPIC: &str = "syla.gateway.metering";
+/// Executions the watchdog flagged as stuck, as [`StuckExecution`](crate::watchdog::StuckExecution)s
+pub const WATCHDOG_ALERTS_TOPIC: &str = "syla.gateway.watchdog-alerts";
+/// Tenants' CORS policy changes, as [`CorsPolicyChange`](crate::cors::CorsPolicyChange)s
+pub const CORS_POLICIES_TOPIC: &str = "syla.gateway.cors-policies";
+
+/// Raw payloads received on a topic
+pub type EventStream = BoxStream<'static, Bytes>;
+
+/// Publish/subscribe transport shared by every event-driven feature.
+///
+/// Delivery is best-effort and at-most-once: subscribers see messages
+/// published after they subscribed, and a slow subscriber may miss some.
+#[async_trait]
+pub trait EventBus: Send + Sync {
+    async fn publish(&self, topic: &str, payload: Bytes) -> Result<()>;
+
+    async fn subscribe(&self, topic: &str) -> Result<EventStream>;
+}
+
+/// Connect the configured event bus backend
+pub async fn connect(config: &EventBusConfig) -> Result<Arc<dyn EventBus>> {
+    match config.backend {
+        EventBusBackend::InProcess => Ok(Arc::new(InProcessBus::new(config.buffer))),
+        #[cfg(feature = "redis-bus")]
+        EventBusBackend::Redis => Ok(Arc::new(redis::RedisBus::connect(required_url(config)?).await?)),
+        #[cfg(feature = "nats-bus")]
+        EventBusBackend::Nats => Ok(Arc::new(nats::NatsBus::connect(required_url(config)?).await?)),
+        #[cfg(feature = "kafka-bus")]
+        EventBusBackend::Kafka => Ok(Arc::new(kafka::KafkaBus::connect(required_url(config)?)?)),
+        #[allow(unreachable_patterns)]
+        backend => Err(anyhow::anyhow!(
+            "Event bus backend {:?} is not compiled into this build",
+            backend
+        )),
+    }
+}
+
+#[cfg(any(feature = "redis-bus", feature = "nats-bus", feature = "kafka-bus"))]
+fn required_url(config: &EventBusConfig) -> Result<&str> {
+    config
+        .url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("EVENT_BUS_URL is required for the {:?} event bus", config.backend))
+}
+
+/// Serialize `value` as JSON and publish it on `topic`
+pub async fn publish_json<T: Serialize + ?Sized>(bus: &dyn EventBus, topic: &str, value: &T) -> Result<()> {
+    let payload = serde_json::to_vec(value)?;
+    bus.publish(topic, Bytes::from(payload)).await
+}
+
+/// Subscribe to `topic`, decoding each message as JSON and skipping ones that don't decode
+pub async fn subscribe_json<T: DeserializeOwned + Send + 'static>(
+    bus: &dyn EventBus,
+    topic: &str,
+) -> Result<BoxStream<'static, T>> {
+    let topic_name = topic.to_string();
+    let stream = bus.subscribe(topic).await?;
+    Ok(stream
+        .filter_map(move |payload| {
+            let decoded = serde_json::from_slice(&payload)
+                .map_err(|e| warn!("Dropping undecodable message on {}: {}", topic_name, e))
+                .ok();
+            futures::future::ready(decoded)
+        })
+        .boxed())
+}
+
+/// An execution changed status; published for streaming and webhook delivery
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionEvent {
+    pub execution_id: Uuid,
+    pub status: ExecutionStatus,
+    pub previous: Option<ExecutionStatus>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// An execution's upstream state changed on the replica that published this
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheInvalidation {
+    pub execution_id: Uuid,
+    /// Publishing replica, which ignores its own invalidations
+    pub origin: Uuid,
+    /// The execution was purged, so replicas drop it instead of refetching
+    #[serde(default)]
+    pub purged: bool,
+}
@@ -0,0 +1,33 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use axum::body::Bytes;
+use futures::StreamExt;
+
+use super::{EventBus, EventStream};
+
+/// Event bus over core NATS subjects
+pub struct NatsBus {
+    client: async_nats::Client,
+}
+
+impl NatsBus {
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = async_nats::connect(url)
+            .await
+            .context("Failed to connect to the NATS event bus")?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl EventBus for NatsBus {
+    async fn publish(&self, topic: &str, payload: Bytes) -> Result<()> {
+        self.client.publish(topic.to_string(), payload).await?;
+        Ok(())
+    }
+
+    async fn subscribe(&self, topic: &str) -> Result<EventStream> {
+        let subscriber = self.client.subscribe(topic.to_string()).await?;
+        Ok(subscriber.map(|message| message.payload).boxed())
+    }
+}
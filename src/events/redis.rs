@@ -0,0 +1,44 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use axum::body::Bytes;
+use futures::StreamExt;
+use redis::aio::MultiplexedConnection;
+use redis::AsyncCommands;
+
+use super::{EventBus, EventStream};
+
+/// Event bus over Redis pub/sub
+pub struct RedisBus {
+    client: redis::Client,
+    publisher: MultiplexedConnection,
+}
+
+impl RedisBus {
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = redis::Client::open(url).context("Invalid Redis event bus URL")?;
+        let publisher = client
+            .get_multiplexed_async_connection()
+            .await
+            .context("Failed to connect to the Redis event bus")?;
+        Ok(Self { client, publisher })
+    }
+}
+
+#[async_trait]
+impl EventBus for RedisBus {
+    async fn publish(&self, topic: &str, payload: Bytes) -> Result<()> {
+        let mut publisher = self.publisher.clone();
+        let _: i64 = publisher.publish(topic, payload.as_ref()).await?;
+        Ok(())
+    }
+
+    async fn subscribe(&self, topic: &str) -> Result<EventStream> {
+        // Each subscription holds its own connection, as Redis requires
+        let mut pubsub = self.client.get_async_pubsub().await?;
+        pubsub.subscribe(topic).await?;
+        Ok(pubsub
+            .into_on_message()
+            .map(|message| Bytes::copy_from_slice(message.get_payload_bytes()))
+            .boxed())
+    }
+}
@@ -1,14 +1,663 @@
+use base64::Engine;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::canary::Backend;
+use crate::config::ResourceCapsConfig;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateExecutionRequest {
-    pub code: String,
+    /// Empty when the code comes from `upload_id`. Shared, so keeping the
+    /// request for resubmission, mirroring it and echoing it don't copy the code
+    #[serde(default)]
+    pub code: Arc<str>,
     pub language: String,
+    /// Runtime version to run under, e.g. `3.11`; the execution service's
+    /// default when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language_version: Option<String>,
     pub timeout_seconds: Option<u64>,
     pub args: Option<Vec<String>>,
     pub workspace_id: Option<Uuid>,
+    /// Environment variables set for the execution
+    pub env: Option<HashMap<String, String>>,
+    pub resources: Option<ResourceLimits>,
+    /// Isolation to run under; sandboxed when unset
+    pub mode: Option<IsolationMode>,
+    /// Run the program attached to a terminal
+    pub tty: Option<bool>,
+    /// IDE session the execution was triggered from
+    pub session_id: Option<String>,
+    /// Completed upload holding the code, for code too large to send inline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_id: Option<Uuid>,
+    /// Where the full result is written once the execution finishes; responses
+    /// then carry only its metadata in place of the output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_destination: Option<ResultDestination>,
+    /// Receives a signed `POST` of the final execution once it finishes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    /// Extra files written next to the code, such as modules or data files
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<ExecutionFile>,
+    /// Caller-defined labels, returned on the execution and filterable with
+    /// `?tag=key:value` when listing
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, String>,
+    /// Scheduling class; normal when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    /// Executions submitted with the same key run one at a time, the rest
+    /// waiting their turn at the gateway
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency_group: Option<String>,
+    /// Batch the execution belongs to, tracked as a whole at `/v1/groups/{id}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    /// Text written to the program's stdin, which is then closed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdin: Option<String>,
+    /// `stdin` as base64, for input that isn't UTF-8 text; at most one of
+    /// the two may be set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdin_base64: Option<String>,
+}
+
+/// Most files one execution can carry
+pub const MAX_EXECUTION_FILES: usize = 100;
+
+/// Most command-line arguments one execution can pass
+pub const MAX_ARGS: usize = 256;
+
+/// Longest command-line argument
+pub const MAX_ARG_BYTES: usize = 4 * 1024;
+
+/// Most bytes of command-line arguments together
+pub const MAX_ARGS_BYTES: usize = 64 * 1024;
+
+/// Longest session ID
+pub const MAX_SESSION_ID_BYTES: usize = 128;
+
+/// Longest concurrency group key
+pub const MAX_CONCURRENCY_GROUP_BYTES: usize = 128;
+
+/// Longest execution group ID
+pub const MAX_GROUP_ID_BYTES: usize = 128;
+
+/// Most bytes of stdin one execution can be given up front
+pub const MAX_STDIN_BYTES: usize = 1024 * 1024;
+
+/// Most environment variables one execution can set
+pub const MAX_ENV_VARS: usize = 128;
+
+/// Longest environment variable name
+pub const MAX_ENV_KEY_BYTES: usize = 256;
+
+/// Longest environment variable value
+pub const MAX_ENV_VALUE_BYTES: usize = 32 * 1024;
+
+/// Most bytes of environment variable names and values together
+pub const MAX_ENV_BYTES: usize = 64 * 1024;
+
+/// Most metadata entries one execution can carry
+pub const MAX_METADATA_ENTRIES: usize = 32;
+
+/// Longest metadata key
+pub const MAX_METADATA_KEY_BYTES: usize = 64;
+
+/// Longest metadata value
+pub const MAX_METADATA_VALUE_BYTES: usize = 256;
+
+/// Most `?tag=` filters one listing can apply
+pub const MAX_TAG_FILTERS: usize = 16;
+
+/// A file written to the execution's working directory before the code runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionFile {
+    /// Relative to the working directory, with `/` separators
+    pub path: String,
+    pub content: String,
+}
+
+/// Object storage the gateway writes a finished execution's full result to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResultDestination {
+    /// A pre-signed URL accepting a `PUT` of the result document
+    PresignedUrl { url: String },
+    /// The bucket linked in the tenant's execution settings, under `prefix`
+    TenantBucket {
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+/// Resources requested for an execution; unset fields use the executor's defaults
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    pub memory_mb: Option<u64>,
+    pub cpu_cores: Option<f64>,
+    /// CPU in thousandths of a core; an alternative to `cpu_cores`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_millis: Option<u64>,
+    pub disk_mb: Option<u64>,
+    pub enable_network: Option<bool>,
+}
+
+impl ResourceLimits {
+    /// Fill fields unset here from `defaults`
+    pub fn or(self, defaults: &ResourceLimits) -> ResourceLimits {
+        let cpu_set = self.cpus().is_some();
+        ResourceLimits {
+            memory_mb: self.memory_mb.or(defaults.memory_mb),
+            cpu_cores: if cpu_set { self.cpu_cores } else { defaults.cpu_cores },
+            cpu_millis: if cpu_set { self.cpu_millis } else { defaults.cpu_millis },
+            disk_mb: self.disk_mb.or(defaults.disk_mb),
+            enable_network: self.enable_network.or(defaults.enable_network),
+        }
+    }
+
+    /// CPU cores asked for, from `cpu_cores` or else `cpu_millis`
+    pub fn cpus(&self) -> Option<f64> {
+        self.cpu_cores
+            .or_else(|| self.cpu_millis.map(|millis| millis as f64 / 1000.0))
+    }
+
+    /// Check the ask is well-formed and within the gateway's `caps`
+    pub fn validate(&self, caps: &ResourceCapsConfig) -> Result<(), String> {
+        first_problem(self.diagnostics(caps))
+    }
+
+    /// Every way the ask is malformed or over the gateway's `caps`
+    pub fn diagnostics(&self, caps: &ResourceCapsConfig) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let cpu_field = if self.cpu_cores.is_some() { "cpu_cores" } else { "cpu_millis" };
+        if self.cpu_cores.is_some() && self.cpu_millis.is_some() {
+            diagnostics.push(Diagnostic::new(
+                "cpu_millis",
+                "conflicting_fields",
+                "Set either cpu_cores or cpu_millis, not both",
+            ));
+        } else if let Some(cpus) = self.cpus() {
+            if !cpus.is_finite() || cpus <= 0.0 {
+                diagnostics.push(Diagnostic::new(cpu_field, "out_of_range", "CPU must be a positive amount"));
+            } else if let Some(max) = caps.max_cpu_millis.filter(|&max| cpus * 1000.0 > max as f64) {
+                diagnostics.push(Diagnostic::new(
+                    cpu_field,
+                    "over_limit",
+                    format!("CPU request of {}m exceeds the maximum of {}m", cpus * 1000.0, max),
+                ));
+            }
+        }
+        let sizes = [
+            ("memory_mb", self.memory_mb, caps.max_memory_mb),
+            ("disk_mb", self.disk_mb, caps.max_disk_mb),
+        ];
+        for (field, requested, max) in sizes {
+            match (requested, max) {
+                (Some(0), _) => {
+                    diagnostics.push(Diagnostic::new(field, "out_of_range", format!("{} must be at least 1", field)))
+                }
+                (Some(requested), Some(max)) if requested > max => diagnostics.push(Diagnostic::new(
+                    field,
+                    "over_limit",
+                    format!("{} of {} exceeds the maximum of {}", field, requested, max),
+                )),
+                _ => {}
+            }
+        }
+        diagnostics
+    }
+}
+
+/// A problem with a request that would make the gateway reject it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    /// Path of the offending field, e.g. `files[2].path`
+    pub field: String,
+    /// Stable machine-readable code
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(field: impl Into<String>, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// The same problem, reported under the parent field `parent`
+    fn within(mut self, parent: &str) -> Self {
+        self.field = format!("{}.{}", parent, self.field);
+        self
+    }
+}
+
+/// The first of `diagnostics` as an error message, for callers that stop at one
+fn first_problem(diagnostics: Vec<Diagnostic>) -> Result<(), String> {
+    match diagnostics.into_iter().next() {
+        Some(diagnostic) => Err(diagnostic.message),
+        None => Ok(()),
+    }
+}
+
+/// How strongly the executor isolates an execution
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IsolationMode {
+    Sandbox,
+    Container,
+    Process,
+}
+
+/// Scheduling class of an execution. The execution service is told it, and
+/// the gateway sheds lower classes first when it's saturated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Priority {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+        }
+    }
+
+    pub fn parse(priority: &str) -> Option<Self> {
+        match priority {
+            "low" => Some(Priority::Low),
+            "normal" => Some(Priority::Normal),
+            "high" => Some(Priority::High),
+            _ => None,
+        }
+    }
+}
+
+/// Overrides applied to a stored request when resubmitting it; `env` and
+/// `metadata` are merged key by key, every other field replaces the original
+/// when present
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResubmitOverrides {
+    pub code: Option<String>,
+    pub language: Option<String>,
+    pub language_version: Option<String>,
+    pub timeout_seconds: Option<u64>,
+    pub args: Option<Vec<String>>,
+    pub workspace_id: Option<Uuid>,
+    pub env: Option<HashMap<String, String>>,
+    pub resources: Option<ResourceLimits>,
+    pub mode: Option<IsolationMode>,
+    pub tty: Option<bool>,
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+impl CreateExecutionRequest {
+    /// Check the code, timeout, environment, resources and files can be
+    /// passed to the executor as given, within the gateway's `caps`
+    pub fn validate(&self, caps: &ResourceCapsConfig) -> Result<(), String> {
+        first_problem(self.diagnostics(caps))
+    }
+
+    /// Every way the request is malformed or over the gateway's `caps`, in
+    /// the order `validate` checks them
+    pub fn diagnostics(&self, caps: &ResourceCapsConfig) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        if self.code.is_empty() {
+            diagnostics.push(Diagnostic::new("code", "missing_code", "Code must not be empty"));
+        } else if let Some(max) = caps.max_code_bytes.filter(|&max| self.code.len() as u64 > max) {
+            diagnostics.push(Diagnostic::new(
+                "code",
+                "over_limit",
+                format!("Code of {} bytes exceeds the maximum of {} bytes", self.code.len(), max),
+            ));
+        }
+        match (self.timeout_seconds, caps.max_timeout_seconds) {
+            (Some(0), _) => diagnostics.push(Diagnostic::new(
+                "timeout_seconds",
+                "out_of_range",
+                "Timeout must be at least one second",
+            )),
+            (Some(timeout), Some(max)) if timeout > max => diagnostics.push(Diagnostic::new(
+                "timeout_seconds",
+                "over_limit",
+                format!("Timeout of {}s exceeds the maximum of {}s", timeout, max),
+            )),
+            _ => {}
+        }
+        if let Some(resources) = &self.resources {
+            diagnostics.extend(resources.diagnostics(caps).into_iter().map(|d| d.within("resources")));
+        }
+        if let Some(args) = &self.args {
+            if let Err(message) = validate_args(args) {
+                diagnostics.push(Diagnostic::new("args", "invalid_args", message));
+            }
+        }
+        if let Some(env) = &self.env {
+            if let Err(message) = validate_env(env) {
+                diagnostics.push(Diagnostic::new("env", "invalid_env", message));
+            }
+        }
+        if let Err(message) = validate_metadata(&self.metadata) {
+            diagnostics.push(Diagnostic::new("metadata", "invalid_metadata", message));
+        }
+        if let Some(session_id) = self.session_id.as_ref().filter(|id| id.len() > MAX_SESSION_ID_BYTES) {
+            diagnostics.push(Diagnostic::new(
+                "session_id",
+                "over_limit",
+                format!(
+                    "Session ID of {} bytes exceeds the maximum of {} bytes",
+                    session_id.len(),
+                    MAX_SESSION_ID_BYTES
+                ),
+            ));
+        }
+        match self.concurrency_group.as_deref() {
+            Some("") => diagnostics.push(Diagnostic::new(
+                "concurrency_group",
+                "invalid_concurrency_group",
+                "Concurrency group must not be empty",
+            )),
+            Some(group) if group.len() > MAX_CONCURRENCY_GROUP_BYTES => diagnostics.push(Diagnostic::new(
+                "concurrency_group",
+                "over_limit",
+                format!(
+                    "Concurrency group of {} bytes exceeds the maximum of {} bytes",
+                    group.len(),
+                    MAX_CONCURRENCY_GROUP_BYTES
+                ),
+            )),
+            _ => {}
+        }
+        match self.group_id.as_deref() {
+            Some("") => diagnostics.push(Diagnostic::new(
+                "group_id",
+                "invalid_group_id",
+                "Group ID must not be empty",
+            )),
+            Some(group_id) if group_id.len() > MAX_GROUP_ID_BYTES => diagnostics.push(Diagnostic::new(
+                "group_id",
+                "over_limit",
+                format!(
+                    "Group ID of {} bytes exceeds the maximum of {} bytes",
+                    group_id.len(),
+                    MAX_GROUP_ID_BYTES
+                ),
+            )),
+            _ => {}
+        }
+        match self.stdin_bytes() {
+            Ok(Some(stdin)) if stdin.len() > MAX_STDIN_BYTES => diagnostics.push(Diagnostic::new(
+                "stdin",
+                "over_limit",
+                format!(
+                    "Stdin of {} bytes exceeds the maximum of {} bytes",
+                    stdin.len(),
+                    MAX_STDIN_BYTES
+                ),
+            )),
+            Ok(_) => {}
+            Err(message) => diagnostics.push(Diagnostic::new("stdin", "invalid_stdin", message)),
+        }
+        if self.files.len() > MAX_EXECUTION_FILES {
+            diagnostics.push(Diagnostic::new(
+                "files",
+                "over_limit",
+                format!("At most {} files may be sent", MAX_EXECUTION_FILES),
+            ));
+        }
+        let mut seen = std::collections::HashSet::new();
+        for (i, file) in self.files.iter().enumerate() {
+            let field = format!("files[{}].path", i);
+            let escapes = file.path.is_empty()
+                || file.path.starts_with('/')
+                || file.path.contains(['\\', '\0'])
+                || file.path.split('/').any(|segment| segment.is_empty() || segment == "." || segment == "..");
+            if escapes {
+                diagnostics.push(Diagnostic::new(
+                    field,
+                    "invalid_path",
+                    format!(
+                        "File path '{}' must be relative, without empty, '.' or '..' segments",
+                        file.path
+                    ),
+                ));
+            } else if !seen.insert(file.path.as_str()) {
+                diagnostics.push(Diagnostic::new(
+                    field,
+                    "duplicate_path",
+                    format!("File path '{}' is given more than once", file.path),
+                ));
+            }
+        }
+        diagnostics
+    }
+
+    /// Bytes to write to the program's stdin, from whichever of `stdin` and
+    /// `stdin_base64` is set
+    pub fn stdin_bytes(&self) -> Result<Option<Vec<u8>>, String> {
+        match (&self.stdin, &self.stdin_base64) {
+            (Some(_), Some(_)) => Err("Only one of stdin and stdin_base64 may be set".to_string()),
+            (Some(stdin), None) => Ok(Some(stdin.clone().into_bytes())),
+            (None, Some(encoded)) => base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .map(Some)
+                .map_err(|e| format!("stdin_base64 is not valid base64: {}", e)),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// The request a resubmission with `overrides` should create
+    pub fn with_overrides(mut self, overrides: ResubmitOverrides) -> Self {
+        if let Some(code) = overrides.code {
+            self.code = code.into();
+        }
+        // A version pinned for the original language doesn't carry over to another
+        if let Some(language) = overrides.language {
+            if !language.eq_ignore_ascii_case(&self.language) {
+                self.language_version = None;
+            }
+            self.language = language;
+        }
+        if overrides.language_version.is_some() {
+            self.language_version = overrides.language_version;
+        }
+        if overrides.timeout_seconds.is_some() {
+            self.timeout_seconds = overrides.timeout_seconds;
+        }
+        if overrides.args.is_some() {
+            self.args = overrides.args;
+        }
+        if overrides.workspace_id.is_some() {
+            self.workspace_id = overrides.workspace_id;
+        }
+        if let Some(env) = overrides.env {
+            self.env.get_or_insert_with(HashMap::new).extend(env);
+        }
+        if let Some(resources) = overrides.resources {
+            let original = self.resources.take().unwrap_or_default();
+            self.resources = Some(resources.or(&original));
+        }
+        if overrides.mode.is_some() {
+            self.mode = overrides.mode;
+        }
+        if overrides.tty.is_some() {
+            self.tty = overrides.tty;
+        }
+        if let Some(metadata) = overrides.metadata {
+            self.metadata.extend(metadata);
+        }
+        self
+    }
+}
+
+/// Check the arguments fit within the executor's limits and can be passed
+/// to a process
+fn validate_args(args: &[String]) -> Result<(), String> {
+    if args.len() > MAX_ARGS {
+        return Err(format!("At most {} arguments may be passed", MAX_ARGS));
+    }
+    let mut total = 0;
+    for (i, arg) in args.iter().enumerate() {
+        if arg.len() > MAX_ARG_BYTES {
+            return Err(format!("Argument {} exceeds {} bytes", i, MAX_ARG_BYTES));
+        }
+        if arg.contains('\0') {
+            return Err(format!("Argument {} must not contain NUL bytes", i));
+        }
+        total += arg.len();
+    }
+    if total > MAX_ARGS_BYTES {
+        return Err(format!("Arguments must total at most {} bytes", MAX_ARGS_BYTES));
+    }
+    Ok(())
+}
+
+/// Check environment variable names are portable (`[A-Za-z_][A-Za-z0-9_]*`)
+/// and the environment fits within the executor's limits
+fn validate_env(env: &HashMap<String, String>) -> Result<(), String> {
+    if env.len() > MAX_ENV_VARS {
+        return Err(format!("At most {} environment variables may be set", MAX_ENV_VARS));
+    }
+    let mut total = 0;
+    for (key, value) in env {
+        let portable = key.len() <= MAX_ENV_KEY_BYTES
+            && key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !portable {
+            return Err(format!(
+                "Environment variable name '{}' must match [A-Za-z_][A-Za-z0-9_]* and be at most {} bytes",
+                key, MAX_ENV_KEY_BYTES
+            ));
+        }
+        if value.contains('\0') {
+            return Err(format!("Environment variable '{}' must not contain NUL bytes", key));
+        }
+        if value.len() > MAX_ENV_VALUE_BYTES {
+            return Err(format!(
+                "Environment variable '{}' exceeds {} bytes",
+                key, MAX_ENV_VALUE_BYTES
+            ));
+        }
+        total += key.len() + value.len();
+    }
+    if total > MAX_ENV_BYTES {
+        return Err(format!("Environment variables must total at most {} bytes", MAX_ENV_BYTES));
+    }
+    Ok(())
+}
+
+/// Check metadata keys can be named in a `key:value` tag filter and the
+/// entries fit within the gateway's limits
+fn validate_metadata(metadata: &HashMap<String, String>) -> Result<(), String> {
+    if metadata.len() > MAX_METADATA_ENTRIES {
+        return Err(format!("At most {} metadata entries may be set", MAX_METADATA_ENTRIES));
+    }
+    for (key, value) in metadata {
+        if key.is_empty() || key.len() > MAX_METADATA_KEY_BYTES || key.contains(':') {
+            return Err(format!(
+                "Metadata key '{}' must be 1-{} bytes without ':'",
+                key, MAX_METADATA_KEY_BYTES
+            ));
+        }
+        if value.len() > MAX_METADATA_VALUE_BYTES {
+            return Err(format!(
+                "Metadata value for '{}' exceeds {} bytes",
+                key, MAX_METADATA_VALUE_BYTES
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A `key:value` filter on execution metadata, as given in `?tag=`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    pub key: String,
+    pub value: String,
+}
+
+impl Tag {
+    /// Split `raw` at its first `:`; values may contain further colons
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        match raw.split_once(':') {
+            Some((key, value)) if !key.is_empty() => Ok(Tag {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+            _ => Err(format!("Tag filter '{}' must have the form key:value", raw)),
+        }
+    }
+
+    /// The `tag` query parameters among `params`, which may repeat
+    pub fn from_query(params: &[(String, String)]) -> Result<Vec<Self>, String> {
+        let tags: Vec<Self> = params
+            .iter()
+            .filter(|(name, _)| name == "tag")
+            .map(|(_, raw)| Tag::parse(raw))
+            .collect::<Result<_, _>>()?;
+        if tags.len() > MAX_TAG_FILTERS {
+            return Err(format!("At most {} tag filters may be given", MAX_TAG_FILTERS));
+        }
+        Ok(tags)
+    }
+
+    /// Whether `metadata` carries every one of `tags`
+    pub fn all_match(tags: &[Tag], metadata: &HashMap<String, String>) -> bool {
+        tags.iter().all(|tag| metadata.get(&tag.key) == Some(&tag.value))
+    }
+}
+
+/// What brought a cancellation about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CancelCause {
+    /// The execution's owner
+    User,
+    /// Another user acting with admin rights, one at a time or in bulk
+    Admin,
+    /// The gateway, once output passed the tenant's cap
+    Quota,
+    /// The watchdog, once the execution sat in one status too long
+    Timeout,
+    /// The execution service on its own, as when it shuts down
+    Shutdown,
+}
+
+/// Who or what cancelled an execution, and why
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cancellation {
+    pub cause: CancelCause,
+    /// User who asked for it; unset when no user did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+    pub reason: String,
+    pub at: DateTime<Utc>,
+}
+
+impl Cancellation {
+    pub fn new(cause: CancelCause, actor: Option<&str>, reason: String) -> Self {
+        Self {
+            cause,
+            actor: actor.map(str::to_string),
+            reason,
+            at: Utc::now(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -19,35 +668,472 @@ pub struct ExecutionResponse {
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub result: Option<ExecutionResult>,
+    /// Pinned executions are exempt from retention cleanup
+    pub pinned: bool,
+    /// Output passed the tenant's cap; the execution was cancelled and its
+    /// stored output truncated
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub output_limit_exceeded: bool,
+    /// The program was run attached to a terminal
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub tty: bool,
+    /// Session the execution was submitted under
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Execution group the execution was submitted under
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    /// Execution backend that ran the execution, reported when it wasn't the primary
+    #[serde(skip_serializing_if = "Backend::is_primary")]
+    pub backend: Backend,
+    /// Execution this one was resubmitted from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resubmitted_from: Option<Uuid>,
+    /// Failed or timed-out execution this one retries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retried_from: Option<Uuid>,
+    /// Who or what cancelled the execution, once it was
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancellation: Option<Cancellation>,
+    /// Notes and scores keyed by the user who left them
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, Annotation>,
+    /// Adjustments the gateway made to the request, reported on the response
+    /// that made them and not stored
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Warning>,
+    /// Progress writing the result to the requested destination; output is
+    /// left out of responses unless delivery failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_delivery: Option<ResultDelivery>,
+    /// Distributed trace of the request that submitted the execution
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace_url: Option<String>,
+    /// Hex SHA-256 of the submitted code, for verifying and deduplicating submissions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_sha256: Option<String>,
+    /// Runtime version the execution was pinned to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_version: Option<String>,
+    /// Caller-defined labels the execution was submitted with
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, String>,
+    /// Submitting user as the execution service records it, for ownership
+    /// checks on executions the gateway has no record of; never serialized
+    #[serde(skip)]
+    pub owner: Option<String>,
 }
 
-#[derive(Debug, Serialize, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryState {
+    /// Waiting for the execution to finish
+    Pending,
+    Delivered,
+    /// Gave up writing the result; output is served inline instead
+    Failed,
+}
+
+/// Where an execution's result was written, in place of its output
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResultDelivery {
+    pub state: DeliveryState,
+    /// Object the result is written to, without any signing query string
+    pub location: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+    /// Hex SHA-256 of the written document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivered_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// How the gateway would handle a request, from `POST /v1/executions:validate`
+#[derive(Debug, Serialize)]
+pub struct ExecutionValidation {
+    /// Whether submitting the request would be accepted
+    pub valid: bool,
+    /// Why it wouldn't be, by field; the other fields are a best effort then
+    pub diagnostics: Vec<Diagnostic>,
+    pub language: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_version: Option<String>,
+    /// The execution service runs unrecognized languages as its default
+    pub language_recognized: bool,
+    /// After tenant defaults and maximums are applied
+    pub timeout_seconds: Option<u64>,
+    pub resources: Option<ResourceLimits>,
+    pub mode: IsolationMode,
+    pub backend: Backend,
+    /// Where the full result would be delivered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_delivery: Option<String>,
+    /// Where the completion webhook would be sent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    pub warnings: Vec<Warning>,
+}
+
+/// A non-fatal notice about how the gateway handled a request
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Warning {
+    /// Stable machine-readable code
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// State change reported by the execution service
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExecutionUpdate {
+    pub status: ExecutionStatus,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub result: Option<ExecutionResult>,
+}
+
+/// Structured feedback attached to a finished execution by its owner or a grader
+#[derive(Debug, Serialize, Clone)]
+pub struct Annotation {
+    pub note: Option<String>,
+    pub score: Option<f64>,
+    /// Free-form structured feedback, e.g. per-criterion rubric results
+    pub data: Option<serde_json::Value>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Longest note accepted on an annotation, in bytes
+pub const MAX_ANNOTATION_NOTE_BYTES: usize = 4 * 1024;
+/// Largest structured payload accepted on an annotation, in serialized bytes
+pub const MAX_ANNOTATION_DATA_BYTES: usize = 16 * 1024;
+
+/// Partial update to the caller's annotation; omitted fields are left unchanged
+#[derive(Debug, Deserialize)]
+pub struct AnnotationPatch {
+    pub note: Option<String>,
+    pub score: Option<f64>,
+    pub data: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum ExecutionStatus {
     Pending,
+    /// Accepted and waiting for executor capacity
+    Queued,
+    /// Assigned to an executor, which is setting up its environment
+    Preparing,
     Running,
+    /// A cancellation was requested and the executor hasn't confirmed it yet
+    Cancelling,
     Completed,
     Failed,
+    Cancelled,
     Timeout,
 }
 
-#[derive(Debug, Serialize, Clone)]
+impl ExecutionStatus {
+    /// Whether the execution has reached a final status
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            ExecutionStatus::Completed
+                | ExecutionStatus::Failed
+                | ExecutionStatus::Cancelled
+                | ExecutionStatus::Timeout
+        )
+    }
+
+    /// Whether a cancellation may still be requested
+    pub fn is_cancellable(&self) -> bool {
+        matches!(
+            self,
+            ExecutionStatus::Pending
+                | ExecutionStatus::Queued
+                | ExecutionStatus::Preparing
+                | ExecutionStatus::Running
+        )
+    }
+
+    /// Name as serialized, also used as the SQL store's status column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExecutionStatus::Pending => "pending",
+            ExecutionStatus::Queued => "queued",
+            ExecutionStatus::Preparing => "preparing",
+            ExecutionStatus::Running => "running",
+            ExecutionStatus::Cancelling => "cancelling",
+            ExecutionStatus::Completed => "completed",
+            ExecutionStatus::Failed => "failed",
+            ExecutionStatus::Cancelled => "cancelled",
+            ExecutionStatus::Timeout => "timeout",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        [
+            ExecutionStatus::Pending,
+            ExecutionStatus::Queued,
+            ExecutionStatus::Preparing,
+            ExecutionStatus::Running,
+            ExecutionStatus::Cancelling,
+            ExecutionStatus::Completed,
+            ExecutionStatus::Failed,
+            ExecutionStatus::Cancelled,
+            ExecutionStatus::Timeout,
+        ]
+        .into_iter()
+        .find(|candidate| candidate.as_str() == status)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ExecutionResult {
     pub exit_code: i32,
     pub stdout: String,
     pub stderr: String,
     pub duration_ms: u64,
+    /// Output contains ANSI escape sequences
+    #[serde(default)]
+    pub ansi: bool,
+    /// Diagnostics the executor reported for a failed compile or run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ExecutionError>,
+    /// Files the program left in its working directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files_created: Vec<CreatedFile>,
+    /// Hex SHA-256 of the final output as stored, before any ANSI stripping
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdout_sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr_sha256: Option<String>,
+}
+
+/// A file an execution produced, downloadable from the artifact store
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CreatedFile {
+    /// Relative to the working directory
+    pub path: String,
+    pub size_bytes: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// Broad class of an executor diagnostic, derived from its code
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionErrorKind {
+    Compile,
+    Runtime,
+    Timeout,
+    /// A memory, disk or other resource limit was hit
+    Resource,
+    Other,
+}
+
+impl ExecutionErrorKind {
+    /// Classify an executor error code such as `COMPILATION_ERROR`
+    pub fn from_code(code: &str) -> Self {
+        let code = code.to_ascii_uppercase();
+        let has = |words: &[&str]| words.iter().any(|word| code.contains(word));
+        if has(&["COMPIL", "SYNTAX", "PARSE", "TYPE_ERROR"]) {
+            ExecutionErrorKind::Compile
+        } else if has(&["TIMEOUT", "DEADLINE"]) {
+            ExecutionErrorKind::Timeout
+        } else if has(&["MEMORY", "OOM", "RESOURCE", "LIMIT", "DISK"]) {
+            ExecutionErrorKind::Resource
+        } else if has(&["RUNTIME", "EXCEPTION", "PANIC", "SIGNAL", "CRASH"]) {
+            ExecutionErrorKind::Runtime
+        } else {
+            ExecutionErrorKind::Other
+        }
+    }
+}
+
+/// A structured diagnostic from the executor, so clients needn't parse stderr
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionError {
+    pub kind: ExecutionErrorKind,
+    /// The executor's own code, e.g. `COMPILATION_ERROR`
+    pub code: String,
+    pub message: String,
+    /// Source position, 1-based, when the executor reported one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stack_trace: Option<String>,
+}
+
+impl ExecutionError {
+    /// Source position in `details`, given either as a JSON object with
+    /// `line`/`column` or as text such as `line 3, column 7` or `3:7`
+    pub fn position(details: &str) -> (Option<u32>, Option<u32>) {
+        if let Ok(serde_json::Value::Object(fields)) = serde_json::from_str(details) {
+            let number = |key: &str| fields.get(key).and_then(|v| v.as_u64()).and_then(|n| u32::try_from(n).ok());
+            return (number("line"), number("column").or_else(|| number("col")));
+        }
+
+        let lower = details.to_ascii_lowercase();
+        let after = |label: &str| {
+            let start = lower.find(label)? + label.len();
+            let digits: String = lower[start..]
+                .trim_start_matches([' ', ':', '='])
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect();
+            digits.parse().ok()
+        };
+        let line = after("line");
+        if line.is_some() {
+            return (line, after("column").or_else(|| after("col")));
+        }
+
+        // `file:line:column` as compilers print it
+        let parts: Vec<&str> = details.split(':').map(str::trim).collect();
+        parts
+            .windows(2)
+            .find_map(|pair| Some((pair[0].parse().ok()?, pair[1].parse().ok()?)))
+            .map_or((None, None), |(line, column)| (Some(line), Some(column)))
+    }
+}
+
+impl ExecutionResult {
+    /// Record whether the output carries escape sequences
+    pub fn detect_ansi(&mut self) {
+        self.ansi = crate::ansi::contains_ansi(&self.stdout) || crate::ansi::contains_ansi(&self.stderr);
+    }
+
+    /// Remove escape sequences from the output
+    pub fn strip_ansi(&mut self) {
+        if self.ansi {
+            self.stdout = crate::ansi::strip_ansi(&self.stdout);
+            self.stderr = crate::ansi::strip_ansi(&self.stderr);
+            self.ansi = false;
+        }
+    }
+
+    /// Checksum the output, once it is final
+    pub fn record_checksums(&mut self) {
+        self.stdout_sha256 = Some(sha256_hex(&self.stdout));
+        self.stderr_sha256 = Some(sha256_hex(&self.stderr));
+    }
+
+    /// Bytes of stdout and stderr together
+    pub fn output_len(&self) -> u64 {
+        (self.stdout.len() + self.stderr.len()) as u64
+    }
+
+    /// Cut output down to `limit` bytes, stdout first, returning whether anything was cut
+    pub fn truncate_output(&mut self, limit: u64) -> bool {
+        if self.output_len() <= limit {
+            return false;
+        }
+        let stdout_limit = limit.min(self.stdout.len() as u64) as usize;
+        truncate_at_char_boundary(&mut self.stdout, stdout_limit);
+        truncate_at_char_boundary(&mut self.stderr, (limit as usize).saturating_sub(self.stdout.len()));
+        true
+    }
+}
+
+/// Lowercase hex SHA-256 of `text`
+pub fn sha256_hex(text: &str) -> String {
+    hex::encode(Sha256::digest(text.as_bytes()))
+}
+
+fn truncate_at_char_boundary(s: &mut String, mut len: usize) {
+    if len >= s.len() {
+        return;
+    }
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    s.truncate(len);
+}
+
+impl AnnotationPatch {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.note.is_none() && self.score.is_none() && self.data.is_none() {
+            return Err("Annotation must set at least one of note, score or data".to_string());
+        }
+        if self.note.as_ref().is_some_and(|note| note.len() > MAX_ANNOTATION_NOTE_BYTES) {
+            return Err(format!("Annotation note exceeds {} bytes", MAX_ANNOTATION_NOTE_BYTES));
+        }
+        if self.score.is_some_and(|score| !score.is_finite()) {
+            return Err("Annotation score must be a finite number".to_string());
+        }
+        if let Some(data) = &self.data {
+            if data.to_string().len() > MAX_ANNOTATION_DATA_BYTES {
+                return Err(format!("Annotation data exceeds {} bytes", MAX_ANNOTATION_DATA_BYTES));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn apply(self, annotation: &mut Annotation) {
+        if let Some(note) = self.note {
+            annotation.note = Some(note);
+        }
+        if let Some(score) = self.score {
+            annotation.score = Some(score);
+        }
+        if let Some(data) = self.data {
+            annotation.data = Some(data);
+        }
+        annotation.updated_at = Utc::now();
+    }
 }
 
 impl ExecutionResponse {
+    /// Render output for a client in `mode`
+    pub fn render_ansi(&mut self, mode: crate::ansi::AnsiMode) {
+        if let (crate::ansi::AnsiMode::Strip, Some(result)) = (mode, self.result.as_mut()) {
+            result.strip_ansi();
+        }
+    }
+
     pub fn new_pending() -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id: crate::ids::generate(),
             status: ExecutionStatus::Pending,
             created_at: Utc::now(),
             started_at: None,
             completed_at: None,
             result: None,
+            pinned: false,
+            output_limit_exceeded: false,
+            tty: false,
+            session_id: None,
+            group_id: None,
+            backend: Backend::Primary,
+            resubmitted_from: None,
+            retried_from: None,
+            cancellation: None,
+            annotations: BTreeMap::new(),
+            warnings: Vec::new(),
+            result_delivery: None,
+            trace_id: None,
+            trace_url: None,
+            code_sha256: None,
+            language_version: None,
+            metadata: HashMap::new(),
+            owner: None,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,368 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::get,
+    Extension, Json, Router,
+};
+use chrono::{DateTime, Utc};
+use futures::stream::{BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+use tokio_util::io::ReaderStream;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::auth::{self, AuthContext, AuthInterceptor};
+use crate::error::ApiError;
+use crate::execution::{Annotation, ExecutionResponse, ExecutionStatus, Tag};
+use crate::ids;
+use crate::state::{AppState, ExecutionFilter};
+
+/// Content type of newline-delimited JSON exports
+pub const JSONL_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Export routes, authenticated; users only ever see their own executions and jobs
+pub fn routes(auth_interceptor: AuthInterceptor) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/executions/export", get(export_executions))
+        .route("/v1/exports/:job_id", get(get_export_job))
+        .route("/v1/exports/:job_id/download", get(download_export))
+        .route_layer(middleware::from_fn_with_state(auth_interceptor, auth::require_auth))
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportQuery {
+    status: Option<ExecutionStatus>,
+    pinned: Option<bool>,
+    /// Also accepted as `from`
+    #[serde(alias = "from")]
+    created_after: Option<DateTime<Utc>>,
+    /// Also accepted as `to`
+    #[serde(alias = "to")]
+    created_before: Option<DateTime<Utc>>,
+    session_id: Option<String>,
+    /// stdout/stderr bytes kept per execution
+    output_limit: Option<usize>,
+    /// Run as a background job even when the export is small
+    #[serde(default, rename = "async")]
+    run_async: bool,
+}
+
+/// One line of an export: execution metadata with outputs truncated
+#[derive(Debug, Serialize)]
+pub struct ExportRecord {
+    pub id: Uuid,
+    pub status: ExecutionStatus,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub exit_code: Option<i32>,
+    pub duration_ms: Option<u64>,
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+    /// Whether stdout or stderr was cut at the output limit
+    pub output_truncated: bool,
+    pub pinned: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, Annotation>,
+}
+
+impl ExportRecord {
+    pub fn new(execution: ExecutionResponse, output_limit: usize) -> Self {
+        let (exit_code, duration_ms, stdout, stderr, output_truncated) = match execution.result {
+            Some(result) => {
+                let (stdout, stdout_truncated) = truncate(result.stdout, output_limit);
+                let (stderr, stderr_truncated) = truncate(result.stderr, output_limit);
+                (
+                    Some(result.exit_code),
+                    Some(result.duration_ms),
+                    Some(stdout),
+                    Some(stderr),
+                    stdout_truncated || stderr_truncated,
+                )
+            }
+            None => (None, None, None, None, false),
+        };
+
+        Self {
+            id: execution.id,
+            status: execution.status,
+            created_at: execution.created_at,
+            started_at: execution.started_at,
+            completed_at: execution.completed_at,
+            exit_code,
+            duration_ms,
+            stdout,
+            stderr,
+            output_truncated,
+            pinned: execution.pinned,
+            session_id: execution.session_id,
+            metadata: execution.metadata,
+            annotations: execution.annotations,
+        }
+    }
+
+    fn to_line(&self) -> Vec<u8> {
+        let mut line = serde_json::to_vec(self).unwrap_or_default();
+        line.push(b'\n');
+        line
+    }
+}
+
+/// Cut `output` to at most `limit` bytes on a character boundary
+fn truncate(mut output: String, limit: usize) -> (String, bool) {
+    if output.len() <= limit {
+        return (output, false);
+    }
+    let mut end = limit;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    output.truncate(end);
+    (output, true)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportState {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// A background export too large to stream in the request that asked for it
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportJob {
+    pub id: Uuid,
+    pub state: ExportState,
+    /// Executions the export may write; ones that turn out not to match,
+    /// or are deleted before they're reached, are skipped
+    pub total: usize,
+    pub rows: u64,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    pub download_url: String,
+    #[serde(skip)]
+    owner: String,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+/// Export jobs in progress or awaiting download
+#[derive(Default)]
+pub struct ExportJobs {
+    jobs: RwLock<HashMap<Uuid, ExportJob>>,
+}
+
+impl ExportJobs {
+    /// A job owned by `user_id`; other users' jobs are reported as not found
+    pub async fn get(&self, id: Uuid, user_id: &str) -> Option<ExportJob> {
+        self.jobs
+            .read()
+            .await
+            .get(&id)
+            .filter(|job| job.owner == user_id)
+            .cloned()
+    }
+
+    async fn insert(&self, job: ExportJob) {
+        self.jobs.write().await.insert(job.id, job);
+    }
+
+    async fn finish(&self, id: Uuid, outcome: anyhow::Result<u64>) {
+        let mut jobs = self.jobs.write().await;
+        let Some(job) = jobs.get_mut(&id) else {
+            return;
+        };
+        job.completed_at = Some(Utc::now());
+        match outcome {
+            Ok(rows) => {
+                job.state = ExportState::Completed;
+                job.rows = rows;
+            }
+            Err(e) => {
+                job.state = ExportState::Failed;
+                job.error = Some(e.to_string());
+            }
+        }
+    }
+
+    async fn remove(&self, id: Uuid) -> Option<ExportJob> {
+        self.jobs.write().await.remove(&id)
+    }
+}
+
+async fn export_executions(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Query(query): Query<ExportQuery>,
+    Query(params): Query<Vec<(String, String)>>,
+) -> Result<Response, ApiError> {
+    let config = &state.config().export;
+    let output_limit = query
+        .output_limit
+        .unwrap_or(config.output_limit_bytes)
+        .min(config.max_output_limit_bytes);
+    if let (Some(from), Some(to)) = (query.created_after, query.created_before) {
+        if from > to {
+            return Err(ApiError::BadRequest(format!(
+                "Export range starts at {} after it ends at {}",
+                from, to
+            )));
+        }
+    }
+    let filter = ExecutionFilter {
+        status: query.status,
+        pinned: query.pinned,
+        created_after: query.created_after,
+        created_before: query.created_before,
+        session_id: query.session_id,
+        group_id: None,
+        tags: Tag::from_query(&params).map_err(ApiError::BadRequest)?,
+        limit: usize::MAX,
+    };
+    let total = state.count_matching_executions(&auth_context, &filter).await;
+    let executions = state.matching_executions(&auth_context, filter);
+
+    if query.run_async || total > config.sync_max_rows {
+        let job = start_job(state.clone(), auth_context.user_id, executions, total, output_limit).await;
+        let location = format!("/v1/exports/{}", job.id);
+        return Ok((
+            StatusCode::ACCEPTED,
+            [(header::LOCATION, location)],
+            Json(job),
+        )
+            .into_response());
+    }
+
+    // Read from the store a page at a time as the body is written, so
+    // executions deleted mid-export are skipped rather than resurrected
+    let body = executions.map(move |execution| {
+        Ok::<_, std::io::Error>(Bytes::from(ExportRecord::new(execution, output_limit).to_line()))
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, JSONL_CONTENT_TYPE)],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+async fn start_job(
+    state: Arc<AppState>,
+    owner: String,
+    executions: BoxStream<'static, ExecutionResponse>,
+    total: usize,
+    output_limit: usize,
+) -> ExportJob {
+    let id = ids::generate();
+    let job = ExportJob {
+        id,
+        state: ExportState::Running,
+        total,
+        rows: 0,
+        created_at: Utc::now(),
+        completed_at: None,
+        error: None,
+        download_url: format!("/v1/exports/{}/download", id),
+        owner,
+        path: state.config().export.dir.join(format!("{}.jsonl", id)),
+    };
+    state.exports().insert(job.clone()).await;
+
+    let path = job.path.clone();
+    tokio::spawn(async move {
+        let outcome = write_export(executions, output_limit, &path).await;
+        match &outcome {
+            Ok(rows) => info!("Export {} completed with {} rows", id, rows),
+            Err(e) => warn!("Export {} failed: {}", id, e),
+        }
+        state.exports().finish(id, outcome).await;
+
+        // Keep the result around long enough to download, then clean up
+        tokio::time::sleep(state.config().export.job_ttl).await;
+        if let Some(job) = state.exports().remove(id).await {
+            if let Err(e) = tokio::fs::remove_file(&job.path).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to remove export file {}: {}", job.path.display(), e);
+                }
+            }
+        }
+    });
+
+    job
+}
+
+async fn write_export(
+    mut executions: BoxStream<'static, ExecutionResponse>,
+    output_limit: usize,
+    path: &std::path::Path,
+) -> anyhow::Result<u64> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut writer = tokio::io::BufWriter::new(tokio::fs::File::create(path).await?);
+
+    let mut rows = 0;
+    while let Some(execution) = executions.next().await {
+        writer
+            .write_all(&ExportRecord::new(execution, output_limit).to_line())
+            .await?;
+        rows += 1;
+    }
+    writer.flush().await?;
+    Ok(rows)
+}
+
+async fn get_export_job(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<ExportJob>, ApiError> {
+    let job = state
+        .exports()
+        .get(job_id, &auth_context.user_id)
+        .await
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(job))
+}
+
+async fn download_export(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(job_id): Path<Uuid>,
+) -> Result<Response, ApiError> {
+    let job = state
+        .exports()
+        .get(job_id, &auth_context.user_id)
+        .await
+        .ok_or(ApiError::NotFound)?;
+    if job.state != ExportState::Completed {
+        return Err(ApiError::BadRequest(format!("Export {} is not complete", job_id)));
+    }
+
+    let file = tokio::fs::File::open(&job.path)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    let disposition = format!("attachment; filename=\"executions-{}.jsonl\"", job_id);
+    Ok((
+        [
+            (header::CONTENT_TYPE, JSONL_CONTENT_TYPE.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    )
+        .into_response())
+}
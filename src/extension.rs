@@ -0,0 +1,262 @@
+//! Lifecycle hooks for optional subsystems.
+//!
+//! Background work such as the pool autoscaler, purger, watchdog and alerting
+//! implements [`GatewayExtension`] and is registered with [`Extensions`]
+//! rather than being started from `main()`. Custom builds register their own
+//! extensions (metrics exporters, schedulers) the same way.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::state::AppState;
+
+/// A subsystem started with the gateway and stopped with it
+#[async_trait]
+pub trait GatewayExtension: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Called once state is initialized, before the listeners accept
+    /// requests; an error aborts startup
+    async fn on_startup(&self, _state: &Arc<AppState>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called after both listeners have drained
+    async fn on_shutdown(&self, _state: &Arc<AppState>) {}
+
+    /// Called with freshly read configuration on SIGHUP; extensions apply
+    /// whatever they can change at runtime
+    async fn on_config_reload(&self, _state: &Arc<AppState>, _config: &Config) {}
+}
+
+/// Registered extensions, started in registration order and stopped in reverse
+#[derive(Default)]
+pub struct Extensions {
+    extensions: Vec<Box<dyn GatewayExtension>>,
+}
+
+impl Extensions {
+    /// The gateway's own background subsystems
+    pub fn builtin() -> Self {
+        Self::default()
+            .register(CacheInvalidation)
+            .register(TransitionRelay)
+            .register(CorsSync)
+            .register(PoolAutoscaler)
+            .register(Purger)
+            .register(Watchdog)
+            .register(AlertMonitor)
+            .register(DeliveryOutbox)
+            .register(Scheduler)
+            .register(PipelineRunner)
+            .register(GroupMonitor)
+    }
+
+    pub fn register(mut self, extension: impl GatewayExtension + 'static) -> Self {
+        self.extensions.push(Box::new(extension));
+        self
+    }
+
+    pub async fn startup(&self, state: &Arc<AppState>) -> Result<()> {
+        for extension in &self.extensions {
+            extension
+                .on_startup(state)
+                .await
+                .with_context(|| format!("Failed to start extension '{}'", extension.name()))?;
+            info!(extension = extension.name(), "Started extension");
+        }
+        Ok(())
+    }
+
+    pub async fn shutdown(&self, state: &Arc<AppState>) {
+        for extension in self.extensions.iter().rev() {
+            extension.on_shutdown(state).await;
+            info!(extension = extension.name(), "Stopped extension");
+        }
+    }
+
+    pub async fn reload(&self, state: &Arc<AppState>, config: &Config) {
+        for extension in &self.extensions {
+            extension.on_config_reload(state, config).await;
+        }
+        info!(extensions = self.extensions.len(), "Configuration reloaded");
+    }
+}
+
+/// Drops cached executions other replicas report changes to
+struct CacheInvalidation;
+
+#[async_trait]
+impl GatewayExtension for CacheInvalidation {
+    fn name(&self) -> &'static str {
+        "cache-invalidation"
+    }
+
+    async fn on_startup(&self, state: &Arc<AppState>) -> Result<()> {
+        state.spawn_cache_invalidation().await
+    }
+}
+
+struct TransitionRelay;
+
+#[async_trait]
+impl GatewayExtension for TransitionRelay {
+    fn name(&self) -> &'static str {
+        "transition-relay"
+    }
+
+    async fn on_startup(&self, state: &Arc<AppState>) -> Result<()> {
+        state.spawn_transition_relay().await
+    }
+}
+
+/// Keeps tenants' CORS policies in step with the store and other replicas
+struct CorsSync;
+
+#[async_trait]
+impl GatewayExtension for CorsSync {
+    fn name(&self) -> &'static str {
+        "cors-sync"
+    }
+
+    async fn on_startup(&self, state: &Arc<AppState>) -> Result<()> {
+        state.spawn_cors_sync().await
+    }
+}
+
+struct PoolAutoscaler;
+
+#[async_trait]
+impl GatewayExtension for PoolAutoscaler {
+    fn name(&self) -> &'static str {
+        "pool-autoscaler"
+    }
+
+    async fn on_startup(&self, state: &Arc<AppState>) -> Result<()> {
+        state.spawn_pool_autoscaler();
+        Ok(())
+    }
+}
+
+struct Purger;
+
+#[async_trait]
+impl GatewayExtension for Purger {
+    fn name(&self) -> &'static str {
+        "purger"
+    }
+
+    async fn on_startup(&self, state: &Arc<AppState>) -> Result<()> {
+        state.spawn_purger();
+        Ok(())
+    }
+}
+
+struct Watchdog;
+
+#[async_trait]
+impl GatewayExtension for Watchdog {
+    fn name(&self) -> &'static str {
+        "watchdog"
+    }
+
+    async fn on_startup(&self, state: &Arc<AppState>) -> Result<()> {
+        state.spawn_watchdog();
+        Ok(())
+    }
+}
+
+struct AlertMonitor;
+
+#[async_trait]
+impl GatewayExtension for AlertMonitor {
+    fn name(&self) -> &'static str {
+        "alert-monitor"
+    }
+
+    async fn on_startup(&self, state: &Arc<AppState>) -> Result<()> {
+        state.spawn_alert_monitor();
+        Ok(())
+    }
+}
+
+struct DeliveryOutbox;
+
+#[async_trait]
+impl GatewayExtension for DeliveryOutbox {
+    fn name(&self) -> &'static str {
+        "delivery-outbox"
+    }
+
+    async fn on_startup(&self, state: &Arc<AppState>) -> Result<()> {
+        state.spawn_outbox_dispatcher();
+        Ok(())
+    }
+}
+
+struct Scheduler;
+
+#[async_trait]
+impl GatewayExtension for Scheduler {
+    fn name(&self) -> &'static str {
+        "scheduler"
+    }
+
+    async fn on_startup(&self, state: &Arc<AppState>) -> Result<()> {
+        state.spawn_scheduler();
+        Ok(())
+    }
+}
+
+struct PipelineRunner;
+
+#[async_trait]
+impl GatewayExtension for PipelineRunner {
+    fn name(&self) -> &'static str {
+        "pipeline-runner"
+    }
+
+    async fn on_startup(&self, state: &Arc<AppState>) -> Result<()> {
+        state.spawn_pipeline_runner();
+        Ok(())
+    }
+}
+
+struct GroupMonitor;
+
+#[async_trait]
+impl GatewayExtension for GroupMonitor {
+    fn name(&self) -> &'static str {
+        "concurrency-group-monitor"
+    }
+
+    async fn on_startup(&self, state: &Arc<AppState>) -> Result<()> {
+        state.spawn_group_monitor();
+        Ok(())
+    }
+}
+
+/// Dispatch config reloads to `extensions` on every SIGHUP
+pub fn spawn_reload_listener(extensions: Arc<Extensions>, state: Arc<AppState>) {
+    #[cfg(unix)]
+    tokio::spawn(async move {
+        let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                warn!("Failed to install SIGHUP handler, config reload disabled: {}", e);
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            info!("Reload requested");
+            extensions.reload(&state, &Config::from_env()).await;
+        }
+    });
+    #[cfg(not(unix))]
+    let _ = (extensions, state);
+}
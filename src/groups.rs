@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    middleware,
+    routing::get,
+    Extension, Json, Router,
+};
+use chrono::{DateTime, Utc};
+use futures::stream::StreamExt;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::auth::{self, AuthContext, AuthInterceptor};
+use crate::error::ApiError;
+use crate::execution::{ExecutionResponse, ExecutionStatus};
+use crate::state::{AppState, ExecutionFilter};
+
+/// Unfinished members read from the execution service at once
+const MEMBER_REFRESH_CONCURRENCY: usize = 16;
+
+/// Execution group routes, authenticated; a group only covers the caller's
+/// own executions
+pub fn routes(auth_interceptor: AuthInterceptor) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/groups/:id", get(get_group))
+        .route_layer(middleware::from_fn_with_state(auth_interceptor, auth::require_auth))
+}
+
+/// Members of a group by where they've got to. Cancelled and timed-out
+/// executions count as failed
+#[derive(Debug, Default, Serialize)]
+pub struct GroupCounts {
+    /// Pending, queued or preparing
+    pub pending: usize,
+    pub running: usize,
+    pub completed: usize,
+    pub failed: usize,
+}
+
+/// One execution of a group
+#[derive(Debug, Serialize)]
+pub struct GroupMember {
+    pub id: Uuid,
+    pub status: ExecutionStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Executions submitted under one `group_id`, in submission order
+#[derive(Debug, Serialize)]
+pub struct ExecutionGroup {
+    pub id: String,
+    pub total: usize,
+    pub counts: GroupCounts,
+    /// Every member has finished
+    pub finished: bool,
+    pub members: Vec<GroupMember>,
+}
+
+impl ExecutionGroup {
+    /// Summarize `executions`, given newest first
+    pub fn new(id: String, executions: Vec<ExecutionResponse>) -> Self {
+        let mut counts = GroupCounts::default();
+        let members: Vec<GroupMember> = executions
+            .into_iter()
+            .rev()
+            .map(|execution| {
+                match execution.status {
+                    ExecutionStatus::Pending | ExecutionStatus::Queued | ExecutionStatus::Preparing => {
+                        counts.pending += 1
+                    }
+                    ExecutionStatus::Running | ExecutionStatus::Cancelling => counts.running += 1,
+                    ExecutionStatus::Completed => counts.completed += 1,
+                    ExecutionStatus::Failed | ExecutionStatus::Cancelled | ExecutionStatus::Timeout => {
+                        counts.failed += 1
+                    }
+                }
+                GroupMember {
+                    id: execution.id,
+                    exit_code: execution.result.as_ref().map(|result| result.exit_code),
+                    duration_ms: execution.result.as_ref().map(|result| result.duration_ms),
+                    status: execution.status,
+                    created_at: execution.created_at,
+                    completed_at: execution.completed_at,
+                }
+            })
+            .collect();
+        Self {
+            id,
+            total: members.len(),
+            finished: counts.pending + counts.running == 0,
+            counts,
+            members,
+        }
+    }
+}
+
+/// Aggregate status of the caller's executions submitted under a group.
+/// With the SQL store, members submitted through any replica are included;
+/// unfinished members are read afresh from the execution service
+async fn get_group(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<ExecutionGroup>, ApiError> {
+    let filter = ExecutionFilter {
+        status: None,
+        pinned: None,
+        created_after: None,
+        created_before: None,
+        session_id: None,
+        group_id: Some(id.clone()),
+        tags: Vec::new(),
+        limit: usize::MAX,
+    };
+    let executions = state.list_stored_executions(&auth_context, &filter).await;
+    if executions.is_empty() {
+        return Err(ApiError::NotFound);
+    }
+    let executions = futures::stream::iter(executions)
+        .map(|execution| {
+            let state = &state;
+            async move {
+                if execution.status.is_terminal() {
+                    return execution;
+                }
+                state.get_execution(execution.id).await.unwrap_or(execution)
+            }
+        })
+        .buffered(MEMBER_REFRESH_CONCURRENCY)
+        .collect()
+        .await;
+    Ok(Json(ExecutionGroup::new(id, executions)))
+}
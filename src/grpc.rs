@@ -1,12 +1,19 @@
 use std::sync::Arc;
-use tonic::{Request, Response, Status};
+use base64::Engine;
+use tonic::{metadata::MetadataValue, Request, Response, Status};
 use uuid::Uuid;
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
 use crate::{
-    auth::AuthInterceptor,
+    auth::{self, AuthInterceptor, ADMIN_SCOPE},
+    cache::STALE_HEADER,
+    canary::Backend,
+    error::ApiError,
+    error_details::RequestIds,
+    output::{Offsets, OutputEvent, OutputStart, ResumeToken},
     proto::*,
     state::AppState,
 };
-use tracing::{debug, info, error};
+use tracing::{debug, info};
 
 /// gRPC service implementation for Syla Gateway
 pub struct SylaGatewayService {
@@ -21,6 +28,204 @@ impl SylaGatewayService {
             auth_interceptor,
         }
     }
+
+    /// Reject calls to route groups disabled for this deployment
+    fn ensure_enabled(&self, enabled: bool, surface: &str) -> Result<(), Box<Status>> {
+        if enabled {
+            Ok(())
+        } else {
+            Err(Box::new(Status::unimplemented(format!(
+                "{} API is disabled in this deployment",
+                surface
+            ))))
+        }
+    }
+
+    fn ensure_executions_enabled(&self) -> Result<(), Box<Status>> {
+        self.ensure_enabled(self.state.config().surface.executions, "Executions")
+    }
+
+    fn ensure_workspaces_enabled(&self) -> Result<(), Box<Status>> {
+        self.ensure_enabled(self.state.config().surface.workspaces, "Workspaces")
+    }
+}
+
+/// Convert the gateway's execution status to the public proto enum, which
+/// has no preparing or cancelling status
+pub fn status_to_proto(status: &crate::execution::ExecutionStatus) -> i32 {
+    use crate::execution::ExecutionStatus as Status;
+    let status = match status {
+        Status::Pending => ExecutionStatus::Pending,
+        Status::Queued | Status::Preparing => ExecutionStatus::Queued,
+        Status::Running | Status::Cancelling => ExecutionStatus::Running,
+        Status::Completed => ExecutionStatus::Completed,
+        Status::Failed => ExecutionStatus::Failed,
+        Status::Cancelled => ExecutionStatus::Cancelled,
+        Status::Timeout => ExecutionStatus::Timeout,
+    };
+    status as i32
+}
+
+/// Convert the gateway's execution result to the public proto message
+pub fn result_to_proto(r: crate::execution::ExecutionResult) -> ExecutionResult {
+    ExecutionResult {
+        exit_code: r.exit_code,
+        stdout: r.stdout,
+        stderr: r.stderr,
+        execution_time: Some(prost_types::Duration {
+            seconds: (r.duration_ms / 1000) as i64,
+            nanos: ((r.duration_ms % 1000) * 1_000_000) as i32,
+        }),
+        files_created: r.files_created.into_iter().map(|file| file.path).collect(),
+        outputs: Default::default(),
+        stdout_sha256: r.stdout_sha256.unwrap_or_default(),
+        stderr_sha256: r.stderr_sha256.unwrap_or_default(),
+        error: r.error.map(|e| ExecutionError {
+            code: e.code,
+            message: e.message,
+            details: e.details.unwrap_or_default(),
+            stack_trace: e.stack_trace.unwrap_or_default(),
+        }),
+    }
+}
+
+pub fn timestamp_to_proto(t: chrono::DateTime<chrono::Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: t.timestamp(),
+        nanos: t.timestamp_subsec_nanos() as i32,
+    }
+}
+
+/// Convert an output event for a client stream, with a resume token after each chunk
+fn stream_response(
+    ids: &RequestIds,
+    execution_id: Uuid,
+    event: OutputEvent,
+    offsets: Offsets,
+) -> Result<StreamExecutionResponse, Box<Status>> {
+    let event = match event {
+        OutputEvent::Output(chunk) => {
+            let token = ResumeToken { execution_id, offsets };
+            stream_execution_response::Event::Output(ExecutionOutput {
+                stream: chunk.stream.as_str().to_string(),
+                data: chunk.data,
+                timestamp: Some(timestamp_to_proto(chunk.timestamp)),
+                resume_token: token.encode(),
+            })
+        }
+        OutputEvent::Status(status) | OutputEvent::Finished(status) => {
+            stream_execution_response::Event::StatusUpdate(ExecutionStatusUpdate {
+                status: status_to_proto(&status),
+                message: String::new(),
+                timestamp: Some(timestamp_to_proto(chrono::Utc::now())),
+            })
+        }
+        OutputEvent::Interrupted(message) => return Err(Box::new(ids.attach(Status::unavailable(message)))),
+    };
+    Ok(StreamExecutionResponse { event: Some(event) })
+}
+
+/// The gateway's request for a public proto request, with the language enum
+/// resolved to the name the execution backend knows it by
+pub async fn execution_request_from_proto(
+    state: &AppState,
+    req: CreateExecutionRequest,
+) -> Result<crate::execution::CreateExecutionRequest, Status> {
+    let language = state
+        .languages()
+        .await
+        .by_proto(req.language)
+        .map(|info| info.name.clone())
+        .ok_or_else(|| Status::invalid_argument("Invalid language"))?;
+
+    Ok(crate::execution::CreateExecutionRequest {
+        code: req.code.into(),
+        language,
+        language_version: Some(req.language_version).filter(|version| !version.is_empty()),
+        timeout_seconds: req.timeout.map(|t| t.seconds as u64),
+        args: Some(req.args),
+        workspace_id: if req.workspace_id.is_empty() {
+            None
+        } else {
+            Uuid::parse_str(&req.workspace_id).ok()
+        },
+        env: Some(req.environment),
+        resources: req.resources.map(|r| crate::execution::ResourceLimits {
+            memory_mb: Some(r.memory_mb).filter(|&mb| mb > 0),
+            cpu_cores: None,
+            cpu_millis: Some(u64::from(r.cpu_cores) * 1000).filter(|&millis| millis > 0),
+            disk_mb: Some(r.disk_mb).filter(|&mb| mb > 0),
+            enable_network: None,
+        }),
+        mode: None,
+        tty: None,
+        session_id: req.metadata.get("session_id").filter(|id| !id.is_empty()).cloned(),
+        upload_id: None,
+        result_destination: None,
+        callback_url: None,
+        files: Vec::new(),
+        priority: req
+            .metadata
+            .get(crate::clients::execution::PRIORITY_KEY)
+            .and_then(|priority| crate::execution::Priority::parse(priority)),
+        concurrency_group: req.metadata.get(crate::concurrency::CONCURRENCY_GROUP_KEY).cloned(),
+        group_id: req.metadata.get("group_id").filter(|id| !id.is_empty()).cloned(),
+        stdin: None,
+        // Carried as base64 so binary input survives
+        stdin_base64: (!req.stdin.is_empty()).then(|| base64::engine::general_purpose::STANDARD.encode(&req.stdin)),
+        metadata: req.metadata,
+    })
+}
+
+/// Convert the gateway's execution to the public proto message. The caller,
+/// language, code and arguments aren't kept with the execution, so they're
+/// left for the caller to fill in where known
+pub fn execution_to_proto(exec_response: crate::execution::ExecutionResponse) -> Execution {
+    let mut execution = Execution {
+        id: exec_response.id.to_string(),
+        user_id: String::new(),
+        workspace_id: "".to_string(), // TODO: Handle workspace
+        status: status_to_proto(&exec_response.status),
+        language: Language::Unspecified as i32, // TODO: Store language
+        code: String::new(), // TODO: Store code
+        args: vec![],
+        result: exec_response.result.map(result_to_proto),
+        resource_usage: None,
+        created_at: Some(timestamp_to_proto(exec_response.created_at)),
+        started_at: exec_response.started_at.map(timestamp_to_proto),
+        completed_at: exec_response.completed_at.map(timestamp_to_proto),
+        metadata: exec_response.metadata,
+        code_sha256: exec_response.code_sha256.unwrap_or_default(),
+        language_version: exec_response.language_version.unwrap_or_default(),
+    };
+    tag_backend(&mut execution.metadata, exec_response.backend);
+    execution
+}
+
+/// Execution metadata key naming the backend that ran a non-primary execution
+pub const BACKEND_KEY: &str = "backend";
+
+/// Tag execution metadata with the backend that ran it, when it wasn't the primary
+fn tag_backend(metadata: &mut std::collections::HashMap<String, String>, backend: Backend) {
+    if !backend.is_primary() {
+        metadata.insert(BACKEND_KEY.to_string(), backend.as_str().to_string());
+    }
+}
+
+/// Metadata key carrying one `<code>: <message>` entry per gateway warning
+pub const WARNING_KEY: &str = "x-syla-warning";
+
+/// Report request adjustments as response metadata, since the public proto
+/// messages have no field for them
+pub fn annotate_warnings<T>(warnings: &[crate::execution::Warning], response: &mut Response<T>) {
+    for warning in warnings {
+        match format!("{}: {}", warning.code, warning.message).parse() {
+            Ok(value) => {
+                response.metadata_mut().append(WARNING_KEY, value);
+            }
+            Err(_) => debug!("Dropping warning not representable as metadata: {}", warning.code),
+        }
+    }
 }
 
 #[tonic::async_trait]
@@ -29,94 +234,44 @@ impl SylaGateway for SylaGatewayService {
         &self,
         request: Request<CreateExecutionRequest>,
     ) -> Result<Response<CreateExecutionResponse>, Status> {
+        let ids = RequestIds::from_request(&request);
+        self.ensure_executions_enabled().map_err(|s| ids.attach(*s))?;
+
         // Authenticate the request
-        let auth_context = self.auth_interceptor.authenticate(&request).await?;
+        let auth_context = self
+            .auth_interceptor
+            .authenticate(&request)
+            .await
+            .map_err(|s| ids.attach(s))?;
         debug!("Authenticated user: {}", auth_context.user_id);
 
         let req = request.into_inner();
-        
-        // Convert Language enum to string
-        let language = match Language::try_from(req.language) {
-            Ok(Language::Python) => "python",
-            Ok(Language::Javascript) => "javascript",
-            Ok(Language::Typescript) => "typescript",
-            Ok(Language::Rust) => "rust",
-            Ok(Language::Go) => "go",
-            Ok(Language::Java) => "java",
-            Ok(Language::Cpp) => "cpp",
-            Ok(Language::Csharp) => "csharp",
-            Ok(Language::Ruby) => "ruby",
-            Ok(Language::Php) => "php",
-            _ => return Err(Status::invalid_argument("Invalid language")),
-        };
-
-        // Create execution request for backend service
-        let execution_req = crate::execution::CreateExecutionRequest {
-            code: req.code.clone(),
-            language: language.to_string(),
-            timeout_seconds: req.timeout.map(|t| t.seconds as u64),
-            args: Some(req.args.clone()),
-            workspace_id: if req.workspace_id.is_empty() {
-                None
-            } else {
-                Uuid::parse_str(&req.workspace_id).ok()
-            },
-        };
+        // Echoed in the response; the code is shared with the request rather
+        // than copied, until it goes into the response message
+        let (language, args) = (req.language, req.args.clone());
+        let execution_req = execution_request_from_proto(&self.state, req)
+            .await
+            .map_err(|s| ids.attach(s))?;
+        let code = execution_req.code.clone();
 
         // Forward to execution service
-        match self.state.create_execution(execution_req).await {
-            Ok(exec_response) => {
-                // Convert response to gRPC format
-                let execution = Execution {
-                    id: exec_response.id.to_string(),
-                    user_id: auth_context.user_id,
-                    workspace_id: "".to_string(), // TODO: Handle workspace
-                    status: match exec_response.status {
-                        crate::execution::ExecutionStatus::Pending => ExecutionStatus::Pending as i32,
-                        crate::execution::ExecutionStatus::Running => ExecutionStatus::Running as i32,
-                        crate::execution::ExecutionStatus::Completed => ExecutionStatus::Completed as i32,
-                        crate::execution::ExecutionStatus::Failed => ExecutionStatus::Failed as i32,
-                        crate::execution::ExecutionStatus::Timeout => ExecutionStatus::Timeout as i32,
-                    },
-                    language: req.language,
-                    code: req.code.clone(),
-                    args: req.args.clone(),
-                    result: exec_response.result.map(|r| ExecutionResult {
-                        exit_code: r.exit_code,
-                        stdout: r.stdout,
-                        stderr: r.stderr,
-                        execution_time: Some(prost_types::Duration {
-                            seconds: (r.duration_ms / 1000) as i64,
-                            nanos: ((r.duration_ms % 1000) * 1_000_000) as i32,
-                        }),
-                        files_created: vec![],
-                        outputs: Default::default(),
-                        error: None,
-                    }),
-                    resource_usage: None,
-                    created_at: Some(prost_types::Timestamp {
-                        seconds: exec_response.created_at.timestamp(),
-                        nanos: exec_response.created_at.timestamp_subsec_nanos() as i32,
-                    }),
-                    started_at: exec_response.started_at.map(|t| prost_types::Timestamp {
-                        seconds: t.timestamp(),
-                        nanos: t.timestamp_subsec_nanos() as i32,
-                    }),
-                    completed_at: exec_response.completed_at.map(|t| prost_types::Timestamp {
-                        seconds: t.timestamp(),
-                        nanos: t.timestamp_subsec_nanos() as i32,
-                    }),
-                    metadata: req.metadata,
-                };
+        match self.state.create_execution(&auth_context, execution_req).await {
+            Ok(mut exec_response) => {
+                let warnings = std::mem::take(&mut exec_response.warnings);
+                let mut execution = execution_to_proto(exec_response);
+                execution.user_id = auth_context.user_id.clone();
+                execution.language = language;
+                execution.code = code.to_string();
+                execution.args = args;
 
-                Ok(Response::new(CreateExecutionResponse {
+                let mut response = Response::new(CreateExecutionResponse {
                     execution: Some(execution),
-                }))
-            }
-            Err(e) => {
-                error!("Failed to create execution: {}", e);
-                Err(Status::internal("Failed to create execution"))
+                });
+                auth::annotate_response(&auth_context, &mut response);
+                annotate_warnings(&warnings, &mut response);
+                Ok(response)
             }
+            Err(e) => Err(ids.error_status(e, "Failed to create execution")),
         }
     }
 
@@ -124,137 +279,208 @@ impl SylaGateway for SylaGatewayService {
         &self,
         request: Request<GetExecutionRequest>,
     ) -> Result<Response<GetExecutionResponse>, Status> {
+        let ids = RequestIds::from_request(&request);
+        self.ensure_executions_enabled().map_err(|s| ids.attach(*s))?;
+
         // Authenticate the request
-        let auth_context = self.auth_interceptor.authenticate(&request).await?;
+        let auth_context = self
+            .auth_interceptor
+            .authenticate(&request)
+            .await
+            .map_err(|s| ids.attach(s))?;
         
         let req = request.into_inner();
         let execution_id = Uuid::parse_str(&req.id)
-            .map_err(|_| Status::invalid_argument("Invalid execution ID"))?;
-
-        match self.state.get_execution(execution_id).await {
-            Ok(exec_response) => {
-                // Convert response to gRPC format
-                let execution = Execution {
-                    id: exec_response.id.to_string(),
-                    user_id: auth_context.user_id,
-                    workspace_id: "".to_string(),
-                    status: match exec_response.status {
-                        crate::execution::ExecutionStatus::Pending => ExecutionStatus::Pending as i32,
-                        crate::execution::ExecutionStatus::Running => ExecutionStatus::Running as i32,
-                        crate::execution::ExecutionStatus::Completed => ExecutionStatus::Completed as i32,
-                        crate::execution::ExecutionStatus::Failed => ExecutionStatus::Failed as i32,
-                        crate::execution::ExecutionStatus::Timeout => ExecutionStatus::Timeout as i32,
-                    },
-                    language: Language::Unspecified as i32, // TODO: Store language
-                    code: String::new(), // TODO: Store code
-                    args: vec![],
-                    result: exec_response.result.map(|r| ExecutionResult {
-                        exit_code: r.exit_code,
-                        stdout: r.stdout,
-                        stderr: r.stderr,
-                        execution_time: Some(prost_types::Duration {
-                            seconds: (r.duration_ms / 1000) as i64,
-                            nanos: ((r.duration_ms % 1000) * 1_000_000) as i32,
-                        }),
-                        files_created: vec![],
-                        outputs: Default::default(),
-                        error: None,
-                    }),
-                    resource_usage: None,
-                    created_at: Some(prost_types::Timestamp {
-                        seconds: exec_response.created_at.timestamp(),
-                        nanos: exec_response.created_at.timestamp_subsec_nanos() as i32,
-                    }),
-                    started_at: exec_response.started_at.map(|t| prost_types::Timestamp {
-                        seconds: t.timestamp(),
-                        nanos: t.timestamp_subsec_nanos() as i32,
-                    }),
-                    completed_at: exec_response.completed_at.map(|t| prost_types::Timestamp {
-                        seconds: t.timestamp(),
-                        nanos: t.timestamp_subsec_nanos() as i32,
-                    }),
-                    metadata: Default::default(),
-                };
+            .map_err(|_| ids.attach(Status::invalid_argument("Invalid execution ID")))?;
 
-                Ok(Response::new(GetExecutionResponse {
-                    execution: Some(execution),
-                }))
-            }
-            Err(e) => {
-                if let crate::error::ApiError::NotFound = e {
-                    Err(Status::not_found("Execution not found"))
-                } else {
-                    error!("Failed to get execution: {}", e);
-                    Err(Status::internal("Failed to get execution"))
+        // Finished executions are answered from the response cache for as
+        // long as their execution cache entry is unchanged
+        let version = match self.state.grpc_cache() {
+            Some(_) => self.state.settled_version(execution_id).await,
+            None => None,
+        };
+        let cached = version.and_then(|version| self.state.grpc_cache()?.get(execution_id, version));
+        let mut stale = false;
+        let mut execution = match cached {
+            Some(execution) => execution,
+            None => {
+                let (exec_response, served_stale) = self
+                    .state
+                    .read_execution(execution_id)
+                    .await
+                    .map_err(|e| ids.error_status(e, "Failed to get execution"))?;
+                stale = served_stale;
+                // The caller is filled in below
+                let execution = execution_to_proto(exec_response);
+                // Kept only if nothing changed while the response was built
+                if let (Some(cache), Some(version)) = (self.state.grpc_cache(), version) {
+                    if self.state.settled_version(execution_id).await == Some(version) {
+                        cache.insert(execution_id, version, execution.clone());
+                    }
                 }
+                execution
             }
+        };
+        execution.user_id = auth_context.user_id.clone();
+
+        let mut response = Response::new(GetExecutionResponse {
+            execution: Some(execution),
+        });
+        if stale {
+            response
+                .metadata_mut()
+                .insert(STALE_HEADER, MetadataValue::from_static("true"));
         }
+        auth::annotate_response(&auth_context, &mut response);
+        Ok(response)
     }
 
     async fn list_executions(
         &self,
-        _request: Request<ListExecutionsRequest>,
+        request: Request<ListExecutionsRequest>,
     ) -> Result<Response<ListExecutionsResponse>, Status> {
+        let ids = RequestIds::from_request(&request);
+        self.ensure_executions_enabled().map_err(|s| ids.attach(*s))?;
         // TODO: Implement list executions
-        Err(Status::unimplemented("List executions not yet implemented"))
+        Err(ids.attach(Status::unimplemented("List executions not yet implemented")))
     }
 
     async fn cancel_execution(
         &self,
-        _request: Request<CancelExecutionRequest>,
+        request: Request<CancelExecutionRequest>,
     ) -> Result<Response<CancelExecutionResponse>, Status> {
+        let ids = RequestIds::from_request(&request);
+        self.ensure_executions_enabled().map_err(|s| ids.attach(*s))?;
         // TODO: Implement cancel execution
-        Err(Status::unimplemented("Cancel execution not yet implemented"))
+        Err(ids.attach(Status::unimplemented("Cancel execution not yet implemented")))
     }
 
-    type StreamExecutionStream = tokio_stream::wrappers::ReceiverStream<Result<StreamExecutionResponse, Status>>;
+    async fn delete_execution(
+        &self,
+        request: Request<DeleteExecutionRequest>,
+    ) -> Result<Response<DeleteExecutionResponse>, Status> {
+        let ids = RequestIds::from_request(&request);
+        self.ensure_executions_enabled().map_err(|s| ids.attach(*s))?;
+
+        let auth_context = self
+            .auth_interceptor
+            .authenticate(&request)
+            .await
+            .map_err(|s| ids.attach(s))?;
+
+        let req = request.into_inner();
+        let execution_id = Uuid::parse_str(&req.id)
+            .map_err(|_| ids.attach(Status::invalid_argument("Invalid execution ID")))?;
+        let deleted = if req.purge {
+            self.state.purge_execution(&auth_context, execution_id).await
+        } else {
+            self.state.delete_execution(&auth_context, execution_id).await
+        };
+        deleted.map_err(|e| ids.error_status(e, "Failed to delete execution"))?;
+
+        let mut response = Response::new(DeleteExecutionResponse {});
+        auth::annotate_response(&auth_context, &mut response);
+        Ok(response)
+    }
+
+    type StreamExecutionStream = BoxStream<'static, Result<StreamExecutionResponse, Status>>;
 
     async fn stream_execution(
         &self,
-        _request: Request<StreamExecutionRequest>,
+        request: Request<StreamExecutionRequest>,
     ) -> Result<Response<Self::StreamExecutionStream>, Status> {
-        // TODO: Implement execution streaming
-        Err(Status::unimplemented("Stream execution not yet implemented"))
+        let ids = RequestIds::from_request(&request);
+        self.ensure_executions_enabled().map_err(|s| ids.attach(*s))?;
+
+        let auth_context = self
+            .auth_interceptor
+            .authenticate(&request)
+            .await
+            .map_err(|s| ids.attach(s))?;
+
+        let req = request.into_inner();
+        let execution_id = Uuid::parse_str(&req.id)
+            .map_err(|_| ids.attach(Status::invalid_argument("Invalid execution ID")))?;
+        let from = if req.resume_token.is_empty() {
+            Offsets::default()
+        } else {
+            ResumeToken::decode(&req.resume_token)
+                .filter(|token| token.execution_id == execution_id)
+                .map(|token| token.offsets)
+                .ok_or_else(|| ids.attach(Status::invalid_argument("Invalid resume token")))?
+        };
+        self.state
+            .get_owned_execution(&auth_context, execution_id, ADMIN_SCOPE)
+            .await
+            .map_err(|e| ids.error_status(e, "Failed to stream execution"))?;
+
+        let output = match self.state.stream_output(execution_id, OutputStart::From(from)).await {
+            Ok(output) => output,
+            Err(ApiError::BadRequest(message)) => {
+                return Err(ids.attach(Status::out_of_range(message)));
+            }
+            Err(e) => return Err(ids.error_status(e, "Failed to stream execution")),
+        };
+
+        let stream = output
+            .map(move |delivered| match delivered {
+                Ok((event, offsets)) => stream_response(&ids, execution_id, event, offsets),
+                Err(e) => Err(Box::new(ids.error_status(e, "Failed to resume execution stream"))),
+            })
+            .map_err(|s| *s);
+        let mut response = Response::new(stream.boxed());
+        auth::annotate_response(&auth_context, &mut response);
+        Ok(response)
     }
 
     async fn create_workspace(
         &self,
-        _request: Request<CreateWorkspaceRequest>,
+        request: Request<CreateWorkspaceRequest>,
     ) -> Result<Response<CreateWorkspaceResponse>, Status> {
+        let ids = RequestIds::from_request(&request);
+        self.ensure_workspaces_enabled().map_err(|s| ids.attach(*s))?;
         // TODO: Implement workspace creation
-        Err(Status::unimplemented("Create workspace not yet implemented"))
+        Err(ids.attach(Status::unimplemented("Create workspace not yet implemented")))
     }
 
     async fn get_workspace(
         &self,
-        _request: Request<GetWorkspaceRequest>,
+        request: Request<GetWorkspaceRequest>,
     ) -> Result<Response<GetWorkspaceResponse>, Status> {
+        let ids = RequestIds::from_request(&request);
+        self.ensure_workspaces_enabled().map_err(|s| ids.attach(*s))?;
         // TODO: Implement get workspace
-        Err(Status::unimplemented("Get workspace not yet implemented"))
+        Err(ids.attach(Status::unimplemented("Get workspace not yet implemented")))
     }
 
     async fn list_workspaces(
         &self,
-        _request: Request<ListWorkspacesRequest>,
+        request: Request<ListWorkspacesRequest>,
     ) -> Result<Response<ListWorkspacesResponse>, Status> {
+        let ids = RequestIds::from_request(&request);
+        self.ensure_workspaces_enabled().map_err(|s| ids.attach(*s))?;
         // TODO: Implement list workspaces
-        Err(Status::unimplemented("List workspaces not yet implemented"))
+        Err(ids.attach(Status::unimplemented("List workspaces not yet implemented")))
     }
 
     async fn update_workspace(
         &self,
-        _request: Request<UpdateWorkspaceRequest>,
+        request: Request<UpdateWorkspaceRequest>,
     ) -> Result<Response<UpdateWorkspaceResponse>, Status> {
+        let ids = RequestIds::from_request(&request);
+        self.ensure_workspaces_enabled().map_err(|s| ids.attach(*s))?;
         // TODO: Implement update workspace
-        Err(Status::unimplemented("Update workspace not yet implemented"))
+        Err(ids.attach(Status::unimplemented("Update workspace not yet implemented")))
     }
 
     async fn delete_workspace(
         &self,
-        _request: Request<DeleteWorkspaceRequest>,
+        request: Request<DeleteWorkspaceRequest>,
     ) -> Result<Response<DeleteWorkspaceResponse>, Status> {
+        let ids = RequestIds::from_request(&request);
+        self.ensure_workspaces_enabled().map_err(|s| ids.attach(*s))?;
         // TODO: Implement delete workspace
-        Err(Status::unimplemented("Delete workspace not yet implemented"))
+        Err(ids.attach(Status::unimplemented("Delete workspace not yet implemented")))
     }
 
     async fn health_check(
@@ -262,21 +488,32 @@ impl SylaGateway for SylaGatewayService {
         _request: Request<HealthCheckRequest>,
     ) -> Result<Response<HealthCheckResponse>, Status> {
         info!("Health check request received");
-        
-        let mut components = std::collections::HashMap::new();
-        
-        // Check execution service health
-        components.insert(
-            "execution_service".to_string(),
-            ComponentHealth {
-                healthy: true, // TODO: Actually check service health
-                message: "Service is running".to_string(),
-                details: Default::default(),
-            },
-        );
+
+        let report = self.state.check_health().await;
+        let components = report
+            .components
+            .into_iter()
+            .map(|(name, component)| {
+                let health = ComponentHealth {
+                    healthy: component.status == crate::health::HealthStatus::Healthy,
+                    message: component.message,
+                    details: [
+                        ("status".to_string(), component.status.as_str().to_string()),
+                        ("latency_ms".to_string(), component.latency_ms.to_string()),
+                    ]
+                    .into(),
+                };
+                (name.to_string(), health)
+            })
+            .collect();
+        let status = match report.status {
+            crate::health::HealthStatus::Healthy => health_check_response::HealthStatus::Healthy,
+            crate::health::HealthStatus::Degraded => health_check_response::HealthStatus::Degraded,
+            crate::health::HealthStatus::Unhealthy => health_check_response::HealthStatus::Unhealthy,
+        };
 
         Ok(Response::new(HealthCheckResponse {
-            status: health_check_response::HealthStatus::Healthy as i32,
+            status: status as i32,
             components,
             timestamp: Some(prost_types::Timestamp {
                 seconds: chrono::Utc::now().timestamp(),
@@ -287,9 +524,10 @@ impl SylaGateway for SylaGatewayService {
 
     async fn get_metrics(
         &self,
-        _request: Request<GetMetricsRequest>,
+        request: Request<GetMetricsRequest>,
     ) -> Result<Response<GetMetricsResponse>, Status> {
+        let ids = RequestIds::from_request(&request);
         // TODO: Implement metrics collection
-        Err(Status::unimplemented("Get metrics not yet implemented"))
+        Err(ids.attach(Status::unimplemented("Get metrics not yet implemented")))
     }
 }
\ No newline at end of file
@@ -0,0 +1,101 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use uuid::Uuid;
+
+use crate::config::GrpcCacheConfig;
+use crate::proto::Execution;
+
+#[derive(Default)]
+struct Entries {
+    executions: HashMap<Uuid, (u64, Execution)>,
+    /// Insertion order, oldest first, for eviction
+    order: VecDeque<Uuid>,
+}
+
+/// Converted `GetExecution` responses for finished executions, so repeated
+/// gRPC reads skip decompressing output and rebuilding the message. Entries
+/// are tied to the version of the execution cache entry they were built
+/// from, so any change to the execution invalidates them
+pub struct GrpcExecutionCache {
+    max_entries: usize,
+    entries: Mutex<Entries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl GrpcExecutionCache {
+    /// `None` when the cache is disabled
+    pub fn new(config: &GrpcCacheConfig) -> Option<Self> {
+        (config.max_entries > 0).then(|| Self {
+            max_entries: config.max_entries,
+            entries: Mutex::new(Entries::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        })
+    }
+
+    /// The response built for execution `id` at `version`, if it's still current
+    pub fn get(&self, id: Uuid, version: u64) -> Option<Execution> {
+        let entries = self.entries.lock().unwrap();
+        let hit = entries
+            .executions
+            .get(&id)
+            .filter(|(cached_version, _)| *cached_version == version)
+            .map(|(_, execution)| execution.clone());
+        let counter = if hit.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        hit
+    }
+
+    /// Keep the response built for execution `id` at `version`
+    pub fn insert(&self, id: Uuid, version: u64, execution: Execution) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.executions.insert(id, (version, execution)).is_none() {
+            entries.order.push_back(id);
+        }
+        while entries.executions.len() > self.max_entries {
+            let Some(oldest) = entries.order.pop_front() else {
+                break;
+            };
+            if entries.executions.remove(&oldest).is_some() {
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Drop the response kept for execution `id`
+    pub fn remove(&self, id: Uuid) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.executions.remove(&id).is_some() {
+            entries.order.retain(|kept| *kept != id);
+        }
+    }
+
+    /// Responses dropped to make room since startup
+    pub fn evictions(&self) -> u64 {
+        self.evictions.load(Ordering::Relaxed)
+    }
+
+    /// Hits, misses and evictions in Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# TYPE syla_gateway_grpc_cache_hits_total counter");
+        let _ = writeln!(out, "syla_gateway_grpc_cache_hits_total {}", self.hits.load(Ordering::Relaxed));
+        let _ = writeln!(out, "# TYPE syla_gateway_grpc_cache_misses_total counter");
+        let _ = writeln!(out, "syla_gateway_grpc_cache_misses_total {}", self.misses.load(Ordering::Relaxed));
+        let _ = writeln!(out, "# TYPE syla_gateway_grpc_cache_evictions_total counter");
+        let _ = writeln!(out, "syla_gateway_grpc_cache_evictions_total {}", self.evictions());
+        let _ = writeln!(out, "# TYPE syla_gateway_grpc_cache_entries gauge");
+        let _ = writeln!(
+            out,
+            "syla_gateway_grpc_cache_entries {}",
+            self.entries.lock().unwrap().executions.len()
+        );
+        out
+    }
+}
@@ -0,0 +1,76 @@
+use futures::future::BoxFuture;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tokio::time::Instant;
+
+use crate::config::HealthConfig;
+
+/// Overall or per-dependency health, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    /// A dependency didn't answer in time; requests may still succeed
+    Degraded,
+    Unhealthy,
+}
+
+impl HealthStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HealthStatus::Healthy => "healthy",
+            HealthStatus::Degraded => "degraded",
+            HealthStatus::Unhealthy => "unhealthy",
+        }
+    }
+}
+
+/// Outcome of probing one dependency
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentReport {
+    pub status: HealthStatus,
+    pub message: String,
+    pub latency_ms: u64,
+}
+
+/// Outcome of probing every dependency
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub components: BTreeMap<&'static str, ComponentReport>,
+}
+
+/// A named dependency check; an error marks the dependency unhealthy
+pub type Probe<'a> = (&'static str, BoxFuture<'a, Result<(), String>>);
+
+/// Run `probes` concurrently, each bounded by the per-probe timeout and all
+/// of them by the overall budget. A probe still running when its time is up
+/// is reported degraded rather than holding up the report.
+pub async fn check(probes: Vec<Probe<'_>>, config: &HealthConfig) -> HealthReport {
+    let started = Instant::now();
+    let deadline = started + config.budget;
+    let probe_deadline = (started + config.probe_timeout).min(deadline);
+
+    let results = futures::future::join_all(probes.into_iter().map(|(name, probe)| async move {
+        let outcome = tokio::time::timeout_at(probe_deadline, probe).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let (status, message) = match outcome {
+            Ok(Ok(())) => (HealthStatus::Healthy, "ok".to_string()),
+            Ok(Err(e)) => (HealthStatus::Unhealthy, e),
+            Err(_) => (
+                HealthStatus::Degraded,
+                format!("No response within {}ms", (probe_deadline - started).as_millis()),
+            ),
+        };
+        (name, ComponentReport { status, message, latency_ms })
+    }))
+    .await;
+
+    let components: BTreeMap<_, _> = results.into_iter().collect();
+    let status = components
+        .values()
+        .map(|c| c.status)
+        .max()
+        .unwrap_or(HealthStatus::Healthy);
+    HealthReport { status, components }
+}
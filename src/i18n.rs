@@ -0,0 +1,113 @@
+use axum::{
+    extract::Request,
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+
+use crate::error::ApiError;
+
+tokio::task_local! {
+    static REQUEST_LOCALE: Locale;
+}
+
+/// Languages error messages are available in; machine-readable codes are
+/// never translated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Ja,
+}
+
+impl Locale {
+    /// BCP 47 tag, as sent in `Content-Language`
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Ja => "ja",
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Locale> {
+        let primary = tag.split(['-', '_']).next()?.trim();
+        if primary.eq_ignore_ascii_case("en") {
+            Some(Locale::En)
+        } else if primary.eq_ignore_ascii_case("ja") {
+            Some(Locale::Ja)
+        } else {
+            None
+        }
+    }
+
+    /// The supported locale an `Accept-Language` value prefers most,
+    /// falling back to English
+    pub fn negotiate(accept_language: &str) -> Locale {
+        let mut best: Option<(Locale, f32)> = None;
+        for range in accept_language.split(',') {
+            let mut parts = range.split(';');
+            let Some(locale) = parts.next().and_then(Locale::from_tag) else {
+                continue;
+            };
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            // Ties go to the range listed first
+            if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+                best = Some((locale, quality));
+            }
+        }
+        best.map(|(locale, _)| locale).unwrap_or_default()
+    }
+
+    /// Locale negotiated for the request being handled, English outside one
+    pub fn current() -> Locale {
+        REQUEST_LOCALE.try_with(|locale| *locale).unwrap_or_default()
+    }
+}
+
+/// Negotiate the locale for REST error messages from `Accept-Language`
+pub async fn negotiate_locale(request: Request, next: Next) -> Response {
+    let locale = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .map(Locale::negotiate)
+        .unwrap_or_default();
+    REQUEST_LOCALE.scope(locale, next.run(request)).await
+}
+
+/// Human-readable message for `error` in `locale`; English, the error's own
+/// display text, is used for any locale without a catalog entry
+pub fn error_message(locale: Locale, error: &ApiError) -> String {
+    match locale {
+        Locale::En => None,
+        Locale::Ja => japanese(error),
+    }
+    .unwrap_or_else(|| error.to_string())
+}
+
+fn japanese(error: &ApiError) -> Option<String> {
+    Some(match error {
+        ApiError::NotFound => "リソースが見つかりません".to_string(),
+        ApiError::BadRequest(detail) => format!("リクエストが不正です: {}", detail),
+        ApiError::Internal(_) | ApiError::Upstream { .. } => {
+            "内部サーバーエラーが発生しました".to_string()
+        }
+        ApiError::ServiceUnavailable => "サービスを一時的に利用できません".to_string(),
+        ApiError::ReadOnly => "メンテナンス中のため読み取り専用です。しばらくしてから再試行してください".to_string(),
+        ApiError::RateLimited(_) => "リクエストが多すぎます。しばらくしてから再試行してください".to_string(),
+        ApiError::Unauthorized(detail) => format!("認証されていません: {}", detail),
+        ApiError::Forbidden(detail) => format!("アクセスが拒否されました: {}", detail),
+        ApiError::Conflict(detail) => format!("現在の状態では実行できません: {}", detail),
+        ApiError::ResponseTooLarge { size, limit, logs_url } => format!(
+            "レスポンスのサイズ ({} バイト) が上限 ({} バイト) を超えています。出力は {} から取得してください",
+            size, limit, logs_url
+        ),
+        ApiError::UpgradeRequired { client, minimum } => format!(
+            "クライアント {} はサポートされていません。{} 以降にアップグレードしてください",
+            client, minimum
+        ),
+    })
+}
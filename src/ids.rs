@@ -0,0 +1,122 @@
+//! IDs the gateway assigns: request and correlation IDs, jobs, uploads and
+//! schedules, and the execution IDs it proposes to the execution service.
+//!
+//! Every ID is a 128-bit value written in UUID form, so the format can change
+//! without touching the routes that parse them. The generator is picked once at
+//! startup from [`IdFormat`]; custom builds can [`install`] their own.
+
+use std::sync::{Arc, OnceLock};
+
+use axum::http::{HeaderValue, Request};
+use chrono::Utc;
+use tower_http::request_id::{MakeRequestId, RequestId};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::config::IdFormat;
+
+static GENERATOR: OnceLock<Arc<dyn IdGenerator>> = OnceLock::new();
+
+/// Source of the IDs the gateway assigns
+pub trait IdGenerator: Send + Sync {
+    fn generate(&self) -> Uuid;
+
+    /// Whether execution IDs are proposed to the execution service rather
+    /// than left for it to assign
+    fn proposes_execution_ids(&self) -> bool {
+        true
+    }
+}
+
+/// Random UUIDv4s
+pub struct UuidV4;
+
+impl IdGenerator for UuidV4 {
+    fn generate(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// UUIDv7s: a millisecond timestamp followed by random bits, so IDs sort by
+/// creation time
+pub struct UuidV7;
+
+impl IdGenerator for UuidV7 {
+    fn generate(&self) -> Uuid {
+        Uuid::now_v7()
+    }
+}
+
+/// ULIDs, a 48-bit millisecond timestamp followed by 80 random bits, in UUID form
+pub struct Ulid;
+
+impl IdGenerator for Ulid {
+    fn generate(&self) -> Uuid {
+        let millis = Utc::now().timestamp_millis().max(0) as u64;
+        // Only the bytes of a UUIDv4 that carry no version or variant bits
+        let random = Uuid::new_v4().into_bytes();
+        let mut bytes = [0u8; 16];
+        bytes[..6].copy_from_slice(&millis.to_be_bytes()[2..]);
+        bytes[6..12].copy_from_slice(&random[..6]);
+        bytes[12..].copy_from_slice(&random[12..]);
+        Uuid::from_bytes(bytes)
+    }
+}
+
+/// Execution IDs as the execution service assigns them; the gateway's own
+/// IDs are random UUIDv4s
+pub struct Upstream;
+
+impl IdGenerator for Upstream {
+    fn generate(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+
+    fn proposes_execution_ids(&self) -> bool {
+        false
+    }
+}
+
+/// The built-in generator for `format`
+pub fn for_format(format: IdFormat) -> Arc<dyn IdGenerator> {
+    match format {
+        IdFormat::UuidV4 => Arc::new(UuidV4),
+        IdFormat::UuidV7 => Arc::new(UuidV7),
+        IdFormat::Ulid => Arc::new(Ulid),
+        IdFormat::Upstream => Arc::new(Upstream),
+    }
+}
+
+/// Use `generator` for every ID assigned from now on. Only the first call
+/// takes effect; install before the gateway starts assigning IDs
+pub fn install(generator: Arc<dyn IdGenerator>) {
+    if GENERATOR.set(generator).is_err() {
+        warn!("An ID generator is already installed, keeping it");
+    }
+}
+
+/// The installed generator, or execution service-assigned IDs when none is
+fn generator() -> &'static Arc<dyn IdGenerator> {
+    GENERATOR.get_or_init(|| Arc::new(Upstream))
+}
+
+/// A new ID from the installed generator
+pub fn generate() -> Uuid {
+    generator().generate()
+}
+
+/// An ID to propose for a new execution, unless the execution service assigns them
+pub fn execution_id() -> Option<Uuid> {
+    let generator = generator();
+    generator.proposes_execution_ids().then(|| generator.generate())
+}
+
+/// Assigns `x-request-id` to REST requests that arrive without one
+#[derive(Clone, Copy, Default)]
+pub struct MakeGatewayRequestId;
+
+impl MakeRequestId for MakeGatewayRequestId {
+    fn make_request_id<B>(&mut self, _request: &Request<B>) -> Option<RequestId> {
+        HeaderValue::from_str(&generate().to_string()).ok().map(RequestId::new)
+    }
+}
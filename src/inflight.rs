@@ -0,0 +1,190 @@
+use axum::{extract::MatchedPath, http};
+use futures::future::BoxFuture;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Which listener a tracked request arrived on
+#[derive(Debug, Clone, Copy)]
+pub enum Listener {
+    Rest,
+    Grpc,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct RouteInflight {
+    pub requests: u64,
+    pub streams: u64,
+}
+
+/// Snapshot of in-flight work, as reported by the admin API and during shutdown
+#[derive(Debug, Serialize)]
+pub struct InflightSnapshot {
+    pub draining: bool,
+    pub total_requests: u64,
+    pub total_streams: u64,
+    pub routes: BTreeMap<String, RouteInflight>,
+}
+
+impl InflightSnapshot {
+    pub fn is_idle(&self) -> bool {
+        self.total_requests == 0 && self.total_streams == 0
+    }
+}
+
+/// Counts active requests and streams per route
+#[derive(Default)]
+pub struct InflightTracker {
+    routes: Mutex<BTreeMap<String, RouteInflight>>,
+    draining: AtomicBool,
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Request,
+    Stream,
+}
+
+impl InflightTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn track_request(self: &Arc<Self>, route: String) -> InflightGuard {
+        self.enter(route, Kind::Request)
+    }
+
+    /// Track a long-lived stream; hold the guard for the lifetime of the stream
+    pub fn track_stream(self: &Arc<Self>, route: String) -> InflightGuard {
+        self.enter(route, Kind::Stream)
+    }
+
+    fn enter(self: &Arc<Self>, route: String, kind: Kind) -> InflightGuard {
+        {
+            let mut routes = self.routes.lock().unwrap();
+            let entry = routes.entry(route.clone()).or_default();
+            match kind {
+                Kind::Request => entry.requests += 1,
+                Kind::Stream => entry.streams += 1,
+            }
+        }
+        InflightGuard {
+            tracker: self.clone(),
+            route,
+            kind,
+        }
+    }
+
+    fn exit(&self, route: &str, kind: Kind) {
+        let mut routes = self.routes.lock().unwrap();
+        if let Some(entry) = routes.get_mut(route) {
+            match kind {
+                Kind::Request => entry.requests = entry.requests.saturating_sub(1),
+                Kind::Stream => entry.streams = entry.streams.saturating_sub(1),
+            }
+            if entry.requests == 0 && entry.streams == 0 {
+                routes.remove(route);
+            }
+        }
+    }
+
+    pub fn begin_drain(&self) {
+        self.draining.store(true, Ordering::Release);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Acquire)
+    }
+
+    pub fn snapshot(&self) -> InflightSnapshot {
+        let routes = self.routes.lock().unwrap().clone();
+        InflightSnapshot {
+            draining: self.is_draining(),
+            total_requests: routes.values().map(|r| r.requests).sum(),
+            total_streams: routes.values().map(|r| r.streams).sum(),
+            routes,
+        }
+    }
+}
+
+/// Decrements the route's in-flight count when dropped
+pub struct InflightGuard {
+    tracker: Arc<InflightTracker>,
+    route: String,
+    kind: Kind,
+}
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        self.tracker.exit(&self.route, self.kind);
+    }
+}
+
+/// Tower layer tracking every request through a listener
+#[derive(Clone)]
+pub struct InflightLayer {
+    tracker: Arc<InflightTracker>,
+    listener: Listener,
+}
+
+impl InflightLayer {
+    pub fn new(tracker: Arc<InflightTracker>, listener: Listener) -> Self {
+        Self { tracker, listener }
+    }
+}
+
+impl<S> Layer<S> for InflightLayer {
+    type Service = InflightService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        InflightService {
+            inner,
+            tracker: self.tracker.clone(),
+            listener: self.listener,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct InflightService<S> {
+    inner: S,
+    tracker: Arc<InflightTracker>,
+    listener: Listener,
+}
+
+impl<S, B> Service<http::Request<B>> for InflightService<S>
+where
+    S: Service<http::Request<B>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let route = match self.listener {
+            // Use the route template so IDs in paths don't explode the key space
+            Listener::Rest => request
+                .extensions()
+                .get::<MatchedPath>()
+                .map(|p| format!("REST {} {}", request.method(), p.as_str()))
+                .unwrap_or_else(|| "REST <unmatched>".to_string()),
+            Listener::Grpc => format!("gRPC {}", request.uri().path()),
+        };
+
+        let guard = self.tracker.track_request(route);
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let result = future.await;
+            drop(guard);
+            result
+        })
+    }
+}
@@ -0,0 +1,158 @@
+use serde::Serialize;
+
+use crate::execution::ResourceLimits;
+use crate::proto::execution::v1::{Language, LanguageRuntime};
+
+/// Languages assumed when the execution service can't list its own, as
+/// (language, name, aliases)
+const BUILTIN: &[(Language, &str, &[&str])] = &[
+    (Language::Python, "python", &[]),
+    (Language::Javascript, "javascript", &[]),
+    (Language::Typescript, "typescript", &[]),
+    (Language::Rust, "rust", &[]),
+    (Language::Go, "go", &[]),
+    (Language::Java, "java", &[]),
+    (Language::Cpp, "cpp", &["c++"]),
+    (Language::Csharp, "csharp", &["c#"]),
+    (Language::Ruby, "ruby", &[]),
+    (Language::Php, "php", &[]),
+    (Language::Shell, "shell", &["bash", "sh"]),
+];
+
+/// A language the execution service runs
+#[derive(Debug, Clone, Serialize)]
+pub struct LanguageInfo {
+    /// Name used in execution requests
+    pub name: String,
+    /// Other names accepted in its place
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Runtime version, when the execution service reports one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Versions requests may pin with `language_version`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<String>,
+    /// Resources an execution gets when it requests none
+    pub default_limits: ResourceLimits,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_timeout_seconds: Option<u64>,
+    #[serde(skip)]
+    pub language: Language,
+}
+
+impl LanguageInfo {
+    /// Whether `version` is one requests may pin
+    pub fn supports_version(&self, version: &str) -> bool {
+        self.versions.iter().any(|supported| supported == version)
+    }
+}
+
+/// Where a catalog came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CatalogSource {
+    /// Listed by the execution service
+    Backend,
+    /// The gateway's own list, used until the execution service answers
+    Builtin,
+}
+
+/// Languages one execution backend supports, used to map request language
+/// names onto the execution service's enum
+#[derive(Debug, Clone, Serialize)]
+pub struct LanguageCatalog {
+    pub languages: Vec<LanguageInfo>,
+    pub source: CatalogSource,
+}
+
+impl LanguageCatalog {
+    pub fn builtin() -> Self {
+        let languages = BUILTIN
+            .iter()
+            .map(|(language, name, aliases)| LanguageInfo {
+                name: name.to_string(),
+                aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+                version: None,
+                versions: Vec::new(),
+                default_limits: ResourceLimits::default(),
+                default_timeout_seconds: None,
+                language: *language,
+            })
+            .collect();
+        Self {
+            languages,
+            source: CatalogSource::Builtin,
+        }
+    }
+
+    /// Catalog from a `ListLanguages` response; runtimes in languages this
+    /// gateway's proto doesn't know are skipped
+    pub fn from_runtimes(runtimes: Vec<LanguageRuntime>) -> Self {
+        let languages = runtimes
+            .into_iter()
+            .filter_map(|runtime| {
+                let language = Language::try_from(runtime.language)
+                    .ok()
+                    .filter(|language| *language != Language::Unspecified)?;
+                let name = if runtime.name.is_empty() {
+                    builtin_name(language)?.to_string()
+                } else {
+                    runtime.name
+                };
+                let defaults = runtime.default_resources.unwrap_or_default();
+                let mut versions: Vec<String> =
+                    runtime.versions.into_iter().filter(|version| !version.is_empty()).collect();
+                if !runtime.version.is_empty() && !versions.contains(&runtime.version) {
+                    versions.push(runtime.version.clone());
+                }
+                Some(LanguageInfo {
+                    name,
+                    aliases: runtime.aliases,
+                    version: Some(runtime.version).filter(|version| !version.is_empty()),
+                    versions,
+                    default_limits: ResourceLimits {
+                        memory_mb: Some(defaults.memory_mb).filter(|mb| *mb > 0),
+                        cpu_cores: Some(defaults.cpu_cores).filter(|cores| *cores > 0.0),
+                        cpu_millis: None,
+                        disk_mb: Some(defaults.disk_mb).filter(|mb| *mb > 0),
+                        enable_network: Some(defaults.enable_network),
+                    },
+                    default_timeout_seconds: runtime
+                        .default_timeout
+                        .map(|timeout| timeout.seconds.max(0) as u64),
+                    language,
+                })
+            })
+            .collect();
+        Self {
+            languages,
+            source: CatalogSource::Backend,
+        }
+    }
+
+    /// The language named `name` in a request, by name or alias, ignoring case
+    pub fn resolve(&self, name: &str) -> Option<&LanguageInfo> {
+        self.languages.iter().find(|info| {
+            info.name.eq_ignore_ascii_case(name) || info.aliases.iter().any(|alias| alias.eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// The language with proto enum value `value`; the public and execution
+    /// service protos number their languages alike
+    pub fn by_proto(&self, value: i32) -> Option<&LanguageInfo> {
+        self.languages.iter().find(|info| info.language as i32 == value)
+    }
+
+    /// Execution service enum value for `name`, unspecified when unsupported
+    pub fn to_proto(&self, name: &str) -> Language {
+        self.resolve(name).map_or(Language::Unspecified, |info| info.language)
+    }
+}
+
+fn builtin_name(language: Language) -> Option<&'static str> {
+    BUILTIN
+        .iter()
+        .find(|(builtin, _, _)| *builtin == language)
+        .map(|(_, name, _)| *name)
+}
@@ -0,0 +1,137 @@
+//! Leader election for background tasks that must run once cluster-wide.
+//!
+//! Each task campaigns for a named lease; the replica holding it does the
+//! work and the others stand by, taking over once the holder stops renewing.
+//! The local backend makes every replica a leader and suits single-replica
+//! deployments; the Redis backend is compiled in with the `redis-leader`
+//! feature.
+
+#[cfg(feature = "redis-leader")]
+mod redis;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::config::{LeaderElectionConfig, LeaseBackend};
+
+/// Storage for named, expiring leases
+#[async_trait]
+pub trait LeaseStore: Send + Sync {
+    /// Take the lease if it is free or renew it if `holder` already has it,
+    /// returning whether `holder` holds it now
+    async fn try_acquire(&self, name: &str, holder: &str, ttl: Duration) -> Result<bool>;
+
+    /// Give the lease up early if `holder` has it
+    async fn release(&self, name: &str, holder: &str) -> Result<()>;
+}
+
+/// Lease store for a single replica, which always holds every lease
+pub struct LocalLeases;
+
+#[async_trait]
+impl LeaseStore for LocalLeases {
+    async fn try_acquire(&self, _name: &str, _holder: &str, _ttl: Duration) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn release(&self, _name: &str, _holder: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Connect the configured lease store
+pub async fn connect(config: &LeaderElectionConfig) -> Result<Arc<dyn LeaseStore>> {
+    match config.backend {
+        LeaseBackend::Local => Ok(Arc::new(LocalLeases)),
+        #[cfg(feature = "redis-leader")]
+        LeaseBackend::Redis => {
+            let url = config
+                .url
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("LEADER_ELECTION_URL is required for Redis leader election"))?;
+            Ok(Arc::new(redis::RedisLeases::connect(url).await?))
+        }
+        #[allow(unreachable_patterns)]
+        backend => Err(anyhow::anyhow!(
+            "Leader election backend {:?} is not compiled into this build",
+            backend
+        )),
+    }
+}
+
+/// Campaigns for leases on behalf of this replica
+pub struct LeaderElector {
+    store: Arc<dyn LeaseStore>,
+    holder: String,
+    config: LeaderElectionConfig,
+}
+
+impl LeaderElector {
+    pub fn new(store: Arc<dyn LeaseStore>, holder: String, config: LeaderElectionConfig) -> Self {
+        Self { store, holder, config }
+    }
+
+    /// Contend for the lease `name` for as long as the process runs, keeping
+    /// it renewed once held
+    pub fn campaign(self: &Arc<Self>, name: &'static str) -> Leadership {
+        let (tx, rx) = watch::channel(false);
+        let elector = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(elector.config.renew_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let held = match elector
+                    .store
+                    .try_acquire(name, &elector.holder, elector.config.lease_ttl)
+                    .await
+                {
+                    Ok(held) => held,
+                    Err(e) => {
+                        // The lease may lapse before the store is reachable again,
+                        // so stop acting as leader rather than risk running twice
+                        warn!("Failed to renew the {} lease: {}", name, e);
+                        false
+                    }
+                };
+                if held != *tx.borrow() {
+                    if held {
+                        info!("Acquired the {} lease", name);
+                    } else {
+                        info!("Lost the {} lease", name);
+                    }
+                }
+                if tx.send(held).is_err() {
+                    // Every task interested in this lease has stopped
+                    if let Err(e) = elector.store.release(name, &elector.holder).await {
+                        warn!("Failed to release the {} lease: {}", name, e);
+                    }
+                    break;
+                }
+            }
+        });
+        Leadership { name, held: rx }
+    }
+}
+
+/// This replica's standing for one lease
+#[derive(Clone)]
+pub struct Leadership {
+    name: &'static str,
+    held: watch::Receiver<bool>,
+}
+
+impl Leadership {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Whether this replica should do the lease's work right now
+    pub fn is_leader(&self) -> bool {
+        *self.held.borrow()
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use redis::aio::MultiplexedConnection;
+use redis::Script;
+use std::time::Duration;
+
+use super::LeaseStore;
+
+/// Renews the lease if `holder` has it, otherwise takes it only if it's free
+const ACQUIRE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    return 1
+end
+if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then
+    return 1
+end
+return 0
+"#;
+
+/// Deletes the lease only if `holder` still has it
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// Leases held as expiring Redis keys
+pub struct RedisLeases {
+    connection: MultiplexedConnection,
+    acquire: Script,
+    release: Script,
+}
+
+impl RedisLeases {
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = redis::Client::open(url).context("Invalid Redis leader election URL")?;
+        let connection = client
+            .get_multiplexed_async_connection()
+            .await
+            .context("Failed to connect to Redis for leader election")?;
+        Ok(Self {
+            connection,
+            acquire: Script::new(ACQUIRE_SCRIPT),
+            release: Script::new(RELEASE_SCRIPT),
+        })
+    }
+}
+
+fn lease_key(name: &str) -> String {
+    format!("syla:gateway:leader:{}", name)
+}
+
+#[async_trait]
+impl LeaseStore for RedisLeases {
+    async fn try_acquire(&self, name: &str, holder: &str, ttl: Duration) -> Result<bool> {
+        let mut connection = self.connection.clone();
+        let held: i64 = self
+            .acquire
+            .key(lease_key(name))
+            .arg(holder)
+            .arg(ttl.as_millis() as u64)
+            .invoke_async(&mut connection)
+            .await?;
+        Ok(held == 1)
+    }
+
+    async fn release(&self, name: &str, holder: &str) -> Result<()> {
+        let mut connection = self.connection.clone();
+        let _: i64 = self
+            .release
+            .key(lease_key(name))
+            .arg(holder)
+            .invoke_async(&mut connection)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+// The OpenAPI schema table is one large json! literal
+#![recursion_limit = "256"]
+
+pub mod admin;
+pub mod admission;
+pub mod alerts;
+pub mod ansi;
+pub mod archive;
+pub mod artifacts;
+pub mod audit;
+pub mod auth;
+pub mod build_info;
+pub mod bulk;
+pub mod cache;
+pub mod callbacks;
+pub mod canary;
+pub mod client_ip;
+pub mod client_version;
+pub mod clients;
+pub mod compat;
+pub mod concurrency;
+pub mod config;
+pub mod cors;
+pub mod db;
+pub mod delivery;
+pub mod error;
+pub mod error_details;
+pub mod events;
+pub mod execution;
+pub mod export;
+pub mod extension;
+pub mod groups;
+pub mod grpc;
+pub mod grpc_cache;
+pub mod health;
+pub mod i18n;
+pub mod ids;
+pub mod inflight;
+pub mod languages;
+pub mod leader;
+pub mod logs;
+pub mod metering;
+pub mod metrics;
+pub mod openapi;
+pub mod outbox;
+pub mod output;
+pub mod pipeline;
+pub mod proto;
+pub mod protobuf;
+pub mod proxy;
+pub mod ratelimit;
+pub mod read_only;
+pub mod records;
+pub mod response;
+pub mod schedule;
+pub mod schema_bundle;
+pub mod server_timing;
+pub mod settings;
+pub mod shadow;
+pub mod slo;
+pub mod state;
+pub mod stream_compression;
+pub mod timeline;
+pub mod trace;
+pub mod uploads;
+pub mod watchdog;
+pub mod webhook;
+pub mod workspace;
@@ -0,0 +1,199 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::ansi::AnsiMode;
+use crate::auth::{self, AuthContext, AuthInterceptor, ADMIN_SCOPE};
+use crate::error::ApiError;
+use crate::state::AppState;
+
+/// Log download routes, authenticated
+pub fn routes(auth_interceptor: AuthInterceptor) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/executions/:id/logs", get(download_logs))
+        .route_layer(middleware::from_fn_with_state(auth_interceptor, auth::require_auth))
+}
+
+/// Where execution `id`'s stdout is downloaded; `?stream=stderr` for stderr
+pub fn url(id: Uuid) -> String {
+    format!("/v1/executions/{}/logs", id)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum LogStream {
+    #[default]
+    Stdout,
+    Stderr,
+}
+
+impl LogStream {
+    fn as_str(self) -> &'static str {
+        match self {
+            LogStream::Stdout => "stdout",
+            LogStream::Stderr => "stderr",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LogsQuery {
+    #[serde(default)]
+    stream: LogStream,
+    #[serde(default)]
+    ansi: AnsiMode,
+    /// Ask browsers to save the log rather than display it
+    #[serde(default)]
+    download: bool,
+}
+
+/// One stream of a finished execution's output as plain text, so large
+/// output can be fetched without embedding it in JSON. Honors a single
+/// `Range: bytes=…` range; multiple ranges get the whole log. Only the
+/// execution's owner or an admin may download it
+async fn download_logs(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<LogsQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let mut execution = state.get_owned_execution(&auth_context, id, ADMIN_SCOPE).await?;
+    // A running execution may already carry partial output
+    if !execution.status.is_terminal() {
+        return Err(ApiError::Conflict(format!("Execution {} has not finished", id)));
+    }
+    execution.render_ansi(query.ansi);
+    let result = execution.result.ok_or(ApiError::NotFound)?;
+    let log = match query.stream {
+        LogStream::Stdout => result.stdout,
+        LogStream::Stderr => result.stderr,
+    }
+    .into_bytes();
+
+    let disposition = format!(
+        "{}; filename=\"{}.{}.log\"",
+        if query.download { "attachment" } else { "inline" },
+        id,
+        query.stream.as_str()
+    );
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/plain; charset=utf-8"));
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    if let Ok(value) = HeaderValue::from_str(&disposition) {
+        response_headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+
+    let len = log.len() as u64;
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| byte_range(value, len));
+    let (status, body) = match range {
+        None | Some(Range::Ignored) => (StatusCode::OK, log),
+        Some(Range::Unsatisfiable) => {
+            if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", len)) {
+                response_headers.insert(header::CONTENT_RANGE, value);
+            }
+            return Ok((StatusCode::RANGE_NOT_SATISFIABLE, response_headers).into_response());
+        }
+        Some(Range::Bytes(start, end)) => {
+            if let Ok(value) = HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end, len)) {
+                response_headers.insert(header::CONTENT_RANGE, value);
+            }
+            (StatusCode::PARTIAL_CONTENT, log[start as usize..=end as usize].to_vec())
+        }
+    };
+    // Content-Length is set from the body
+    Ok((status, response_headers, body).into_response())
+}
+
+#[derive(Debug, PartialEq)]
+enum Range {
+    /// Inclusive first and last byte
+    Bytes(u64, u64),
+    Unsatisfiable,
+    /// Malformed or multi-range, served as a full response
+    Ignored,
+}
+
+/// Resolve a `Range` header against a body of `len` bytes
+fn byte_range(value: &str, len: u64) -> Range {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return Range::Ignored;
+    };
+    if spec.contains(',') {
+        return Range::Ignored;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return Range::Ignored;
+    };
+    let (start, end) = match (start.trim(), end.trim()) {
+        // The last `suffix` bytes
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return Range::Unsatisfiable,
+            Ok(suffix) => (len.saturating_sub(suffix), len.saturating_sub(1)),
+            Err(_) => return Range::Ignored,
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => (start, len.saturating_sub(1)),
+            Err(_) => return Range::Ignored,
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+            _ => return Range::Ignored,
+        },
+    };
+    if len == 0 || start >= len {
+        return Range::Unsatisfiable;
+    }
+    Range::Bytes(start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_range_resolves_bounded_ranges() {
+        assert_eq!(byte_range("bytes=0-99", 1000), Range::Bytes(0, 99));
+        assert_eq!(byte_range(" bytes= 10 - 20 ", 1000), Range::Bytes(10, 20));
+        // The end is clamped to the body
+        assert_eq!(byte_range("bytes=900-2000", 1000), Range::Bytes(900, 999));
+    }
+
+    #[test]
+    fn byte_range_resolves_open_and_suffix_ranges() {
+        assert_eq!(byte_range("bytes=500-", 1000), Range::Bytes(500, 999));
+        assert_eq!(byte_range("bytes=-100", 1000), Range::Bytes(900, 999));
+        // A suffix longer than the body covers all of it
+        assert_eq!(byte_range("bytes=-5000", 1000), Range::Bytes(0, 999));
+    }
+
+    #[test]
+    fn byte_range_rejects_ranges_outside_the_body() {
+        assert_eq!(byte_range("bytes=1000-", 1000), Range::Unsatisfiable);
+        assert_eq!(byte_range("bytes=1000-1100", 1000), Range::Unsatisfiable);
+        assert_eq!(byte_range("bytes=-0", 1000), Range::Unsatisfiable);
+        assert_eq!(byte_range("bytes=0-", 0), Range::Unsatisfiable);
+        assert_eq!(byte_range("bytes=-10", 0), Range::Unsatisfiable);
+    }
+
+    #[test]
+    fn byte_range_ignores_malformed_and_multi_ranges() {
+        assert_eq!(byte_range("items=0-10", 1000), Range::Ignored);
+        assert_eq!(byte_range("bytes=0-10,20-30", 1000), Range::Ignored);
+        assert_eq!(byte_range("bytes=10", 1000), Range::Ignored);
+        assert_eq!(byte_range("bytes=20-10", 1000), Range::Ignored);
+        assert_eq!(byte_range("bytes=a-10", 1000), Range::Ignored);
+        assert_eq!(byte_range("bytes=-x", 1000), Range::Ignored);
+    }
+}
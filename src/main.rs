@@ -1,37 +1,180 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use axum::{
-    extract::{Path, State},
-    response::IntoResponse,
-    routing::{get, post},
-    Json, Router,
+    extract::{
+        ws::Message,
+        Path, Query, State,
+    },
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, patch, post},
+    Extension, Json, Router, ServiceExt,
 };
-use serde::Serialize;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use tower::{util::MapRequestLayer, Layer};
 use tower_http::{
-    cors::{Any, CorsLayer},
     limit::RequestBodyLimitLayer,
+    request_id::{PropagateRequestIdLayer, SetRequestIdLayer},
     trace::TraceLayer,
 };
+use tokio::sync::watch;
+use tokio_stream::wrappers::TcpListenerStream;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use uuid::Uuid;
 
-mod auth;
-mod clients;
-mod error;
-mod execution;
-mod grpc;
-mod proto;
-mod state;
+use syla_api_gateway::{
+    admin, admission::{self, AdmissionLayer}, ansi::{self, AnsiMode}, archive, artifacts, auth, build_info, cache::STALE_HEADER, callbacks, client_version,
+    auth::{AuthContext, ADMIN_SCOPE},
+    client_ip::ClientIpLayer,
+    clients::execution::UpstreamListQuery,
+    inflight::{InflightLayer, Listener},
+    languages::LanguageCatalog,
+    compat::{CompatJson, SchemaVersion, VersionedExecution},
+    config::{self, Config},
+    cors,
+    db,
+    error::ApiError,
+    execution, export, extension::{self, Extensions}, groups, grpc, health, i18n, ids, logs, openapi, proto, proxy::ProxyLayer,
+    protobuf::{self, ExecutionRequestBody, Protobuf},
+    ratelimit::{self, RateCharge, RateLimitLayer}, read_only::{self, ReadOnlyLayer}, pipeline, response, schedule, schema_bundle, server_timing::{self, ServerTimingLayer}, settings, slo, timeline, trace, uploads,
+    stream_compression::{self, SessionSocket, SessionUpgrade},
+    webhook,
+    workspace,
+    output::{self, OutputEvent, OutputStart, ResumeToken},
+    state::{AppState, ExecutionFilter, StdinWriter, DEFAULT_LIST_LIMIT, MAX_LIST_LIMIT, MAX_STATUS_BATCH},
+};
+
+
+#[derive(Deserialize)]
+struct CreateExecutionQuery {
+    /// Seconds to wait for the execution to finish before responding, or
+    /// `true` to wait up to `timeout`
+    wait: Option<String>,
+    /// Longest wait with `wait=true`, e.g. `30s`, `500ms` or `2m`; the
+    /// configured maximum when unset
+    timeout: Option<String>,
+    #[serde(default)]
+    ansi: AnsiMode,
+}
+
+impl CreateExecutionQuery {
+    /// How long to hold the request for the execution to finish, if at all
+    fn wait(&self, max_wait: std::time::Duration) -> Result<Option<std::time::Duration>, ApiError> {
+        let invalid = |what: &str, value: &str| ApiError::BadRequest(format!("Invalid {} '{}'", what, value));
+        let wait = match self.wait.as_deref() {
+            None | Some("false") | Some("0") => return Ok(None),
+            Some("true") => match self.timeout.as_deref() {
+                Some(timeout) => parse_duration(timeout).ok_or_else(|| invalid("timeout", timeout))?,
+                None => max_wait,
+            },
+            Some(secs) => std::time::Duration::from_secs(secs.parse().map_err(|_| invalid("wait", secs))?),
+        };
+        Ok(Some(wait).filter(|wait| !wait.is_zero()))
+    }
+}
+
+/// A duration such as `30s`, `500ms` or `2m`; bare numbers are seconds
+fn parse_duration(value: &str) -> Option<std::time::Duration> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse().ok()?;
+    match unit {
+        "" | "s" => Some(std::time::Duration::from_secs(amount)),
+        "ms" => Some(std::time::Duration::from_millis(amount)),
+        "m" => Some(std::time::Duration::from_secs(amount.checked_mul(60)?)),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize)]
+struct OutputQuery {
+    #[serde(default)]
+    ansi: AnsiMode,
+}
+
+/// State a read can wait for the execution to reach
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum WaitFor {
+    Terminal,
+}
+
+#[derive(Deserialize)]
+struct WaitForQuery {
+    wait_for: Option<WaitFor>,
+    /// Longest hold with `wait_for`, e.g. `30s`, `500ms` or `2m`; the
+    /// configured maximum when unset
+    timeout: Option<String>,
+}
+
+impl WaitForQuery {
+    /// How long to hold the read for the execution to finish, if at all
+    fn wait(&self, max_wait: std::time::Duration) -> Result<Option<std::time::Duration>, ApiError> {
+        match (&self.wait_for, self.timeout.as_deref()) {
+            (None, _) => Ok(None),
+            (Some(WaitFor::Terminal), Some(timeout)) => parse_duration(timeout)
+                .map(Some)
+                .ok_or_else(|| ApiError::BadRequest(format!("Invalid timeout '{}'", timeout))),
+            (Some(WaitFor::Terminal), None) => Ok(Some(max_wait)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ListExecutionsQuery {
+    status: Option<execution::ExecutionStatus>,
+    pinned: Option<bool>,
+    created_after: Option<chrono::DateTime<chrono::Utc>>,
+    created_before: Option<chrono::DateTime<chrono::Utc>>,
+    session_id: Option<String>,
+    language: Option<String>,
+    limit: Option<usize>,
+    page_token: Option<String>,
+}
 
-use error::ApiError;
-use state::AppState;
+#[derive(Deserialize)]
+struct DeleteQuery {
+    /// Remove the execution's code, output, logs and artifacts now rather
+    /// than after the purge window
+    #[serde(default)]
+    purge: bool,
+}
+
+#[derive(Serialize)]
+struct ListExecutionsResponse {
+    executions: Vec<VersionedExecution>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BulkStatusRequest {
+    ids: Vec<Uuid>,
+}
+
+#[derive(Serialize)]
+struct BulkStatusResponse {
+    statuses: BTreeMap<Uuid, execution::ExecutionStatus>,
+    /// Error code for each execution whose status couldn't be read
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    errors: BTreeMap<Uuid, &'static str>,
+}
 
 #[derive(Serialize)]
 struct HealthResponse {
     status: String,
     version: String,
     timestamp: chrono::DateTime<chrono::Utc>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    components: BTreeMap<&'static str, health::ComponentReport>,
 }
 
 #[tokio::main]
@@ -45,8 +188,37 @@ async fn main() -> Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    // Initialize application state
-    let state = Arc::new(AppState::new().await?);
+    // Load configuration and initialize application state
+    let config = Config::from_env();
+    ids::install(ids::for_format(config.id_format));
+
+    let build = build_info::build_info();
+    tracing::info!(
+        version = build.version,
+        git_sha = build.git_sha,
+        build_time = %build.build_time,
+        features = ?build.features,
+        proto_descriptor_sha256 = %build.proto_descriptor_sha256,
+        execution_service = %config.upstream.execution_service_url,
+        event_bus = ?config.event_bus.backend,
+        "Starting Syla API gateway"
+    );
+
+    // Apply schema migrations and exit, e.g. from a deploy hook
+    if std::env::args().any(|arg| arg == "--migrate-only") {
+        let pool = db::connect(&config.database)
+            .await?
+            .context("--migrate-only requires DATABASE_URL")?;
+        return db::migrate(&pool).await;
+    }
+
+    let state = Arc::new(AppState::new(&config).await?);
+    state.warm_up().await?;
+
+    // Optional subsystems; custom builds register theirs here
+    let extensions = Arc::new(Extensions::builtin());
+    extensions.startup(&state).await?;
+    extension::spawn_reload_listener(extensions.clone(), state.clone());
 
     // Get configuration
     let rest_port = std::env::var("REST_PORT")
@@ -68,77 +240,633 @@ async fn main() -> Result<()> {
         .unwrap_or(false);
 
     // Create auth interceptor
-    let auth_interceptor = auth::AuthInterceptor::new(auth_service_url, skip_auth);
+    let mut auth_interceptor = auth::AuthInterceptor::new(auth_service_url, skip_auth);
+    if let Some(alerter) = state.alerter() {
+        auth_interceptor = auth_interceptor.with_latency_recorder(alerter.auth_latency());
+    }
+
+    // Both listeners charge callers by who they authenticate as
+    let rate_limit = RateLimitLayer::new(state.rate_limiter().clone(), auth_interceptor.clone());
 
     // Create gRPC service
-    let grpc_service = grpc::SylaGatewayService::new(state.clone(), auth_interceptor);
+    let grpc_service = grpc::SylaGatewayService::new(state.clone(), auth_interceptor.clone());
     let grpc_server = proto::SylaGatewayServer::new(grpc_service);
 
-    // Build REST router
-    let rest_app = Router::new()
+    // Build REST router, mounting only the route groups enabled for this deployment
+    let mut rest_app = Router::new()
         .route("/health", get(health_handler))
-        .route("/v1/executions", post(create_execution))
-        .route("/v1/executions/:id", get(get_execution))
-        .route("/v1/executions/:id/status", get(get_execution_status))
-        .layer(CorsLayer::new().allow_origin(Any))
-        .layer(RequestBodyLimitLayer::new(10 * 1024 * 1024)) // 10MB limit
+        .route("/ready", get(readiness_handler))
+        .route("/metrics", get(metrics_handler))
+        .route("/v1/version", get(version_handler))
+        .route("/v1/schema-bundle", get(schema_bundle::schema_bundle_handler))
+        .route("/openapi.json", get(openapi::openapi_handler))
+        .route("/docs", get(openapi::docs_handler));
+    if config.surface.cors {
+        rest_app = rest_app.merge(cors::routes(auth_interceptor.clone()));
+    }
+    if config.surface.executions {
+        rest_app = rest_app
+            .merge(execution_routes(auth_interceptor.clone()))
+            .merge(export::routes(auth_interceptor.clone()))
+            .merge(logs::routes(auth_interceptor.clone()))
+            .merge(timeline::routes(auth_interceptor.clone()))
+            .merge(schedule::routes(auth_interceptor.clone()))
+            .merge(pipeline::routes(auth_interceptor.clone()))
+            .merge(groups::routes(auth_interceptor.clone()))
+            .merge(artifacts::routes(auth_interceptor.clone()))
+            .merge(settings::routes(auth_interceptor.clone()));
+    }
+    if config.surface.workspaces {
+        rest_app = rest_app.merge(workspace::routes(auth_interceptor.clone()));
+    }
+    if config.surface.webhooks {
+        rest_app = rest_app.merge(webhook::routes(auth_interceptor.clone()));
+        if config.callbacks.secret.is_some() {
+            rest_app = rest_app.merge(callbacks::routes(config.callbacks.clone()));
+        }
+    }
+    if config.surface.admin {
+        rest_app = rest_app.merge(admin::routes(auth_interceptor.clone()));
+    }
+    // Upload content is streamed under its own cap, so uploads are mounted
+    // outside the inline body limit
+    rest_app = rest_app.layer(RequestBodyLimitLayer::new(config::MAX_REQUEST_BODY_BYTES));
+    if config.surface.executions {
+        rest_app = rest_app.merge(uploads::routes(auth_interceptor.clone()));
+    }
+    tracing::info!(surface = ?config.surface, "Configured API surface");
+    if config.read_only {
+        tracing::warn!("Starting read-only; mutations are rejected until it's turned off via the admin API");
+    }
+
+    let rest_app = rest_app
+        .layer(middleware::from_fn_with_state(state.clone(), archive::capture))
+        .layer(middleware::from_fn_with_state(state.clone(), client_version::track))
+        .layer(middleware::from_fn_with_state(state.clone(), read_only::reject_mutations))
+        .layer(middleware::from_fn_with_state(rate_limit.clone(), ratelimit::limit_rest))
+        .layer(middleware::from_fn_with_state(state.clone(), admission::admit_rest))
+        .layer(middleware::from_fn(i18n::negotiate_locale))
+        .layer(middleware::from_fn_with_state(state.clone(), slo::track_rest))
+        .layer(middleware::from_fn_with_state(state.clone(), trace::propagate))
+        .layer(middleware::from_fn_with_state(state.clone(), server_timing::annotate))
+        .layer(InflightLayer::new(state.inflight().clone(), Listener::Rest))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(ids::MakeGatewayRequestId))
+        .layer(middleware::from_fn_with_state(state.clone(), cors::apply))
         .layer(TraceLayer::new_for_http())
-        .with_state(state);
+        .layer(ClientIpLayer::new(config.trusted_proxies.clone()))
+        .with_state(state.clone());
 
-    // Start both servers
+    // Bind both listeners before reporting ready, so bind failures surface at startup
     let rest_addr = SocketAddr::from(([0, 0, 0, 0], rest_port));
     let grpc_addr = SocketAddr::from(([0, 0, 0, 0], grpc_port));
 
+    let rest_listener = tokio::net::TcpListener::bind(rest_addr)
+        .await
+        .with_context(|| format!("Failed to bind REST listener on {}", rest_addr))?;
+    let grpc_listener = tokio::net::TcpListener::bind(grpc_addr)
+        .await
+        .with_context(|| format!("Failed to bind gRPC listener on {}", grpc_addr))?;
+
     tracing::info!("Starting REST API on {}", rest_addr);
     tracing::info!("Starting gRPC API on {}", grpc_addr);
+    state.mark_ready();
+
+    // On SIGTERM/ctrl-c, stop accepting work and report drain progress
+    let (shutdown_tx, shutdown_rx) = watch::channel(());
+    tokio::spawn({
+        let state = state.clone();
+        let drain_timeout = config.drain_timeout;
+        async move {
+            shutdown_signal().await;
+            tracing::info!("Shutdown requested, draining in-flight requests");
+            state.inflight().begin_drain();
+            let _ = shutdown_tx.send(());
+            report_drain_progress(state, drain_timeout).await;
+        }
+    });
 
     // Spawn REST server
+    let mut rest_shutdown = shutdown_rx.clone();
     let rest_handle = tokio::spawn(async move {
-        let listener = tokio::net::TcpListener::bind(rest_addr)
-            .await
-            .expect("Failed to bind REST listener");
-        axum::serve(listener, rest_app)
+        let rest_app = MapRequestLayer::new(rewrite_custom_method).layer(rest_app);
+        let rest_service = ServiceExt::<axum::extract::Request>::into_make_service_with_connect_info::<SocketAddr>(rest_app);
+        axum::serve(rest_listener, rest_service)
+            .with_graceful_shutdown(async move {
+                let _ = rest_shutdown.changed().await;
+            })
             .await
             .expect("REST server failed");
     });
 
     // Spawn gRPC server
+    let mut grpc_shutdown = shutdown_rx;
+    let grpc_inflight = state.inflight().clone();
+    let grpc_admission = AdmissionLayer::new(state.admission().clone());
+    let grpc_rate_limit = rate_limit;
+    let grpc_read_only = ReadOnlyLayer::new(state.read_only().clone());
+    let grpc_proxy = ProxyLayer::new(state.upstream_proxy().cloned(), auth_interceptor.clone());
+    let grpc_clients = client_version::ClientVersionLayer::new(state.clone());
+    let grpc_client_ip = ClientIpLayer::new(config.trusted_proxies.clone());
+    let grpc_server_timing = ServerTimingLayer::new(config.response.server_timing);
     let grpc_handle = tokio::spawn(async move {
         tonic::transport::Server::builder()
+            .layer(InflightLayer::new(grpc_inflight, Listener::Grpc))
+            .layer(grpc_server_timing)
+            .layer(grpc_admission)
+            .layer(grpc_clients)
+            .layer(grpc_client_ip)
+            .layer(grpc_rate_limit)
+            .layer(grpc_read_only)
+            .layer(grpc_proxy)
             .add_service(grpc_server)
-            .serve(grpc_addr)
+            .serve_with_incoming_shutdown(TcpListenerStream::new(grpc_listener), async move {
+                let _ = grpc_shutdown.changed().await;
+            })
             .await
             .expect("gRPC server failed");
     });
 
     // Wait for both servers
     tokio::try_join!(rest_handle, grpc_handle)?;
+    extensions.shutdown(&state).await;
 
     Ok(())
 }
 
-async fn health_handler() -> impl IntoResponse {
-    Json(HealthResponse {
-        status: "healthy".to_string(),
-        version: env!("CARGO_PKG_VERSION").to_string(),
-        timestamp: chrono::Utc::now(),
-    })
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install ctrl-c handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Log in-flight counts until drained, exiting if the drain timeout elapses
+async fn report_drain_progress(state: Arc<AppState>, drain_timeout: std::time::Duration) {
+    let deadline = tokio::time::Instant::now() + drain_timeout;
+    loop {
+        let snapshot = state.inflight().snapshot();
+        if snapshot.is_idle() {
+            tracing::info!("Drain complete");
+            return;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            tracing::warn!(
+                requests = snapshot.total_requests,
+                streams = snapshot.total_streams,
+                "Drain timeout reached, exiting with work in flight"
+            );
+            std::process::exit(1);
+        }
+        tracing::info!(
+            requests = snapshot.total_requests,
+            streams = snapshot.total_streams,
+            "Draining"
+        );
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+fn execution_routes(auth_interceptor: auth::AuthInterceptor) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/executions", post(create_execution).get(list_executions))
+        .route("/v1/executions/validate", post(validate_execution))
+        .route("/v1/executions/status", post(get_execution_statuses))
+        .route("/v1/executions/:id", get(get_execution).delete(delete_execution))
+        .route("/v1/executions/:id/status", get(get_execution_status))
+        .route("/v1/executions/:id/result", get(get_execution_result))
+        .route("/v1/executions/:id/stream", get(stream_execution))
+        .route("/v1/executions/:id/ws", get(execution_websocket))
+        .route("/v1/executions/:id/pin", post(pin_execution).delete(unpin_execution))
+        .route("/v1/executions/:id/annotations", patch(annotate_execution))
+        .route("/v1/executions/:id/cancel", post(cancel_execution))
+        .route("/v1/executions/:id/resubmit", post(resubmit_execution))
+        .route("/v1/executions/:id/retry", post(retry_execution))
+        .route("/v1/sessions/:id/executions", get(list_session_executions))
+        .route("/v1/languages", get(list_languages))
+        .route_layer(middleware::from_fn_with_state(auth_interceptor, auth::require_auth))
+}
+
+/// Probes dependencies within the configured budget; degraded still answers 200
+async fn health_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let report = state.check_health().await;
+    let status = match report.status {
+        health::HealthStatus::Unhealthy => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::OK,
+    };
+
+    (
+        status,
+        Json(HealthResponse {
+            status: report.status.as_str().to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: chrono::Utc::now(),
+            components: report.components,
+        }),
+    )
 }
 
+async fn readiness_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let (status, label) = if state.is_ready() {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "starting")
+    };
+
+    (
+        status,
+        Json(HealthResponse {
+            status: label.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: chrono::Utc::now(),
+            components: BTreeMap::new(),
+        }),
+    )
+}
+
+/// What's deployed: version, git SHA, build time, features and proto descriptor hash
+async fn version_handler() -> Json<&'static build_info::BuildInfo> {
+    Json(build_info::build_info())
+}
+
+async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.render_metrics().await,
+    )
+}
+
+/// With `?wait=<seconds>`, or `?wait=true&timeout=30s`, holds the request until
+/// the execution finishes. If it's still running at the deadline, responds 202
+/// with the latest snapshot, including any partial output, so the client can
+/// carry on polling the execution.
 async fn create_execution(
     State(state): State<Arc<AppState>>,
-    Json(request): Json<execution::CreateExecutionRequest>,
-) -> Result<Json<execution::ExecutionResponse>, ApiError> {
-    let execution = state.create_execution(request).await?;
-    Ok(Json(execution))
+    Extension(auth_context): Extension<AuthContext>,
+    version: SchemaVersion,
+    Query(query): Query<CreateExecutionQuery>,
+    headers: HeaderMap,
+    ExecutionRequestBody(request): ExecutionRequestBody,
+) -> Result<Response, ApiError> {
+    let max_wait = state.config().sync_wait.max_wait;
+    let wait = query.wait(max_wait)?;
+    let mut execution = state.create_execution(&auth_context, request).await?;
+
+    if let Some(wait) = wait {
+        let mut warnings = std::mem::take(&mut execution.warnings);
+        if wait > max_wait {
+            warnings.push(execution::Warning::new(
+                "wait_clamped",
+                format!("Wait of {:?} clamped to the maximum of {}s", wait, max_wait.as_secs()),
+            ));
+        }
+        let deadline = tokio::time::Instant::now() + wait.min(max_wait);
+        execution = state.wait_for_completion(execution.id, deadline).await?;
+        execution.warnings = warnings;
+        execution.render_ansi(query.ansi);
+
+        let running = !execution.status.is_terminal();
+        if running {
+            let location = format!("/v1/executions/{}", execution.id);
+            let mut response = if protobuf::wants_protobuf(&headers) {
+                created_protobuf(&auth_context, execution)
+            } else {
+                response::execution_json(
+                    VersionedExecution::new(execution, version),
+                    &state.config().response,
+                )?
+            };
+            *response.status_mut() = StatusCode::ACCEPTED;
+            if let Ok(location) = header::HeaderValue::from_str(&location) {
+                response.headers_mut().insert(header::LOCATION, location);
+            }
+            return Ok(response);
+        }
+    }
+
+    execution.render_ansi(query.ansi);
+    if protobuf::wants_protobuf(&headers) {
+        return Ok(created_protobuf(&auth_context, execution));
+    }
+    response::execution_json(
+        VersionedExecution::new(execution, version),
+        &state.config().response,
+    )
+}
+
+/// A created execution as the gRPC API's `CreateExecutionResponse`, with its
+/// warnings in the same `x-syla-warning` headers the gRPC API uses
+fn created_protobuf(auth_context: &AuthContext, mut execution: execution::ExecutionResponse) -> Response {
+    let warnings = std::mem::take(&mut execution.warnings);
+    let mut execution = grpc::execution_to_proto(execution);
+    execution.user_id = auth_context.user_id.clone();
+    let mut response = Protobuf(proto::CreateExecutionResponse {
+        execution: Some(execution),
+    })
+    .into_response();
+    for warning in warnings {
+        if let Ok(value) = HeaderValue::from_str(&format!("{}: {}", warning.code, warning.message)) {
+            response.headers_mut().append(grpc::WARNING_KEY, value);
+        }
+    }
+    response
 }
 
 async fn get_execution(
     State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+    version: SchemaVersion,
+    Query(query): Query<OutputQuery>,
+    Query(wait): Query<WaitForQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let max_wait = state.config().sync_wait.max_wait;
+    // Held until the execution finishes or the wait runs out, then answered
+    // with the latest snapshot either way
+    let (mut execution, stale) = match wait.wait(max_wait)? {
+        Some(wait) => {
+            let deadline = tokio::time::Instant::now() + wait.min(max_wait);
+            let mut execution = state.wait_for_completion(id, deadline).await?;
+            if wait > max_wait {
+                execution.warnings.push(execution::Warning::new(
+                    "wait_clamped",
+                    format!("Wait of {:?} clamped to the maximum of {}s", wait, max_wait.as_secs()),
+                ));
+            }
+            (execution, false)
+        }
+        None => state.read_execution(id).await?,
+    };
+    execution.render_ansi(query.ansi);
+    let mut response = if protobuf::wants_protobuf(&headers) {
+        let mut execution = grpc::execution_to_proto(execution);
+        execution.user_id = auth_context.user_id;
+        Protobuf(proto::GetExecutionResponse {
+            execution: Some(execution),
+        })
+        .into_response()
+    } else {
+        response::execution_json(
+            VersionedExecution::new(execution, version),
+            &state.config().response,
+        )?
+    };
+    if stale {
+        response
+            .headers_mut()
+            .insert(STALE_HEADER, HeaderValue::from_static("true"));
+    }
+    Ok(response)
+}
+
+/// Just the result of a finished execution, for clients that only want its
+/// output. Only the execution's owner or an admin may fetch it
+async fn get_execution_result(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(id): Path<Uuid>,
-) -> Result<Json<execution::ExecutionResponse>, ApiError> {
-    let execution = state.get_execution(id).await?;
-    Ok(Json(execution))
+    Query(query): Query<OutputQuery>,
+) -> Result<Response, ApiError> {
+    let mut execution = state.get_owned_execution(&auth_context, id, ADMIN_SCOPE).await?;
+    if !execution.status.is_terminal() {
+        return Err(ApiError::Conflict(format!("Execution {} has not finished", id)));
+    }
+    execution.render_ansi(query.ansi);
+    let result = execution.result.ok_or(ApiError::NotFound)?;
+    response::result_json(id, result, &state.config().response)
+}
+
+/// A page of the caller's executions from the execution service, newest
+/// first. `pinned`, `session_id` and `tag` are gateway-owned, so they filter
+/// each page rather than the listing
+async fn list_executions(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    version: SchemaVersion,
+    Query(query): Query<ListExecutionsQuery>,
+    Query(params): Query<Vec<(String, String)>>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let tags = execution::Tag::from_query(&params).map_err(ApiError::BadRequest)?;
+    let upstream_query = UpstreamListQuery {
+        page_size: query.limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT) as u32,
+        page_token: query.page_token,
+        status: query.status,
+        language: query.language,
+        created_after: query.created_after,
+        created_before: query.created_before,
+    };
+    let page = state.list_executions(&auth_context, &upstream_query).await?;
+    let executions = page
+        .executions
+        .into_iter()
+        .filter(|execution| query.pinned.is_none_or(|pinned| execution.pinned == pinned))
+        .filter(|execution| {
+            query.session_id.is_none() || execution.session_id == query.session_id
+        })
+        .filter(|execution| execution::Tag::all_match(&tags, &execution.metadata));
+    if protobuf::wants_protobuf(&headers) {
+        let executions: Vec<_> = executions
+            .map(|execution| {
+                let mut execution = grpc::execution_to_proto(execution);
+                execution.user_id = auth_context.user_id.clone();
+                execution
+            })
+            .collect();
+        return Ok(Protobuf(proto::ListExecutionsResponse {
+            total_count: executions.len() as u32,
+            executions,
+            next_page_token: page.next_page_token.unwrap_or_default(),
+        })
+        .into_response());
+    }
+    Ok(Json(ListExecutionsResponse {
+        executions: executions
+            .map(|execution| VersionedExecution::new(execution, version))
+            .collect(),
+        next_page_token: page.next_page_token,
+    })
+    .into_response())
+}
+
+/// The caller's executions submitted under one session, newest first
+async fn list_session_executions(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(session_id): Path<String>,
+    version: SchemaVersion,
+    Query(query): Query<ListExecutionsQuery>,
+    Query(params): Query<Vec<(String, String)>>,
+) -> Result<Json<ListExecutionsResponse>, ApiError> {
+    let filter = ExecutionFilter {
+        status: query.status,
+        pinned: query.pinned,
+        created_after: query.created_after,
+        created_before: query.created_before,
+        session_id: Some(session_id),
+        group_id: None,
+        tags: execution::Tag::from_query(&params).map_err(ApiError::BadRequest)?,
+        limit: query.limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT),
+    };
+    let executions = state
+        .list_stored_executions(&auth_context, &filter)
+        .await
+        .into_iter()
+        .map(|execution| VersionedExecution::new(execution, version))
+        .collect();
+    Ok(Json(ListExecutionsResponse {
+        executions,
+        next_page_token: None,
+    }))
+}
+
+async fn pin_execution(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+    version: SchemaVersion,
+) -> Result<Response, ApiError> {
+    let execution = state.set_pinned(&auth_context, id, true).await?;
+    response::execution_json(
+        VersionedExecution::new(execution, version),
+        &state.config().response,
+    )
+}
+
+async fn unpin_execution(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+    version: SchemaVersion,
+) -> Result<Response, ApiError> {
+    let execution = state.set_pinned(&auth_context, id, false).await?;
+    response::execution_json(
+        VersionedExecution::new(execution, version),
+        &state.config().response,
+    )
+}
+
+async fn cancel_execution(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+    version: SchemaVersion,
+) -> Result<Response, ApiError> {
+    let execution = state.cancel_execution(&auth_context, id).await?;
+    response::execution_json(
+        VersionedExecution::new(execution, version),
+        &state.config().response,
+    )
+}
+
+/// Report how a request would be handled without submitting it. Problems with
+/// the request come back as diagnostics, every one at once, rather than as the
+/// error creating the execution would return
+async fn validate_execution(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    ExecutionRequestBody(request): ExecutionRequestBody,
+) -> Result<Json<execution::ExecutionValidation>, ApiError> {
+    Ok(Json(state.validate_execution(&auth_context, request).await?))
+}
+
+/// Serve custom methods like `/v1/executions:validate` from their
+/// `/v1/executions/validate` routes, since the router can't match a `:`
+/// inside a path segment
+fn rewrite_custom_method(mut request: axum::extract::Request) -> axum::extract::Request {
+    const CUSTOM_METHODS: &[(&str, &str)] = &[("/v1/executions:validate", "/v1/executions/validate")];
+    let path = request.uri().path();
+    if let Some(&(_, route)) = CUSTOM_METHODS.iter().find(|(custom, _)| *custom == path) {
+        let mut parts = request.uri().clone().into_parts();
+        let path_and_query = match request.uri().query() {
+            Some(query) => format!("{}?{}", route, query),
+            None => route.to_string(),
+        };
+        if let Ok(path_and_query) = path_and_query.parse() {
+            parts.path_and_query = Some(path_and_query);
+            if let Ok(uri) = axum::http::Uri::from_parts(parts) {
+                *request.uri_mut() = uri;
+            }
+        }
+    }
+    request
+}
+
+/// Re-run an execution's original request with a partial body of overrides
+async fn resubmit_execution(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+    version: SchemaVersion,
+    CompatJson(overrides): CompatJson<execution::ResubmitOverrides>,
+) -> Result<Response, ApiError> {
+    let execution = state.resubmit_execution(&auth_context, id, overrides).await?;
+    response::execution_json(
+        VersionedExecution::new(execution, version),
+        &state.config().response,
+    )
+}
+
+/// Re-run a failed or timed-out execution's original request
+async fn retry_execution(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+    version: SchemaVersion,
+) -> Result<Response, ApiError> {
+    let execution = state.retry_execution(&auth_context, id).await?;
+    response::execution_json(
+        VersionedExecution::new(execution, version),
+        &state.config().response,
+    )
+}
+
+/// Attach or update the caller's notes and score on a finished execution
+async fn annotate_execution(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+    version: SchemaVersion,
+    Json(annotation): Json<execution::AnnotationPatch>,
+) -> Result<Response, ApiError> {
+    let execution = state.annotate_execution(&auth_context, id, annotation).await?;
+    response::execution_json(
+        VersionedExecution::new(execution, version),
+        &state.config().response,
+    )
+}
+
+/// Remove an execution from history; unlike cancel, this doesn't touch a running execution
+async fn delete_execution(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DeleteQuery>,
+) -> Result<StatusCode, ApiError> {
+    if query.purge {
+        state.purge_execution(&auth_context, id).await?;
+    } else {
+        state.delete_execution(&auth_context, id).await?;
+    }
+    Ok(StatusCode::NO_CONTENT)
 }
 
 async fn get_execution_status(
@@ -147,4 +875,221 @@ async fn get_execution_status(
 ) -> Result<Json<execution::ExecutionStatus>, ApiError> {
     let status = state.get_execution_status(id).await?;
     Ok(Json(status))
-}
\ No newline at end of file
+}
+
+/// Languages the execution backend supports, as discovered at startup
+async fn list_languages(State(state): State<Arc<AppState>>) -> Json<LanguageCatalog> {
+    Json(state.languages().await.as_ref().clone())
+}
+
+/// Statuses of up to `MAX_STATUS_BATCH` executions in one call, each charged
+/// to the rate limit; IDs that can't be read, or aren't the caller's, are
+/// reported under `errors` rather than failing the batch
+async fn get_execution_statuses(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Extension(charge): Extension<RateCharge>,
+    Json(request): Json<BulkStatusRequest>,
+) -> Result<Json<BulkStatusResponse>, ApiError> {
+    let mut ids = request.ids;
+    ids.sort_unstable();
+    ids.dedup();
+    if ids.len() > MAX_STATUS_BATCH {
+        return Err(ApiError::BadRequest(format!(
+            "At most {} execution IDs can be queried at once",
+            MAX_STATUS_BATCH
+        )));
+    }
+    state.rate_limiter().charge_batch(&charge, ids.len())?;
+    let mut response = BulkStatusResponse {
+        statuses: BTreeMap::new(),
+        errors: BTreeMap::new(),
+    };
+    for (id, status) in state.get_execution_statuses(&auth_context, &ids).await {
+        match status {
+            Ok(status) => {
+                response.statuses.insert(id, status);
+            }
+            Err(e) => {
+                response.errors.insert(id, e.status_and_code().1);
+            }
+        }
+    }
+    Ok(Json(response))
+}
+
+#[derive(Serialize)]
+struct OutputData {
+    stream: &'static str,
+    data: String,
+    offset: u64,
+}
+
+/// Server-sent execution events: `status` on every status change, `output`
+/// for each stdout/stderr chunk, and `done` with the final status last.
+/// Subscribers start with the current status and recent output and follow
+/// live; reconnecting with `Last-Event-ID` resumes after the last event seen.
+/// With `?ansi=strip`, escape sequences are removed chunk by chunk, so one
+/// split across chunks may survive. Gzipped when enabled and accepted.
+/// Only the execution's owner or an admin may subscribe.
+async fn stream_execution(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<OutputQuery>,
+    headers: header::HeaderMap,
+) -> Result<Response, ApiError> {
+    state.get_owned_execution(&auth_context, id, ADMIN_SCOPE).await?;
+    let start = match headers.get("last-event-id").and_then(|v| v.to_str().ok()) {
+        Some(token) => OutputStart::From(
+            ResumeToken::decode(token)
+                .filter(|token| token.execution_id == id)
+                .map(|token| token.offsets)
+                .ok_or_else(|| ApiError::BadRequest("Invalid Last-Event-ID".to_string()))?,
+        ),
+        None => OutputStart::Recent,
+    };
+    let output = state.stream_output(id, start).await?;
+
+    let status_event = |event: &'static str, status: &execution::ExecutionStatus| {
+        Event::default()
+            .event(event)
+            .json_data(status)
+            .unwrap_or_else(|_| Event::default().event("error"))
+    };
+    let events = output.flat_map(move |delivered| {
+        let events = match delivered {
+            Ok((OutputEvent::Output(chunk), offsets)) => vec![Event::default()
+                .event("output")
+                .id(ResumeToken { execution_id: id, offsets }.encode())
+                .json_data(OutputData {
+                    stream: chunk.stream.as_str(),
+                    offset: chunk.offset,
+                    data: match query.ansi {
+                        AnsiMode::Strip => ansi::strip_ansi(&chunk.data),
+                        AnsiMode::Preserve => chunk.data,
+                    },
+                })
+                .unwrap_or_else(|_| Event::default().event("error"))],
+            Ok((OutputEvent::Status(status), _)) => vec![status_event("status", &status)],
+            Ok((OutputEvent::Finished(status), _)) => {
+                vec![status_event("status", &status), status_event("done", &status)]
+            }
+            Ok((OutputEvent::Interrupted(message), _)) => vec![Event::default().event("error").data(message)],
+            Err(e) => vec![Event::default().event("error").data(e.to_string())],
+        };
+        futures::stream::iter(events.into_iter().map(Ok::<_, std::convert::Infallible>))
+    });
+    let sse = Sse::new(events).keep_alive(KeepAlive::default()).into_response();
+    Ok(stream_compression::gzip_stream(sse, &headers, &state.config().stream_compression))
+}
+
+/// A frame sent to an interactive session
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum SessionFrame {
+    Output { stream: &'static str, data: String, offset: u64 },
+    Status { status: execution::ExecutionStatus },
+    /// The execution finished; the socket closes after this
+    Done { status: execution::ExecutionStatus },
+    Error { message: String },
+}
+
+/// Interactive session over a WebSocket. Text and binary frames from the client
+/// are written to the execution's stdin; output, status changes and a final
+/// `done` come back as JSON text frames. Closing the socket closes stdin.
+/// Frames are compressed with permessage-deflate when enabled and offered.
+async fn execution_websocket(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+    upgrade: SessionUpgrade,
+) -> Result<Response, ApiError> {
+    let stdin = state.open_stdin(&auth_context, id).await?;
+    let output = state.stream_output(id, OutputStart::Recent).await?;
+    let guard = state.inflight().track_stream("/v1/executions/:id/ws".to_string());
+    Ok(upgrade.on_upgrade(move |socket| async move {
+        let _guard = guard;
+        interactive_session(socket, stdin, output).await;
+    }))
+}
+
+async fn interactive_session(
+    mut socket: SessionSocket,
+    stdin: StdinWriter,
+    mut output: futures::stream::BoxStream<'static, Result<(OutputEvent, output::Offsets), ApiError>>,
+) {
+    loop {
+        let frames = tokio::select! {
+            incoming = socket.recv() => {
+                let written = match incoming {
+                    Some(Ok(Message::Text(text))) => stdin.write(text.into_bytes(), false).await,
+                    Some(Ok(Message::Binary(data))) => stdin.write(data, false).await,
+                    Some(Ok(Message::Ping(_) | Message::Pong(_))) => Ok(()),
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => {
+                        let _ = stdin.write(Vec::new(), true).await;
+                        return;
+                    }
+                };
+                match written {
+                    Ok(()) => continue,
+                    Err(e) => vec![SessionFrame::Error { message: e.to_string() }],
+                }
+            }
+            event = output.next() => match event {
+                Some(Ok((OutputEvent::Output(chunk), _))) => vec![SessionFrame::Output {
+                    stream: chunk.stream.as_str(),
+                    offset: chunk.offset,
+                    data: chunk.data,
+                }],
+                Some(Ok((OutputEvent::Status(status), _))) => vec![SessionFrame::Status { status }],
+                Some(Ok((OutputEvent::Finished(status), _))) => vec![
+                    SessionFrame::Status { status: status.clone() },
+                    SessionFrame::Done { status },
+                ],
+                Some(Ok((OutputEvent::Interrupted(message), _))) => vec![SessionFrame::Error { message }],
+                Some(Err(e)) => vec![SessionFrame::Error { message: e.to_string() }],
+                None => break,
+            },
+        };
+        for frame in &frames {
+            let Ok(text) = serde_json::to_string(frame) else {
+                continue;
+            };
+            if socket.send(Message::Text(text)).await.is_err() {
+                return;
+            }
+        }
+        if frames.iter().any(|frame| matches!(frame, SessionFrame::Done { .. })) {
+            break;
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn parse_duration_reads_each_unit() {
+        assert_eq!(parse_duration("30"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration(" 5s "), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn parse_duration_rejects_malformed_values() {
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("s"), None);
+        assert_eq!(parse_duration("-5s"), None);
+        assert_eq!(parse_duration("1.5s"), None);
+        assert_eq!(parse_duration("2h"), None);
+        assert_eq!(parse_duration("5 s"), None);
+        // Minutes that overflow in seconds
+        assert_eq!(parse_duration(&format!("{}m", u64::MAX)), None);
+    }
+}
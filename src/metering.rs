@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::execution::ExecutionStatus;
+
+/// Tracing target used for metering records so billing can consume them separately
+pub const METERING_TARGET: &str = "metering";
+
+/// Usage recorded once per execution, when it reaches a final status
+#[derive(Debug, Serialize)]
+pub struct MeteringEvent<'a> {
+    pub execution_id: Uuid,
+    pub user_id: Option<&'a str>,
+    pub tenant_id: Option<&'a str>,
+    pub status: &'a ExecutionStatus,
+    pub duration_ms: Option<u64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Write a usage record already serialized, as the delivery outbox stores
+/// them. Records may repeat after a restart; the execution ID identifies them
+pub fn record_serialized(json: &str) {
+    info!(target: METERING_TARGET, "{}", json);
+}
+
+/// Write a usage record for billing
+pub fn record(event: MeteringEvent<'_>) {
+    match serde_json::to_string(&event) {
+        Ok(json) => info!(target: METERING_TARGET, "{}", json),
+        Err(e) => info!(target: METERING_TARGET, "failed to serialize metering event {:?}: {}", event, e),
+    }
+}
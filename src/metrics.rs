@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::client_version::ClientVersion;
+use crate::clients::ChannelStats;
+
+/// Distinct client name/version pairs tracked before the rest are counted as "other"
+const MAX_CLIENT_SERIES: usize = 256;
+
+#[derive(Default)]
+struct ClientCounts {
+    requests: u64,
+    rejected: u64,
+}
+
+type ClientSeries = (&'static str, &'static str, fn(&ClientCounts) -> u64);
+
+/// Process-wide gateway counters, rendered in Prometheus text format
+#[derive(Default)]
+pub struct Metrics {
+    outputs_compressed: AtomicU64,
+    output_bytes_raw: AtomicU64,
+    output_bytes_compressed: AtomicU64,
+    canary_executions: AtomicU64,
+    stale_reads: AtomicU64,
+    revalidations: AtomicU64,
+    /// Requests by client name and version
+    clients: Mutex<BTreeMap<(String, String), ClientCounts>>,
+    /// Request bodies upgraded from each legacy shape
+    legacy_shapes: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one stdout/stderr payload stored compressed
+    pub fn record_compression(&self, raw_bytes: usize, compressed_bytes: usize) {
+        self.outputs_compressed.fetch_add(1, Ordering::Relaxed);
+        self.output_bytes_raw.fetch_add(raw_bytes as u64, Ordering::Relaxed);
+        self.output_bytes_compressed
+            .fetch_add(compressed_bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Record one execution routed to the canary backend
+    pub fn record_canary_execution(&self) {
+        self.canary_executions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record one execution read served from the cache because upstream was too slow
+    pub fn record_stale_read(&self) {
+        self.stale_reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record one finished execution refreshed in the background after being served
+    pub fn record_revalidation(&self) {
+        self.revalidations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record one request body that used a legacy shape
+    pub fn record_legacy_shape(&self, shape: &'static str) {
+        *self.legacy_shapes.lock().unwrap().entry(shape).or_default() += 1;
+    }
+
+    /// Record one request from `client`, unidentified clients included
+    pub fn record_client(&self, client: Option<&ClientVersion>, rejected: bool) {
+        let key = match client {
+            Some(client) => (client.name.clone(), client.version.to_string()),
+            None => ("unknown".to_string(), String::new()),
+        };
+        let mut clients = self.clients.lock().unwrap();
+        let key = if clients.len() >= MAX_CLIENT_SERIES && !clients.contains_key(&key) {
+            ("other".to_string(), String::new())
+        } else {
+            key
+        };
+        let counts = clients.entry(key).or_default();
+        counts.requests += 1;
+        if rejected {
+            counts.rejected += 1;
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        let compressed = self.outputs_compressed.load(Ordering::Relaxed);
+        let raw = self.output_bytes_raw.load(Ordering::Relaxed);
+        let stored = self.output_bytes_compressed.load(Ordering::Relaxed);
+        let ratio = if stored == 0 { 0.0 } else { raw as f64 / stored as f64 };
+
+        write_metric(
+            &mut out,
+            "syla_gateway_outputs_compressed_total",
+            "counter",
+            "Outputs stored zstd-compressed",
+            compressed as f64,
+        );
+        write_metric(
+            &mut out,
+            "syla_gateway_output_bytes_raw_total",
+            "counter",
+            "Uncompressed size of compressed outputs",
+            raw as f64,
+        );
+        write_metric(
+            &mut out,
+            "syla_gateway_output_bytes_compressed_total",
+            "counter",
+            "Stored size of compressed outputs",
+            stored as f64,
+        );
+        write_metric(
+            &mut out,
+            "syla_gateway_output_compression_ratio",
+            "gauge",
+            "Raw to stored byte ratio of compressed outputs",
+            ratio,
+        );
+        write_metric(
+            &mut out,
+            "syla_gateway_canary_executions_total",
+            "counter",
+            "Executions routed to the canary execution backend",
+            self.canary_executions.load(Ordering::Relaxed) as f64,
+        );
+        write_metric(
+            &mut out,
+            "syla_gateway_stale_reads_total",
+            "counter",
+            "Execution reads served from the cache while upstream was slow",
+            self.stale_reads.load(Ordering::Relaxed) as f64,
+        );
+        write_metric(
+            &mut out,
+            "syla_gateway_cache_revalidations_total",
+            "counter",
+            "Finished executions refreshed in the background after being served",
+            self.revalidations.load(Ordering::Relaxed) as f64,
+        );
+        self.render_clients(&mut out);
+        self.render_legacy_shapes(&mut out);
+
+        out
+    }
+
+    fn render_legacy_shapes(&self, out: &mut String) {
+        let name = "syla_gateway_legacy_request_shapes_total";
+        let _ = writeln!(out, "# HELP {} Request bodies upgraded from a legacy field name or shape", name);
+        let _ = writeln!(out, "# TYPE {} counter", name);
+        for (shape, count) in self.legacy_shapes.lock().unwrap().iter() {
+            let _ = writeln!(out, "{}{{shape=\"{}\"}} {}", name, shape, count);
+        }
+    }
+
+    fn render_clients(&self, out: &mut String) {
+        let clients = self.clients.lock().unwrap();
+        let series: [ClientSeries; 2] = [
+            ("syla_gateway_client_requests_total", "Requests by client SDK and version", |c| c.requests),
+            (
+                "syla_gateway_client_upgrade_rejections_total",
+                "Requests rejected for coming from a client older than the minimum",
+                |c| c.rejected,
+            ),
+        ];
+        for (name, help, value) in series {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            for ((client, version), counts) in clients.iter() {
+                let _ = writeln!(
+                    out,
+                    "{}{{client=\"{}\",version=\"{}\"}} {}",
+                    name,
+                    client,
+                    version,
+                    value(counts)
+                );
+            }
+        }
+    }
+}
+
+type ChannelSeries = (&'static str, &'static str, fn(&ChannelStats) -> u64);
+
+/// Render per-channel upstream accounting as labelled series
+pub fn render_channel_stats(stats: &[ChannelStats]) -> String {
+    let mut out = String::new();
+    let series: [ChannelSeries; 3] = [
+        ("syla_gateway_upstream_active_calls", "gauge", |s| s.active_calls),
+        ("syla_gateway_upstream_active_streams", "gauge", |s| s.active_streams),
+        ("syla_gateway_upstream_calls_total", "counter", |s| s.total_calls),
+    ];
+
+    for (name, kind, value) in series {
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        for s in stats {
+            let _ = writeln!(
+                out,
+                "{}{{service=\"{}\",channel=\"{}\"}} {}",
+                name,
+                s.service,
+                s.channel,
+                value(s)
+            );
+        }
+    }
+
+    out
+}
+
+fn write_metric(out: &mut String, name: &str, kind: &str, help: &str, value: f64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
@@ -0,0 +1,780 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock};
+
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse},
+    Json,
+};
+use serde_json::{json, Value};
+
+use crate::build_info;
+use crate::config::Config;
+use crate::execution;
+use crate::protobuf::PROTOBUF_CONTENT_TYPE;
+use crate::state::AppState;
+
+/// Route group an operation is mounted with
+#[derive(Clone, Copy)]
+enum Surface {
+    /// Always served
+    Core,
+    Executions,
+    Workspaces,
+    Cors,
+    Webhooks,
+}
+
+/// Public operations: method, path, operation ID, summary, whether it needs a
+/// bearer token, and the surface it belongs to
+const OPERATIONS: &[(&str, &str, &str, &str, bool, Surface)] = &[
+    ("get", "/health", "health", "Dependency health", false, Surface::Core),
+    ("get", "/ready", "ready", "Readiness to take traffic", false, Surface::Core),
+    ("get", "/v1/version", "getVersion", "Build information", false, Surface::Core),
+    ("get", "/v1/schema-bundle", "getSchemaBundle", "Schema bundle for SDKs", false, Surface::Core),
+    ("get", "/openapi.json", "getOpenApi", "This document", false, Surface::Core),
+    ("get", "/docs", "docs", "Interactive API documentation", false, Surface::Core),
+    ("get", "/v1/settings/cors", "getCorsPolicy", "Tenant's allowed browser origins", true, Surface::Cors),
+    ("put", "/v1/settings/cors", "putCorsPolicy", "Replace the tenant's allowed browser origins; new ones must serve their verification token", true, Surface::Cors),
+    ("get", "/v1/settings/cors/verification", "getOriginVerification", "Token proving the tenant controls an origin", true, Surface::Cors),
+    ("get", "/v1/settings/webhooks", "getWebhookSecret", "Secret the caller's completion webhooks are signed with", true, Surface::Webhooks),
+    ("post", "/v1/executions", "createExecution", "Submit an execution", true, Surface::Executions),
+    ("get", "/v1/executions", "listExecutions", "List the caller's executions", true, Surface::Executions),
+    ("post", "/v1/executions/status", "getExecutionStatuses", "Get the statuses of several executions", true, Surface::Executions),
+    ("post", "/v1/executions:validate", "validateExecution", "Check a request without submitting it", true, Surface::Executions),
+    ("get", "/v1/executions/:id", "getExecution", "Get an execution; `?wait_for=terminal&timeout=30s` holds the request until it finishes", true, Surface::Executions),
+    ("delete", "/v1/executions/:id", "deleteExecution", "Soft-delete an execution, or purge it with ?purge=true", true, Surface::Executions),
+    ("get", "/v1/executions/:id/result", "getExecutionResult", "Get a finished execution's result", true, Surface::Executions),
+    ("get", "/v1/executions/:id/status", "getExecutionStatus", "Get an execution's status", true, Surface::Executions),
+    ("get", "/v1/executions/:id/artifacts", "listExecutionArtifacts", "List created files with download URLs", true, Surface::Executions),
+    ("get", "/v1/executions/:id/logs", "downloadExecutionLogs", "Download stdout or stderr as plain text", true, Surface::Executions),
+    ("get", "/v1/executions/:id/events", "getExecutionEvents", "Status transitions with queue and run time", true, Surface::Executions),
+    ("get", "/v1/executions/:id/stream", "streamExecution", "Stream output as server-sent events", true, Surface::Executions),
+    ("get", "/v1/executions/:id/ws", "executionWebSocket", "Interactive session with stdin over a WebSocket", true, Surface::Executions),
+    ("post", "/v1/executions/:id/pin", "pinExecution", "Exempt from retention", true, Surface::Executions),
+    ("delete", "/v1/executions/:id/pin", "unpinExecution", "Subject to retention again", true, Surface::Executions),
+    ("patch", "/v1/executions/:id/annotations", "annotateExecution", "Annotate a finished execution", true, Surface::Executions),
+    ("post", "/v1/executions/:id/cancel", "cancelExecution", "Cancel an unfinished execution", true, Surface::Executions),
+    ("post", "/v1/executions/:id/resubmit", "resubmitExecution", "Resubmit with overrides", true, Surface::Executions),
+    ("post", "/v1/executions/:id/retry", "retryExecution", "Retry a failed or timed-out execution", true, Surface::Executions),
+    ("get", "/v1/executions/export", "exportExecutions", "Export executions as NDJSON", true, Surface::Executions),
+    ("get", "/v1/exports/:job_id", "getExportJob", "Get an export job", true, Surface::Executions),
+    ("get", "/v1/exports/:job_id/download", "downloadExport", "Download a finished export", true, Surface::Executions),
+    ("get", "/v1/schedules", "listSchedules", "List the caller's schedules", true, Surface::Executions),
+    ("post", "/v1/schedules", "createSchedule", "Submit an execution later, once or on a cron expression", true, Surface::Executions),
+    ("get", "/v1/schedules/:id", "getSchedule", "Get a schedule and the executions it submitted", true, Surface::Executions),
+    ("delete", "/v1/schedules/:id", "deleteSchedule", "Stop a schedule", true, Surface::Executions),
+    ("get", "/v1/pipelines", "listPipelines", "List the caller's pipelines", true, Surface::Executions),
+    ("post", "/v1/pipelines", "createPipeline", "Run executions in sequence, passing output along", true, Surface::Executions),
+    ("get", "/v1/pipelines/:id", "getPipeline", "Get a pipeline and the status of each step", true, Surface::Executions),
+    ("post", "/v1/pipelines/:id/cancel", "cancelPipeline", "Cancel the step in progress and skip the rest", true, Surface::Executions),
+    ("post", "/v1/uploads", "createUpload", "Start an upload for large code", true, Surface::Executions),
+    ("get", "/v1/uploads/:id", "getUpload", "Get an upload", true, Surface::Executions),
+    ("put", "/v1/uploads/:id", "putUploadContent", "Send an upload's content", true, Surface::Executions),
+    ("get", "/v1/groups/:id", "getGroup", "Get aggregate status of an execution group", true, Surface::Executions),
+    ("get", "/v1/sessions/:id/executions", "listSessionExecutions", "List a session's executions", true, Surface::Executions),
+    ("get", "/v1/languages", "listLanguages", "Languages, runtime versions and default limits", true, Surface::Executions),
+    ("get", "/v1/settings/executions", "getExecutionSettings", "Tenant execution defaults", true, Surface::Executions),
+    ("put", "/v1/settings/executions", "putExecutionSettings", "Replace tenant execution defaults", true, Surface::Executions),
+    ("post", "/v1/settings/executions/preview", "previewExecutionSettings", "Resolve settings for a request", true, Surface::Executions),
+    ("get", "/v1/workspaces", "listWorkspaces", "List the caller's workspaces", true, Surface::Workspaces),
+    ("post", "/v1/workspaces", "createWorkspace", "Create a workspace", true, Surface::Workspaces),
+    ("get", "/v1/workspaces/:id", "getWorkspace", "Get a workspace", true, Surface::Workspaces),
+    ("patch", "/v1/workspaces/:id", "updateWorkspace", "Update a workspace", true, Surface::Workspaces),
+    ("delete", "/v1/workspaces/:id", "deleteWorkspace", "Delete a workspace", true, Surface::Workspaces),
+];
+
+/// JSON bodies of operations that have a described shape: operation ID,
+/// request schema, success status and response schema
+const BODIES: &[(&str, Option<&str>, &str, Option<&str>)] = &[
+    ("createExecution", Some("CreateExecutionRequest"), "200", Some("Execution")),
+    ("listExecutions", None, "200", Some("ExecutionList")),
+    ("validateExecution", Some("CreateExecutionRequest"), "200", Some("ExecutionValidation")),
+    ("getExecution", None, "200", Some("Execution")),
+    ("getExecutionResult", None, "200", Some("ExecutionResult")),
+    ("deleteExecution", None, "204", None),
+    ("getExecutionStatus", None, "200", Some("ExecutionStatus")),
+    ("getExecutionEvents", None, "200", Some("ExecutionTimeline")),
+    ("pinExecution", None, "200", Some("Execution")),
+    ("unpinExecution", None, "200", Some("Execution")),
+    ("annotateExecution", Some("AnnotationPatch"), "200", Some("Execution")),
+    ("cancelExecution", None, "200", Some("Execution")),
+    ("resubmitExecution", Some("ResubmitOverrides"), "200", Some("Execution")),
+    ("retryExecution", None, "200", Some("Execution")),
+    ("listSchedules", None, "200", Some("ScheduleList")),
+    ("createSchedule", Some("CreateScheduleRequest"), "201", Some("Schedule")),
+    ("getSchedule", None, "200", Some("Schedule")),
+    ("deleteSchedule", None, "204", None),
+    ("listPipelines", None, "200", Some("PipelineList")),
+    ("createPipeline", Some("CreatePipelineRequest"), "201", Some("Pipeline")),
+    ("getPipeline", None, "200", Some("Pipeline")),
+    ("cancelPipeline", None, "200", Some("Pipeline")),
+    ("getGroup", None, "200", Some("ExecutionGroup")),
+    ("listSessionExecutions", None, "200", Some("ExecutionList")),
+    ("listLanguages", None, "200", Some("LanguageCatalog")),
+    ("getCorsPolicy", None, "200", Some("CorsPolicy")),
+    ("putCorsPolicy", Some("CorsPolicy"), "200", Some("CorsPolicy")),
+    ("getOriginVerification", None, "200", Some("OriginVerification")),
+    ("getWebhookSecret", None, "200", Some("WebhookSecret")),
+    ("listWorkspaces", None, "200", Some("WorkspacePage")),
+    ("createWorkspace", Some("CreateWorkspaceRequest"), "201", Some("Workspace")),
+    ("getWorkspace", None, "200", Some("Workspace")),
+    ("updateWorkspace", Some("UpdateWorkspaceRequest"), "200", Some("Workspace")),
+    ("deleteWorkspace", None, "204", None),
+];
+
+/// Operations that also speak the gRPC API's messages as
+/// `application/x-protobuf`: operation ID, and whether the request and the
+/// success response can be protobuf
+const PROTOBUF_OPERATIONS: &[(&str, bool, bool)] = &[
+    ("createExecution", true, true),
+    ("validateExecution", true, false),
+    ("getExecution", false, true),
+    ("listExecutions", false, true),
+];
+
+/// OpenAPI description of the operations this deployment serves
+pub fn document(config: &Config) -> Value {
+    let mut paths: BTreeMap<String, serde_json::Map<String, Value>> = BTreeMap::new();
+    for &(method, path, operation_id, summary, authenticated, surface) in OPERATIONS {
+        let mounted = match surface {
+            Surface::Core => true,
+            Surface::Executions => config.surface.executions,
+            Surface::Workspaces => config.surface.workspaces,
+            Surface::Cors => config.surface.cors,
+            Surface::Webhooks => config.surface.webhooks,
+        };
+        if !mounted {
+            continue;
+        }
+        let parameters: Vec<Value> = path
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix(':'))
+            .map(|name| json!({"name": name, "in": "path", "required": true, "schema": {"type": "string"}}))
+            .collect();
+        let mut operation = json!({
+            "operationId": operation_id,
+            "summary": summary,
+            "responses": {"default": {
+                "description": "See the error catalog for failures",
+                "content": {"application/json": {"schema": schema_ref("Error")}},
+            }},
+        });
+        if !parameters.is_empty() {
+            operation["parameters"] = Value::Array(parameters);
+        }
+        if !authenticated {
+            operation["security"] = json!([]);
+        }
+        if let Some(&(_, request, status, response)) = BODIES.iter().find(|(id, ..)| *id == operation_id) {
+            if let Some(request) = request {
+                operation["requestBody"] = json!({
+                    "required": true,
+                    "content": {"application/json": {"schema": schema_ref(request)}},
+                });
+            }
+            let mut success = json!({"description": "Success"});
+            if let Some(response) = response {
+                success["content"] = json!({"application/json": {"schema": schema_ref(response)}});
+            }
+            operation["responses"][status] = success;
+
+            let protobuf = PROTOBUF_OPERATIONS.iter().find(|(id, ..)| *id == operation_id);
+            if let Some(&(_, protobuf_request, protobuf_response)) = protobuf {
+                let binary = json!({"schema": {"type": "string", "format": "binary"}});
+                if protobuf_request {
+                    operation["requestBody"]["content"][PROTOBUF_CONTENT_TYPE] = binary.clone();
+                }
+                if protobuf_response {
+                    operation["responses"][status]["content"][PROTOBUF_CONTENT_TYPE] = binary;
+                }
+            }
+        }
+        let path = path
+            .split('/')
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(name) => format!("{{{}}}", name),
+                None => segment.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        paths.entry(path).or_default().insert(method.to_string(), operation);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Syla API Gateway",
+            "version": build_info::build_info().version,
+        },
+        "components": {
+            "securitySchemes": {"bearer": {"type": "http", "scheme": "bearer"}},
+            "schemas": schemas(),
+        },
+        "security": [{"bearer": []}],
+        "paths": paths,
+    })
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({"$ref": format!("#/components/schemas/{}", name)})
+}
+
+/// JSON shapes of the request and response types, in the current schema
+/// version. Kept beside the route table; update them with the types in
+/// `execution`, `workspace` and `error`
+fn schemas() -> Value {
+    let string_map = json!({"type": "object", "additionalProperties": {"type": "string"}});
+    json!({
+        "Error": {
+            "type": "object",
+            "required": ["error", "message"],
+            "properties": {
+                "error": {"type": "string", "description": "Code from the error catalog"},
+                "message": {"type": "string"},
+                "details": {"type": "object"},
+                "trace_id": {"type": "string"},
+                "trace_url": {"type": "string", "format": "uri"},
+            },
+        },
+        "Cancellation": {
+            "type": "object",
+            "required": ["cause", "reason", "at"],
+            "properties": {
+                "cause": {"type": "string", "enum": ["user", "admin", "quota", "timeout", "shutdown"]},
+                "actor": {"type": "string", "description": "User who asked for the cancellation; unset when no user did"},
+                "reason": {"type": "string"},
+                "at": {"type": "string", "format": "date-time"},
+            },
+        },
+        "ExecutionStatus": {
+            "type": "string",
+            "enum": ["pending", "queued", "preparing", "running", "cancelling", "completed", "failed", "cancelled", "timeout"],
+        },
+        "ExecutionTimeline": {
+            "type": "object",
+            "required": ["execution_id", "events"],
+            "properties": {
+                "execution_id": {"type": "string", "format": "uuid"},
+                "events": {
+                    "type": "array",
+                    "description": "Oldest first; each status appears once",
+                    "items": {
+                        "type": "object",
+                        "required": ["event", "status", "at"],
+                        "properties": {
+                            "event": {"type": "string", "description": "created, started, or the status entered"},
+                            "status": schema_ref("ExecutionStatus"),
+                            "at": {"type": "string", "format": "date-time"},
+                        },
+                    },
+                },
+                "queue_time_ms": {"type": "integer", "description": "From creation until it started running"},
+                "run_time_ms": {"type": "integer", "description": "From start until it reached a final status"},
+            },
+        },
+        "CreateScheduleRequest": {
+            "description": "An execution request, run once at run_at or on every match of cron; set exactly one",
+            "allOf": [
+                schema_ref("CreateExecutionRequest"),
+                {
+                    "type": "object",
+                    "properties": {
+                        "run_at": {"type": "string", "format": "date-time"},
+                        "cron": {"type": "string", "description": "Five-field cron expression, evaluated in UTC"},
+                    },
+                },
+            ],
+        },
+        "Schedule": {
+            "type": "object",
+            "required": ["id", "language", "next_run_at", "last_run_at", "execution_ids", "created_at"],
+            "properties": {
+                "id": {"type": "string", "format": "uuid"},
+                "language": {"type": "string"},
+                "run_at": {"type": "string", "format": "date-time"},
+                "cron": {"type": "string"},
+                "next_run_at": {"type": "string", "format": "date-time", "nullable": true, "description": "Unset once a one-off schedule has run"},
+                "last_run_at": {"type": "string", "format": "date-time", "nullable": true},
+                "last_error": {"type": "string", "description": "Why the last run could not be submitted"},
+                "execution_ids": {
+                    "type": "array",
+                    "description": "Most recent first, up to 100",
+                    "items": {"type": "string", "format": "uuid"},
+                },
+                "created_at": {"type": "string", "format": "date-time"},
+            },
+        },
+        "ScheduleList": {"type": "array", "items": schema_ref("Schedule")},
+        "CreatePipelineRequest": {
+            "type": "object",
+            "required": ["steps"],
+            "properties": {
+                "steps": {
+                    "type": "array",
+                    "minItems": 1,
+                    "description": "Run in order; each starts once the one before it completed with exit code 0",
+                    "items": {
+                        "allOf": [
+                            schema_ref("CreateExecutionRequest"),
+                            {
+                                "type": "object",
+                                "properties": {
+                                    "input": {
+                                        "type": "object",
+                                        "description": "What the step takes from the step before it",
+                                        "properties": {
+                                            "stdin": {"type": "boolean", "description": "Previous stdout, written to stdin"},
+                                            "stdout_file": {"type": "string", "description": "Path to pass the previous stdout as a file at"},
+                                            "artifacts": {
+                                                "type": "array",
+                                                "description": "UTF-8 files the previous step created, copied in at the same paths",
+                                                "items": {"type": "string"},
+                                            },
+                                        },
+                                    },
+                                },
+                            },
+                        ],
+                    },
+                },
+            },
+        },
+        "Pipeline": {
+            "type": "object",
+            "required": ["id", "status", "steps", "created_at", "completed_at"],
+            "properties": {
+                "id": {"type": "string", "format": "uuid"},
+                "status": {"type": "string", "enum": ["running", "completed", "failed", "cancelled"]},
+                "steps": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["language", "status"],
+                        "properties": {
+                            "language": {"type": "string"},
+                            "status": {"type": "string", "enum": ["waiting", "running", "completed", "failed", "cancelled", "skipped"]},
+                            "execution_id": {"type": "string", "format": "uuid"},
+                            "error": {"type": "string"},
+                        },
+                    },
+                },
+                "created_at": {"type": "string", "format": "date-time"},
+                "completed_at": {"type": "string", "format": "date-time", "nullable": true},
+            },
+        },
+        "PipelineList": {"type": "array", "items": schema_ref("Pipeline")},
+        "ExecutionGroup": {
+            "type": "object",
+            "required": ["id", "total", "counts", "finished", "members"],
+            "properties": {
+                "id": {"type": "string"},
+                "total": {"type": "integer"},
+                "counts": {
+                    "type": "object",
+                    "description": "Cancelled and timed-out executions count as failed",
+                    "properties": {
+                        "pending": {"type": "integer"},
+                        "running": {"type": "integer"},
+                        "completed": {"type": "integer"},
+                        "failed": {"type": "integer"},
+                    },
+                },
+                "finished": {"type": "boolean"},
+                "members": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["id", "status", "created_at"],
+                        "properties": {
+                            "id": {"type": "string", "format": "uuid"},
+                            "status": schema_ref("ExecutionStatus"),
+                            "exit_code": {"type": "integer"},
+                            "duration_ms": {"type": "integer"},
+                            "created_at": {"type": "string", "format": "date-time"},
+                            "completed_at": {"type": "string", "format": "date-time", "nullable": true},
+                        },
+                    },
+                },
+            },
+        },
+        "IsolationMode": {"type": "string", "enum": ["sandbox", "container", "process"]},
+        "CorsPolicy": {
+            "type": "object",
+            "properties": {
+                "allowed_origins": {
+                    "type": "array",
+                    "items": {"type": "string", "format": "uri"},
+                    "description": "Origins such as https://play.example.com; each belongs to one tenant",
+                },
+                "allow_credentials": {"type": "boolean"},
+                "max_age_seconds": {"type": "integer", "description": "Preflight cache lifetime"},
+                "updated_at": {"type": "string", "format": "date-time", "readOnly": true},
+                "updated_by": {"type": "string", "readOnly": true},
+            },
+        },
+        "OriginVerification": {
+            "type": "object",
+            "required": ["origin", "token", "url"],
+            "properties": {
+                "origin": {"type": "string", "format": "uri"},
+                "token": {"type": "string", "description": "Served as a line of the body at url"},
+                "url": {"type": "string", "format": "uri"},
+            },
+        },
+        "WebhookSecret": {
+            "type": "object",
+            "required": ["signing_secret"],
+            "properties": {
+                "signing_secret": {
+                    "type": "string",
+                    "description": "HMAC-SHA256 key x-syla-signature is computed with; one per tenant, or per user outside tenants",
+                },
+            },
+        },
+        "ResourceLimits": {
+            "type": "object",
+            "description": "Unset fields use the executor's defaults",
+            "properties": {
+                "memory_mb": {"type": "integer", "minimum": 1},
+                "cpu_cores": {"type": "number"},
+                "cpu_millis": {"type": "integer", "minimum": 1, "description": "Alternative to cpu_cores"},
+                "disk_mb": {"type": "integer", "minimum": 1},
+                "enable_network": {"type": "boolean"},
+            },
+        },
+        "Language": {
+            "type": "object",
+            "required": ["name", "default_limits"],
+            "properties": {
+                "name": {"type": "string", "description": "Name used in execution requests"},
+                "aliases": {"type": "array", "items": {"type": "string"}},
+                "version": {"type": "string"},
+                "versions": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Versions requests may pin with language_version",
+                },
+                "default_limits": schema_ref("ResourceLimits"),
+                "default_timeout_seconds": {"type": "integer"},
+            },
+        },
+        "LanguageCatalog": {
+            "type": "object",
+            "required": ["languages", "source"],
+            "properties": {
+                "languages": {"type": "array", "items": schema_ref("Language")},
+                "source": {
+                    "type": "string",
+                    "enum": ["backend", "builtin"],
+                    "description": "builtin when the execution service couldn't list its languages",
+                },
+            },
+        },
+        "ExecutionFile": {
+            "type": "object",
+            "required": ["path", "content"],
+            "properties": {
+                "path": {"type": "string", "description": "Relative to the working directory"},
+                "content": {"type": "string"},
+            },
+        },
+        "ResultDestination": {
+            "type": "object",
+            "required": ["type"],
+            "properties": {
+                "type": {"type": "string", "enum": ["presigned_url", "tenant_bucket"]},
+                "url": {"type": "string", "description": "For presigned_url"},
+                "prefix": {"type": "string", "description": "For tenant_bucket"},
+            },
+        },
+        "CreateExecutionRequest": {
+            "type": "object",
+            "required": ["language"],
+            "properties": {
+                "code": {"type": "string", "description": "Empty when the code comes from upload_id"},
+                "language": {"type": "string"},
+                "language_version": {"type": "string", "description": "One of the language's listed versions"},
+                "timeout_seconds": {"type": "integer"},
+                "args": {
+                    "type": "array",
+                    "maxItems": execution::MAX_ARGS,
+                    "items": {"type": "string", "maxLength": execution::MAX_ARG_BYTES},
+                },
+                "workspace_id": {"type": "string", "format": "uuid"},
+                "env": {
+                    "type": "object",
+                    "maxProperties": execution::MAX_ENV_VARS,
+                    "additionalProperties": {"type": "string", "maxLength": execution::MAX_ENV_VALUE_BYTES},
+                },
+                "resources": schema_ref("ResourceLimits"),
+                "mode": schema_ref("IsolationMode"),
+                "tty": {"type": "boolean"},
+                "session_id": {"type": "string", "maxLength": execution::MAX_SESSION_ID_BYTES},
+                "group_id": {"type": "string", "maxLength": execution::MAX_GROUP_ID_BYTES},
+                "upload_id": {"type": "string", "format": "uuid"},
+                "result_destination": schema_ref("ResultDestination"),
+                "callback_url": {"type": "string", "format": "uri"},
+                "files": {"type": "array", "maxItems": execution::MAX_EXECUTION_FILES, "items": schema_ref("ExecutionFile")},
+                "priority": {
+                    "type": "string",
+                    "enum": ["low", "normal", "high"],
+                    "description": "Lower priorities are shed first when the gateway is saturated; normal when unset",
+                },
+                "concurrency_group": {
+                    "type": "string",
+                    "minLength": 1,
+                    "maxLength": execution::MAX_CONCURRENCY_GROUP_BYTES,
+                    "description": "Executions with the same group run one at a time; the create request waits until it's this one's turn",
+                },
+                "stdin": {"type": "string", "description": "Written to the program's stdin, which is then closed"},
+                "stdin_base64": {
+                    "type": "string",
+                    "format": "byte",
+                    "description": "stdin as base64, for binary input; at most one of stdin and stdin_base64",
+                },
+                "metadata": {
+                    "type": "object",
+                    "maxProperties": execution::MAX_METADATA_ENTRIES,
+                    "additionalProperties": {"type": "string", "maxLength": execution::MAX_METADATA_VALUE_BYTES},
+                    "description": "Labels to filter listings by with ?tag=key:value; keys can't contain ':'",
+                },
+            },
+        },
+        "ResubmitOverrides": {
+            "type": "object",
+            "description": "env and metadata are merged key by key; other fields replace the original when present",
+            "properties": {
+                "code": {"type": "string"},
+                "language": {"type": "string"},
+                "language_version": {"type": "string", "description": "One of the language's listed versions"},
+                "timeout_seconds": {"type": "integer"},
+                "args": {"type": "array", "items": {"type": "string"}},
+                "workspace_id": {"type": "string", "format": "uuid"},
+                "env": string_map,
+                "resources": schema_ref("ResourceLimits"),
+                "mode": schema_ref("IsolationMode"),
+                "tty": {"type": "boolean"},
+                "metadata": string_map,
+            },
+        },
+        "ExecutionError": {
+            "type": "object",
+            "required": ["kind", "code", "message"],
+            "properties": {
+                "kind": {"type": "string", "enum": ["compile", "runtime", "timeout", "resource", "other"]},
+                "code": {"type": "string"},
+                "message": {"type": "string"},
+                "line": {"type": "integer"},
+                "column": {"type": "integer"},
+                "details": {"type": "string"},
+                "stack_trace": {"type": "string"},
+            },
+        },
+        "ExecutionResult": {
+            "type": "object",
+            "required": ["exit_code", "stdout", "stderr", "duration_ms"],
+            "properties": {
+                "exit_code": {"type": "integer"},
+                "stdout": {"type": "string"},
+                "stderr": {"type": "string"},
+                "duration_ms": {"type": "integer"},
+                "ansi": {"type": "boolean"},
+                "error": schema_ref("ExecutionError"),
+                "files_created": {"type": "array", "items": schema_ref("CreatedFile")},
+                "stdout_sha256": {"type": "string"},
+                "stderr_sha256": {"type": "string"},
+            },
+        },
+        "CreatedFile": {
+            "type": "object",
+            "required": ["path", "size_bytes"],
+            "properties": {
+                "path": {"type": "string"},
+                "size_bytes": {"type": "integer"},
+                "mime_type": {"type": "string"},
+            },
+        },
+        "Warning": {
+            "type": "object",
+            "required": ["code", "message"],
+            "properties": {"code": {"type": "string"}, "message": {"type": "string"}},
+        },
+        "Diagnostic": {
+            "type": "object",
+            "required": ["field", "code", "message"],
+            "properties": {
+                "field": {"type": "string", "description": "Path of the offending field, e.g. files[2].path"},
+                "code": {"type": "string"},
+                "message": {"type": "string"},
+            },
+        },
+        "Annotation": {
+            "type": "object",
+            "required": ["updated_at"],
+            "properties": {
+                "note": {"type": "string", "nullable": true},
+                "score": {"type": "number", "nullable": true},
+                "data": {"nullable": true},
+                "updated_at": {"type": "string", "format": "date-time"},
+            },
+        },
+        "AnnotationPatch": {
+            "type": "object",
+            "properties": {
+                "note": {"type": "string"},
+                "score": {"type": "number"},
+                "data": {},
+            },
+        },
+        "ResultDelivery": {
+            "type": "object",
+            "required": ["state", "location"],
+            "properties": {
+                "state": {"type": "string", "enum": ["pending", "delivered", "failed"]},
+                "location": {"type": "string"},
+                "size_bytes": {"type": "integer"},
+                "sha256": {"type": "string"},
+                "delivered_at": {"type": "string", "format": "date-time"},
+                "error": {"type": "string"},
+            },
+        },
+        "Execution": {
+            "type": "object",
+            "required": ["schema_version", "id", "status", "created_at", "pinned"],
+            "properties": {
+                "schema_version": {"type": "integer"},
+                "id": {"type": "string", "format": "uuid"},
+                "status": schema_ref("ExecutionStatus"),
+                "created_at": {"type": "string", "format": "date-time"},
+                "started_at": {"type": "string", "format": "date-time", "nullable": true},
+                "completed_at": {"type": "string", "format": "date-time", "nullable": true},
+                "result": {"allOf": [schema_ref("ExecutionResult")], "nullable": true},
+                "pinned": {"type": "boolean"},
+                "output_limit_exceeded": {"type": "boolean"},
+                "tty": {"type": "boolean"},
+                "session_id": {"type": "string"},
+                "group_id": {"type": "string"},
+                "backend": {"type": "string", "enum": ["primary", "canary"]},
+                "resubmitted_from": {"type": "string", "format": "uuid"},
+                "retried_from": {"type": "string", "format": "uuid"},
+                "cancellation": schema_ref("Cancellation"),
+                "annotations": {"type": "object", "additionalProperties": schema_ref("Annotation")},
+                "warnings": {"type": "array", "items": schema_ref("Warning")},
+                "result_delivery": schema_ref("ResultDelivery"),
+                "trace_id": {"type": "string"},
+                "trace_url": {"type": "string", "format": "uri"},
+                "code_sha256": {"type": "string"},
+                "language_version": {"type": "string", "description": "Runtime version the execution was pinned to"},
+                "metadata": string_map,
+            },
+        },
+        "ExecutionList": {
+            "type": "object",
+            "required": ["executions"],
+            "properties": {
+                "executions": {"type": "array", "items": schema_ref("Execution")},
+                "next_page_token": {"type": "string"},
+            },
+        },
+        "ExecutionValidation": {
+            "type": "object",
+            "required": ["valid", "diagnostics", "language", "language_recognized", "mode", "backend", "warnings"],
+            "properties": {
+                "valid": {"type": "boolean"},
+                "diagnostics": {"type": "array", "items": schema_ref("Diagnostic")},
+                "language": {"type": "string"},
+                "language_version": {"type": "string"},
+                "language_recognized": {"type": "boolean"},
+                "timeout_seconds": {"type": "integer", "nullable": true},
+                "resources": {"allOf": [schema_ref("ResourceLimits")], "nullable": true},
+                "mode": schema_ref("IsolationMode"),
+                "backend": {"type": "string", "enum": ["primary", "canary"]},
+                "result_delivery": {"type": "string"},
+                "callback_url": {"type": "string", "format": "uri"},
+                "warnings": {"type": "array", "items": schema_ref("Warning")},
+            },
+        },
+        "WorkspaceType": {"type": "string", "enum": ["ephemeral", "session", "persistent", "collaborative"]},
+        "Workspace": {
+            "type": "object",
+            "required": ["id", "user_id", "name", "description", "type", "status", "environment", "allowed_packages", "metadata"],
+            "properties": {
+                "id": {"type": "string"},
+                "user_id": {"type": "string"},
+                "name": {"type": "string"},
+                "description": {"type": "string"},
+                "type": schema_ref("WorkspaceType"),
+                "status": {"type": "string", "enum": ["pending", "active", "suspended", "terminated", "error"]},
+                "environment": string_map,
+                "allowed_packages": {"type": "array", "items": {"type": "string"}},
+                "created_at": {"type": "string", "format": "date-time", "nullable": true},
+                "updated_at": {"type": "string", "format": "date-time", "nullable": true},
+                "expires_at": {"type": "string", "format": "date-time", "nullable": true},
+                "metadata": string_map,
+            },
+        },
+        "WorkspacePage": {
+            "type": "object",
+            "required": ["workspaces"],
+            "properties": {
+                "workspaces": {"type": "array", "items": schema_ref("Workspace")},
+                "next_page_token": {"type": "string"},
+            },
+        },
+        "CreateWorkspaceRequest": {
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": {"type": "string"},
+                "description": {"type": "string"},
+                "type": schema_ref("WorkspaceType"),
+                "environment": string_map,
+                "allowed_packages": {"type": "array", "items": {"type": "string"}},
+                "ttl_seconds": {"type": "integer"},
+                "metadata": string_map,
+            },
+        },
+        "UpdateWorkspaceRequest": {
+            "type": "object",
+            "description": "Unset fields are left as they are",
+            "properties": {
+                "name": {"type": "string"},
+                "description": {"type": "string"},
+                "environment": string_map,
+                "allowed_packages": {"type": "array", "items": {"type": "string"}},
+                "metadata": string_map,
+            },
+        },
+    })
+}
+
+/// The OpenAPI document for this deployment
+pub async fn openapi_handler(State(state): State<Arc<AppState>>) -> Json<Value> {
+    static DOCUMENT: OnceLock<Value> = OnceLock::new();
+    Json(DOCUMENT.get_or_init(|| document(state.config())).clone())
+}
+
+/// Swagger UI over `/openapi.json`, loaded from a CDN
+pub async fn docs_handler() -> impl IntoResponse {
+    Html(DOCS_PAGE)
+}
+
+const DOCS_PAGE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Syla API Gateway</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>
+"##;
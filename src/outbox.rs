@@ -0,0 +1,240 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgPool;
+use sqlx::Row;
+
+use crate::config::OutboxConfig;
+
+/// What an outbox entry delivers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboxKind {
+    /// A completion webhook to the execution's callback URL
+    Webhook,
+    /// A usage record for billing
+    Metering,
+}
+
+impl OutboxKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OutboxKind::Webhook => "webhook",
+            OutboxKind::Metering => "metering",
+        }
+    }
+
+    fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "webhook" => Some(OutboxKind::Webhook),
+            "metering" => Some(OutboxKind::Metering),
+            _ => None,
+        }
+    }
+}
+
+/// An undelivered entry that's due for an attempt
+#[derive(Debug, Clone)]
+pub struct OutboxEntry {
+    pub id: i64,
+    pub kind: OutboxKind,
+    pub idempotency_key: String,
+    pub execution_id: String,
+    pub target: Option<String>,
+    /// A metering record, or the principal a webhook is signed for
+    pub payload: Option<String>,
+    pub attempts: i32,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct OutboxCounters {
+    delivered: AtomicU64,
+    failed: AtomicU64,
+    pending: AtomicU64,
+}
+
+/// Webhooks and metering records kept in the SQL store until delivered, so
+/// they survive restarts. Delivery is at least once: an entry is marked
+/// delivered only after it's sent, and carries its idempotency key on every
+/// attempt so receivers can drop repeats
+pub struct Outbox {
+    pool: PgPool,
+    config: OutboxConfig,
+    counters: OutboxCounters,
+}
+
+impl Outbox {
+    pub fn new(pool: PgPool, config: &OutboxConfig) -> Self {
+        Self {
+            pool,
+            config: config.clone(),
+            counters: OutboxCounters::default(),
+        }
+    }
+
+    pub fn config(&self) -> &OutboxConfig {
+        &self.config
+    }
+
+    /// Queue an entry for delivery now, unless one with the same key already is
+    pub async fn enqueue(
+        &self,
+        kind: OutboxKind,
+        idempotency_key: &str,
+        execution_id: &str,
+        target: Option<&str>,
+        payload: Option<&str>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let now = Utc::now();
+        sqlx::query(
+            "INSERT INTO delivery_outbox \
+             (kind, idempotency_key, execution_id, target, payload, next_attempt_at, expires_at, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $6) \
+             ON CONFLICT (idempotency_key) DO NOTHING",
+        )
+        .bind(kind.as_str())
+        .bind(idempotency_key)
+        .bind(execution_id)
+        .bind(target)
+        .bind(payload)
+        .bind(now)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Oldest entries due for an attempt
+    pub async fn due(&self) -> Result<Vec<OutboxEntry>> {
+        let rows = sqlx::query(
+            "SELECT id, kind, idempotency_key, execution_id, target, payload, attempts, expires_at \
+             FROM delivery_outbox \
+             WHERE delivered_at IS NULL AND failed_at IS NULL AND next_attempt_at <= $1 \
+             ORDER BY next_attempt_at \
+             LIMIT $2",
+        )
+        .bind(Utc::now())
+        .bind(self.config.batch_size as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut entries = Vec::with_capacity(rows.len());
+        for row in rows {
+            let kind: String = row.try_get("kind")?;
+            let Some(kind) = OutboxKind::parse(&kind) else {
+                continue;
+            };
+            entries.push(OutboxEntry {
+                id: row.try_get("id")?,
+                kind,
+                idempotency_key: row.try_get("idempotency_key")?,
+                execution_id: row.try_get("execution_id")?,
+                target: row.try_get("target")?,
+                payload: row.try_get("payload")?,
+                attempts: row.try_get("attempts")?,
+                expires_at: row.try_get("expires_at")?,
+            });
+        }
+        Ok(entries)
+    }
+
+    pub async fn mark_delivered(&self, entry: &OutboxEntry) -> Result<()> {
+        sqlx::query("UPDATE delivery_outbox SET delivered_at = $2, attempts = attempts + 1 WHERE id = $1")
+            .bind(entry.id)
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await?;
+        self.counters.delivered.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Check on `entry` again after `delay` without counting an attempt, e.g.
+    /// while its execution is still running
+    pub async fn defer(&self, entry: &OutboxEntry, delay: Duration) -> Result<()> {
+        sqlx::query("UPDATE delivery_outbox SET next_attempt_at = $2 WHERE id = $1")
+            .bind(entry.id)
+            .bind(Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Record a failed attempt, retrying with exponential backoff until the
+    /// attempt limit; returns whether the entry was given up on
+    pub async fn record_failure(&self, entry: &OutboxEntry, error: &str, retryable: bool) -> Result<bool> {
+        let attempts = entry.attempts + 1;
+        let give_up = !retryable || attempts as u32 >= self.config.max_attempts;
+        let now = Utc::now();
+        let backoff = self
+            .config
+            .retry_backoff
+            .saturating_mul(2_u32.saturating_pow(attempts.saturating_sub(1) as u32))
+            .min(self.config.max_retry_backoff);
+        sqlx::query(
+            "UPDATE delivery_outbox \
+             SET attempts = $2, last_error = $3, next_attempt_at = $4, failed_at = $5 \
+             WHERE id = $1",
+        )
+        .bind(entry.id)
+        .bind(attempts)
+        .bind(error)
+        .bind(now + chrono::Duration::from_std(backoff).unwrap_or_default())
+        .bind(give_up.then_some(now))
+        .execute(&self.pool)
+        .await?;
+        if give_up {
+            self.counters.failed.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(give_up)
+    }
+
+    /// Give up on `entry` without an attempt, e.g. once it has expired
+    pub async fn abandon(&self, entry: &OutboxEntry, error: &str) -> Result<()> {
+        sqlx::query("UPDATE delivery_outbox SET last_error = $2, failed_at = $3 WHERE id = $1")
+            .bind(entry.id)
+            .bind(error)
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await?;
+        self.counters.failed.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Drop finished entries past the retention period and refresh the
+    /// pending count; returns how many were dropped
+    pub async fn sweep(&self) -> Result<u64> {
+        let cutoff = Utc::now() - chrono::Duration::from_std(self.config.retention).unwrap_or_default();
+        let result = sqlx::query(
+            "DELETE FROM delivery_outbox \
+             WHERE COALESCE(delivered_at, failed_at) <= $1",
+        )
+        .bind(cutoff)
+        .execute(&self.pool)
+        .await?;
+        let pending: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM delivery_outbox WHERE delivered_at IS NULL AND failed_at IS NULL",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        self.counters.pending.store(pending.max(0) as u64, Ordering::Relaxed);
+        Ok(result.rows_affected())
+    }
+
+    /// Outbox state in Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# TYPE syla_gateway_outbox_pending gauge");
+        let _ = writeln!(out, "syla_gateway_outbox_pending {}", self.counters.pending.load(Ordering::Relaxed));
+        for (name, counter) in [
+            ("syla_gateway_outbox_delivered_total", &self.counters.delivered),
+            ("syla_gateway_outbox_failed_total", &self.counters.failed),
+        ] {
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, counter.load(Ordering::Relaxed));
+        }
+        out
+    }
+}
@@ -0,0 +1,368 @@
+//! Execution output buffered at the gateway while it streams.
+//!
+//! One upstream stream per execution fills a bounded buffer; every client
+//! stream replays from the buffer and then follows live output, so a client
+//! that reconnects resumes from the offsets in its resume token instead of
+//! re-reading everything, and one joining late starts with recent output
+//! rather than a blank screen.
+
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::config::OutputBufferConfig;
+use crate::error::ApiError;
+use crate::execution::{ExecutionResponse, ExecutionStatus};
+
+/// Live events a subscriber may fall behind by before it must replay
+const LIVE_CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl OutputStream {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        }
+    }
+}
+
+/// Bytes of each stream already delivered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Offsets {
+    pub stdout: u64,
+    pub stderr: u64,
+}
+
+impl Offsets {
+    pub fn get(&self, stream: OutputStream) -> u64 {
+        match stream {
+            OutputStream::Stdout => self.stdout,
+            OutputStream::Stderr => self.stderr,
+        }
+    }
+
+    fn get_mut(&mut self, stream: OutputStream) -> &mut u64 {
+        match stream {
+            OutputStream::Stdout => &mut self.stdout,
+            OutputStream::Stderr => &mut self.stderr,
+        }
+    }
+
+    /// Record `chunk` as delivered
+    pub fn advance(&mut self, chunk: &OutputChunk) {
+        let offset = self.get_mut(chunk.stream);
+        *offset = (*offset).max(chunk.end());
+    }
+}
+
+/// A piece of one output stream, starting `offset` bytes into it
+#[derive(Debug, Clone)]
+pub struct OutputChunk {
+    pub stream: OutputStream,
+    pub offset: u64,
+    pub data: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl OutputChunk {
+    pub fn end(&self) -> u64 {
+        self.offset + self.data.len() as u64
+    }
+
+    /// The part of this chunk not yet delivered according to `offsets`
+    pub fn after(&self, offsets: &Offsets) -> Option<OutputChunk> {
+        let delivered = offsets.get(self.stream);
+        if self.end() <= delivered {
+            return None;
+        }
+        let skip = delivered.saturating_sub(self.offset) as usize;
+        // Offsets from a token always fall on a chunk's character boundary;
+        // resend the whole chunk rather than split a character
+        let data = self.data.get(skip..).unwrap_or(&self.data);
+        Some(OutputChunk {
+            stream: self.stream,
+            offset: self.end() - data.len() as u64,
+            data: data.to_string(),
+            timestamp: self.timestamp,
+        })
+    }
+}
+
+/// Where a client stream left off, handed out with each output chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResumeToken {
+    pub execution_id: Uuid,
+    pub offsets: Offsets,
+}
+
+impl ResumeToken {
+    pub fn encode(&self) -> String {
+        hex::encode(format!(
+            "{}:{}:{}",
+            self.execution_id, self.offsets.stdout, self.offsets.stderr
+        ))
+    }
+
+    pub fn decode(token: &str) -> Option<ResumeToken> {
+        let decoded = String::from_utf8(hex::decode(token).ok()?).ok()?;
+        let mut parts = decoded.split(':');
+        let token = ResumeToken {
+            execution_id: parts.next()?.parse().ok()?,
+            offsets: Offsets {
+                stdout: parts.next()?.parse().ok()?,
+                stderr: parts.next()?.parse().ok()?,
+            },
+        };
+        parts.next().is_none().then_some(token)
+    }
+}
+
+/// Where a new subscriber starts reading
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStart {
+    /// Just after the given offsets, as when resuming
+    From(Offsets),
+    /// Within the configured replay window before the live output
+    Recent,
+}
+
+/// What a client stream receives
+#[derive(Debug, Clone)]
+pub enum OutputEvent {
+    Output(OutputChunk),
+    /// The execution moved to another status that isn't final
+    Status(ExecutionStatus),
+    /// The execution reached a final status; nothing follows
+    Finished(ExecutionStatus),
+    /// The upstream stream broke; clients should reconnect with their token
+    Interrupted(String),
+}
+
+/// Buffered events to send first, then live ones to follow
+pub struct OutputSubscription {
+    pub backlog: Vec<OutputEvent>,
+    pub live: Option<broadcast::Receiver<OutputEvent>>,
+}
+
+impl OutputSubscription {
+    /// Replay a finished execution's stored output; recent output is the
+    /// tail of each stream within the replay byte limit
+    pub fn completed(
+        execution: &ExecutionResponse,
+        start: OutputStart,
+        config: &OutputBufferConfig,
+    ) -> (Offsets, Self) {
+        let from = match (start, &execution.result) {
+            (OutputStart::From(offsets), _) => offsets,
+            (OutputStart::Recent, Some(result)) => Offsets {
+                stdout: tail_start(&result.stdout, config.replay_bytes),
+                stderr: tail_start(&result.stderr, config.replay_bytes),
+            },
+            (OutputStart::Recent, None) => Offsets::default(),
+        };
+        let mut backlog = Vec::new();
+        if let Some(result) = &execution.result {
+            for (stream, data) in [
+                (OutputStream::Stdout, &result.stdout),
+                (OutputStream::Stderr, &result.stderr),
+            ] {
+                let chunk = OutputChunk {
+                    stream,
+                    offset: 0,
+                    data: data.clone(),
+                    timestamp: execution.completed_at.unwrap_or(execution.created_at),
+                };
+                backlog.extend(chunk.after(&from).map(OutputEvent::Output));
+            }
+        }
+        backlog.push(OutputEvent::Finished(execution.status.clone()));
+        (from, Self { backlog, live: None })
+    }
+}
+
+/// Offset of the last `limit` bytes of `data`, moved forward to a character boundary
+fn tail_start(data: &str, limit: usize) -> u64 {
+    let mut start = data.len().saturating_sub(limit);
+    while !data.is_char_boundary(start) {
+        start += 1;
+    }
+    start as u64
+}
+
+struct Buffer {
+    chunks: VecDeque<OutputChunk>,
+    bytes: usize,
+    /// Offsets of the earliest bytes still held
+    retained_from: Offsets,
+    /// Offsets the next appended bytes start at
+    next: Offsets,
+    /// Latest status seen upstream, sent first on every replay
+    status: ExecutionStatus,
+    finished: Option<OutputEvent>,
+    live: broadcast::Sender<OutputEvent>,
+}
+
+impl Buffer {
+    fn new(status: ExecutionStatus) -> Self {
+        Self {
+            chunks: VecDeque::new(),
+            bytes: 0,
+            retained_from: Offsets::default(),
+            next: Offsets::default(),
+            status,
+            finished: None,
+            live: broadcast::channel(LIVE_CHANNEL_CAPACITY).0,
+        }
+    }
+}
+
+/// Output buffers of executions currently streaming through this replica
+pub struct OutputBuffers {
+    buffers: Mutex<HashMap<Uuid, Buffer>>,
+    config: OutputBufferConfig,
+}
+
+impl OutputBuffers {
+    pub fn new(config: OutputBufferConfig) -> Self {
+        Self {
+            buffers: Mutex::new(HashMap::new()),
+            config,
+        }
+    }
+
+    /// Start buffering `id`, currently in `status`; false if it already is,
+    /// so only one upstream stream is opened per execution
+    pub fn open(&self, id: Uuid, status: ExecutionStatus) -> bool {
+        let mut buffers = self.buffers.lock().unwrap();
+        if buffers.contains_key(&id) {
+            return false;
+        }
+        buffers.insert(id, Buffer::new(status));
+        true
+    }
+
+    /// Record a change to a status that isn't final and hand it to live subscribers
+    pub fn status(&self, id: Uuid, status: ExecutionStatus) {
+        let mut buffers = self.buffers.lock().unwrap();
+        let Some(buffer) = buffers.get_mut(&id) else {
+            return;
+        };
+        if buffer.status != status {
+            buffer.status = status.clone();
+            let _ = buffer.live.send(OutputEvent::Status(status));
+        }
+    }
+
+    /// Append output and hand it to live subscribers, evicting the oldest
+    /// chunks past the size limit
+    pub fn append(&self, id: Uuid, stream: OutputStream, data: String) {
+        let mut buffers = self.buffers.lock().unwrap();
+        let Some(buffer) = buffers.get_mut(&id) else {
+            return;
+        };
+        let chunk = OutputChunk {
+            stream,
+            offset: buffer.next.get(stream),
+            data,
+            timestamp: Utc::now(),
+        };
+        buffer.next.advance(&chunk);
+        buffer.bytes += chunk.data.len();
+        buffer.chunks.push_back(chunk.clone());
+        while buffer.bytes > self.config.max_bytes && buffer.chunks.len() > 1 {
+            let Some(evicted) = buffer.chunks.pop_front() else {
+                break;
+            };
+            buffer.bytes -= evicted.data.len();
+            buffer.retained_from.advance(&evicted);
+        }
+        let _ = buffer.live.send(OutputEvent::Output(chunk));
+    }
+
+    /// Record the end of the upstream stream; the buffer stays readable until removed
+    pub fn finish(&self, id: Uuid, event: OutputEvent) {
+        let mut buffers = self.buffers.lock().unwrap();
+        if let Some(buffer) = buffers.get_mut(&id) {
+            let _ = buffer.live.send(event.clone());
+            buffer.finished = Some(event);
+        }
+    }
+
+    pub fn remove(&self, id: Uuid) {
+        self.buffers.lock().unwrap().remove(&id);
+    }
+
+    /// Buffered events from `start` plus a receiver for what follows, with the
+    /// offsets they start after, or `None` when `id` isn't buffered here
+    pub fn replay(
+        &self,
+        id: Uuid,
+        start: OutputStart,
+    ) -> Option<Result<(Offsets, OutputSubscription), ApiError>> {
+        let buffers = self.buffers.lock().unwrap();
+        let buffer = buffers.get(&id)?;
+        let from = match start {
+            OutputStart::From(offsets) => offsets,
+            OutputStart::Recent => self.recent_start(buffer),
+        };
+        if from.stdout < buffer.retained_from.stdout || from.stderr < buffer.retained_from.stderr {
+            return Some(Err(ApiError::BadRequest(
+                "Output at the resume point is no longer buffered; restart the stream without a resume token"
+                    .to_string(),
+            )));
+        }
+
+        let mut backlog = vec![OutputEvent::Status(buffer.status.clone())];
+        backlog.extend(
+            buffer
+                .chunks
+                .iter()
+                .filter_map(|chunk| chunk.after(&from))
+                .map(OutputEvent::Output),
+        );
+        let live = match &buffer.finished {
+            Some(event) => {
+                backlog.remove(0);
+                backlog.push(event.clone());
+                None
+            }
+            // Subscribed under the lock, so nothing appended is missed or repeated
+            None => Some(buffer.live.subscribe()),
+        };
+        Some(Ok((from, OutputSubscription { backlog, live })))
+    }
+
+    /// Offsets of the oldest buffered output still within the replay window
+    fn recent_start(&self, buffer: &Buffer) -> Offsets {
+        let cutoff = chrono::Duration::from_std(self.config.replay_window)
+            .ok()
+            .and_then(|window| Utc::now().checked_sub_signed(window));
+        let mut from = buffer.next;
+        let mut bytes = 0;
+        for chunk in buffer.chunks.iter().rev() {
+            bytes += chunk.data.len();
+            let too_old = cutoff.is_some_and(|cutoff| chunk.timestamp < cutoff);
+            if bytes > self.config.replay_bytes || too_old {
+                break;
+            }
+            *from.get_mut(chunk.stream) = chunk.offset;
+        }
+        from
+    }
+
+    pub fn config(&self) -> &OutputBufferConfig {
+        &self.config
+    }
+
+    pub fn linger(&self) -> std::time::Duration {
+        self.config.linger
+    }
+}
@@ -0,0 +1,879 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgPool, PgRow};
+use sqlx::Row;
+use tokio::sync::{Notify, RwLock};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::auth::{self, AuthContext, AuthInterceptor};
+use crate::error::ApiError;
+use crate::execution::{CreateExecutionRequest, ExecutionFile, ExecutionResponse, ExecutionStatus};
+use crate::ids;
+use crate::leader::Leadership;
+use crate::ratelimit::RateCharge;
+use crate::state::AppState;
+
+/// Pipeline routes, authenticated; users only ever see their own pipelines
+pub fn routes(auth_interceptor: AuthInterceptor) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/pipelines", get(list_pipelines).post(create_pipeline))
+        .route("/v1/pipelines/:id", get(get_pipeline))
+        .route("/v1/pipelines/:id/cancel", post(cancel_pipeline))
+        .route_layer(middleware::from_fn_with_state(auth_interceptor, auth::require_auth))
+}
+
+/// What a step takes from the step before it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StepInput {
+    /// Write the previous step's stdout to this step's stdin, then close it
+    #[serde(default)]
+    pub stdin: bool,
+    /// Also pass the previous step's stdout as a file at this path
+    pub stdout_file: Option<String>,
+    /// Files the previous step created to copy in at the same paths; they
+    /// are read back from the artifact store, so must be UTF-8 text
+    #[serde(default)]
+    pub artifacts: Vec<String>,
+}
+
+impl StepInput {
+    fn takes_input(&self) -> bool {
+        self.stdin || self.stdout_file.is_some() || !self.artifacts.is_empty()
+    }
+
+    /// Paths of the files this step is given by the previous one
+    fn file_paths(&self) -> impl Iterator<Item = &str> {
+        self.stdout_file.iter().chain(&self.artifacts).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineStepRequest {
+    #[serde(flatten)]
+    pub execution: CreateExecutionRequest,
+    #[serde(default)]
+    pub input: StepInput,
+}
+
+/// Executions run one after another, each started once the one before it
+/// completed
+#[derive(Debug, Deserialize)]
+pub struct CreatePipelineRequest {
+    pub steps: Vec<PipelineStepRequest>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PipelineStatus {
+    Running,
+    /// Every step completed with exit code 0
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl PipelineStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        match s {
+            "running" => Ok(Self::Running),
+            "completed" => Ok(Self::Completed),
+            "failed" => Ok(Self::Failed),
+            "cancelled" => Ok(Self::Cancelled),
+            other => anyhow::bail!("unknown pipeline status {}", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    /// Waiting for the steps before it
+    Waiting,
+    Running,
+    Completed,
+    /// Exited non-zero, failed or timed out, or could not be submitted
+    Failed,
+    Cancelled,
+    /// Not run because an earlier step didn't complete
+    Skipped,
+}
+
+impl StepStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Waiting => "waiting",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::Skipped => "skipped",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        match s {
+            "waiting" => Ok(Self::Waiting),
+            "running" => Ok(Self::Running),
+            "completed" => Ok(Self::Completed),
+            "failed" => Ok(Self::Failed),
+            "cancelled" => Ok(Self::Cancelled),
+            "skipped" => Ok(Self::Skipped),
+            other => anyhow::bail!("unknown step status {}", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineStep {
+    pub language: String,
+    pub status: StepStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub execution_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// When the step was claimed for submission
+    #[serde(skip)]
+    started_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    request: PipelineStepRequest,
+}
+
+/// A pipeline's steps and where it has got to
+#[derive(Debug, Clone, Serialize)]
+pub struct Pipeline {
+    pub id: Uuid,
+    pub status: PipelineStatus,
+    pub steps: Vec<PipelineStep>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Whom steps are submitted as, without their token
+    #[serde(skip)]
+    owner: AuthContext,
+    #[serde(skip)]
+    cancel_requested: bool,
+}
+
+impl Pipeline {
+    /// The execution of the step in progress, if one has been submitted
+    fn running_execution(&self) -> Option<Uuid> {
+        self.steps
+            .iter()
+            .find(|step| step.status == StepStatus::Running)
+            .and_then(|step| step.execution_id)
+    }
+
+    fn from_row(row: &PgRow, steps: Vec<PipelineStep>) -> Result<Self> {
+        let id: String = row.try_get("id")?;
+        let status: String = row.try_get("status")?;
+        Ok(Self {
+            id: id.parse()?,
+            status: PipelineStatus::parse(&status)?,
+            steps,
+            created_at: row.try_get("created_at")?,
+            completed_at: row.try_get("completed_at")?,
+            owner: AuthContext {
+                user_id: row.try_get("user_id")?,
+                tenant_id: row.try_get("tenant_id")?,
+                token: String::new(),
+                scopes: row.try_get("scopes")?,
+                impersonated_by: row.try_get("impersonated_by")?,
+            },
+            cancel_requested: row.try_get("cancel_requested")?,
+        })
+    }
+}
+
+impl PipelineStep {
+    fn from_row(row: &PgRow) -> Result<Self> {
+        let status: String = row.try_get("status")?;
+        let request: String = row.try_get("request")?;
+        let execution_id: Option<String> = row.try_get("execution_id")?;
+        Ok(Self {
+            language: row.try_get("language")?,
+            status: StepStatus::parse(&status)?,
+            execution_id: execution_id.map(|id| id.parse()).transpose()?,
+            error: row.try_get("error")?,
+            started_at: row.try_get("started_at")?,
+            request: serde_json::from_str(&request)?,
+        })
+    }
+}
+
+const PIPELINE_COLUMNS: &str =
+    "id::text AS id, user_id, tenant_id, scopes, impersonated_by, status, cancel_requested, created_at, completed_at";
+
+const STEP_COLUMNS: &str =
+    "pipeline_id::text AS pipeline_id, language, status, execution_id::text AS execution_id, error, started_at, request";
+
+/// Pipelines running or recently finished, kept in the SQL store when one is
+/// configured so they survive restarts and any replica holding the pipeline
+/// lease can drive them, and in memory otherwise
+pub struct Pipelines {
+    pipelines: RwLock<HashMap<Uuid, Pipeline>>,
+    pool: Option<PgPool>,
+    /// Pipelines this replica is driving
+    driving: Mutex<HashSet<Uuid>>,
+    /// Wakes the runner when a pipeline is created here
+    created: Notify,
+}
+
+impl Pipelines {
+    pub fn new(pool: Option<PgPool>) -> Self {
+        Self {
+            pipelines: RwLock::new(HashMap::new()),
+            pool,
+            driving: Mutex::new(HashSet::new()),
+            created: Notify::new(),
+        }
+    }
+
+    async fn insert(&self, pipeline: &Pipeline) -> Result<()> {
+        let Some(pool) = &self.pool else {
+            self.pipelines.write().await.insert(pipeline.id, pipeline.clone());
+            self.created.notify_one();
+            return Ok(());
+        };
+        let mut tx = pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO pipelines (id, user_id, tenant_id, scopes, impersonated_by, status, created_at) \
+             VALUES ($1::uuid, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(pipeline.id.to_string())
+        .bind(&pipeline.owner.user_id)
+        .bind(&pipeline.owner.tenant_id)
+        .bind(&pipeline.owner.scopes)
+        .bind(&pipeline.owner.impersonated_by)
+        .bind(pipeline.status.as_str())
+        .bind(pipeline.created_at)
+        .execute(&mut *tx)
+        .await?;
+        for (position, step) in pipeline.steps.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO pipeline_steps (pipeline_id, position, language, status, request) \
+                 VALUES ($1::uuid, $2, $3, $4, $5)",
+            )
+            .bind(pipeline.id.to_string())
+            .bind(i32::try_from(position)?)
+            .bind(&step.language)
+            .bind(step.status.as_str())
+            .bind(serde_json::to_string(&step.request)?)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        self.created.notify_one();
+        Ok(())
+    }
+
+    /// Pipeline `id` whoever owns it
+    async fn load(&self, id: Uuid) -> Result<Option<Pipeline>> {
+        let Some(pool) = &self.pool else {
+            return Ok(self.pipelines.read().await.get(&id).cloned());
+        };
+        let query = format!("SELECT {} FROM pipelines WHERE id = $1::uuid", PIPELINE_COLUMNS);
+        let row = sqlx::query(&query).bind(id.to_string()).fetch_optional(pool).await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let mut steps = self.steps_of(pool, &[id]).await?;
+        Ok(Some(Pipeline::from_row(&row, steps.remove(&id).unwrap_or_default())?))
+    }
+
+    /// Steps of each of `ids`, in order
+    async fn steps_of(&self, pool: &PgPool, ids: &[Uuid]) -> Result<HashMap<Uuid, Vec<PipelineStep>>> {
+        let query = format!(
+            "SELECT {} FROM pipeline_steps WHERE pipeline_id = ANY($1::uuid[]) ORDER BY pipeline_id, position",
+            STEP_COLUMNS
+        );
+        let ids: Vec<String> = ids.iter().map(Uuid::to_string).collect();
+        let rows = sqlx::query(&query).bind(ids).fetch_all(pool).await?;
+        let mut steps: HashMap<Uuid, Vec<PipelineStep>> = HashMap::new();
+        for row in &rows {
+            let pipeline_id: String = row.try_get("pipeline_id")?;
+            steps.entry(pipeline_id.parse()?).or_default().push(PipelineStep::from_row(row)?);
+        }
+        Ok(steps)
+    }
+
+    /// A pipeline owned by `user_id`; other users' pipelines are reported as not found
+    pub async fn get(&self, id: Uuid, user_id: &str) -> Result<Option<Pipeline>> {
+        Ok(self.load(id).await?.filter(|pipeline| pipeline.owner.user_id == user_id))
+    }
+
+    /// Pipelines owned by `user_id`, newest first
+    pub async fn list(&self, user_id: &str) -> Result<Vec<Pipeline>> {
+        let Some(pool) = &self.pool else {
+            let mut owned: Vec<_> = self
+                .pipelines
+                .read()
+                .await
+                .values()
+                .filter(|pipeline| pipeline.owner.user_id == user_id)
+                .cloned()
+                .collect();
+            owned.sort_by_key(|pipeline| std::cmp::Reverse(pipeline.created_at));
+            return Ok(owned);
+        };
+        let query = format!(
+            "SELECT {} FROM pipelines WHERE user_id = $1 ORDER BY created_at DESC",
+            PIPELINE_COLUMNS
+        );
+        let rows = sqlx::query(&query).bind(user_id).fetch_all(pool).await?;
+        let ids = rows
+            .iter()
+            .map(|row| Ok(row.try_get::<String, _>("id")?.parse()?))
+            .collect::<Result<Vec<Uuid>>>()?;
+        let mut steps = self.steps_of(pool, &ids).await?;
+        rows.iter()
+            .zip(ids)
+            .map(|(row, id)| Pipeline::from_row(row, steps.remove(&id).unwrap_or_default()))
+            .collect()
+    }
+
+    /// IDs of unfinished pipelines, oldest first
+    pub async fn running(&self) -> Result<Vec<Uuid>> {
+        let Some(pool) = &self.pool else {
+            let pipelines = self.pipelines.read().await;
+            let mut running: Vec<_> = pipelines
+                .values()
+                .filter(|pipeline| pipeline.status == PipelineStatus::Running)
+                .collect();
+            running.sort_by_key(|pipeline| pipeline.created_at);
+            return Ok(running.iter().map(|pipeline| pipeline.id).collect());
+        };
+        let ids: Vec<String> =
+            sqlx::query_scalar("SELECT id::text FROM pipelines WHERE status = 'running' ORDER BY created_at")
+                .fetch_all(pool)
+                .await?;
+        Ok(ids.iter().filter_map(|id| id.parse().ok()).collect())
+    }
+
+    /// Stop a running pipeline owned by `user_id` before its next step,
+    /// returning the execution of the step in progress
+    async fn request_cancel(&self, id: Uuid, user_id: &str) -> Result<Option<Uuid>, ApiError> {
+        let Some(pool) = &self.pool else {
+            let mut pipelines = self.pipelines.write().await;
+            let pipeline = pipelines
+                .get_mut(&id)
+                .filter(|pipeline| pipeline.owner.user_id == user_id)
+                .ok_or(ApiError::NotFound)?;
+            if pipeline.status != PipelineStatus::Running {
+                return Err(ApiError::BadRequest(format!("Pipeline {} has already finished", id)));
+            }
+            pipeline.cancel_requested = true;
+            return Ok(pipeline.running_execution());
+        };
+        let pipeline = self.get(id, user_id).await?.ok_or(ApiError::NotFound)?;
+        let requested = sqlx::query(
+            "UPDATE pipelines SET cancel_requested = TRUE WHERE id = $1::uuid AND status = 'running'",
+        )
+        .bind(id.to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| ApiError::Internal(e.into()))?;
+        if requested.rows_affected() == 0 {
+            return Err(ApiError::BadRequest(format!("Pipeline {} has already finished", id)));
+        }
+        Ok(pipeline.running_execution())
+    }
+
+    async fn cancel_requested(&self, id: Uuid) -> Result<bool> {
+        let Some(pool) = &self.pool else {
+            let pipelines = self.pipelines.read().await;
+            return Ok(pipelines.get(&id).is_some_and(|pipeline| pipeline.cancel_requested));
+        };
+        let requested: Option<bool> =
+            sqlx::query_scalar("SELECT cancel_requested FROM pipelines WHERE id = $1::uuid")
+                .bind(id.to_string())
+                .fetch_optional(pool)
+                .await?;
+        Ok(requested.unwrap_or(false))
+    }
+
+    /// Mark step `index` running ahead of submitting it. Returns false when
+    /// it was already claimed, so a step is never submitted twice
+    async fn claim_step(&self, id: Uuid, index: usize, now: DateTime<Utc>) -> Result<bool> {
+        let Some(pool) = &self.pool else {
+            let mut pipelines = self.pipelines.write().await;
+            let Some(step) = pipelines
+                .get_mut(&id)
+                .and_then(|pipeline| pipeline.steps.get_mut(index))
+                .filter(|step| step.status == StepStatus::Waiting)
+            else {
+                return Ok(false);
+            };
+            step.status = StepStatus::Running;
+            step.started_at = Some(now);
+            return Ok(true);
+        };
+        let claimed = sqlx::query(
+            "UPDATE pipeline_steps SET status = 'running', started_at = $3 \
+             WHERE pipeline_id = $1::uuid AND position = $2 AND status = 'waiting'",
+        )
+        .bind(id.to_string())
+        .bind(i32::try_from(index)?)
+        .bind(now)
+        .execute(pool)
+        .await?;
+        Ok(claimed.rows_affected() > 0)
+    }
+
+    /// Record where step `index` has got to
+    async fn save_step(&self, id: Uuid, index: usize, step: &PipelineStep) -> Result<()> {
+        let Some(pool) = &self.pool else {
+            if let Some(stored) = self
+                .pipelines
+                .write()
+                .await
+                .get_mut(&id)
+                .and_then(|pipeline| pipeline.steps.get_mut(index))
+            {
+                *stored = step.clone();
+            }
+            return Ok(());
+        };
+        sqlx::query(
+            "UPDATE pipeline_steps SET status = $3, execution_id = $4::uuid, error = $5, started_at = $6 \
+             WHERE pipeline_id = $1::uuid AND position = $2",
+        )
+        .bind(id.to_string())
+        .bind(i32::try_from(index)?)
+        .bind(step.status.as_str())
+        .bind(step.execution_id.map(|id| id.to_string()))
+        .bind(&step.error)
+        .bind(step.started_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Record how the pipeline ended, skipping the steps it never reached
+    async fn finish(&self, id: Uuid, status: PipelineStatus) -> Result<()> {
+        let now = Utc::now();
+        let Some(pool) = &self.pool else {
+            let mut pipelines = self.pipelines.write().await;
+            let Some(pipeline) = pipelines.get_mut(&id) else {
+                return Ok(());
+            };
+            pipeline.status = status;
+            pipeline.completed_at = Some(now);
+            for step in &mut pipeline.steps {
+                if step.status == StepStatus::Waiting {
+                    step.status = StepStatus::Skipped;
+                }
+            }
+            return Ok(());
+        };
+        let mut tx = pool.begin().await?;
+        sqlx::query("UPDATE pipelines SET status = $2, completed_at = $3 WHERE id = $1::uuid AND status = 'running'")
+            .bind(id.to_string())
+            .bind(status.as_str())
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE pipeline_steps SET status = 'skipped' WHERE pipeline_id = $1::uuid AND status = 'waiting'")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Drop pipelines that finished before `before`, returning how many
+    pub async fn sweep(&self, before: DateTime<Utc>) -> Result<u64> {
+        let Some(pool) = &self.pool else {
+            let mut pipelines = self.pipelines.write().await;
+            let count = pipelines.len();
+            pipelines.retain(|_, pipeline| pipeline.completed_at.is_none_or(|at| at >= before));
+            return Ok((count - pipelines.len()) as u64);
+        };
+        let deleted = sqlx::query("DELETE FROM pipelines WHERE completed_at < $1")
+            .bind(before)
+            .execute(pool)
+            .await?;
+        Ok(deleted.rows_affected())
+    }
+
+    /// Note that this replica drives pipeline `id`; false if it already does
+    pub fn start_driving(&self, id: Uuid) -> bool {
+        self.driving.lock().unwrap().insert(id)
+    }
+
+    pub fn stop_driving(&self, id: Uuid) {
+        self.driving.lock().unwrap().remove(&id);
+    }
+
+    /// Resolves when a pipeline is created on this replica
+    pub async fn created(&self) {
+        self.created.notified().await
+    }
+}
+
+/// Validate every step up front, then run them in the background
+async fn create_pipeline(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Extension(charge): Extension<RateCharge>,
+    Json(request): Json<CreatePipelineRequest>,
+) -> Result<Response, ApiError> {
+    let max_steps = state.config().pipelines.max_steps;
+    if request.steps.is_empty() || request.steps.len() > max_steps {
+        return Err(ApiError::BadRequest(format!(
+            "A pipeline takes between 1 and {} steps",
+            max_steps
+        )));
+    }
+    // Each step is a submission
+    state.rate_limiter().charge_batch(&charge, request.steps.len())?;
+
+    let mut steps = Vec::with_capacity(request.steps.len());
+    for (index, mut step) in request.steps.into_iter().enumerate() {
+        if index == 0 && step.input.takes_input() {
+            return Err(ApiError::BadRequest(
+                "The first step has no previous step to take input from".to_string(),
+            ));
+        }
+        if !step.input.artifacts.is_empty() && state.artifact_store().is_none() {
+            return Err(ApiError::BadRequest(
+                "Passing artifacts between steps needs an artifact store".to_string(),
+            ));
+        }
+        state.inline_upload(&auth_context, &mut step.execution).await?;
+        // Files passed on from the previous step are checked as if empty
+        let mut probe = step.execution.clone();
+        probe.files.extend(step.input.file_paths().map(|path| ExecutionFile {
+            path: path.to_string(),
+            content: String::new(),
+        }));
+        let validation = state.validate_execution(&auth_context, probe).await?;
+        if let Some(diagnostic) = validation.diagnostics.into_iter().next() {
+            return Err(ApiError::BadRequest(format!("Step {}: {}", index, diagnostic.message)));
+        }
+        steps.push(step);
+    }
+
+    let pipeline = Pipeline {
+        id: ids::generate(),
+        status: PipelineStatus::Running,
+        steps: steps
+            .into_iter()
+            .map(|step| PipelineStep {
+                language: step.execution.language.clone(),
+                status: StepStatus::Waiting,
+                execution_id: None,
+                error: None,
+                started_at: None,
+                request: step,
+            })
+            .collect(),
+        created_at: Utc::now(),
+        completed_at: None,
+        // Steps run as the caller, without holding on to their token
+        owner: AuthContext {
+            token: String::new(),
+            ..auth_context
+        },
+        cancel_requested: false,
+    };
+    state
+        .pipelines()
+        .insert(&pipeline)
+        .await
+        .map_err(ApiError::Internal)?;
+
+    let location = format!("/v1/pipelines/{}", pipeline.id);
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(pipeline)).into_response())
+}
+
+/// Run pipeline `id` on from wherever it got to, and record how it ended.
+/// Stops early once `lease` is lost or the gateway turns read-only, leaving
+/// the rest to whichever replica picks it up next
+pub async fn drive(state: &AppState, id: Uuid, lease: Option<&Leadership>) {
+    match run(state, id, lease).await {
+        Ok(Some(status)) => {
+            info!("Pipeline {} finished as {:?}", id, status);
+            if let Err(e) = state.pipelines().finish(id, status).await {
+                warn!("Failed to record the end of pipeline {}: {}", id, e);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Failed to update pipeline {}: {}", id, e),
+    }
+}
+
+/// Run the steps of pipeline `id` not yet finished, in order, stopping at the
+/// first that doesn't complete. None when stopped before the pipeline ended
+async fn run(state: &AppState, id: Uuid, lease: Option<&Leadership>) -> Result<Option<PipelineStatus>> {
+    let Some(pipeline) = state.pipelines().load(id).await? else {
+        return Ok(None);
+    };
+    if pipeline.status != PipelineStatus::Running {
+        return Ok(None);
+    }
+    let owner = &pipeline.owner;
+    let mut previous: Option<ExecutionResponse> = None;
+    for (index, mut step) in pipeline.steps.iter().cloned().enumerate() {
+        match step.status {
+            StepStatus::Completed => continue,
+            StepStatus::Waiting => {
+                if state.pipelines().cancel_requested(id).await? {
+                    return Ok(Some(PipelineStatus::Cancelled));
+                }
+                if lease.is_some_and(|lease| !lease.is_leader()) || state.read_only().is_enabled() {
+                    return Ok(None);
+                }
+                if previous.is_none() && index > 0 {
+                    previous = previous_execution(state, &pipeline.steps[index - 1]).await;
+                }
+                let now = Utc::now();
+                if !state.pipelines().claim_step(id, index, now).await? {
+                    return Ok(None);
+                }
+                step.status = StepStatus::Running;
+                step.started_at = Some(now);
+                match submit_step(state, id, index, owner, &step.request, previous.as_ref()).await {
+                    Ok(execution_id) => {
+                        step.execution_id = Some(execution_id);
+                        state.pipelines().save_step(id, index, &step).await?;
+                    }
+                    Err(error) => {
+                        step.status = StepStatus::Failed;
+                        step.error = Some(error);
+                        state.pipelines().save_step(id, index, &step).await?;
+                        return Ok(Some(PipelineStatus::Failed));
+                    }
+                }
+            }
+            StepStatus::Running if step.execution_id.is_none() => {
+                // Claimed by a replica that stopped before recording the
+                // submission, which may or may not have gone through
+                step.status = StepStatus::Failed;
+                step.error = Some(format!("Step {} was interrupted while being submitted", index));
+                state.pipelines().save_step(id, index, &step).await?;
+                return Ok(Some(PipelineStatus::Failed));
+            }
+            StepStatus::Running => {}
+            StepStatus::Cancelled => return Ok(Some(PipelineStatus::Cancelled)),
+            StepStatus::Failed | StepStatus::Skipped => return Ok(Some(PipelineStatus::Failed)),
+        }
+
+        let outcome = wait_for_step(state, index, &step).await;
+        step.status = match &outcome {
+            Ok(execution) => step_status(execution),
+            Err(_) => StepStatus::Failed,
+        };
+        step.error = outcome.as_ref().err().cloned();
+        state.pipelines().save_step(id, index, &step).await?;
+        match (step.status, outcome) {
+            (StepStatus::Completed, Ok(execution)) => previous = Some(execution),
+            (StepStatus::Cancelled, _) => return Ok(Some(PipelineStatus::Cancelled)),
+            _ => return Ok(Some(PipelineStatus::Failed)),
+        }
+    }
+    Ok(Some(PipelineStatus::Completed))
+}
+
+/// The execution of a completed step, for the step after it to take input
+/// from when the pipeline is resumed
+async fn previous_execution(state: &AppState, step: &PipelineStep) -> Option<ExecutionResponse> {
+    let execution_id = step.execution_id?;
+    match state.get_execution(execution_id).await {
+        Ok(execution) => Some(execution),
+        Err(e) => {
+            warn!("Failed to read execution {} of a finished step: {}", execution_id, e);
+            None
+        }
+    }
+}
+
+/// Submit one step with the previous step's output, returning its execution
+async fn submit_step(
+    state: &AppState,
+    id: Uuid,
+    index: usize,
+    owner: &AuthContext,
+    step: &PipelineStepRequest,
+    previous: Option<&ExecutionResponse>,
+) -> Result<Uuid, String> {
+    let PipelineStepRequest { mut execution, input } = step.clone();
+    let previous_result = previous.and_then(|previous| previous.result.as_ref());
+    let stdout = previous_result.map(|result| result.stdout.clone()).unwrap_or_default();
+    if let Some(path) = input.stdout_file.clone() {
+        execution.files.push(ExecutionFile {
+            path,
+            content: stdout.clone(),
+        });
+    }
+    if let Some(previous) = previous {
+        for path in &input.artifacts {
+            execution.files.push(read_artifact(state, previous, path).await?);
+        }
+    }
+
+    let submitted = state
+        .create_execution(owner, execution)
+        .await
+        .map_err(|e| format!("Submitting step {} failed: {}", index, e))?;
+    info!("Pipeline {} started step {} as execution {}", id, index, submitted.id);
+
+    // A cancel that arrived while the step was being submitted
+    if state.pipelines().cancel_requested(id).await.unwrap_or(false) {
+        if let Err(e) = state.cancel_execution(owner, submitted.id).await {
+            warn!("Failed to cancel execution {} of pipeline {}: {}", submitted.id, id, e);
+        }
+    }
+    if input.stdin {
+        let written = match state.open_stdin(owner, submitted.id).await {
+            Ok(stdin) => stdin.write(stdout.into_bytes(), true).await,
+            Err(e) => Err(e),
+        };
+        written.map_err(|e| format!("Writing stdin of step {} failed: {}", index, e))?;
+    }
+    Ok(submitted.id)
+}
+
+/// Wait for a submitted step to finish, giving up on it once it has run for
+/// as long as a step may
+async fn wait_for_step(state: &AppState, index: usize, step: &PipelineStep) -> Result<ExecutionResponse, String> {
+    let Some(execution_id) = step.execution_id else {
+        return Err(format!("Step {} has no execution", index));
+    };
+    let config = &state.config().pipelines;
+    let ran_for = step
+        .started_at
+        .and_then(|at| (Utc::now() - at).to_std().ok())
+        .unwrap_or_default();
+    let give_up_at = tokio::time::Instant::now() + config.max_step_wait.saturating_sub(ran_for);
+    loop {
+        let deadline = (tokio::time::Instant::now() + state.config().sync_wait.max_wait).min(give_up_at);
+        let execution = state
+            .wait_for_completion(execution_id, deadline)
+            .await
+            .map_err(|e| format!("Checking on step {} failed: {}", index, e))?;
+        if execution.status.is_terminal() {
+            return Ok(execution);
+        }
+        if tokio::time::Instant::now() >= give_up_at {
+            return Err(format!(
+                "Step {} did not finish within {}s",
+                index,
+                config.max_step_wait.as_secs()
+            ));
+        }
+    }
+}
+
+/// A file the previous step created, to give to the next
+async fn read_artifact(state: &AppState, previous: &ExecutionResponse, path: &str) -> Result<ExecutionFile, String> {
+    let store = state
+        .artifact_store()
+        .ok_or_else(|| "No artifact store is configured".to_string())?;
+    let created = previous
+        .result
+        .as_ref()
+        .and_then(|result| result.files_created.iter().find(|file| file.path == path))
+        .ok_or_else(|| format!("Execution {} created no file '{}'", previous.id, path))?;
+    let limit = state.config().pipelines.max_artifact_bytes;
+    if created.size_bytes > limit as u64 {
+        return Err(format!(
+            "Artifact '{}' is {} bytes, over the {} bytes steps can pass on",
+            path, created.size_bytes, limit
+        ));
+    }
+    let content = store
+        .read(previous.id, path)
+        .await
+        .map_err(|e| format!("Reading artifact '{}' failed: {}", path, e))?;
+    let content = String::from_utf8(content).map_err(|_| format!("Artifact '{}' is not UTF-8 text", path))?;
+    Ok(ExecutionFile {
+        path: path.to_string(),
+        content,
+    })
+}
+
+/// How a finished execution counts as a step: only a zero exit lets the
+/// pipeline continue
+fn step_status(execution: &ExecutionResponse) -> StepStatus {
+    match execution.status {
+        ExecutionStatus::Completed if execution.result.as_ref().is_none_or(|result| result.exit_code == 0) => {
+            StepStatus::Completed
+        }
+        ExecutionStatus::Cancelled => StepStatus::Cancelled,
+        _ => StepStatus::Failed,
+    }
+}
+
+async fn list_pipelines(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<Vec<Pipeline>>, ApiError> {
+    let pipelines = state
+        .pipelines()
+        .list(&auth_context.user_id)
+        .await
+        .map_err(ApiError::Internal)?;
+    Ok(Json(pipelines))
+}
+
+async fn get_pipeline(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Pipeline>, ApiError> {
+    let pipeline = state
+        .pipelines()
+        .get(id, &auth_context.user_id)
+        .await
+        .map_err(ApiError::Internal)?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(pipeline))
+}
+
+/// Cancel the step in progress and skip the rest
+async fn cancel_pipeline(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Pipeline>, ApiError> {
+    let running = state
+        .pipelines()
+        .request_cancel(id, &auth_context.user_id)
+        .await?;
+    if let Some(execution_id) = running {
+        match state.cancel_execution(&auth_context, execution_id).await {
+            // Already finishing; the pipeline stops before the next step
+            Ok(_) | Err(ApiError::BadRequest(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let pipeline = state
+        .pipelines()
+        .get(id, &auth_context.user_id)
+        .await
+        .map_err(ApiError::Internal)?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(pipeline))
+}
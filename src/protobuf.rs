@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use prost::Message;
+
+use crate::compat::CompatJson;
+use crate::error::ApiError;
+use crate::execution::CreateExecutionRequest;
+use crate::grpc;
+use crate::proto;
+use crate::server_timing::{self, Phase};
+use crate::state::AppState;
+
+/// Content type of protobuf request and response bodies
+pub const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// Whether the request body is protobuf
+fn is_protobuf(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case(PROTOBUF_CONTENT_TYPE))
+}
+
+/// Whether the client's `Accept` asks for a protobuf response. JSON stays
+/// the default, so only clients naming protobuf explicitly get it
+pub fn wants_protobuf(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| {
+            accept.split(',').any(|candidate| {
+                let mut parts = candidate.split(';');
+                let media_type = parts.next().unwrap_or_default().trim();
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                media_type.eq_ignore_ascii_case(PROTOBUF_CONTENT_TYPE) && quality > 0.0
+            })
+        })
+}
+
+/// A response encoded as protobuf
+pub struct Protobuf<T>(pub T);
+
+impl<T: Message> IntoResponse for Protobuf<T> {
+    fn into_response(self) -> Response {
+        let body = server_timing::measure(Phase::Serialization, || self.0.encode_to_vec());
+        ([(header::CONTENT_TYPE, PROTOBUF_CONTENT_TYPE)], body).into_response()
+    }
+}
+
+/// An execution request sent as JSON, or as the public proto message with
+/// `Content-Type: application/x-protobuf`, decoded straight into the
+/// generated type and converted as the gRPC API would
+pub struct ExecutionRequestBody(pub CreateExecutionRequest);
+
+#[async_trait]
+impl FromRequest<Arc<AppState>> for ExecutionRequestBody {
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        if !is_protobuf(request.headers()) {
+            let CompatJson(request) = CompatJson::<CreateExecutionRequest>::from_request(request, state).await?;
+            return Ok(ExecutionRequestBody(request));
+        }
+        let body = Bytes::from_request(request, state)
+            .await
+            .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+        let request = proto::CreateExecutionRequest::decode(body)
+            .map_err(|e| ApiError::BadRequest(format!("Invalid protobuf request body: {}", e)))?;
+        let request = grpc::execution_request_from_proto(state, request).await?;
+        Ok(ExecutionRequestBody(request))
+    }
+}
@@ -0,0 +1,261 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use prost::bytes::{Buf, BufMut, Bytes};
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::{http, Body, StdError};
+use tonic::transport::Channel;
+use tonic::Status;
+use tower::{Layer, Service};
+use tracing::{info, warn};
+
+use crate::audit::{self, AuditEvent, AuditOutcome};
+use crate::auth::{AuthInterceptor, TENANT_ID_KEY, USER_ID_KEY};
+use crate::config::{ProxyConfig, ProxyRoute, UpstreamConfig};
+
+#[derive(Default)]
+struct RouteCounters {
+    calls: AtomicU64,
+    failures: AtomicU64,
+}
+
+struct Upstream {
+    route: ProxyRoute,
+    channel: Channel,
+    counters: RouteCounters,
+}
+
+/// RPCs of other internal Syla services passed through the gateway's gRPC
+/// listener as declared in `PROXY_RPCS`, so exposing one needs no new
+/// handler. Messages are forwarded as opaque bytes; only unary RPCs are
+/// supported. Callers are authenticated like any other gRPC call and the
+/// upstream sees their identity in request metadata, never their token
+pub struct UpstreamProxy {
+    upstreams: Vec<Upstream>,
+    config: ProxyConfig,
+}
+
+impl UpstreamProxy {
+    /// Set up channels to every declared upstream, or `None` when nothing is
+    /// proxied; channels connect on first use
+    pub fn new(config: &ProxyConfig, upstream: &UpstreamConfig) -> Result<Option<Self>> {
+        if config.routes.is_empty() {
+            return Ok(None);
+        }
+        let upstreams = config
+            .routes
+            .iter()
+            .map(|route| {
+                info!(rpc = %route.rpc, url = %route.url, "Proxying RPC");
+                Ok(Upstream {
+                    route: route.clone(),
+                    channel: crate::clients::lazy_channel(&route.url, upstream)?,
+                    counters: RouteCounters::default(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Some(Self {
+            upstreams,
+            config: config.clone(),
+        }))
+    }
+
+    /// Index of the first route serving gRPC request path `path`
+    fn route(&self, path: &str) -> Option<usize> {
+        self.upstreams.iter().position(|upstream| upstream.route.matches(path))
+    }
+
+    /// Authenticate `request` and forward it to route `index`
+    async fn forward(
+        &self,
+        auth_interceptor: &AuthInterceptor,
+        index: usize,
+        path: http::uri::PathAndQuery,
+        request: tonic::Request<Bytes>,
+    ) -> Result<tonic::Response<Bytes>, Status> {
+        let upstream = &self.upstreams[index];
+        let auth_context = auth_interceptor.authenticate(&request).await?;
+        if let Some(scope) = &upstream.route.scope {
+            if !auth_context.has_scope(scope) {
+                audit::record(
+                    AuditEvent::new("proxy.call", &auth_context.user_id, AuditOutcome::Denied)
+                        .subject(path.path())
+                        .tenant(auth_context.tenant_id.as_deref()),
+                );
+                return Err(Status::permission_denied(format!("Requires the '{}' scope", scope)));
+            }
+        }
+
+        let (mut outbound, correlation_id) = crate::clients::correlated(request.into_inner());
+        outbound.set_timeout(self.config.timeout);
+        let metadata = outbound.metadata_mut();
+        if let Ok(value) = auth_context.user_id.parse() {
+            metadata.insert(USER_ID_KEY, value);
+        }
+        if let Some(value) = auth_context.tenant_id.as_deref().and_then(|t| t.parse().ok()) {
+            metadata.insert(TENANT_ID_KEY, value);
+        }
+
+        upstream.counters.calls.fetch_add(1, Ordering::Relaxed);
+        let mut client = tonic::client::Grpc::new(upstream.channel.clone());
+        let result = match client.ready().await {
+            Ok(()) => client.unary(outbound, path.clone(), RawCodec).await,
+            Err(e) => Err(Status::unavailable(format!("Upstream unavailable: {}", e))),
+        };
+        if let Err(status) = &result {
+            upstream.counters.failures.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                rpc = %path.path(),
+                correlation_id = %correlation_id,
+                code = ?status.code(),
+                "Proxied call failed: {}",
+                status.message()
+            );
+        }
+        result
+    }
+
+    /// Proxied calls per route in Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (name, failures) in [("syla_gateway_proxy_calls_total", false), ("syla_gateway_proxy_failures_total", true)] {
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            for upstream in &self.upstreams {
+                let counter = if failures { &upstream.counters.failures } else { &upstream.counters.calls };
+                let _ = writeln!(out, "{}{{rpc=\"{}\"}} {}", name, upstream.route.rpc, counter.load(Ordering::Relaxed));
+            }
+        }
+        out
+    }
+}
+
+/// Passes message bytes through without decoding them
+#[derive(Clone, Copy, Default)]
+struct RawCodec;
+
+impl Codec for RawCodec {
+    type Encode = Bytes;
+    type Decode = Bytes;
+    type Encoder = RawCodec;
+    type Decoder = RawCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        RawCodec
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        RawCodec
+    }
+}
+
+impl Encoder for RawCodec {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn encode(&mut self, item: Bytes, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        dst.put(item);
+        Ok(())
+    }
+}
+
+impl Decoder for RawCodec {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Bytes>, Status> {
+        Ok(Some(src.copy_to_bytes(src.remaining())))
+    }
+}
+
+/// One proxied call, in the shape the gRPC server machinery drives
+struct ProxyCall {
+    proxy: Arc<UpstreamProxy>,
+    auth_interceptor: AuthInterceptor,
+    index: usize,
+    path: http::uri::PathAndQuery,
+}
+
+impl tonic::server::UnaryService<Bytes> for ProxyCall {
+    type Response = Bytes;
+    type Future = BoxFuture<'static, Result<tonic::Response<Bytes>, Status>>;
+
+    fn call(&mut self, request: tonic::Request<Bytes>) -> Self::Future {
+        let proxy = self.proxy.clone();
+        let auth_interceptor = self.auth_interceptor.clone();
+        let (index, path) = (self.index, self.path.clone());
+        Box::pin(async move { proxy.forward(&auth_interceptor, index, path, request).await })
+    }
+}
+
+/// Tower layer serving proxied RPCs ahead of the gateway's own services
+#[derive(Clone)]
+pub struct ProxyLayer {
+    proxy: Option<Arc<UpstreamProxy>>,
+    auth_interceptor: AuthInterceptor,
+}
+
+impl ProxyLayer {
+    pub fn new(proxy: Option<Arc<UpstreamProxy>>, auth_interceptor: AuthInterceptor) -> Self {
+        Self { proxy, auth_interceptor }
+    }
+}
+
+impl<S> Layer<S> for ProxyLayer {
+    type Service = ProxyService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ProxyService {
+            inner,
+            proxy: self.proxy.clone(),
+            auth_interceptor: self.auth_interceptor.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ProxyService<S> {
+    inner: S,
+    proxy: Option<Arc<UpstreamProxy>>,
+    auth_interceptor: AuthInterceptor,
+}
+
+impl<S, B> Service<http::Request<B>> for ProxyService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<tonic::body::BoxBody>>,
+    S::Future: Send + 'static,
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let routed = self.proxy.as_ref().and_then(|proxy| {
+            let index = proxy.route(request.uri().path())?;
+            let path = request.uri().path_and_query()?.clone();
+            Some((proxy.clone(), index, path))
+        });
+        let Some((proxy, index, path)) = routed else {
+            return Box::pin(self.inner.call(request));
+        };
+        let call = ProxyCall {
+            proxy,
+            auth_interceptor: self.auth_interceptor.clone(),
+            index,
+            path,
+        };
+        Box::pin(async move {
+            let mut grpc = tonic::server::Grpc::new(RawCodec);
+            Ok(grpc.unary(call, request).await)
+        })
+    }
+}
@@ -0,0 +1,342 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{self, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use hashlink::LruCache;
+use tonic::metadata::MetadataMap;
+use tower::{Layer, Service};
+
+use crate::auth::AuthInterceptor;
+use crate::client_ip::ClientIp;
+use crate::config::RateLimitConfig;
+use crate::error::ApiError;
+
+/// REST routes that are never limited, so probes and scrapes keep working
+const EXEMPT_ROUTES: &[&str] = &["GET /health", "GET /ready", "GET /metrics"];
+
+/// Buckets are split across independently locked shards, so callers rarely contend
+const SHARDS: usize = 16;
+/// Buckets kept per shard; past this the least recently used is dropped,
+/// and starts full again if its caller returns
+const MAX_BUCKETS_PER_SHARD: usize = 100_000 / SHARDS;
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Token buckets per caller, shared by both listeners. Each operation spends
+/// its configured weight, so limits follow backend cost rather than raw
+/// request counts
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    shards: Vec<Mutex<LruCache<String, Bucket>>>,
+    limited: AtomicU64,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            shards: (0..SHARDS)
+                .map(|_| Mutex::new(LruCache::new(MAX_BUCKETS_PER_SHARD)))
+                .collect(),
+            limited: AtomicU64::new(0),
+        }
+    }
+
+    /// Spend the cost of `route` from `caller`'s bucket, or return how long
+    /// until enough tokens have refilled
+    pub fn check(&self, caller: &str, route: &str) -> Result<(), Duration> {
+        self.spend(caller, self.config.weight(route))
+    }
+
+    /// Charge the rest of a batch of `items` once its size is known; the
+    /// first item was paid for when the request was admitted
+    pub fn charge_batch(&self, charge: &RateCharge, items: usize) -> Result<(), ApiError> {
+        let cost = self.config.weight(&charge.route) * items.saturating_sub(1) as f64;
+        self.spend(&charge.caller, cost).map_err(ApiError::RateLimited)
+    }
+
+    fn spend(&self, caller: &str, cost: f64) -> Result<(), Duration> {
+        if self.config.per_second <= 0.0 || cost <= 0.0 {
+            return Ok(());
+        }
+        let now = Instant::now();
+        let mut buckets = self.shard(caller).lock().unwrap();
+        if buckets.get_mut(caller).is_none() {
+            buckets.insert(
+                caller.to_string(),
+                Bucket {
+                    tokens: self.config.burst,
+                    refilled_at: now,
+                },
+            );
+        }
+        let bucket = buckets.get_mut(caller).expect("bucket was just inserted");
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.config.per_second).min(self.config.burst);
+        bucket.refilled_at = now;
+        if bucket.tokens >= cost {
+            bucket.tokens -= cost;
+            return Ok(());
+        }
+        self.limited.fetch_add(1, Ordering::Relaxed);
+        // Operations costing more than the burst can never pass; report the time to a full bucket
+        let missing = cost.min(self.config.burst) - bucket.tokens;
+        Err(Duration::from_secs_f64(missing.max(0.0) / self.config.per_second))
+    }
+
+    fn shard(&self, caller: &str) -> &Mutex<LruCache<String, Bucket>> {
+        let mut hasher = DefaultHasher::new();
+        caller.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % SHARDS]
+    }
+
+    /// Rejections in Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        if self.config.per_second <= 0.0 {
+            return out;
+        }
+        let _ = writeln!(out, "# TYPE syla_gateway_rate_limited_total counter");
+        let _ = writeln!(out, "syla_gateway_rate_limited_total {}", self.limited.load(Ordering::Relaxed));
+        out
+    }
+}
+
+/// Who a REST request was charged to and for which route, so handlers of
+/// batch operations can charge for the rest of the batch
+#[derive(Debug, Clone)]
+pub struct RateCharge {
+    caller: String,
+    route: String,
+}
+
+/// Bucket key for a request: the user its credentials authenticate as, or
+/// the impersonating admin, else its client address. The auth context is
+/// kept on the request, so the route doesn't validate the token again
+async fn caller<B>(interceptor: &AuthInterceptor, request: &mut http::Request<B>) -> String {
+    if request.headers().contains_key(header::AUTHORIZATION) {
+        let metadata = MetadataMap::from_headers(request.headers().clone());
+        if let Ok(auth_context) = interceptor.authenticate_metadata(&metadata).await {
+            let user = auth_context.impersonated_by.as_ref().unwrap_or(&auth_context.user_id);
+            let caller = format!("user:{}", user);
+            request.extensions_mut().insert(auth_context);
+            return caller;
+        }
+    }
+    match request.extensions().get::<ClientIp>() {
+        Some(ClientIp(ip)) => format!("ip:{}", ip),
+        None => "anonymous".to_string(),
+    }
+}
+
+/// `<METHOD> <route template>` for a routed REST request, e.g. `GET /v1/executions/:id`
+pub(crate) fn route_key(request: &Request) -> String {
+    format!(
+        "{} {}",
+        request.method(),
+        request
+            .extensions()
+            .get::<MatchedPath>()
+            .map_or(request.uri().path(), |path| path.as_str())
+    )
+}
+
+/// Rate limit a REST request by its route template
+pub async fn limit_rest(State(limit): State<RateLimitLayer>, mut request: Request, next: Next) -> Response {
+    let route = route_key(&request);
+    if EXEMPT_ROUTES.contains(&route.as_str()) {
+        return next.run(request).await;
+    }
+    let caller = caller(&limit.auth_interceptor, &mut request).await;
+    if let Err(retry_after) = limit.limiter.check(&caller, &route) {
+        return ApiError::RateLimited(retry_after).into_response();
+    }
+    request.extensions_mut().insert(RateCharge { caller, route });
+    next.run(request).await
+}
+
+/// Rate limiting for both listeners: as middleware state for REST, and as
+/// a Tower layer limiting gRPC requests by method
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<RateLimiter>,
+    auth_interceptor: AuthInterceptor,
+}
+
+impl RateLimitLayer {
+    pub fn new(limiter: Arc<RateLimiter>, auth_interceptor: AuthInterceptor) -> Self {
+        Self {
+            limiter,
+            auth_interceptor,
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            limiter: self.limiter.clone(),
+            auth_interceptor: self.auth_interceptor.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>,
+    auth_interceptor: AuthInterceptor,
+}
+
+impl<S, B> Service<http::Request<B>> for RateLimitService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<tonic::body::BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        // The service that was polled ready takes the call; a clone stays behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let limiter = self.limiter.clone();
+        let auth_interceptor = self.auth_interceptor.clone();
+        Box::pin(async move {
+            let caller = caller(&auth_interceptor, &mut request).await;
+            if let Err(retry_after) = limiter.check(&caller, request.uri().path()) {
+                let status = tonic::Status::resource_exhausted(format!(
+                    "Rate limit exceeded; retry in {:.1}s",
+                    retry_after.as_secs_f64()
+                ));
+                return Ok(status.into_http());
+            }
+            inner.call(request).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const CREATE: &str = "POST /v1/executions";
+    const BULK_CANCEL: &str = "POST /v1/executions/cancel";
+
+    /// One token a second from a bucket of ten, with creates costing four
+    /// and bulk cancels two per item
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            per_second: 1.0,
+            burst: 10.0,
+            weights: HashMap::from([(CREATE.to_string(), 4.0), (BULK_CANCEL.to_string(), 2.0)]),
+        })
+    }
+
+    fn charge(caller: &str, route: &str) -> RateCharge {
+        RateCharge {
+            caller: caller.to_string(),
+            route: route.to_string(),
+        }
+    }
+
+    /// Whether `retry_after` is `expected` seconds, give or take refill during the test
+    fn is_about(retry_after: Duration, expected: f64) -> bool {
+        (retry_after.as_secs_f64() - expected).abs() < 0.1
+    }
+
+    #[test]
+    fn routes_cost_their_weight() {
+        let limiter = limiter();
+        assert!(limiter.check("user:a", CREATE).is_ok());
+        assert!(limiter.check("user:a", CREATE).is_ok());
+        // Two tokens left, two short of another create
+        let retry_after = limiter.check("user:a", CREATE).unwrap_err();
+        assert!(is_about(retry_after, 2.0), "{:?}", retry_after);
+        // Unweighted routes cost one
+        assert!(limiter.check("user:a", "GET /v1/executions").is_ok());
+        assert!(limiter.check("user:a", "GET /v1/executions").is_ok());
+        assert!(limiter.check("user:a", "GET /v1/executions").is_err());
+    }
+
+    #[test]
+    fn callers_have_separate_buckets() {
+        let limiter = limiter();
+        for _ in 0..2 {
+            limiter.check("user:a", CREATE).unwrap();
+        }
+        assert!(limiter.check("user:a", CREATE).is_err());
+        assert!(limiter.check("user:b", CREATE).is_ok());
+    }
+
+    #[test]
+    fn costs_past_the_burst_wait_for_a_full_bucket() {
+        let limiter = limiter();
+        limiter.check("user:a", CREATE).unwrap();
+        let retry_after = limiter.spend("user:a", 25.0).unwrap_err();
+        assert!(is_about(retry_after, 4.0), "{:?}", retry_after);
+    }
+
+    #[test]
+    fn nothing_is_limited_when_disabled() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            per_second: 0.0,
+            burst: 1.0,
+            weights: HashMap::new(),
+        });
+        for _ in 0..100 {
+            assert!(limiter.check("user:a", CREATE).is_ok());
+        }
+        assert!(limiter.render().is_empty());
+    }
+
+    #[test]
+    fn batches_pay_for_every_item_after_the_first() {
+        let limiter = limiter();
+        let charge = charge("user:a", BULK_CANCEL);
+        limiter.check("user:a", BULK_CANCEL).unwrap();
+        // The admitted request already paid for a batch of one
+        assert!(limiter.charge_batch(&charge, 1).is_ok());
+        assert!(limiter.charge_batch(&charge, 0).is_ok());
+        // Four more items at two each leaves none of the eight remaining
+        assert!(limiter.charge_batch(&charge, 5).is_ok());
+        assert!(matches!(
+            limiter.charge_batch(&charge, 2),
+            Err(ApiError::RateLimited(retry_after)) if is_about(retry_after, 2.0)
+        ));
+    }
+
+    #[test]
+    fn rejected_batches_spend_nothing() {
+        let limiter = limiter();
+        let charge = charge("user:a", BULK_CANCEL);
+        limiter.check("user:a", BULK_CANCEL).unwrap();
+        // Ten more items would cost eighteen
+        assert!(limiter.charge_batch(&charge, 10).is_err());
+        assert!(limiter.charge_batch(&charge, 5).is_ok());
+    }
+}
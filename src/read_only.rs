@@ -0,0 +1,174 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use axum::{
+    extract::{Request, State},
+    http::{self, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::Serialize;
+use tower::{Layer, Service};
+use tracing::info;
+
+use crate::error::ApiError;
+use crate::state::AppState;
+
+/// POST routes that only read, so they keep working in read-only mode
+const READ_ONLY_POSTS: &[&str] = &[
+    "/v1/executions/status",
+    "/v1/executions/validate",
+    "/v1/settings/executions/preview",
+];
+
+/// Route prefixes exempt from read-only mode: operators must be able to
+/// turn it off, and the execution service still reports on running executions
+const EXEMPT_PREFIXES: &[&str] = &["/admin/", "/internal/"];
+
+/// gRPC methods rejected in read-only mode
+const MUTATING_GRPC_METHODS: &[&str] = &[
+    "CreateExecution",
+    "CancelExecution",
+    "DeleteExecution",
+    "CreateWorkspace",
+    "UpdateWorkspace",
+    "DeleteWorkspace",
+];
+
+/// Whether the gateway is read-only, and who last changed that
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadOnlyStatus {
+    pub read_only: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_by: Option<String>,
+}
+
+/// Read-only mode of this replica: reads, listings and streams are served
+/// from whatever the gateway can reach while mutations are rejected, e.g. to
+/// keep dashboards up through a backend maintenance window
+pub struct ReadOnlyMode {
+    enabled: AtomicBool,
+    status: Mutex<ReadOnlyStatus>,
+}
+
+impl ReadOnlyMode {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: AtomicBool::new(enabled),
+            status: Mutex::new(ReadOnlyStatus {
+                read_only: enabled,
+                reason: None,
+                changed_at: None,
+                changed_by: None,
+            }),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn status(&self) -> ReadOnlyStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, enabled: bool, reason: Option<String>, changed_by: &str) -> ReadOnlyStatus {
+        let mut status = self.status.lock().unwrap();
+        *status = ReadOnlyStatus {
+            read_only: enabled,
+            reason,
+            changed_at: Some(Utc::now()),
+            changed_by: Some(changed_by.to_string()),
+        };
+        self.enabled.store(enabled, Ordering::Relaxed);
+        info!(read_only = enabled, changed_by, "Read-only mode changed");
+        status.clone()
+    }
+
+    /// Read-only state in Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# TYPE syla_gateway_read_only gauge");
+        let _ = writeln!(out, "syla_gateway_read_only {}", u8::from(self.is_enabled()));
+        out
+    }
+}
+
+fn is_mutation(method: &Method, path: &str) -> bool {
+    if EXEMPT_PREFIXES.iter().any(|prefix| path.starts_with(prefix)) {
+        return false;
+    }
+    match *method {
+        Method::GET | Method::HEAD | Method::OPTIONS => false,
+        Method::POST => !READ_ONLY_POSTS.contains(&path),
+        _ => true,
+    }
+}
+
+/// Reject REST mutations while the gateway is read-only
+pub async fn reject_mutations(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    if state.read_only().is_enabled() && is_mutation(request.method(), request.uri().path()) {
+        return ApiError::ReadOnly.into_response();
+    }
+    next.run(request).await
+}
+
+/// Tower layer rejecting mutating gRPC calls while the gateway is read-only
+#[derive(Clone)]
+pub struct ReadOnlyLayer {
+    mode: Arc<ReadOnlyMode>,
+}
+
+impl ReadOnlyLayer {
+    pub fn new(mode: Arc<ReadOnlyMode>) -> Self {
+        Self { mode }
+    }
+}
+
+impl<S> Layer<S> for ReadOnlyLayer {
+    type Service = ReadOnlyService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ReadOnlyService {
+            inner,
+            mode: self.mode.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ReadOnlyService<S> {
+    inner: S,
+    mode: Arc<ReadOnlyMode>,
+}
+
+impl<S, B> Service<http::Request<B>> for ReadOnlyService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<tonic::body::BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let method = request.uri().path().rsplit('/').next().unwrap_or_default();
+        if self.mode.is_enabled() && MUTATING_GRPC_METHODS.contains(&method) {
+            let status = tonic::Status::unavailable(ApiError::ReadOnly.to_string());
+            return Box::pin(async move { Ok(status.into_http()) });
+        }
+        Box::pin(self.inner.call(request))
+    }
+}
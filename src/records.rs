@@ -0,0 +1,316 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use sqlx::postgres::PgPool;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::auth::AuthContext;
+use crate::cache::ExecutionMeta;
+use crate::execution::{Annotation, CreateExecutionRequest, ExecutionResponse};
+use crate::state::ExecutionFilter;
+
+/// Where an execution sits in a newest-first listing: when it was created,
+/// then its ID to order executions created together
+pub type PageCursor = (DateTime<Utc>, Uuid);
+
+/// Gateway-owned state of an execution as the SQL store keeps it
+#[derive(Debug, Clone)]
+pub struct ExecutionRecord {
+    pub owner: String,
+    pub tenant_id: Option<String>,
+    pub pinned: bool,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub code_sha256: Option<String>,
+    /// By author
+    pub annotations: BTreeMap<String, Annotation>,
+}
+
+impl ExecutionRecord {
+    /// Overlay the stored state onto a cache entry's metadata
+    pub fn apply_to(&self, meta: &mut ExecutionMeta) {
+        meta.owner = Some(self.owner.clone());
+        meta.tenant_id = self.tenant_id.clone();
+        meta.pinned = self.pinned;
+        meta.deleted_at = self.deleted_at;
+        if self.code_sha256.is_some() {
+            meta.code_sha256 = self.code_sha256.clone();
+        }
+        meta.annotations = self.annotations.clone();
+    }
+}
+
+/// Executions submitted through the gateway, kept in the SQL store when one
+/// is configured so ownership, pins and soft deletes survive restarts and
+/// every replica sees the same ones. Without a store the cache is all there
+/// is, and each method does nothing
+pub struct ExecutionRecords {
+    pool: Option<PgPool>,
+}
+
+impl ExecutionRecords {
+    pub fn new(pool: Option<PgPool>) -> Self {
+        Self { pool }
+    }
+
+    /// Record an execution just submitted by `auth_context` from `request`
+    pub async fn insert(
+        &self,
+        execution: &ExecutionResponse,
+        auth_context: &AuthContext,
+        request: &CreateExecutionRequest,
+    ) -> Result<()> {
+        let Some(pool) = &self.pool else {
+            return Ok(());
+        };
+        sqlx::query(
+            "INSERT INTO executions \
+             (id, user_id, tenant_id, workspace_id, language, status, created_at, session_id, code_sha256, \
+              concurrency_group, group_id) \
+             VALUES ($1::uuid, $2, $3, $4::uuid, $5, $6, $7, $8, $9, $10, $11) \
+             ON CONFLICT (id) DO NOTHING",
+        )
+        .bind(execution.id.to_string())
+        .bind(&auth_context.user_id)
+        .bind(&auth_context.tenant_id)
+        .bind(request.workspace_id.map(|id| id.to_string()))
+        .bind(&request.language)
+        .bind(execution.status.as_str())
+        .bind(execution.created_at)
+        .bind(&request.session_id)
+        .bind(&execution.code_sha256)
+        .bind(&request.concurrency_group)
+        .bind(&request.group_id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Stored state of execution `id`; None if it wasn't submitted through
+    /// the gateway or there is no store
+    pub async fn load(&self, id: Uuid) -> Result<Option<ExecutionRecord>> {
+        let Some(pool) = &self.pool else {
+            return Ok(None);
+        };
+        let row = sqlx::query(
+            "SELECT user_id, tenant_id, pinned, deleted_at, code_sha256 FROM executions WHERE id = $1::uuid",
+        )
+        .bind(id.to_string())
+        .fetch_optional(pool)
+        .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        Ok(Some(ExecutionRecord {
+            owner: row.try_get("user_id")?,
+            tenant_id: row.try_get("tenant_id")?,
+            pinned: row.try_get("pinned")?,
+            deleted_at: row.try_get("deleted_at")?,
+            code_sha256: row.try_get("code_sha256")?,
+            annotations: self.annotations(id).await?.unwrap_or_default(),
+        }))
+    }
+
+    /// Annotations on execution `id` by author; None if there is no store
+    pub async fn annotations(&self, id: Uuid) -> Result<Option<BTreeMap<String, Annotation>>> {
+        let Some(pool) = &self.pool else {
+            return Ok(None);
+        };
+        let rows = sqlx::query(
+            "SELECT author, note, score, data, updated_at FROM execution_annotations WHERE execution_id = $1::uuid",
+        )
+        .bind(id.to_string())
+        .fetch_all(pool)
+        .await?;
+        let annotations = rows
+            .iter()
+            .map(|row| {
+                let data: Option<String> = row.try_get("data")?;
+                let annotation = Annotation {
+                    note: row.try_get("note")?,
+                    score: row.try_get("score")?,
+                    data: data.as_deref().map(serde_json::from_str).transpose()?,
+                    updated_at: row.try_get("updated_at")?,
+                };
+                Ok((row.try_get("author")?, annotation))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Some(annotations))
+    }
+
+    /// Store `author`'s annotation on execution `id`, replacing any they made
+    /// before. Executions the gateway didn't submit aren't stored, so are
+    /// left alone
+    pub async fn annotate(&self, id: Uuid, author: &str, annotation: &Annotation) -> Result<()> {
+        let Some(pool) = &self.pool else {
+            return Ok(());
+        };
+        sqlx::query(
+            "INSERT INTO execution_annotations (execution_id, author, note, score, data, updated_at) \
+             SELECT id, $2, $3, $4, $5, $6 FROM executions WHERE id = $1::uuid \
+             ON CONFLICT (execution_id, author) DO UPDATE \
+             SET note = EXCLUDED.note, score = EXCLUDED.score, data = EXCLUDED.data, updated_at = EXCLUDED.updated_at",
+        )
+        .bind(id.to_string())
+        .bind(author)
+        .bind(&annotation.note)
+        .bind(annotation.score)
+        .bind(annotation.data.as_ref().map(serde_json::Value::to_string))
+        .bind(annotation.updated_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Record the status `execution` moved to, with its outcome once it
+    /// finished, so the store can be swept and watched like the cache.
+    /// Executions the gateway didn't submit aren't stored, so are left alone,
+    /// and a finished execution's record no longer changes
+    pub async fn record_status(&self, execution: &ExecutionResponse) -> Result<()> {
+        let Some(pool) = &self.pool else {
+            return Ok(());
+        };
+        let result = execution.result.as_ref().filter(|_| execution.status.is_terminal());
+        sqlx::query(
+            "UPDATE executions SET status = $2, started_at = COALESCE($3, started_at), completed_at = $4, \
+                    exit_code = $5, duration_ms = $6, stdout_sha256 = $7, stderr_sha256 = $8 \
+             WHERE id = $1::uuid AND status NOT IN ('completed', 'failed', 'cancelled', 'timeout')",
+        )
+        .bind(execution.id.to_string())
+        .bind(execution.status.as_str())
+        .bind(execution.started_at)
+        .bind(execution.completed_at.filter(|_| execution.status.is_terminal()))
+        .bind(result.map(|result| result.exit_code))
+        .bind(result.map(|result| i64::try_from(result.duration_ms).unwrap_or(i64::MAX)))
+        .bind(result.and_then(|result| result.stdout_sha256.as_deref()))
+        .bind(result.and_then(|result| result.stderr_sha256.as_deref()))
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// IDs of unfinished executions created before `before`, oldest first;
+    /// with `grouped`, only those holding a concurrency group
+    pub async fn unfinished(&self, before: DateTime<Utc>, grouped: bool) -> Result<Vec<Uuid>> {
+        let Some(pool) = &self.pool else {
+            return Ok(Vec::new());
+        };
+        let ids: Vec<String> = sqlx::query_scalar(
+            "SELECT id::text FROM executions \
+             WHERE status NOT IN ('completed', 'failed', 'cancelled', 'timeout') \
+               AND deleted_at IS NULL AND created_at < $1 \
+               AND (NOT $2 OR concurrency_group IS NOT NULL) \
+             ORDER BY created_at",
+        )
+        .bind(before)
+        .bind(grouped)
+        .fetch_all(pool)
+        .await?;
+        Ok(ids.iter().filter_map(|id| id.parse().ok()).collect())
+    }
+
+    /// One page of `user_id`'s undeleted executions that may match `filter`,
+    /// newest first, with when each was created: at most `limit` of them,
+    /// starting after `after`. Status and tags aren't stored, so those are
+    /// left for the caller to check
+    pub async fn matching(
+        &self,
+        user_id: &str,
+        filter: &ExecutionFilter,
+        after: Option<PageCursor>,
+        limit: usize,
+    ) -> Result<Vec<PageCursor>> {
+        let Some(pool) = &self.pool else {
+            return Ok(Vec::new());
+        };
+        let rows = sqlx::query(
+            "SELECT id::text AS id, created_at FROM executions \
+             WHERE user_id = $1 AND deleted_at IS NULL \
+               AND ($2::text IS NULL OR session_id = $2) \
+               AND ($3::boolean IS NULL OR pinned = $3) \
+               AND ($4::timestamptz IS NULL OR created_at >= $4) \
+               AND ($5::timestamptz IS NULL OR created_at < $5) \
+               AND ($6::text IS NULL OR group_id = $6) \
+               AND ($7::timestamptz IS NULL OR (created_at, id) < ($7, $8::uuid)) \
+             ORDER BY created_at DESC, id DESC \
+             LIMIT $9",
+        )
+        .bind(user_id)
+        .bind(&filter.session_id)
+        .bind(filter.pinned)
+        .bind(filter.created_after)
+        .bind(filter.created_before)
+        .bind(&filter.group_id)
+        .bind(after.map(|(created_at, _)| created_at))
+        .bind(after.map(|(_, id)| id.to_string()))
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+        .fetch_all(pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                let id: String = row.try_get("id")?;
+                Ok((row.try_get("created_at")?, id.parse()?))
+            })
+            .collect()
+    }
+
+    /// How many of `user_id`'s undeleted executions may match `filter`; an
+    /// upper bound, as status and tags aren't stored
+    pub async fn count_matching(&self, user_id: &str, filter: &ExecutionFilter) -> Result<u64> {
+        let Some(pool) = &self.pool else {
+            return Ok(0);
+        };
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM executions \
+             WHERE user_id = $1 AND deleted_at IS NULL \
+               AND ($2::text IS NULL OR session_id = $2) \
+               AND ($3::boolean IS NULL OR pinned = $3) \
+               AND ($4::timestamptz IS NULL OR created_at >= $4) \
+               AND ($5::timestamptz IS NULL OR created_at < $5) \
+               AND ($6::text IS NULL OR group_id = $6)",
+        )
+        .bind(user_id)
+        .bind(&filter.session_id)
+        .bind(filter.pinned)
+        .bind(filter.created_after)
+        .bind(filter.created_before)
+        .bind(&filter.group_id)
+        .fetch_one(pool)
+        .await?;
+        Ok(u64::try_from(count).unwrap_or_default())
+    }
+
+    /// Whether executions are kept in a SQL store
+    pub fn is_stored(&self) -> bool {
+        self.pool.is_some()
+    }
+
+    pub async fn set_pinned(&self, id: Uuid, pinned: bool) -> Result<()> {
+        let Some(pool) = &self.pool else {
+            return Ok(());
+        };
+        sqlx::query("UPDATE executions SET pinned = $2 WHERE id = $1::uuid")
+            .bind(id.to_string())
+            .bind(pinned)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Soft-delete execution `id` at `deleted_at`, or restore it when None.
+    /// Returns whether a stored execution changed
+    pub async fn set_deleted(&self, id: Uuid, deleted_at: Option<DateTime<Utc>>) -> Result<bool> {
+        let Some(pool) = &self.pool else {
+            return Ok(false);
+        };
+        let updated = sqlx::query(
+            "UPDATE executions SET deleted_at = $2 \
+             WHERE id = $1::uuid AND (deleted_at IS NULL) = ($2::timestamptz IS NOT NULL)",
+        )
+        .bind(id.to_string())
+        .bind(deleted_at)
+        .execute(pool)
+        .await?;
+        Ok(updated.rows_affected() > 0)
+    }
+}
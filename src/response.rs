@@ -0,0 +1,166 @@
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std::io::{self, Write};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use uuid::Uuid;
+
+use crate::compat::VersionedExecution;
+use crate::config::ResponseConfig;
+use crate::error::ApiError;
+use crate::execution::ExecutionResult;
+use crate::logs;
+use crate::server_timing::{self, Phase};
+
+/// Size of chunks handed to the response body when streaming
+const STREAM_CHUNK_BYTES: usize = 64 * 1024;
+/// Chunks buffered ahead of the client before serialization waits
+const STREAM_CHANNEL_DEPTH: usize = 4;
+/// Allowance for JSON structure around the raw output
+const ENVELOPE_OVERHEAD_BYTES: usize = 4 * 1024;
+/// Most bytes JSON escaping turns one byte of output into (`\u001b`)
+const MAX_ESCAPE_EXPANSION: usize = 6;
+
+/// Whether the client's `Accept-Encoding` allows `encoding`
+pub fn accepts_encoding(headers: &HeaderMap, encoding: &str) -> bool {
+    headers
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|encodings| {
+            encodings.split(',').any(|candidate| {
+                let mut parts = candidate.split(';');
+                let name = parts.next().unwrap_or_default().trim();
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                name.eq_ignore_ascii_case(encoding) && quality > 0.0
+            })
+        })
+}
+
+/// Render an execution as JSON within the configured size limits
+pub fn execution_json(
+    execution: VersionedExecution,
+    limits: &ResponseConfig,
+) -> Result<Response, ApiError> {
+    let (id, output_len) = (execution.id(), execution.output_len());
+    sized_json(execution, id, output_len, limits)
+}
+
+/// Render execution `id`'s result as JSON within the configured size limits
+pub fn result_json(id: Uuid, result: ExecutionResult, limits: &ResponseConfig) -> Result<Response, ApiError> {
+    let output_len = result.output_len() as usize;
+    sized_json(result, id, output_len, limits)
+}
+
+/// Render `value`, carrying `output_len` bytes of execution `id`'s output,
+/// streaming it past the threshold and refusing it past the limit. Output
+/// that can't reach the threshold even fully escaped isn't measured;
+/// anything larger is sized by serializing it without keeping the bytes
+fn sized_json<T: Serialize + Send + 'static>(
+    value: T,
+    id: Uuid,
+    output_len: usize,
+    limits: &ResponseConfig,
+) -> Result<Response, ApiError> {
+    let worst_case = output_len.saturating_mul(MAX_ESCAPE_EXPANSION) + ENVELOPE_OVERHEAD_BYTES;
+    if worst_case <= limits.streaming_threshold_bytes.min(limits.max_body_bytes) {
+        return Ok(server_timing::measure(Phase::Serialization, || Json(value).into_response()));
+    }
+
+    let size = server_timing::measure(Phase::Serialization, || serialized_len(&value))?;
+    if size > limits.max_body_bytes {
+        return Err(ApiError::ResponseTooLarge {
+            size,
+            limit: limits.max_body_bytes,
+            logs_url: logs::url(id),
+        });
+    }
+
+    if size > limits.streaming_threshold_bytes {
+        Ok(streaming_json(value))
+    } else {
+        Ok(server_timing::measure(Phase::Serialization, || Json(value).into_response()))
+    }
+}
+
+/// Bytes `value` serializes to as JSON
+fn serialized_len<T: Serialize>(value: &T) -> Result<usize, ApiError> {
+    let mut counter = ByteCounter(0);
+    serde_json::to_writer(&mut counter, value).map_err(|e| ApiError::Internal(e.into()))?;
+    Ok(counter.0)
+}
+
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0 += data.len();
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serialize on a blocking thread straight into the response body, so the
+/// full JSON document is never held in memory at once
+pub fn streaming_json<T: Serialize + Send + 'static>(value: T) -> Response {
+    let (tx, rx) = mpsc::channel(STREAM_CHANNEL_DEPTH);
+
+    tokio::task::spawn_blocking(move || {
+        let mut writer = ChannelWriter {
+            tx,
+            buf: Vec::with_capacity(STREAM_CHUNK_BYTES),
+        };
+        let result = serde_json::to_writer(&mut writer, &value)
+            .map_err(io::Error::from)
+            .and_then(|_| writer.flush());
+        if let Err(e) = result {
+            // The client sees a truncated body; nothing more can be sent
+            tracing::warn!("Streaming JSON serialization failed: {}", e);
+            let _ = writer.tx.blocking_send(Err(e));
+        }
+    });
+
+    (
+        [(header::CONTENT_TYPE, "application/json")],
+        Body::from_stream(ReceiverStream::new(rx)),
+    )
+        .into_response()
+}
+
+struct ChannelWriter {
+    tx: mpsc::Sender<Result<Bytes, io::Error>>,
+    buf: Vec<u8>,
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= STREAM_CHUNK_BYTES {
+            self.flush()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = Bytes::from(std::mem::replace(
+            &mut self.buf,
+            Vec::with_capacity(STREAM_CHUNK_BYTES),
+        ));
+        self.tx
+            .blocking_send(Ok(chunk))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client disconnected"))
+    }
+}
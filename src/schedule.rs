@@ -0,0 +1,564 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::get,
+    Extension, Json, Router,
+};
+use chrono::{DateTime, Utc};
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgPool, PgRow};
+use sqlx::Row;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::auth::{self, AuthContext, AuthInterceptor};
+use crate::error::ApiError;
+use crate::execution::CreateExecutionRequest;
+use crate::ids;
+use crate::state::AppState;
+
+/// Executions listed per schedule, most recent first
+const MAX_LISTED_RUNS: usize = 100;
+
+/// Schedule routes, authenticated; users only ever see their own schedules
+pub fn routes(auth_interceptor: AuthInterceptor) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/schedules", get(list_schedules).post(create_schedule))
+        .route("/v1/schedules/:id", get(get_schedule).delete(delete_schedule))
+        .route_layer(middleware::from_fn_with_state(auth_interceptor, auth::require_auth))
+}
+
+/// An execution request to submit later, once at `run_at` or on every match
+/// of `cron`
+#[derive(Debug, Deserialize)]
+pub struct CreateScheduleRequest {
+    #[serde(flatten)]
+    pub execution: CreateExecutionRequest,
+    pub run_at: Option<DateTime<Utc>>,
+    /// Five-field cron expression, evaluated in UTC
+    pub cron: Option<String>,
+}
+
+/// An execution request submitted on a schedule by its owner
+#[derive(Debug, Clone, Serialize)]
+pub struct Schedule {
+    pub id: Uuid,
+    pub language: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+    /// Unset once a one-off schedule has run
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// Why the last run could not be submitted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Executions submitted so far, most recent first
+    pub execution_ids: Vec<Uuid>,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip)]
+    owner: String,
+    #[serde(skip)]
+    tenant_id: Option<String>,
+    #[serde(skip)]
+    request: CreateExecutionRequest,
+}
+
+impl Schedule {
+    /// A schedule submitting `request` as `auth_context`, either once at
+    /// `run_at` or on every match of `cron`
+    pub fn new(
+        auth_context: &AuthContext,
+        request: CreateExecutionRequest,
+        run_at: Option<DateTime<Utc>>,
+        cron: Option<String>,
+    ) -> Result<Self, String> {
+        let now = Utc::now();
+        let next_run_at = match (run_at, cron.as_deref()) {
+            (Some(run_at), None) if run_at <= now => {
+                return Err(format!("run_at {} is in the past", run_at));
+            }
+            (Some(run_at), None) => run_at,
+            (None, Some(expression)) => parse_cron(expression)?
+                .find_next_occurrence(&now, false)
+                .map_err(|e| format!("Cron expression '{}' never matches: {}", expression, e))?,
+            _ => return Err("Set exactly one of run_at and cron".to_string()),
+        };
+        Ok(Self {
+            id: ids::generate(),
+            language: request.language.clone(),
+            run_at,
+            cron,
+            next_run_at: Some(next_run_at),
+            last_run_at: None,
+            last_error: None,
+            execution_ids: Vec::new(),
+            created_at: now,
+            owner: auth_context.user_id.clone(),
+            tenant_id: auth_context.tenant_id.clone(),
+            request,
+        })
+    }
+
+    /// The identity runs are submitted as
+    pub fn owner(&self) -> AuthContext {
+        AuthContext {
+            user_id: self.owner.clone(),
+            tenant_id: self.tenant_id.clone(),
+            token: String::new(),
+            scopes: Vec::new(),
+            impersonated_by: None,
+        }
+    }
+
+    pub fn request(&self) -> &CreateExecutionRequest {
+        &self.request
+    }
+
+    /// When the schedule runs next after a run at `now`; missed cron matches
+    /// are skipped rather than caught up on
+    pub fn following(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let expression = self.cron.as_deref()?;
+        parse_cron(expression).ok()?.find_next_occurrence(&now, false).ok()
+    }
+
+    fn from_row(row: &PgRow) -> Result<Self> {
+        let id: String = row.try_get("id")?;
+        let request: String = row.try_get("request")?;
+        let request: CreateExecutionRequest = serde_json::from_str(&request)?;
+        let execution_ids: Vec<String> = row.try_get("execution_ids")?;
+        Ok(Self {
+            id: id.parse()?,
+            language: request.language.clone(),
+            run_at: row.try_get("run_at")?,
+            cron: row.try_get("cron")?,
+            next_run_at: row.try_get("next_run_at")?,
+            last_run_at: row.try_get("last_run_at")?,
+            last_error: row.try_get("last_error")?,
+            execution_ids: execution_ids.iter().filter_map(|id| id.parse().ok()).collect(),
+            created_at: row.try_get("created_at")?,
+            owner: row.try_get("user_id")?,
+            tenant_id: row.try_get("tenant_id")?,
+            request,
+        })
+    }
+}
+
+fn parse_cron(expression: &str) -> Result<Cron, String> {
+    Cron::new(expression)
+        .parse()
+        .map_err(|e| format!("Invalid cron expression '{}': {}", expression, e))
+}
+
+/// Columns of a schedule row, with its most recent executions
+fn schedule_columns() -> String {
+    format!(
+        "id::text AS id, user_id, tenant_id, request, run_at, cron, next_run_at, last_run_at, last_error, created_at, \
+         ARRAY(SELECT execution_id::text FROM schedule_runs \
+               WHERE schedule_runs.schedule_id = schedules.id \
+               ORDER BY schedule_runs.created_at DESC, schedule_runs.id DESC \
+               LIMIT {}) AS execution_ids",
+        MAX_LISTED_RUNS
+    )
+}
+
+#[derive(Default)]
+struct ScheduleCounters {
+    submitted: AtomicU64,
+    failed: AtomicU64,
+}
+
+/// Schedules, kept in the SQL store when one is configured so they survive
+/// restarts and any replica holding the scheduler lease can run them, and in
+/// memory otherwise
+pub struct Schedules {
+    schedules: RwLock<HashMap<Uuid, Schedule>>,
+    pool: Option<PgPool>,
+    counters: ScheduleCounters,
+}
+
+impl Schedules {
+    pub fn new(pool: Option<PgPool>) -> Self {
+        Self {
+            schedules: RwLock::new(HashMap::new()),
+            pool,
+            counters: ScheduleCounters::default(),
+        }
+    }
+
+    pub async fn insert(&self, schedule: &Schedule) -> Result<()> {
+        let Some(pool) = &self.pool else {
+            self.schedules.write().await.insert(schedule.id, schedule.clone());
+            return Ok(());
+        };
+        sqlx::query(
+            "INSERT INTO schedules \
+             (id, user_id, tenant_id, request, run_at, cron, next_run_at, created_at) \
+             VALUES ($1::uuid, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(schedule.id.to_string())
+        .bind(&schedule.owner)
+        .bind(&schedule.tenant_id)
+        .bind(serde_json::to_string(&schedule.request)?)
+        .bind(schedule.run_at)
+        .bind(&schedule.cron)
+        .bind(schedule.next_run_at)
+        .bind(schedule.created_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// How many schedules `user_id` keeps
+    pub async fn count(&self, user_id: &str) -> Result<usize> {
+        let Some(pool) = &self.pool else {
+            let schedules = self.schedules.read().await;
+            return Ok(schedules.values().filter(|schedule| schedule.owner == user_id).count());
+        };
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM schedules WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?;
+        Ok(count.max(0) as usize)
+    }
+
+    /// A schedule owned by `user_id`; other users' schedules are reported as not found
+    pub async fn get(&self, id: Uuid, user_id: &str) -> Result<Option<Schedule>> {
+        let Some(pool) = &self.pool else {
+            let schedules = self.schedules.read().await;
+            return Ok(schedules.get(&id).filter(|schedule| schedule.owner == user_id).cloned());
+        };
+        let query = format!(
+            "SELECT {} FROM schedules WHERE id = $1::uuid AND user_id = $2",
+            schedule_columns()
+        );
+        let row = sqlx::query(&query)
+            .bind(id.to_string())
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+        row.as_ref().map(Schedule::from_row).transpose()
+    }
+
+    /// Schedules owned by `user_id`, newest first
+    pub async fn list(&self, user_id: &str) -> Result<Vec<Schedule>> {
+        let Some(pool) = &self.pool else {
+            let schedules = self.schedules.read().await;
+            let mut owned: Vec<_> = schedules
+                .values()
+                .filter(|schedule| schedule.owner == user_id)
+                .cloned()
+                .collect();
+            owned.sort_by_key(|schedule| std::cmp::Reverse(schedule.created_at));
+            return Ok(owned);
+        };
+        let query = format!(
+            "SELECT {} FROM schedules WHERE user_id = $1 ORDER BY created_at DESC",
+            schedule_columns()
+        );
+        let rows = sqlx::query(&query).bind(user_id).fetch_all(pool).await?;
+        rows.iter().map(Schedule::from_row).collect()
+    }
+
+    /// Delete a schedule owned by `user_id`, returning whether there was one
+    pub async fn delete(&self, id: Uuid, user_id: &str) -> Result<bool> {
+        let Some(pool) = &self.pool else {
+            let mut schedules = self.schedules.write().await;
+            if schedules.get(&id).is_none_or(|schedule| schedule.owner != user_id) {
+                return Ok(false);
+            }
+            schedules.remove(&id);
+            return Ok(true);
+        };
+        let deleted = sqlx::query("DELETE FROM schedules WHERE id = $1::uuid AND user_id = $2")
+            .bind(id.to_string())
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+
+    /// Schedules due to run at `now`, longest overdue first
+    pub async fn due(&self, now: DateTime<Utc>) -> Result<Vec<Schedule>> {
+        let Some(pool) = &self.pool else {
+            let schedules = self.schedules.read().await;
+            let mut due: Vec<_> = schedules
+                .values()
+                .filter(|schedule| schedule.next_run_at.is_some_and(|at| at <= now))
+                .cloned()
+                .collect();
+            due.sort_by_key(|schedule| schedule.next_run_at);
+            return Ok(due);
+        };
+        let query = format!(
+            "SELECT {} FROM schedules WHERE next_run_at <= $1 ORDER BY next_run_at",
+            schedule_columns()
+        );
+        let rows = sqlx::query(&query).bind(now).fetch_all(pool).await?;
+        rows.iter().map(Schedule::from_row).collect()
+    }
+
+    /// Move `schedule` on to `next_run_at` ahead of running it at `now`.
+    /// Returns false when it was deleted or already moved on, so a run is
+    /// never submitted twice
+    pub async fn claim(
+        &self,
+        schedule: &Schedule,
+        now: DateTime<Utc>,
+        next_run_at: Option<DateTime<Utc>>,
+    ) -> Result<bool> {
+        let Some(pool) = &self.pool else {
+            let mut schedules = self.schedules.write().await;
+            let Some(stored) = schedules
+                .get_mut(&schedule.id)
+                .filter(|stored| stored.next_run_at == schedule.next_run_at)
+            else {
+                return Ok(false);
+            };
+            stored.next_run_at = next_run_at;
+            stored.last_run_at = Some(now);
+            return Ok(true);
+        };
+        let claimed = sqlx::query(
+            "UPDATE schedules SET next_run_at = $2, last_run_at = $3 \
+             WHERE id = $1::uuid AND next_run_at = $4",
+        )
+        .bind(schedule.id.to_string())
+        .bind(next_run_at)
+        .bind(now)
+        .bind(schedule.next_run_at)
+        .execute(pool)
+        .await?;
+        Ok(claimed.rows_affected() > 0)
+    }
+
+    /// Record the execution a run submitted, or why it could not be
+    pub async fn record_run(&self, schedule_id: Uuid, outcome: Result<Uuid, String>) -> Result<()> {
+        let counter = match outcome {
+            Ok(_) => &self.counters.submitted,
+            Err(_) => &self.counters.failed,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        let Some(pool) = &self.pool else {
+            let mut schedules = self.schedules.write().await;
+            if let Some(schedule) = schedules.get_mut(&schedule_id) {
+                match outcome {
+                    Ok(execution_id) => {
+                        schedule.execution_ids.insert(0, execution_id);
+                        schedule.execution_ids.truncate(MAX_LISTED_RUNS);
+                        schedule.last_error = None;
+                    }
+                    Err(error) => schedule.last_error = Some(error),
+                }
+            }
+            return Ok(());
+        };
+        let last_error = match outcome {
+            Ok(execution_id) => {
+                sqlx::query(
+                    "INSERT INTO schedule_runs (schedule_id, execution_id, created_at) \
+                     VALUES ($1::uuid, $2::uuid, $3)",
+                )
+                .bind(schedule_id.to_string())
+                .bind(execution_id.to_string())
+                .bind(Utc::now())
+                .execute(pool)
+                .await?;
+                None
+            }
+            Err(error) => Some(error),
+        };
+        sqlx::query("UPDATE schedules SET last_error = $2 WHERE id = $1::uuid")
+            .bind(schedule_id.to_string())
+            .bind(last_error)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Scheduled runs in Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# TYPE syla_gateway_schedule_runs_total counter");
+        for (outcome, counter) in [("submitted", &self.counters.submitted), ("failed", &self.counters.failed)] {
+            let _ = writeln!(
+                out,
+                "syla_gateway_schedule_runs_total{{outcome=\"{}\"}} {}",
+                outcome,
+                counter.load(Ordering::Relaxed)
+            );
+        }
+        out
+    }
+}
+
+/// Schedule `request` for later; the execution request is validated now,
+/// with any upload inlined so runs don't depend on it
+async fn create_schedule(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Json(request): Json<CreateScheduleRequest>,
+) -> Result<Response, ApiError> {
+    let CreateScheduleRequest {
+        execution: mut execution_request,
+        run_at,
+        cron,
+    } = request;
+    state.inline_upload(&auth_context, &mut execution_request).await?;
+    let validation = state
+        .validate_execution(&auth_context, execution_request.clone())
+        .await?;
+    if let Some(diagnostic) = validation.diagnostics.into_iter().next() {
+        return Err(ApiError::BadRequest(diagnostic.message));
+    }
+
+    let limit = state.config().schedules.max_per_user;
+    if state.schedules().count(&auth_context.user_id).await? >= limit {
+        return Err(ApiError::Conflict(format!(
+            "At most {} schedules can be kept per user",
+            limit
+        )));
+    }
+    let schedule = Schedule::new(&auth_context, execution_request, run_at, cron).map_err(ApiError::BadRequest)?;
+    state.schedules().insert(&schedule).await?;
+
+    let location = format!("/v1/schedules/{}", schedule.id);
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(schedule)).into_response())
+}
+
+async fn list_schedules(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<Vec<Schedule>>, ApiError> {
+    Ok(Json(state.schedules().list(&auth_context.user_id).await?))
+}
+
+async fn get_schedule(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Schedule>, ApiError> {
+    let schedule = state
+        .schedules()
+        .get(id, &auth_context.user_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(schedule))
+}
+
+/// Stop a schedule; executions it already submitted are left alone
+async fn delete_schedule(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    if !state.schedules().delete(id, &auth_context.user_id).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn auth_context() -> AuthContext {
+        AuthContext {
+            user_id: "user-1".to_string(),
+            tenant_id: Some("tenant-1".to_string()),
+            token: "token".to_string(),
+            scopes: Vec::new(),
+            impersonated_by: None,
+        }
+    }
+
+    fn request() -> CreateExecutionRequest {
+        serde_json::from_value(serde_json::json!({ "language": "python", "code": "print(1)" })).unwrap()
+    }
+
+    #[test]
+    fn parse_cron_accepts_five_field_expressions() {
+        assert!(parse_cron("*/15 * * * *").is_ok());
+        assert!(parse_cron("0 9 * * 1-5").is_ok());
+    }
+
+    #[test]
+    fn parse_cron_rejects_invalid_expressions() {
+        let error = parse_cron("every minute").unwrap_err();
+        assert!(error.starts_with("Invalid cron expression 'every minute'"), "{}", error);
+        assert!(parse_cron("61 * * * *").is_err());
+        assert!(parse_cron("").is_err());
+    }
+
+    #[test]
+    fn schedules_need_exactly_one_of_run_at_and_cron() {
+        let run_at = Some(Utc::now() + Duration::hours(1));
+        let cron = Some("0 * * * *".to_string());
+        let both = Schedule::new(&auth_context(), request(), run_at, cron);
+        assert_eq!(both.unwrap_err(), "Set exactly one of run_at and cron");
+        let neither = Schedule::new(&auth_context(), request(), None, None);
+        assert_eq!(neither.unwrap_err(), "Set exactly one of run_at and cron");
+    }
+
+    #[test]
+    fn one_off_schedules_run_once_in_the_future() {
+        let past = Utc::now() - Duration::minutes(1);
+        let error = Schedule::new(&auth_context(), request(), Some(past), None).unwrap_err();
+        assert!(error.ends_with("is in the past"), "{}", error);
+
+        let run_at = Utc::now() + Duration::hours(1);
+        let schedule = Schedule::new(&auth_context(), request(), Some(run_at), None).unwrap();
+        assert_eq!(schedule.next_run_at, Some(run_at));
+        assert_eq!(schedule.language, "python");
+        assert_eq!(schedule.following(run_at), None);
+    }
+
+    #[test]
+    fn cron_schedules_run_on_each_match() {
+        let schedule = Schedule::new(&auth_context(), request(), None, Some("*/15 * * * *".to_string())).unwrap();
+        let next_run_at = schedule.next_run_at.unwrap();
+        assert!(next_run_at > schedule.created_at);
+        assert!(next_run_at <= schedule.created_at + Duration::minutes(15));
+
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 10, 7, 30).unwrap();
+        assert_eq!(
+            schedule.following(now),
+            Some(Utc.with_ymd_and_hms(2026, 10, 17, 10, 15, 0).unwrap())
+        );
+        // A run exactly on a match waits for the next one
+        let on_match = Utc.with_ymd_and_hms(2026, 10, 17, 10, 15, 0).unwrap();
+        assert_eq!(
+            schedule.following(on_match),
+            Some(Utc.with_ymd_and_hms(2026, 10, 17, 10, 30, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn schedules_run_as_their_creator() {
+        let run_at = Utc::now() + Duration::hours(1);
+        let schedule = Schedule::new(&auth_context(), request(), Some(run_at), None).unwrap();
+        let owner = schedule.owner();
+        assert_eq!(owner.user_id, "user-1");
+        assert_eq!(owner.tenant_id.as_deref(), Some("tenant-1"));
+        assert!(owner.token.is_empty());
+        assert!(owner.scopes.is_empty());
+    }
+
+    #[test]
+    fn invalid_cron_schedules_are_rejected() {
+        let error = Schedule::new(&auth_context(), request(), None, Some("nope".to_string())).unwrap_err();
+        assert!(error.starts_with("Invalid cron expression 'nope'"), "{}", error);
+    }
+}
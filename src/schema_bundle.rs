@@ -0,0 +1,156 @@
+use std::io::Write;
+use std::sync::{Arc, OnceLock};
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::build_info::{self, BuildInfo};
+use crate::compat::{CURRENT_SCHEMA_VERSION, LEGACY_SCHEMA_VERSION};
+use crate::config::{Config, MAX_REQUEST_BODY_BYTES};
+use crate::error::ERROR_CATALOG;
+use crate::openapi;
+use crate::response::accepts_encoding;
+use crate::state::{AppState, DEFAULT_LIST_LIMIT, MAX_LIST_LIMIT};
+
+/// Layout version of the bundle itself; bumped when fields are removed or change meaning
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// How long clients and caches may reuse a bundle without revalidating
+const BUNDLE_MAX_AGE_SECS: u64 = 3600;
+
+/// Everything an SDK needs to configure itself against this deployment
+#[derive(Serialize)]
+struct Bundle<'a> {
+    bundle_version: u32,
+    build: &'a BuildInfo,
+    schema_versions: SchemaVersions,
+    limits: Limits,
+    errors: Vec<ErrorEntry>,
+    openapi: Value,
+    /// Base64 of the public API's `FileDescriptorSet`
+    proto_descriptor_set: String,
+}
+
+#[derive(Serialize)]
+struct SchemaVersions {
+    legacy: u32,
+    current: u32,
+}
+
+#[derive(Serialize)]
+struct Limits {
+    max_request_body_bytes: usize,
+    max_upload_bytes: u64,
+    max_response_body_bytes: usize,
+    max_sync_wait_seconds: u64,
+    default_list_limit: usize,
+    max_list_limit: usize,
+    output_buffer_bytes: usize,
+    output_replay_bytes: usize,
+}
+
+#[derive(Serialize)]
+struct ErrorEntry {
+    code: &'static str,
+    http_status: u16,
+    description: &'static str,
+}
+
+/// The serialized bundle, its brotli encoding and its entity tag
+struct EncodedBundle {
+    json: Vec<u8>,
+    brotli: Vec<u8>,
+    etag: HeaderValue,
+}
+
+impl EncodedBundle {
+    fn build(config: &Config) -> Self {
+        let bundle = Bundle {
+            bundle_version: BUNDLE_VERSION,
+            build: build_info::build_info(),
+            schema_versions: SchemaVersions {
+                legacy: LEGACY_SCHEMA_VERSION,
+                current: CURRENT_SCHEMA_VERSION,
+            },
+            limits: Limits {
+                max_request_body_bytes: MAX_REQUEST_BODY_BYTES,
+                max_upload_bytes: config.uploads.max_bytes,
+                max_response_body_bytes: config.response.max_body_bytes,
+                max_sync_wait_seconds: config.sync_wait.max_wait.as_secs(),
+                default_list_limit: DEFAULT_LIST_LIMIT,
+                max_list_limit: MAX_LIST_LIMIT,
+                output_buffer_bytes: config.output_buffer.max_bytes,
+                output_replay_bytes: config.output_buffer.replay_bytes,
+            },
+            errors: ERROR_CATALOG
+                .iter()
+                .map(|(code, status, description)| ErrorEntry {
+                    code,
+                    http_status: status.as_u16(),
+                    description,
+                })
+                .collect(),
+            openapi: openapi::document(config),
+            proto_descriptor_set: base64::engine::general_purpose::STANDARD
+                .encode(build_info::proto_descriptor()),
+        };
+
+        let json = serde_json::to_vec(&bundle).expect("schema bundle serializes");
+        let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+        encoder.write_all(&json).expect("writing to a Vec can't fail");
+        let brotli = encoder.into_inner();
+        let etag = format!("\"{}\"", hex::encode(&Sha256::digest(&json)[..16]));
+
+        Self {
+            json,
+            brotli,
+            etag: HeaderValue::from_str(&etag).expect("hex entity tag is a valid header"),
+        }
+    }
+}
+
+/// The schema bundle, brotli-compressed for clients that accept it and
+/// revalidated by entity tag
+pub async fn schema_bundle_handler(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    static BUNDLE: OnceLock<EncodedBundle> = OnceLock::new();
+    let bundle = BUNDLE.get_or_init(|| EncodedBundle::build(state.config()));
+
+    let cache_control = HeaderValue::from_str(&format!("public, max-age={}", BUNDLE_MAX_AGE_SECS))
+        .expect("cache control is a valid header");
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|tags| tags.split(',').any(|tag| tag.trim() == bundle.etag || tag.trim() == "*"));
+    if not_modified {
+        return (
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, bundle.etag.clone()), (header::CACHE_CONTROL, cache_control)],
+        )
+            .into_response();
+    }
+
+    let accepts_brotli = accepts_encoding(&headers, "br");
+
+    let mut response = Response::new(Body::from(if accepts_brotli {
+        bundle.brotli.clone()
+    } else {
+        bundle.json.clone()
+    }));
+    let response_headers = response.headers_mut();
+    response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response_headers.insert(header::ETAG, bundle.etag.clone());
+    response_headers.insert(header::CACHE_CONTROL, cache_control);
+    response_headers.insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+    if accepts_brotli {
+        response_headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static("br"));
+    }
+    response
+}
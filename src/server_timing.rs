@@ -0,0 +1,220 @@
+use std::fmt::Write;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::Bytes,
+    extract::{Request, State},
+    http::{self, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use futures::future::BoxFuture;
+use http_body::{Body, Frame, SizeHint};
+use tower::{Layer, Service};
+
+use crate::state::AppState;
+
+tokio::task_local! {
+    static REQUEST_TIMINGS: Timings;
+}
+
+/// Header, and gRPC trailer, carrying the per-phase breakdown
+pub const SERVER_TIMING_HEADER: &str = "server-timing";
+
+/// A part of handling a request that is timed separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Validating the caller's token with the auth service
+    Auth,
+    /// Validating the request and resolving where it goes
+    Validation,
+    /// Unary calls to upstream services
+    Upstream,
+    /// Encoding the response body
+    Serialization,
+}
+
+impl Phase {
+    const ALL: [Phase; 4] = [Phase::Auth, Phase::Validation, Phase::Upstream, Phase::Serialization];
+
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Auth => "auth",
+            Phase::Validation => "validate",
+            Phase::Upstream => "upstream",
+            Phase::Serialization => "serialize",
+        }
+    }
+}
+
+/// Time spent in each phase of the request being handled
+#[derive(Clone, Default)]
+struct Timings(Arc<Mutex<Vec<(Phase, Duration)>>>);
+
+impl Timings {
+    /// `Server-Timing` value with one entry per phase that was entered,
+    /// durations summed, followed by the total
+    fn header_value(&self, total: Duration) -> Option<HeaderValue> {
+        let recorded = self.0.lock().unwrap();
+        let mut value = String::new();
+        for phase in Phase::ALL {
+            let spent: Vec<Duration> = recorded
+                .iter()
+                .filter(|(recorded, _)| *recorded == phase)
+                .map(|(_, spent)| *spent)
+                .collect();
+            if !spent.is_empty() {
+                let _ = write!(value, "{};dur={:.1}, ", phase.name(), millis(spent.iter().sum()));
+            }
+        }
+        let _ = write!(value, "total;dur={:.1}", millis(total));
+        HeaderValue::from_str(&value).ok()
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Add `spent` to `phase` of the request being handled. Outside a request,
+/// or with server timing disabled, this does nothing
+pub fn record(phase: Phase, spent: Duration) {
+    let _ = REQUEST_TIMINGS.try_with(|timings| timings.0.lock().unwrap().push((phase, spent)));
+}
+
+/// Run `f`, counting the time it takes towards `phase`
+pub fn measure<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let output = f();
+    record(phase, started.elapsed());
+    output
+}
+
+/// Run `future`, counting the time it takes towards `phase`
+pub async fn time<F: Future>(phase: Phase, future: F) -> F::Output {
+    let started = Instant::now();
+    let output = future.await;
+    record(phase, started.elapsed());
+    output
+}
+
+/// Report where the time on a REST request went in a `Server-Timing` header
+pub async fn annotate(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    if !state.config().response.server_timing {
+        return next.run(request).await;
+    }
+    let started = Instant::now();
+    let timings = Timings::default();
+    let mut response = REQUEST_TIMINGS.scope(timings.clone(), next.run(request)).await;
+    if let Some(value) = timings.header_value(started.elapsed()) {
+        response.headers_mut().insert(SERVER_TIMING_HEADER, value);
+    }
+    response
+}
+
+/// Tower layer reporting where the time on a gRPC call went in a
+/// `server-timing` trailer, or in the headers of trailers-only responses
+#[derive(Clone)]
+pub struct ServerTimingLayer {
+    enabled: bool,
+}
+
+impl ServerTimingLayer {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+}
+
+impl<S> Layer<S> for ServerTimingLayer {
+    type Service = ServerTimingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ServerTimingService {
+            inner,
+            enabled: self.enabled,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ServerTimingService<S> {
+    inner: S,
+    enabled: bool,
+}
+
+impl<S, B> Service<http::Request<B>> for ServerTimingService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<tonic::body::BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        if !self.enabled {
+            return Box::pin(self.inner.call(request));
+        }
+        let started = Instant::now();
+        let timings = Timings::default();
+        let call = REQUEST_TIMINGS.scope(timings.clone(), self.inner.call(request));
+        Box::pin(async move {
+            let mut response = call.await?;
+            // Errors raised before any message are sent with grpc-status in
+            // the headers and no trailers
+            if response.headers().contains_key("grpc-status") {
+                if let Some(value) = timings.header_value(started.elapsed()) {
+                    response.headers_mut().insert(SERVER_TIMING_HEADER, value);
+                }
+                return Ok(response);
+            }
+            Ok(response.map(|inner| tonic::body::boxed(TimedBody { inner, timings, started })))
+        })
+    }
+}
+
+/// Response body that adds the timings to the trailers once the last
+/// message has been sent
+struct TimedBody {
+    inner: tonic::body::BoxBody,
+    timings: Timings,
+    started: Instant,
+}
+
+impl Body for TimedBody {
+    type Data = Bytes;
+    type Error = tonic::Status;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        match ready!(Pin::new(&mut self.inner).poll_frame(cx)) {
+            Some(Ok(mut frame)) => {
+                if let Some(trailers) = frame.trailers_mut() {
+                    if let Some(value) = self.timings.header_value(self.started.elapsed()) {
+                        trailers.insert(SERVER_TIMING_HEADER, value);
+                    }
+                }
+                Poll::Ready(Some(Ok(frame)))
+            }
+            other => Poll::Ready(other),
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,332 @@
+use axum::{
+    extract::State,
+    middleware,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::audit::{self, AuditEvent, AuditOutcome};
+use crate::auth::{self, AuthContext, AuthInterceptor, ADMIN_SCOPE, SETTINGS_SCOPE};
+use crate::config::ResourceCapsConfig;
+use crate::error::ApiError;
+use crate::execution::{CreateExecutionRequest, IsolationMode, ResourceLimits, Warning};
+use crate::state::AppState;
+
+/// Per-tenant execution default routes, authenticated and scoped to the caller's tenant
+pub fn routes(auth_interceptor: AuthInterceptor) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/v1/settings/executions",
+            get(get_settings).put(put_settings),
+        )
+        .route("/v1/settings/executions/preview", post(preview_settings))
+        .route_layer(middleware::from_fn_with_state(auth_interceptor, auth::require_auth))
+}
+
+/// Defaults a tenant applies to every execution it submits
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutionSettings {
+    /// Used when a request sets no timeout
+    pub timeout_seconds: Option<u64>,
+    /// Upper bound on any request's timeout, including the executor's default
+    pub max_timeout_seconds: Option<u64>,
+    /// Filled in field by field where a request leaves resources unset
+    pub resources: Option<ResourceLimits>,
+    /// Merged under a request's environment; request keys win
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    pub mode: Option<IsolationMode>,
+    /// Combined stdout and stderr bytes an execution may produce before the
+    /// gateway cancels it
+    pub max_output_bytes: Option<u64>,
+    /// Base URL of the tenant's linked bucket, which `tenant_bucket` result
+    /// destinations write under; it must accept `PUT`s from the gateway
+    pub result_bucket_url: Option<String>,
+    #[serde(default, skip_deserializing)]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_deserializing)]
+    pub updated_by: Option<String>,
+}
+
+impl ExecutionSettings {
+    fn validate(&self) -> Result<(), ApiError> {
+        if let (Some(timeout), Some(max)) = (self.timeout_seconds, self.max_timeout_seconds) {
+            if timeout > max {
+                return Err(ApiError::BadRequest(format!(
+                    "Default timeout {}s exceeds the maximum of {}s",
+                    timeout, max
+                )));
+            }
+        }
+        if self.timeout_seconds == Some(0) || self.max_timeout_seconds == Some(0) {
+            return Err(ApiError::BadRequest("Timeouts must be at least one second".to_string()));
+        }
+        if self.max_output_bytes == Some(0) {
+            return Err(ApiError::BadRequest("max_output_bytes must be at least one byte".to_string()));
+        }
+        if let Some(resources) = &self.resources {
+            resources
+                .validate(&ResourceCapsConfig::default())
+                .map_err(ApiError::BadRequest)?;
+        }
+        if let Some(url) = &self.result_bucket_url {
+            let valid = reqwest::Url::parse(url)
+                .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.query().is_none());
+            if !valid {
+                return Err(ApiError::BadRequest(
+                    "result_bucket_url must be an HTTP(S) URL without a query string".to_string(),
+                ));
+            }
+        }
+        if self.env.keys().any(|key| key.is_empty()) {
+            return Err(ApiError::BadRequest("Environment variable names must not be empty".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Effective settings for `requested`; the request wins over tenant
+    /// defaults, which win over the executor's, and the tenant maximum
+    /// caps whatever timeout results
+    pub fn resolve(&self, requested: &RequestedSettings) -> EffectiveSettings {
+        let mut sources = BTreeMap::new();
+
+        let mut timeout_seconds = pick(
+            &mut sources,
+            "timeout_seconds",
+            requested.timeout_seconds,
+            self.timeout_seconds,
+        );
+        if let Some(max) = self.max_timeout_seconds {
+            if timeout_seconds.is_none_or(|timeout| timeout > max) {
+                timeout_seconds = Some(max);
+                sources.insert("timeout_seconds".to_string(), SettingSource::TenantMax);
+            }
+        }
+
+        let mode = pick(&mut sources, "mode", requested.mode, self.mode);
+
+        let requested_resources = requested.resources.clone().unwrap_or_default();
+        let tenant_resources = self.resources.clone().unwrap_or_default();
+        let resources = ResourceLimits {
+            memory_mb: pick(
+                &mut sources,
+                "resources.memory_mb",
+                requested_resources.memory_mb,
+                tenant_resources.memory_mb,
+            ),
+            cpu_cores: pick(
+                &mut sources,
+                "resources.cpu_cores",
+                requested_resources.cpus(),
+                tenant_resources.cpus(),
+            ),
+            cpu_millis: None,
+            disk_mb: pick(
+                &mut sources,
+                "resources.disk_mb",
+                requested_resources.disk_mb,
+                tenant_resources.disk_mb,
+            ),
+            enable_network: pick(
+                &mut sources,
+                "resources.enable_network",
+                requested_resources.enable_network,
+                tenant_resources.enable_network,
+            ),
+        };
+
+        let mut env = self.env.clone();
+        for key in self.env.keys() {
+            sources.insert(format!("env.{}", key), SettingSource::Tenant);
+        }
+        for (key, value) in requested.env.iter().flatten() {
+            env.insert(key.clone(), value.clone());
+            sources.insert(format!("env.{}", key), SettingSource::Request);
+        }
+
+        EffectiveSettings {
+            timeout_seconds,
+            resources: (resources != ResourceLimits::default()).then_some(resources),
+            env,
+            mode,
+            sources,
+        }
+    }
+}
+
+/// Take the requested value if set, else the tenant default, noting which was used
+fn pick<T>(
+    sources: &mut BTreeMap<String, SettingSource>,
+    field: &str,
+    requested: Option<T>,
+    tenant: Option<T>,
+) -> Option<T> {
+    let (value, source) = match (requested, tenant) {
+        (Some(value), _) => (Some(value), SettingSource::Request),
+        (None, Some(value)) => (Some(value), SettingSource::Tenant),
+        (None, None) => (None, SettingSource::Executor),
+    };
+    sources.insert(field.to_string(), source);
+    value
+}
+
+/// The settings-related fields of an execution request
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RequestedSettings {
+    pub timeout_seconds: Option<u64>,
+    pub resources: Option<ResourceLimits>,
+    pub env: Option<HashMap<String, String>>,
+    pub mode: Option<IsolationMode>,
+}
+
+impl From<&CreateExecutionRequest> for RequestedSettings {
+    fn from(request: &CreateExecutionRequest) -> Self {
+        Self {
+            timeout_seconds: request.timeout_seconds,
+            resources: request.resources.clone(),
+            env: request.env.clone(),
+            mode: request.mode,
+        }
+    }
+}
+
+/// Where an effective setting came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingSource {
+    Request,
+    Tenant,
+    /// Clamped to the tenant's maximum
+    TenantMax,
+    /// Left unset for the executor to decide
+    Executor,
+}
+
+/// Settings an execution will actually run with, and where each came from
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveSettings {
+    pub timeout_seconds: Option<u64>,
+    pub resources: Option<ResourceLimits>,
+    pub env: HashMap<String, String>,
+    pub mode: Option<IsolationMode>,
+    pub sources: BTreeMap<String, SettingSource>,
+}
+
+impl EffectiveSettings {
+    /// `request` with its settings replaced by these
+    pub fn apply_to(self, mut request: CreateExecutionRequest) -> CreateExecutionRequest {
+        request.timeout_seconds = self.timeout_seconds;
+        request.resources = self.resources;
+        request.env = (!self.env.is_empty()).then_some(self.env);
+        request.mode = self.mode;
+        request
+    }
+}
+
+/// Execution defaults by tenant ID
+#[derive(Default)]
+pub struct TenantSettings {
+    settings: RwLock<HashMap<String, ExecutionSettings>>,
+}
+
+impl TenantSettings {
+    pub async fn get(&self, tenant_id: &str) -> Option<ExecutionSettings> {
+        self.settings.read().await.get(tenant_id).cloned()
+    }
+
+    pub async fn put(&self, tenant_id: &str, settings: ExecutionSettings) {
+        self.settings.write().await.insert(tenant_id.to_string(), settings);
+    }
+
+    /// The output cap for executions of `tenant_id`, if it set one
+    pub async fn output_limit(&self, tenant_id: Option<&str>) -> Option<u64> {
+        self.settings.read().await.get(tenant_id?)?.max_output_bytes
+    }
+
+    /// Merge the caller's tenant defaults into `request`, with a warning for
+    /// each requested value the defaults overrode
+    pub async fn apply(
+        &self,
+        auth_context: &AuthContext,
+        request: CreateExecutionRequest,
+    ) -> (CreateExecutionRequest, Vec<Warning>) {
+        let Some(tenant_id) = auth_context.tenant_id.as_deref() else {
+            return (request, Vec::new());
+        };
+        let settings = self.settings.read().await;
+        let Some(settings) = settings.get(tenant_id) else {
+            return (request, Vec::new());
+        };
+
+        let mut warnings = Vec::new();
+        let effective = settings.resolve(&RequestedSettings::from(&request));
+        if let (Some(requested), Some(max)) = (request.timeout_seconds, settings.max_timeout_seconds) {
+            if requested > max {
+                warnings.push(Warning::new(
+                    "timeout_clamped",
+                    format!("Timeout of {}s clamped to the tenant maximum of {}s", requested, max),
+                ));
+            }
+        }
+        (effective.apply_to(request), warnings)
+    }
+}
+
+fn caller_tenant(auth_context: &AuthContext) -> Result<&str, ApiError> {
+    auth_context
+        .tenant_id
+        .as_deref()
+        .ok_or_else(|| ApiError::BadRequest("No tenant is associated with this caller".to_string()))
+}
+
+async fn get_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<ExecutionSettings>, ApiError> {
+    let tenant_id = caller_tenant(&auth_context)?;
+    Ok(Json(state.tenant_settings().get(tenant_id).await.unwrap_or_default()))
+}
+
+async fn put_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Json(mut settings): Json<ExecutionSettings>,
+) -> Result<Json<ExecutionSettings>, ApiError> {
+    let tenant_id = caller_tenant(&auth_context)?;
+    let event = AuditEvent::new("settings.executions.update", &auth_context.user_id, AuditOutcome::Allowed)
+        .tenant(Some(tenant_id));
+
+    if !auth_context.has_scope(SETTINGS_SCOPE) && !auth_context.has_scope(ADMIN_SCOPE) {
+        audit::record(AuditEvent {
+            outcome: AuditOutcome::Denied,
+            ..event
+        });
+        return Err(ApiError::Forbidden(format!("Requires the {} scope", SETTINGS_SCOPE)));
+    }
+    settings.validate()?;
+
+    settings.updated_at = Some(Utc::now());
+    settings.updated_by = Some(auth_context.user_id.clone());
+    state.tenant_settings().put(tenant_id, settings.clone()).await;
+    audit::record(event);
+
+    Ok(Json(settings))
+}
+
+/// What a request with these settings would run with under the caller's tenant defaults
+async fn preview_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Json(requested): Json<RequestedSettings>,
+) -> Json<EffectiveSettings> {
+    let settings = match auth_context.tenant_id.as_deref() {
+        Some(tenant_id) => state.tenant_settings().get(tenant_id).await.unwrap_or_default(),
+        None => ExecutionSettings::default(),
+    };
+    Json(settings.resolve(&requested))
+}
@@ -0,0 +1,198 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::clients::execution::{self, ExecutionClient};
+use crate::config::{ShadowConfig, UpstreamConfig};
+use crate::execution::{CreateExecutionRequest, ExecutionResponse, ExecutionStatus};
+
+/// Divergences kept for inspection; older ones are only in the logs
+const MAX_RECENT_DIVERGENCES: usize = 100;
+
+/// A mirrored execution whose result differed from the one served
+#[derive(Debug, Clone, Serialize)]
+pub struct Divergence {
+    pub execution_id: Uuid,
+    pub shadow_execution_id: Uuid,
+    /// Result fields that differed
+    pub fields: Vec<&'static str>,
+    pub primary_status: ExecutionStatus,
+    pub shadow_status: ExecutionStatus,
+    pub detected_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct ShadowCounters {
+    mirrored: AtomicU64,
+    matched: AtomicU64,
+    diverged: AtomicU64,
+    failed: AtomicU64,
+}
+
+/// Mirrors a sample of submissions to a secondary execution backend and
+/// compares results, without the secondary affecting what clients see
+pub struct ShadowTraffic {
+    client: Arc<RwLock<ExecutionClient>>,
+    config: ShadowConfig,
+    counters: ShadowCounters,
+    recent: Mutex<VecDeque<Divergence>>,
+}
+
+impl ShadowTraffic {
+    /// Connect to the shadow backend, if one is configured
+    pub async fn connect(config: &ShadowConfig, upstream: &UpstreamConfig) -> Result<Option<Arc<Self>>> {
+        let Some(url) = &config.url else {
+            return Ok(None);
+        };
+        let client = ExecutionClient::new(&upstream.with_url(url, config.protocol)).await?;
+        info!(
+            "Mirroring {}% of executions to shadow execution service at {}",
+            config.sample_rate * 100.0,
+            url
+        );
+        Ok(Some(Arc::new(Self {
+            client: Arc::new(RwLock::new(client)),
+            config: config.clone(),
+            counters: ShadowCounters::default(),
+            recent: Mutex::new(VecDeque::new()),
+        })))
+    }
+
+    /// Decide whether the current submission should be mirrored
+    pub fn should_mirror(&self) -> bool {
+        if self.config.sample_rate <= 0.0 {
+            return false;
+        }
+        let roll = (Uuid::new_v4().as_u128() % 1_000_000) as f64 / 1_000_000.0;
+        roll < self.config.sample_rate
+    }
+
+    /// Submit `request` to the shadow backend in the background and compare its
+    /// result with execution `primary_id` on `primary` once both finish
+    pub fn mirror(
+        self: &Arc<Self>,
+        primary: Arc<RwLock<ExecutionClient>>,
+        primary_id: Uuid,
+        user_id: String,
+        workspace_id: Option<String>,
+        request: CreateExecutionRequest,
+    ) {
+        let shadow = self.clone();
+        tokio::spawn(async move {
+            shadow.counters.mirrored.fetch_add(1, Ordering::Relaxed);
+            let submitted = shadow
+                .client
+                .read()
+                .await
+                .create_execution(user_id, workspace_id, request)
+                .await;
+            let shadow_id = match submitted {
+                Ok(execution) => execution.id,
+                Err(e) => {
+                    shadow.counters.failed.fetch_add(1, Ordering::Relaxed);
+                    warn!("Failed to mirror execution {} to the shadow backend: {}", primary_id, e);
+                    return;
+                }
+            };
+
+            let deadline = Instant::now() + shadow.config.result_timeout;
+            let (primary_result, shadow_result) = tokio::join!(
+                execution::wait_for_result(&primary, primary_id, shadow.config.poll_interval, deadline),
+                execution::wait_for_result(&shadow.client, shadow_id, shadow.config.poll_interval, deadline),
+            );
+            match (primary_result, shadow_result) {
+                (Ok(primary), Ok(mirrored)) => shadow.compare(&primary, &mirrored),
+                (Err(e), _) | (_, Err(e)) => {
+                    shadow.counters.failed.fetch_add(1, Ordering::Relaxed);
+                    warn!(
+                        "Could not compare execution {} with shadow execution {}: {}",
+                        primary_id, shadow_id, e
+                    );
+                }
+            }
+        });
+    }
+
+    fn compare(&self, primary: &ExecutionResponse, shadow: &ExecutionResponse) {
+        let fields = diverging_fields(primary, shadow);
+        if fields.is_empty() {
+            self.counters.matched.fetch_add(1, Ordering::Relaxed);
+            debug!("Shadow execution {} matched execution {}", shadow.id, primary.id);
+            return;
+        }
+
+        self.counters.diverged.fetch_add(1, Ordering::Relaxed);
+        warn!(
+            execution_id = %primary.id,
+            shadow_execution_id = %shadow.id,
+            fields = ?fields,
+            "Shadow execution diverged from the primary result"
+        );
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() >= MAX_RECENT_DIVERGENCES {
+            recent.pop_front();
+        }
+        recent.push_back(Divergence {
+            execution_id: primary.id,
+            shadow_execution_id: shadow.id,
+            fields,
+            primary_status: primary.status.clone(),
+            shadow_status: shadow.status.clone(),
+            detected_at: Utc::now(),
+        });
+    }
+
+    /// Recorded divergences, newest first
+    pub fn divergences(&self) -> Vec<Divergence> {
+        self.recent.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    /// Mirroring outcomes in Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let series = [
+            ("syla_gateway_shadow_executions_total", &self.counters.mirrored),
+            ("syla_gateway_shadow_matches_total", &self.counters.matched),
+            ("syla_gateway_shadow_divergences_total", &self.counters.diverged),
+            ("syla_gateway_shadow_failures_total", &self.counters.failed),
+        ];
+        for (name, counter) in series {
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, counter.load(Ordering::Relaxed));
+        }
+        out
+    }
+}
+
+/// Names of the result fields that differ between two finished executions
+fn diverging_fields(primary: &ExecutionResponse, shadow: &ExecutionResponse) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    if primary.status != shadow.status {
+        fields.push("status");
+    }
+    match (&primary.result, &shadow.result) {
+        (Some(primary), Some(shadow)) => {
+            if primary.exit_code != shadow.exit_code {
+                fields.push("exit_code");
+            }
+            if primary.stdout != shadow.stdout {
+                fields.push("stdout");
+            }
+            if primary.stderr != shadow.stderr {
+                fields.push("stderr");
+            }
+        }
+        (None, None) => {}
+        _ => fields.push("result"),
+    }
+    fields
+}
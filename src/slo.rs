@@ -0,0 +1,256 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+
+use crate::config::SloConfig;
+use crate::state::AppState;
+
+/// Windows burn rates are reported over, as (label, minutes)
+const WINDOWS: &[(&str, u64)] = &[("5m", 5), ("30m", 30), ("1h", 60), ("6h", 360)];
+
+/// Minutes of history kept per route, enough for the longest window
+const HISTORY_MINUTES: u64 = 360;
+
+/// Window the remaining error budget is reported over
+const BUDGET_WINDOW: &str = "6h";
+
+/// Multiwindow alert rules: (severity, long window, short window); both must
+/// burn faster than the severity's threshold, so alerts fire quickly and
+/// clear as soon as the burn stops
+const ALERTS: &[(&str, &str, &str)] = &[("page", "1h", "5m"), ("ticket", "6h", "30m")];
+
+#[derive(Clone, Copy, Default)]
+struct MinuteCounts {
+    minute: u64,
+    total: u64,
+    errors: u64,
+    slow: u64,
+}
+
+/// Objectives of one route and its recent outcomes, one slot per minute
+struct RouteSlo {
+    availability: Option<f64>,
+    latency: Option<(Duration, f64)>,
+    history: Mutex<Vec<MinuteCounts>>,
+}
+
+/// Compliance of one objective over the recent windows
+#[derive(Debug, Clone, Serialize)]
+pub struct SloStatus {
+    pub route: String,
+    /// `availability` or `latency`
+    pub objective: &'static str,
+    /// Fraction of requests that must be good
+    pub target: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_threshold_ms: Option<u64>,
+    /// How fast the error budget is being spent per window; 1 spends it
+    /// exactly over the objective's period
+    pub burn_rates: BTreeMap<&'static str, f64>,
+    /// Share of the error budget left over the last 6h; negative once overspent
+    pub error_budget_remaining: f64,
+    /// `page` or `ticket` while an alert rule is firing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alert: Option<&'static str>,
+}
+
+/// Request outcomes for routes with objectives, and the error budget burn
+/// derived from them, so alerts can key off gauges instead of recording rules
+pub struct SloTracker {
+    routes: HashMap<String, RouteSlo>,
+    page_burn_rate: f64,
+    ticket_burn_rate: f64,
+    started: Instant,
+}
+
+impl SloTracker {
+    pub fn new(config: &SloConfig) -> Self {
+        let mut routes: HashMap<String, RouteSlo> = HashMap::new();
+        let empty = || RouteSlo {
+            availability: None,
+            latency: None,
+            history: Mutex::new(vec![MinuteCounts::default(); HISTORY_MINUTES as usize]),
+        };
+        for (route, target) in &config.availability {
+            routes.entry(route.clone()).or_insert_with(empty).availability = Some(*target);
+        }
+        for (route, objective) in &config.latency {
+            routes.entry(route.clone()).or_insert_with(empty).latency = Some(*objective);
+        }
+        Self {
+            routes,
+            page_burn_rate: config.page_burn_rate,
+            ticket_burn_rate: config.ticket_burn_rate,
+            started: Instant::now(),
+        }
+    }
+
+    /// Whether `route` has any objective
+    pub fn tracks(&self, route: &str) -> bool {
+        self.routes.contains_key(route)
+    }
+
+    fn current_minute(&self) -> u64 {
+        self.started.elapsed().as_secs() / 60
+    }
+
+    /// Record one completed request to `route`
+    pub fn record(&self, route: &str, failed: bool, latency: Duration) {
+        let Some(slo) = self.routes.get(route) else {
+            return;
+        };
+        let minute = self.current_minute();
+        let slow = slo.latency.is_some_and(|(threshold, _)| latency > threshold);
+        let mut history = slo.history.lock().unwrap();
+        let slot = &mut history[(minute % HISTORY_MINUTES) as usize];
+        if slot.minute != minute || slot.total == 0 {
+            *slot = MinuteCounts {
+                minute,
+                ..Default::default()
+            };
+        }
+        slot.total += 1;
+        slot.errors += u64::from(failed);
+        slot.slow += u64::from(slow);
+    }
+
+    /// Current status of every objective, ordered by route
+    pub fn report(&self) -> Vec<SloStatus> {
+        let now = self.current_minute();
+        let mut statuses = Vec::new();
+        let mut routes: Vec<_> = self.routes.iter().collect();
+        routes.sort_by(|a, b| a.0.cmp(b.0));
+        for (route, slo) in routes {
+            let history = slo.history.lock().unwrap().clone();
+            // Totals over each window as (requests, errors, slow)
+            let totals: BTreeMap<&'static str, (u64, u64, u64)> = WINDOWS
+                .iter()
+                .map(|(label, minutes)| {
+                    let totals = history
+                        .iter()
+                        .filter(|slot| slot.total > 0 && slot.minute <= now && now - slot.minute < *minutes)
+                        .fold((0, 0, 0), |acc, slot| (acc.0 + slot.total, acc.1 + slot.errors, acc.2 + slot.slow));
+                    (*label, totals)
+                })
+                .collect();
+            if let Some(target) = slo.availability {
+                let bad = totals.iter().map(|(label, (total, errors, _))| (*label, (*total, *errors))).collect();
+                statuses.push(self.status(route, "availability", target, None, bad));
+            }
+            if let Some((threshold, target)) = slo.latency {
+                let bad = totals.iter().map(|(label, (total, _, slow))| (*label, (*total, *slow))).collect();
+                statuses.push(self.status(route, "latency", target, Some(threshold), bad));
+            }
+        }
+        statuses
+    }
+
+    fn status(
+        &self,
+        route: &str,
+        objective: &'static str,
+        target: f64,
+        threshold: Option<Duration>,
+        bad: BTreeMap<&'static str, (u64, u64)>,
+    ) -> SloStatus {
+        let budget = 1.0 - target;
+        let burn_rates: BTreeMap<&'static str, f64> = bad
+            .into_iter()
+            .map(|(label, (total, bad))| {
+                let rate = if total == 0 { 0.0 } else { bad as f64 / total as f64 / budget };
+                (label, rate)
+            })
+            .collect();
+        let firing = |severity: &str, threshold: f64| {
+            ALERTS.iter().any(|(s, long, short)| {
+                *s == severity && burn_rates[long] > threshold && burn_rates[short] > threshold
+            })
+        };
+        let alert = if firing("page", self.page_burn_rate) {
+            Some("page")
+        } else if firing("ticket", self.ticket_burn_rate) {
+            Some("ticket")
+        } else {
+            None
+        };
+        SloStatus {
+            route: route.to_string(),
+            objective,
+            target,
+            latency_threshold_ms: threshold.map(|t| t.as_millis() as u64),
+            error_budget_remaining: 1.0 - burn_rates[BUDGET_WINDOW],
+            burn_rates,
+            alert,
+        }
+    }
+
+    /// Burn rates, remaining budgets and firing alerts in Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        if self.routes.is_empty() {
+            return out;
+        }
+        let statuses = self.report();
+        let _ = writeln!(out, "# HELP syla_gateway_slo_burn_rate Error budget burn rate per objective and window");
+        let _ = writeln!(out, "# TYPE syla_gateway_slo_burn_rate gauge");
+        for status in &statuses {
+            for (window, rate) in &status.burn_rates {
+                let _ = writeln!(
+                    out,
+                    "syla_gateway_slo_burn_rate{{route=\"{}\",objective=\"{}\",window=\"{}\"}} {}",
+                    status.route, status.objective, window, rate
+                );
+            }
+        }
+        let _ = writeln!(
+            out,
+            "# HELP syla_gateway_slo_error_budget_remaining Share of the error budget left over the last {}",
+            BUDGET_WINDOW
+        );
+        let _ = writeln!(out, "# TYPE syla_gateway_slo_error_budget_remaining gauge");
+        for status in &statuses {
+            let _ = writeln!(
+                out,
+                "syla_gateway_slo_error_budget_remaining{{route=\"{}\",objective=\"{}\"}} {}",
+                status.route, status.objective, status.error_budget_remaining
+            );
+        }
+        let _ = writeln!(out, "# HELP syla_gateway_slo_alerting Whether a burn rate alert is firing");
+        let _ = writeln!(out, "# TYPE syla_gateway_slo_alerting gauge");
+        for status in &statuses {
+            for (severity, _, _) in ALERTS {
+                let _ = writeln!(
+                    out,
+                    "syla_gateway_slo_alerting{{route=\"{}\",objective=\"{}\",severity=\"{}\"}} {}",
+                    status.route,
+                    status.objective,
+                    severity,
+                    u8::from(status.alert == Some(*severity))
+                );
+            }
+        }
+        out
+    }
+}
+
+/// Record the outcome and latency of REST requests to routes with objectives
+pub async fn track_rest(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let route = crate::ratelimit::route_key(&request);
+    if !state.slo_tracker().tracks(&route) {
+        return next.run(request).await;
+    }
+    let started = Instant::now();
+    let response = next.run(request).await;
+    state
+        .slo_tracker()
+        .record(&route, response.status().is_server_error(), started.elapsed());
+    response
+}
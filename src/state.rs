@@ -1,81 +1,2910 @@
-use crate::clients::execution::ExecutionClient;
+use crate::alerts::Alerter;
+use crate::archive::PayloadArchive;
+use crate::artifacts::ArtifactStore;
+use crate::audit::{self, AuditEvent, AuditOutcome};
+use crate::auth::{AuthContext, ADMIN_SCOPE, GRADER_SCOPE};
+use crate::bulk::{BulkOutcome, BulkReport, BulkRequest, BULK_CONCURRENCY};
+use crate::canary::{self, Backend};
+use crate::cache::{CachedExecution, ExecutionMeta};
+use crate::clients::execution::{ExecutionClient, ExecutionPage, PoolResize, UpstreamEvent, UpstreamListQuery};
+use crate::clients::workspace::WorkspaceClient;
+use crate::clients::ChannelStats;
+use crate::concurrency::ConcurrencyGroups;
+use crate::config::Config;
+use crate::cors::{CorsPolicy, CorsPolicyChange, TenantOrigins};
+use crate::timeline::ExecutionTimelines;
+use crate::delivery::{self, ResultDeliveries, ResultTarget};
 use crate::error::ApiError;
-use crate::execution::{CreateExecutionRequest, ExecutionResponse, ExecutionStatus};
+use crate::execution::{
+    Annotation, AnnotationPatch, CancelCause, Cancellation, CreateExecutionRequest, Diagnostic, ExecutionResponse,
+    ExecutionStatus,
+    ExecutionUpdate, ExecutionValidation, IsolationMode, ResubmitOverrides, ResultDestination, Tag, Warning,
+    sha256_hex,
+};
+use crate::events::{
+    self, CacheInvalidation, EventBus, ExecutionEvent, CACHE_INVALIDATION_TOPIC, CORS_POLICIES_TOPIC,
+    EXECUTION_EVENTS_TOPIC, METERING_TOPIC, WATCHDOG_ALERTS_TOPIC,
+};
+use crate::export::ExportJobs;
+use crate::uploads::Uploads;
+use crate::health::{self, HealthReport, Probe};
+use crate::admission::AdmissionBudget;
+use crate::inflight::InflightTracker;
+use crate::proxy::UpstreamProxy;
+use crate::ratelimit::RateLimiter;
+use crate::read_only::ReadOnlyMode;
+use crate::records::ExecutionRecords;
+use crate::grpc_cache::GrpcExecutionCache;
+use crate::languages::LanguageCatalog;
+use crate::leader::{self, LeaderElector};
+use crate::metering::{self, MeteringEvent};
+use crate::metrics::Metrics;
+use crate::outbox::{Outbox, OutboxEntry, OutboxKind};
+use crate::output::{OutputBuffers, OutputEvent, OutputStart, OutputSubscription, Offsets};
+use crate::pipeline::{self, Pipelines};
+use crate::schedule::{Schedule, Schedules};
+use crate::server_timing::{self, Phase};
+use crate::settings::TenantSettings;
+use crate::slo::SloTracker;
+use crate::trace::TraceContext;
+use crate::shadow::ShadowTraffic;
+use crate::watchdog::{StuckExecution, Watchdog};
+use crate::webhook::Webhooks;
 use anyhow::Result;
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use chrono::{DateTime, Utc};
+use futures::future::{BoxFuture, FutureExt, Shared};
+use futures::stream::{BoxStream, StreamExt};
+use reqwest::Url;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tracing::{info, warn};
 use uuid::Uuid;
 
+/// Executions listed per page when the caller doesn't say
+pub const DEFAULT_LIST_LIMIT: usize = 50;
+/// Most executions listed per page
+pub const MAX_LIST_LIMIT: usize = 500;
+
+/// Criteria for listing a user's executions
+#[derive(Debug, Clone)]
+pub struct ExecutionFilter {
+    pub status: Option<ExecutionStatus>,
+    pub pinned: Option<bool>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub session_id: Option<String>,
+    pub group_id: Option<String>,
+    /// Metadata entries an execution must carry, all of them
+    pub tags: Vec<Tag>,
+    pub limit: usize,
+}
+
+impl ExecutionFilter {
+    fn matches(&self, cached: &CachedExecution) -> bool {
+        let meta = cached.meta();
+        self.pinned.is_none_or(|pinned| pinned == meta.pinned)
+            && self.status.as_ref().is_none_or(|status| cached.status() == status)
+            && self.created_after.is_none_or(|after| cached.created_at() >= after)
+            && self.created_before.is_none_or(|before| cached.created_at() < before)
+            && self
+                .session_id
+                .as_deref()
+                .is_none_or(|session| meta.session_id() == Some(session))
+            && self
+                .group_id
+                .as_deref()
+                .is_none_or(|group| meta.group_id() == Some(group))
+            && (self.tags.is_empty()
+                || meta
+                    .metadata()
+                    .is_some_and(|metadata| Tag::all_match(&self.tags, metadata)))
+    }
+
+    /// Whether `cached` belongs in a listing of `user_id`'s executions: owned
+    /// by them, not deleted, and matching the filter
+    fn lists(&self, cached: &CachedExecution, user_id: &str) -> bool {
+        let meta = cached.meta();
+        !meta.is_deleted() && meta.is_owned_by(user_id) && self.matches(cached)
+    }
+}
+
+/// A request checked and resolved for submission, not yet sent upstream
+struct PreparedSubmission {
+    /// As the caller sent it, for resubmission
+    original: CreateExecutionRequest,
+    /// With tenant defaults merged in
+    request: CreateExecutionRequest,
+    warnings: Vec<Warning>,
+    result_target: Option<ResultTarget>,
+    callback_url: Option<Url>,
+    backend: Backend,
+    language_recognized: bool,
+    /// Reasons the request would be rejected; nothing is submitted unless empty
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Earlier execution a submission repeats
+#[derive(Debug, Clone, Copy)]
+enum Lineage {
+    /// Resubmitted with overrides
+    Resubmitted(Uuid),
+    /// Retried as originally requested after failing
+    Retried(Uuid),
+}
+
+/// Record a rejection of `field` as a diagnostic, passing other failures on
+fn diagnose<T>(
+    result: Result<T, ApiError>,
+    field: &str,
+    code: &'static str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Option<T>, ApiError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(ApiError::BadRequest(message)) => {
+            diagnostics.push(Diagnostic::new(field, code, message));
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes to the stdin of one execution on the backend running it
+pub struct StdinWriter {
+    client: Arc<RwLock<ExecutionClient>>,
+    id: Uuid,
+}
+
+impl StdinWriter {
+    pub async fn write(&self, data: Vec<u8>, close: bool) -> Result<(), ApiError> {
+        self.client.read().await.write_stdin(self.id, data, close).await
+    }
+}
+
+/// An upstream GetExecution shared by every concurrent reader of the same ID
+type SharedFetch = Shared<BoxFuture<'static, Arc<Result<ExecutionResponse, ApiError>>>>;
+
 pub struct AppState {
     execution_client: Arc<RwLock<ExecutionClient>>,
+    /// Alternate execution service a share of executions is routed to
+    canary_client: Option<Arc<RwLock<ExecutionClient>>>,
+    /// Secondary execution service a sample of submissions is mirrored to
+    shadow: Option<Arc<ShadowTraffic>>,
+    /// Set when the workspaces surface is enabled
+    workspace_client: Option<WorkspaceClient>,
+    upstream_proxy: Option<Arc<UpstreamProxy>>,
+    artifact_store: Option<ArtifactStore>,
     // In-memory cache for MVP (will be Redis later)
-    executions: Arc<RwLock<HashMap<Uuid, ExecutionResponse>>>,
+    executions: Arc<RwLock<HashMap<Uuid, CachedExecution>>>,
+    /// Ownership, pins and soft deletes, shared by every replica through the SQL store
+    records: ExecutionRecords,
+    // Upstream fetches in progress, so polling storms on one ID coalesce into one call
+    execution_fetches: Mutex<HashMap<Uuid, SharedFetch>>,
+    /// Executions purged here and when, so reads don't fetch them back from
+    /// an execution service that can't delete them; kept for the retention
+    /// period, or the purge window when executions are kept indefinitely
+    purged: Mutex<HashMap<Uuid, DateTime<Utc>>>,
+    payload_archive: PayloadArchive,
+    exports: ExportJobs,
+    uploads: Uploads,
+    tenant_settings: TenantSettings,
+    tenant_origins: TenantOrigins,
+    output_buffers: OutputBuffers,
+    timelines: ExecutionTimelines,
+    schedules: Schedules,
+    pipelines: Pipelines,
+    concurrency_groups: ConcurrencyGroups,
+    watchdog: Watchdog,
+    /// Set when an alert webhook is configured
+    alerter: Option<Arc<Alerter>>,
+    result_deliveries: Arc<ResultDeliveries>,
+    webhooks: Arc<Webhooks>,
+    /// SQL store, when `DATABASE_URL` is configured
+    db: Option<PgPool>,
+    /// Durable webhook and metering delivery, when the SQL store is configured
+    outbox: Option<Outbox>,
+    metrics: Metrics,
+    event_bus: Arc<dyn EventBus>,
+    /// Identifies this replica's messages on the event bus and its leases
+    instance_id: Uuid,
+    leader: Arc<LeaderElector>,
+    inflight: Arc<InflightTracker>,
+    admission: Arc<AdmissionBudget>,
+    rate_limiter: Arc<RateLimiter>,
+    read_only: Arc<ReadOnlyMode>,
+    /// Status changes from every replica, relayed off the event bus once
+    /// for everything on this replica waiting on an execution
+    transitions: broadcast::Sender<ExecutionEvent>,
+    slo_tracker: SloTracker,
+    /// Set when gRPC response caching is enabled
+    grpc_cache: Option<GrpcExecutionCache>,
+    config: Config,
+    /// Set once upstream warm-up and listener binding have succeeded
+    ready: AtomicBool,
 }
 
-impl AppState {
-    pub async fn new() -> Result<Self> {
-        let execution_service_url = std::env::var("EXECUTION_SERVICE_URL")
-            .unwrap_or_else(|_| "http://localhost:8081".to_string());
+impl AppState {
+    pub async fn new(config: &Config) -> Result<Self> {
+        let execution_client = ExecutionClient::new(&config.upstream).await?;
+        info!(
+            "Established {} channels to execution service at {}",
+            execution_client.pool_size(),
+            config.upstream.execution_service_url
+        );
+
+        let canary_client = match &config.canary.url {
+            Some(url) => {
+                let client = ExecutionClient::new(&config.upstream.with_url(url, config.canary.protocol)).await?;
+                info!(
+                    "Routing {}% of executions to canary execution service at {}",
+                    config.canary.percent, url
+                );
+                Some(Arc::new(RwLock::new(client)))
+            }
+            None => None,
+        };
+
+        let shadow = ShadowTraffic::connect(&config.shadow, &config.upstream).await?;
+
+        let workspace_client = config
+            .surface
+            .workspaces
+            .then(|| WorkspaceClient::new(&config.workspace_service, &config.upstream))
+            .transpose()?;
+        let upstream_proxy = UpstreamProxy::new(&config.proxy, &config.upstream)?.map(Arc::new);
+        let artifact_store = ArtifactStore::new(&config.artifacts)?;
+
+        let event_bus = events::connect(&config.event_bus).await?;
+        info!("Using {:?} event bus", config.event_bus.backend);
+
+        let instance_id = Uuid::new_v4();
+        let leases = leader::connect(&config.leader_election).await?;
+        info!("Using {:?} leader election", config.leader_election.backend);
+        let leader = LeaderElector::new(leases.clone(), instance_id.to_string(), config.leader_election.clone());
+
+        let db = crate::db::connect(&config.database).await?;
+        if let Some(pool) = &db {
+            if config.database.migrate_on_startup {
+                crate::db::migrate(pool).await?;
+            }
+        }
+        let outbox = db
+            .clone()
+            .filter(|_| config.outbox.enabled)
+            .map(|pool| Outbox::new(pool, &config.outbox));
+
+        Ok(Self {
+            execution_client: Arc::new(RwLock::new(execution_client)),
+            canary_client,
+            shadow,
+            workspace_client,
+            upstream_proxy,
+            artifact_store,
+            executions: Arc::new(RwLock::new(HashMap::new())),
+            records: ExecutionRecords::new(db.clone()),
+            execution_fetches: Mutex::new(HashMap::new()),
+            purged: Mutex::new(HashMap::new()),
+            payload_archive: PayloadArchive::new(config.archive.clone()),
+            exports: ExportJobs::default(),
+            uploads: Uploads::default(),
+            tenant_settings: TenantSettings::default(),
+            tenant_origins: TenantOrigins::new(db.clone()),
+            output_buffers: OutputBuffers::new(config.output_buffer.clone()),
+            timelines: ExecutionTimelines::new(db.clone()),
+            schedules: Schedules::new(db.clone()),
+            pipelines: Pipelines::new(db.clone()),
+            concurrency_groups: ConcurrencyGroups::new(
+                config.concurrency_groups.clone(),
+                leases,
+                instance_id.to_string(),
+                &config.leader_election,
+            ),
+            watchdog: Watchdog::new(config.watchdog.clone()),
+            alerter: Alerter::new(&config.alerts, instance_id)?.map(Arc::new),
+            result_deliveries: Arc::new(ResultDeliveries::new(&config.result_delivery)),
+            webhooks: Arc::new(Webhooks::new(&config.webhooks, config.surface.webhooks)),
+            db,
+            outbox,
+            metrics: Metrics::new(),
+            event_bus,
+            instance_id,
+            leader: Arc::new(leader),
+            inflight: Arc::new(InflightTracker::new()),
+            admission: Arc::new(AdmissionBudget::new(config.admission.clone())),
+            rate_limiter: Arc::new(RateLimiter::new(config.rate_limit.clone())),
+            read_only: Arc::new(ReadOnlyMode::new(config.read_only)),
+            transitions: broadcast::channel(TRANSITION_CHANNEL_DEPTH).0,
+            slo_tracker: SloTracker::new(&config.slo),
+            grpc_cache: GrpcExecutionCache::new(&config.grpc_cache),
+            config: config.clone(),
+            ready: AtomicBool::new(false),
+        })
+    }
+
+    /// Probe the execution service until it answers, so traffic isn't accepted
+    /// before upstream channels are known to work
+    pub async fn warm_up(&self) -> Result<()> {
+        let attempts = self.config.upstream.warmup_attempts.max(1);
+        for attempt in 1..=attempts {
+            match self.execution_client.read().await.probe().await {
+                Ok(()) => {
+                    info!("Execution service probe succeeded on attempt {}", attempt);
+                    self.discover_languages().await;
+                    return Ok(());
+                }
+                Err(e) if attempt < attempts => {
+                    warn!("Execution service probe attempt {} failed: {}", attempt, e);
+                    tokio::time::sleep(self.config.upstream.warmup_retry_delay).await;
+                }
+                Err(e) => {
+                    return Err(anyhow::anyhow!(
+                        "Execution service probe failed after {} attempts: {}",
+                        attempts,
+                        e
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Learn the languages each execution backend supports; a backend that
+    /// can't say keeps the built-in list
+    async fn discover_languages(&self) {
+        let mut backends = vec![("execution_service", &self.execution_client)];
+        if let Some(canary_client) = &self.canary_client {
+            backends.push(("canary_execution_service", canary_client));
+        }
+        for (name, client) in backends {
+            let client = client.read().await;
+            match client.discover_languages().await {
+                Ok(source) => info!(
+                    backend = name,
+                    source = ?source,
+                    "Serving {} languages",
+                    client.languages().languages.len()
+                ),
+                Err(e) => warn!(backend = name, "Language discovery failed, using the built-in list: {}", e),
+            }
+        }
+    }
+
+    /// Languages the primary execution backend supports
+    pub async fn languages(&self) -> Arc<LanguageCatalog> {
+        self.execution_client.read().await.languages()
+    }
+
+    /// Periodically resize the upstream pool within its configured bounds.
+    /// A no-op when the bounds pin the pool to a fixed size.
+    pub fn spawn_pool_autoscaler(self: &Arc<Self>) {
+        let upstream = &self.config.upstream;
+        if upstream.pool_min == upstream.pool_max {
+            return;
+        }
+        info!(
+            "Adaptive upstream pool sizing enabled ({}..={} channels)",
+            upstream.pool_min, upstream.pool_max
+        );
+
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(state.config.upstream.pool_scale_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if state.inflight.is_draining() {
+                    break;
+                }
+                match state.execution_client.read().await.autoscale().await {
+                    Ok(PoolResize::Grew(size)) => info!("Grew execution service pool to {} channels", size),
+                    Ok(PoolResize::Shrank(size)) => info!("Shrank execution service pool to {} channels", size),
+                    Ok(PoolResize::Unchanged) => {}
+                    Err(e) => warn!("Failed to grow execution service pool: {}", e),
+                }
+            }
+        });
+    }
+
+    pub fn mark_ready(&self) {
+        self.ready.store(true, Ordering::Release);
+    }
+
+    /// Ready once started, until a shutdown drain begins
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire) && !self.inflight.is_draining()
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Probe the execution service and, when configured, the database
+    pub async fn check_health(&self) -> HealthReport {
+        let probe_upstream = |client: &'static str, execution_client: &Arc<RwLock<ExecutionClient>>| -> Probe<'_> {
+            let execution_client = execution_client.clone();
+            (
+                client,
+                Box::pin(async move {
+                    let client = execution_client.read().await;
+                    client.probe().await.map_err(|e| match e {
+                        ApiError::Upstream { code, message, .. } => format!("{}: {}", code, message),
+                        e => e.to_string(),
+                    })
+                }),
+            )
+        };
+        let mut probes = vec![probe_upstream("execution_service", &self.execution_client)];
+        if let Some(canary_client) = &self.canary_client {
+            probes.push(probe_upstream("canary_execution_service", canary_client));
+        }
+        if let Some(pool) = &self.db {
+            probes.push((
+                "database",
+                Box::pin(async move {
+                    sqlx::query("SELECT 1")
+                        .execute(pool)
+                        .await
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())
+                }),
+            ));
+        }
+        health::check(probes, &self.config.health).await
+    }
+
+    /// Client for the execution backend `backend`
+    fn client_for(&self, backend: Backend) -> &Arc<RwLock<ExecutionClient>> {
+        match (backend, &self.canary_client) {
+            (Backend::Canary, Some(canary_client)) => canary_client,
+            _ => &self.execution_client,
+        }
+    }
+
+    /// Backend an execution was routed to; primary unless it was created here
+    /// and sent to the canary
+    async fn backend_of(&self, id: Uuid) -> Backend {
+        self.executions
+            .read()
+            .await
+            .get(&id)
+            .map(|cached| cached.meta().backend)
+            .unwrap_or_default()
+    }
+
+    pub fn workspace_client(&self) -> Option<&WorkspaceClient> {
+        self.workspace_client.as_ref()
+    }
+
+    pub fn upstream_proxy(&self) -> Option<&Arc<UpstreamProxy>> {
+        self.upstream_proxy.as_ref()
+    }
+
+    /// Where created files are downloaded from, when configured
+    pub fn artifact_store(&self) -> Option<&ArtifactStore> {
+        self.artifact_store.as_ref()
+    }
+
+    pub fn shadow(&self) -> Option<&Arc<ShadowTraffic>> {
+        self.shadow.as_ref()
+    }
+
+    pub fn db(&self) -> Option<&PgPool> {
+        self.db.as_ref()
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    pub async fn upstream_channel_stats(&self) -> Vec<ChannelStats> {
+        self.execution_client.read().await.channel_stats()
+    }
+
+    /// Prometheus text for gateway counters and upstream channel usage
+    pub async fn render_metrics(&self) -> String {
+        let mut out = self.metrics.render();
+        if let Some(shadow) = &self.shadow {
+            out.push_str(&shadow.render());
+        }
+        out.push_str(&self.watchdog.render());
+        if let Some(alerter) = &self.alerter {
+            out.push_str(&alerter.render());
+        }
+        out.push_str(&self.result_deliveries.render());
+        out.push_str(&self.webhooks.render());
+        if let Some(outbox) = &self.outbox {
+            out.push_str(&outbox.render());
+        }
+        out.push_str(&self.admission.render());
+        out.push_str(&self.rate_limiter.render());
+        out.push_str(&self.read_only.render());
+        out.push_str(&self.schedules.render());
+        out.push_str(&self.concurrency_groups.render());
+        out.push_str(&self.slo_tracker.render());
+        if let Some(grpc_cache) = &self.grpc_cache {
+            out.push_str(&grpc_cache.render());
+        }
+        if let Some(proxy) = &self.upstream_proxy {
+            out.push_str(&proxy.render());
+        }
+        out.push_str(&crate::metrics::render_channel_stats(
+            &self.upstream_channel_stats().await,
+        ));
+        out
+    }
+
+    pub fn inflight(&self) -> &Arc<InflightTracker> {
+        &self.inflight
+    }
+
+    pub fn admission(&self) -> &Arc<AdmissionBudget> {
+        &self.admission
+    }
+
+    pub fn rate_limiter(&self) -> &Arc<RateLimiter> {
+        &self.rate_limiter
+    }
+
+    pub fn read_only(&self) -> &Arc<ReadOnlyMode> {
+        &self.read_only
+    }
+
+    pub fn slo_tracker(&self) -> &SloTracker {
+        &self.slo_tracker
+    }
+
+    pub fn grpc_cache(&self) -> Option<&GrpcExecutionCache> {
+        self.grpc_cache.as_ref()
+    }
+
+    pub fn payload_archive(&self) -> &PayloadArchive {
+        &self.payload_archive
+    }
+
+    pub fn exports(&self) -> &ExportJobs {
+        &self.exports
+    }
+
+    pub fn uploads(&self) -> &Uploads {
+        &self.uploads
+    }
+
+    pub fn tenant_settings(&self) -> &TenantSettings {
+        &self.tenant_settings
+    }
+
+    pub fn webhooks(&self) -> &Webhooks {
+        &self.webhooks
+    }
+
+    pub fn timelines(&self) -> &ExecutionTimelines {
+        &self.timelines
+    }
+
+    pub fn schedules(&self) -> &Schedules {
+        &self.schedules
+    }
+
+    pub fn pipelines(&self) -> &Pipelines {
+        &self.pipelines
+    }
+
+    pub fn tenant_origins(&self) -> &TenantOrigins {
+        &self.tenant_origins
+    }
+
+    pub fn watchdog(&self) -> &Watchdog {
+        &self.watchdog
+    }
+
+    pub fn alerter(&self) -> Option<&Arc<Alerter>> {
+        self.alerter.as_ref()
+    }
+
+    /// Purge soft-deleted executions once their purge window has passed, and
+    /// unpinned executions once the retention period has. Every replica sweeps
+    /// its own cache; the shared SQL store is swept by the lease holder only.
+    pub fn spawn_purger(self: &Arc<Self>) {
+        let state = self.clone();
+        let store_sweep = self.db.is_some().then(|| self.leader.campaign("execution-purger"));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(state.config.retention.purge_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if state.inflight.is_draining() {
+                    break;
+                }
+                let purged = state.purge_expired().await;
+                if purged > 0 {
+                    info!("Purged {} expired executions", purged);
+                }
+                if store_sweep.as_ref().is_some_and(|lease| lease.is_leader()) {
+                    match state.purge_expired_stored().await {
+                        Ok(0) => {}
+                        Ok(purged) => info!("Purged {} expired executions from the SQL store", purged),
+                        Err(e) => warn!("Failed to purge expired executions from the SQL store: {}", e),
+                    }
+                }
+            }
+        });
+    }
+
+    /// Periodically flag executions stuck in a non-final status past their
+    /// thresholds, alerting once per execution and cancelling them upstream
+    /// when configured. Only the lease holder checks, covering unfinished
+    /// executions in the SQL store as well as its own cache
+    pub fn spawn_watchdog(self: &Arc<Self>) {
+        let config = &self.config.watchdog;
+        info!(
+            "Watching for executions pending over {}s or running over {}s{}",
+            config.pending_threshold.as_secs(),
+            config.running_threshold.as_secs(),
+            if config.auto_cancel { ", cancelling them" } else { "" }
+        );
+
+        let lease = self.leader.campaign("watchdog");
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(state.config.watchdog.interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if state.inflight.is_draining() {
+                    break;
+                }
+                if !lease.is_leader() {
+                    continue;
+                }
+                // Nothing younger than the shorter threshold can be stuck yet
+                let config = &state.config.watchdog;
+                let threshold = config.pending_threshold.min(config.running_threshold);
+                state
+                    .load_unfinished(Utc::now() - chrono::Duration::from_std(threshold).unwrap_or_default())
+                    .await;
+                state.check_stuck_executions().await;
+            }
+        });
+    }
+
+    /// Bring unfinished executions created before `before` from the SQL
+    /// store, submitted through any replica, into the cache. Ones already
+    /// cached are left to be refreshed as usual
+    async fn load_unfinished(&self, before: DateTime<Utc>) {
+        let ids = match self.records.unfinished(before, false).await {
+            Ok(ids) => ids,
+            Err(e) => {
+                warn!("Failed to read unfinished executions from the SQL store: {}", e);
+                return;
+            }
+        };
+        let missing: Vec<Uuid> = {
+            let executions = self.executions.read().await;
+            ids.into_iter().filter(|id| !executions.contains_key(id)).collect()
+        };
+        futures::stream::iter(missing)
+            .for_each_concurrent(STORED_FETCH_CONCURRENCY, |id| async move {
+                if let Err(e) = self.get_execution(id).await {
+                    warn!("Failed to load unfinished execution {}: {}", id, e);
+                }
+            })
+            .await;
+    }
+
+    /// Periodically check on executions holding a concurrency group, so the
+    /// group moves on once they finish even when nothing else reads them.
+    /// With the SQL store only the lease holder checks, on those submitted
+    /// through any replica; the replica holding the group hears of the finish
+    /// through the relayed status change. Keeps going while draining, as
+    /// submissions may still be waiting
+    pub fn spawn_group_monitor(self: &Arc<Self>) {
+        let lease = self.db.is_some().then(|| self.leader.campaign("group-monitor"));
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(state.concurrency_groups.config().poll_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if lease.as_ref().is_some_and(|lease| !lease.is_leader()) {
+                    continue;
+                }
+                let mut holding: HashSet<Uuid> = state.concurrency_groups.running().into_iter().collect();
+                if lease.is_some() {
+                    match state.records.unfinished(Utc::now(), true).await {
+                        Ok(ids) => holding.extend(ids),
+                        Err(e) => warn!("Failed to read executions holding a concurrency group: {}", e),
+                    }
+                }
+                for id in holding {
+                    // Reads that see the execution finish release its group
+                    match state.get_execution(id).await {
+                        Ok(_) => {}
+                        Err(ApiError::NotFound) => state.concurrency_groups.release(id),
+                        Err(e) => warn!("Failed to check on execution {} holding a concurrency group: {}", id, e),
+                    }
+                }
+            }
+        });
+    }
+
+    /// Check for upstream outages, cache eviction storms and slow token
+    /// validation, alerting operators on each. Only the lease holder checks,
+    /// so each anomaly is alerted on once
+    pub fn spawn_alert_monitor(self: &Arc<Self>) {
+        let Some(alerter) = self.alerter.clone() else {
+            return;
+        };
+        info!(
+            "Alerting on gateway anomalies every {}s ({:?} format)",
+            alerter.config().interval.as_secs(),
+            alerter.config().format
+        );
+
+        let lease = self.leader.campaign("alert-monitor");
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(alerter.config().interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if state.inflight.is_draining() {
+                    break;
+                }
+                if !lease.is_leader() {
+                    continue;
+                }
+                let probe_timeout = state.config.health.probe_timeout;
+                let upstream = match tokio::time::timeout(probe_timeout, async {
+                    state.execution_client.read().await.probe().await
+                })
+                .await
+                {
+                    Ok(outcome) => outcome.map_err(|e| e.to_string()),
+                    Err(_) => Err(format!("No response within {}ms", probe_timeout.as_millis())),
+                };
+                let evictions = state.grpc_cache.as_ref().map(GrpcExecutionCache::evictions);
+                alerter.notify(alerter.evaluate(upstream, evictions)).await;
+            }
+        });
+    }
+
+    async fn check_stuck_executions(&self) {
+        let now = Utc::now();
+        let overdue: Vec<Uuid> = {
+            let executions = self.executions.read().await;
+            executions
+                .iter()
+                .filter(|(id, cached)| self.stuck_execution(**id, cached, now).is_some())
+                .map(|(id, _)| *id)
+                .collect()
+        };
+
+        let mut still_stuck = HashSet::new();
+        for id in overdue {
+            // The cache may only be behind; a fresh read settles executions
+            // that finished without the gateway seeing it
+            if let Err(e) = self.get_execution(id).await {
+                warn!("Failed to refresh possibly stuck execution {}: {}", id, e);
+            }
+            let stuck = {
+                let executions = self.executions.read().await;
+                executions
+                    .get(&id)
+                    .and_then(|cached| self.stuck_execution(id, cached, Utc::now()))
+            };
+            let Some(mut stuck) = stuck else {
+                continue;
+            };
+            still_stuck.insert(id);
+
+            let newly_flagged = self.watchdog.flag(stuck.clone());
+            if newly_flagged {
+                warn!(
+                    execution_id = %id,
+                    status = ?stuck.status,
+                    stuck_for_secs = stuck.stuck_for_secs,
+                    "Execution is stuck"
+                );
+            }
+            // Retried every sweep until the execution settles
+            if self.config.watchdog.auto_cancel {
+                let reason = format!("Stuck in {:?} for {}s", stuck.status, stuck.stuck_for_secs);
+                stuck.cancelled = self
+                    .cancel_upstream(id, Cancellation::new(CancelCause::Timeout, None, reason))
+                    .await
+                    .inspect_err(|e| warn!("Failed to cancel stuck execution {}: {}", id, e))
+                    .is_ok();
+                self.watchdog.record_cancel(id, stuck.cancelled);
+            }
+            if newly_flagged {
+                self.publish(WATCHDOG_ALERTS_TOPIC, &stuck).await;
+            }
+        }
+        self.watchdog.retain(|id| still_stuck.contains(id));
+    }
+
+    /// `cached` as a stuck execution, if it has sat in a non-final status past its threshold
+    fn stuck_execution(&self, id: Uuid, cached: &CachedExecution, now: DateTime<Utc>) -> Option<StuckExecution> {
+        let meta = cached.meta();
+        if meta.is_deleted() {
+            return None;
+        }
+        let threshold = self.watchdog.threshold(cached.status())?;
+        let since = match cached.status() {
+            ExecutionStatus::Running | ExecutionStatus::Cancelling => {
+                cached.started_at().unwrap_or_else(|| cached.created_at())
+            }
+            _ => cached.created_at(),
+        };
+        let stuck_for = (now - since).to_std().ok().filter(|age| *age > threshold)?;
+        Some(StuckExecution {
+            execution_id: id,
+            status: cached.status().clone(),
+            owner: meta.owner.clone(),
+            tenant_id: meta.tenant_id.clone(),
+            since,
+            stuck_for_secs: stuck_for.as_secs(),
+            flagged_at: now,
+            cancelled: false,
+        })
+    }
+
+    /// Cancel an execution on its backend, recording who or what cancelled it
+    /// and the status it was left in
+    async fn cancel_upstream(&self, id: Uuid, cancellation: Cancellation) -> Result<(), ApiError> {
+        let backend = self.backend_of(id).await;
+        let status = self
+            .client_for(backend)
+            .read()
+            .await
+            .cancel_execution(id, &cancellation.reason)
+            .await?;
+
+        let tenant_id = self.executions.write().await.get_mut(&id).and_then(|cached| {
+            // A cancel retried or repeated keeps the first one's cause
+            cached.meta_mut().cancellation.get_or_insert_with(|| cancellation.clone());
+            cached.meta().tenant_id.clone()
+        });
+        audit::record(
+            AuditEvent::new(
+                "execution.cancel",
+                cancellation.actor.as_deref().unwrap_or("system"),
+                AuditOutcome::Allowed,
+            )
+            .subject(&id.to_string())
+            .tenant(tenant_id.as_deref())
+            .reason(&cancellation.reason),
+        );
+
+        if !status.is_terminal() {
+            // Still winding down; reported as cancelling until upstream confirms,
+            // and the next read goes upstream for the outcome
+            let cancelling = self.executions.write().await.get_mut(&id).map(|cached| {
+                let previous = cached.meta().reported_status(cached.status());
+                cached.meta_mut().cancel_requested = true;
+                cached.mark_stale();
+                (previous, cached.unpack(), cached.meta().clone())
+            });
+            if let Some((previous, Ok(execution), meta)) = cancelling {
+                if previous != execution.status {
+                    self.announce_transition(&execution, Some(previous), &meta).await;
+                }
+            }
+            return Ok(());
+        }
+        let cached = self.executions.read().await.get(&id).map(CachedExecution::unpack);
+        match cached {
+            Some(Ok(mut execution)) => {
+                execution.status = status;
+                execution.completed_at.get_or_insert_with(Utc::now);
+                self.cache_execution(&mut execution, None).await;
+            }
+            // The next read refetches it, outcome included
+            Some(Err(e)) => {
+                warn!("{}", e);
+                self.mark_stale(id).await;
+            }
+            None => return Ok(()),
+        }
+        self.invalidate(id, false).await;
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> usize {
+        let now = chrono::Utc::now();
+        let cutoff = |age: std::time::Duration| {
+            chrono::Duration::from_std(age)
+                .ok()
+                .and_then(|age| now.checked_sub_signed(age))
+        };
+        let deleted_cutoff = cutoff(self.config.retention.purge_window);
+        let retention_cutoff = self.config.retention.execution_ttl.and_then(cutoff);
+
+        let mut executions = self.executions.write().await;
+        let before = executions.len();
+        executions.retain(|_, cached| {
+            let meta = cached.meta();
+            if let (Some(deleted_at), Some(cutoff)) = (meta.deleted_at, deleted_cutoff) {
+                return deleted_at > cutoff;
+            }
+            match retention_cutoff {
+                Some(cutoff) if !meta.pinned && cached.is_terminal() => cached.created_at() > cutoff,
+                _ => true,
+            }
+        });
+        self.timelines.retain(|id| executions.contains_key(id)).await;
+        let marker_ttl = self.config.retention.execution_ttl.unwrap_or(self.config.retention.purge_window);
+        if let Some(cutoff) = cutoff(marker_ttl) {
+            self.purged.lock().unwrap().retain(|_, purged_at| *purged_at > cutoff);
+        }
+        before - executions.len()
+    }
+
+    /// The SQL store counterpart of `purge_expired`
+    async fn purge_expired_stored(&self) -> Result<u64> {
+        let Some(pool) = &self.db else {
+            return Ok(0);
+        };
+        let now = chrono::Utc::now();
+        let cutoff = |age: std::time::Duration| {
+            chrono::Duration::from_std(age)
+                .ok()
+                .and_then(|age| now.checked_sub_signed(age))
+        };
+        let purged: Vec<String> = sqlx::query_scalar(
+            "DELETE FROM executions \
+             WHERE (deleted_at IS NOT NULL AND deleted_at <= $1) \
+                OR (NOT pinned AND status IN ('completed', 'failed', 'cancelled', 'timeout') AND created_at <= $2) \
+             RETURNING id::text",
+        )
+        .bind(cutoff(self.config.retention.purge_window))
+        .bind(self.config.retention.execution_ttl.and_then(cutoff))
+        .fetch_all(pool)
+        .await?;
+        if !purged.is_empty() {
+            sqlx::query("DELETE FROM execution_events WHERE execution_id = ANY($1::uuid[])")
+                .bind(&purged)
+                .execute(pool)
+                .await?;
+        }
+        Ok(purged.len() as u64)
+    }
+
+    /// Cache an upstream snapshot, keeping gateway-owned metadata of an existing
+    /// entry, and announce the change if the status moved. New and invalidated
+    /// entries take that metadata from the SQL store, or failing that their
+    /// owner from upstream. Output past the tenant's cap is truncated here, in
+    /// `execution` as well as the cache.
+    async fn cache_execution(&self, execution: &mut ExecutionResponse, owner: Option<&AuthContext>) {
+        let (current, cached_tenant) = self
+            .executions
+            .read()
+            .await
+            .get(&execution.id)
+            .map_or((false, None), |cached| (!cached.is_stale(), cached.meta().tenant_id.clone()));
+        let record = match owner {
+            None if !current => self.records.load(execution.id).await.unwrap_or_else(|e| {
+                warn!("Failed to load stored state of execution {}: {}", execution.id, e);
+                None
+            }),
+            _ => None,
+        };
+        let tenant_id = match (owner, &record) {
+            (Some(auth_context), _) => auth_context.tenant_id.clone(),
+            (None, Some(record)) => record.tenant_id.clone(),
+            (None, None) => cached_tenant,
+        };
+        let limit = self.tenant_settings.output_limit(tenant_id.as_deref()).await;
+        let truncated = match (limit, execution.result.as_mut()) {
+            (Some(limit), Some(result)) => result.truncate_output(limit),
+            _ => false,
+        };
+        if let Some(result) = execution.result.as_mut() {
+            result.detect_ansi();
+            if execution.status.is_terminal() {
+                result.record_checksums();
+            }
+        }
+
+        let cached = CachedExecution::pack(execution.clone(), &self.config.storage, &self.metrics);
+        let (previous, meta, over_limit) = {
+            let mut executions = self.executions.write().await;
+            let (previous, entry) = match executions.entry(execution.id) {
+                std::collections::hash_map::Entry::Occupied(entry) => {
+                    let existing = entry.into_mut();
+                    let previous = existing.meta().reported_status(existing.status());
+                    existing.refresh(cached);
+                    (Some(previous), existing)
+                }
+                std::collections::hash_map::Entry::Vacant(entry) => (None, entry.insert(cached)),
+            };
+            if let Some(auth_context) = owner {
+                entry.meta_mut().owner = Some(auth_context.user_id.clone());
+                entry.meta_mut().tenant_id = auth_context.tenant_id.clone();
+            } else if let Some(record) = &record {
+                record.apply_to(entry.meta_mut());
+            }
+            if entry.meta().owner.is_none() && execution.owner.is_some() {
+                entry.meta_mut().owner = execution.owner.clone();
+            }
+            if truncated {
+                entry.meta_mut().output_limit_exceeded = true;
+            }
+            let over_limit = truncated
+                && !execution.status.is_terminal()
+                && !entry.meta().cancel_requested
+                && entry.meta().cancellation.is_none();
+            // Cancelled without the gateway asking, as the execution service
+            // does when it shuts down
+            if execution.status == ExecutionStatus::Cancelled
+                && previous.as_ref().is_some_and(|previous| !previous.is_terminal())
+                && entry.meta().cancellation.is_none()
+            {
+                entry.meta_mut().cancellation = Some(Cancellation::new(
+                    CancelCause::Shutdown,
+                    None,
+                    "Cancelled by the execution service".to_string(),
+                ));
+            }
+            execution.cancellation = entry.meta().cancellation.clone();
+            execution.output_limit_exceeded = entry.meta().output_limit_exceeded;
+            execution.tty = entry.meta().requested_tty();
+            execution.status = entry.meta().reported_status(&execution.status);
+            execution.result_delivery = entry.meta().result_delivery.clone();
+            entry.meta().withhold_output(execution);
+            (previous, entry.meta().clone(), over_limit)
+        };
+
+        if previous.as_ref() != Some(&execution.status) {
+            if let Err(e) = self.records.record_status(execution).await {
+                warn!("Failed to record the status of execution {} in the SQL store: {}", execution.id, e);
+            }
+            self.announce_transition(&*execution, previous, &meta).await;
+        }
+        if execution.status.is_terminal() {
+            self.concurrency_groups.release(execution.id);
+        }
+        // Output is capped however it arrives, so a running execution past
+        // its cap is stopped whether or not anyone streams it
+        if let (true, Some(limit)) = (over_limit, limit) {
+            Box::pin(self.stop_for_output_limit(execution.id, limit)).await;
+        }
+    }
+
+    async fn announce_transition(
+        &self,
+        execution: &ExecutionResponse,
+        previous: Option<ExecutionStatus>,
+        meta: &ExecutionMeta,
+    ) {
+        // Only transitions seen by this gateway are metered, so an execution
+        // first loaded in a final state isn't billed again
+        if execution.status.is_terminal() && previous.as_ref().is_some_and(|previous| !previous.is_terminal()) {
+            let usage = MeteringEvent {
+                execution_id: execution.id,
+                user_id: meta.owner.as_deref(),
+                tenant_id: meta.tenant_id.as_deref(),
+                status: &execution.status,
+                duration_ms: execution.result.as_ref().map(|r| r.duration_ms),
+                timestamp: Utc::now(),
+            };
+            self.publish(METERING_TOPIC, &usage).await;
+            match &self.outbox {
+                Some(outbox) => {
+                    let enqueued = match serde_json::to_string(&usage) {
+                        Ok(payload) => {
+                            let key = format!("metering:{}", execution.id);
+                            let id = execution.id.to_string();
+                            // Metering records are always deliverable, so never effectively expire
+                            let expires_at = Utc::now() + chrono::Duration::days(365);
+                            outbox
+                                .enqueue(OutboxKind::Metering, &key, &id, None, Some(&payload), expires_at)
+                                .await
+                        }
+                        Err(e) => Err(e.into()),
+                    };
+                    if let Err(e) = enqueued {
+                        warn!("Failed to queue metering record of execution {}, writing it directly: {}", execution.id, e);
+                        metering::record(usage);
+                    }
+                }
+                None => metering::record(usage),
+            }
+        }
+
+        self.timelines.record(execution, previous.as_ref()).await;
+
+        let event = ExecutionEvent {
+            execution_id: execution.id,
+            status: execution.status.clone(),
+            previous,
+            occurred_at: Utc::now(),
+        };
+        self.publish(EXECUTION_EVENTS_TOPIC, &event).await;
+    }
+
+    /// Publish on the event bus; delivery is best-effort, so failures are only logged
+    async fn publish<T: Serialize + ?Sized>(&self, topic: &str, value: &T) {
+        if let Err(e) = events::publish_json(&*self.event_bus, topic, value).await {
+            warn!("Failed to publish to {}: {}", topic, e);
+        }
+    }
+
+    pub fn event_bus(&self) -> &Arc<dyn EventBus> {
+        &self.event_bus
+    }
+
+    /// Status changes of any execution, for streaming and webhook delivery
+    pub async fn subscribe_events(&self) -> Result<BoxStream<'static, ExecutionEvent>> {
+        events::subscribe_json(&*self.event_bus, EXECUTION_EVENTS_TOPIC).await
+    }
+
+    /// Drop cached upstream state when another replica reports a newer one,
+    /// so the next read here fetches it instead of serving a stale snapshot
+    pub async fn spawn_cache_invalidation(self: &Arc<Self>) -> Result<()> {
+        let mut invalidations =
+            events::subscribe_json::<CacheInvalidation>(&*self.event_bus, CACHE_INVALIDATION_TOPIC).await?;
+        let state = self.clone();
+        tokio::spawn(async move {
+            while let Some(invalidation) = invalidations.next().await {
+                if invalidation.origin == state.instance_id {
+                    continue;
+                }
+                if invalidation.purged {
+                    state.forget_execution(invalidation.execution_id).await;
+                } else if let Some(cached) = state.executions.write().await.get_mut(&invalidation.execution_id) {
+                    cached.mark_stale();
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Replace `tenant_id`'s CORS policy and tell other replicas
+    pub async fn put_cors_policy(&self, tenant_id: &str, policy: CorsPolicy) -> Result<(), ApiError> {
+        self.tenant_origins.put(tenant_id, policy.clone()).await?;
+        let change = CorsPolicyChange {
+            tenant_id: tenant_id.to_string(),
+            policy,
+            replica: self.instance_id,
+        };
+        self.publish(CORS_POLICIES_TOPIC, &change).await;
+        Ok(())
+    }
+
+    /// Load stored CORS policies, then follow changes other replicas make
+    pub async fn spawn_cors_sync(self: &Arc<Self>) -> Result<()> {
+        let mut changes = events::subscribe_json::<CorsPolicyChange>(&*self.event_bus, CORS_POLICIES_TOPIC).await?;
+        self.tenant_origins.load().await?;
+        let state = self.clone();
+        tokio::spawn(async move {
+            while let Some(change) = changes.next().await {
+                if change.replica != state.instance_id {
+                    state.tenant_origins.adopt(&change.tenant_id, change.policy).await;
+                }
+            }
+            warn!("CORS policy subscription ended, changes on other replicas won't apply here");
+        });
+        Ok(())
+    }
+
+    /// Relay status changes off the event bus to waiters on this replica,
+    /// so each wait doesn't hold its own subscription
+    pub async fn spawn_transition_relay(self: &Arc<Self>) -> Result<()> {
+        let mut events = self.subscribe_events().await?;
+        let state = self.clone();
+        tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                // Another replica may have seen an execution holding a group here finish
+                if event.status.is_terminal() {
+                    state.concurrency_groups.release(event.execution_id);
+                }
+                // No receivers just means nothing is waiting
+                let _ = state.transitions.send(event);
+            }
+            warn!("Execution event subscription ended, waits fall back to polling");
+        });
+        Ok(())
+    }
+
+    pub async fn create_execution(
+        &self,
+        auth_context: &AuthContext,
+        mut request: CreateExecutionRequest,
+    ) -> Result<ExecutionResponse, ApiError> {
+        self.inline_upload(auth_context, &mut request).await?;
+        self.submit_execution(auth_context, request, None).await
+    }
+
+    /// Run `request` through everything submitting it would, short of going
+    /// upstream, and report how it would be handled
+    pub async fn validate_execution(
+        &self,
+        auth_context: &AuthContext,
+        mut request: CreateExecutionRequest,
+    ) -> Result<ExecutionValidation, ApiError> {
+        self.inline_upload(auth_context, &mut request).await?;
+        let prepared = server_timing::time(Phase::Validation, self.prepare_submission(auth_context, request)).await?;
+        let request = prepared.request;
+        Ok(ExecutionValidation {
+            valid: prepared.diagnostics.is_empty(),
+            diagnostics: prepared.diagnostics,
+            language_recognized: prepared.language_recognized,
+            language: request.language,
+            language_version: request.language_version,
+            timeout_seconds: request.timeout_seconds,
+            resources: request.resources,
+            mode: request.mode.unwrap_or(IsolationMode::Sandbox),
+            backend: prepared.backend,
+            result_delivery: prepared.result_target.map(|target| target.location()),
+            callback_url: prepared.callback_url.as_ref().map(delivery::location),
+            warnings: prepared.warnings,
+        })
+    }
+
+    /// Uploaded code is inlined here, so the stored request can be resubmitted
+    /// after the upload expires
+    pub async fn inline_upload(
+        &self,
+        auth_context: &AuthContext,
+        request: &mut CreateExecutionRequest,
+    ) -> Result<(), ApiError> {
+        if let Some(upload_id) = request.upload_id.take() {
+            if !request.code.is_empty() {
+                return Err(ApiError::BadRequest(
+                    "Set either code or upload_id, not both".to_string(),
+                ));
+            }
+            request.code = self.uploads.read_code(upload_id, &auth_context.user_id).await?.into();
+        }
+        Ok(())
+    }
+
+    /// Validate `request`, merge tenant defaults into it and pick the
+    /// backend and result destination it would go to. Problems with the
+    /// request are collected rather than returned, so all of them can be reported
+    async fn prepare_submission(
+        &self,
+        auth_context: &AuthContext,
+        request: CreateExecutionRequest,
+    ) -> Result<PreparedSubmission, ApiError> {
+        let mut diagnostics = request.diagnostics(&self.config.resource_caps);
+        // Kept so the execution can be resubmitted later; tenant defaults are
+        // merged afresh on every submission so resubmits pick up changes
+        let original = request.clone();
+        let (request, mut warnings) = self.tenant_settings.apply(auth_context, request).await;
+        let result_target = match &original.result_destination {
+            Some(destination) => {
+                let bucket_url = match auth_context.tenant_id.as_deref() {
+                    Some(tenant_id) => self
+                        .tenant_settings
+                        .get(tenant_id)
+                        .await
+                        .and_then(|settings| settings.result_bucket_url),
+                    None => None,
+                };
+                diagnose(
+                    self.result_deliveries.target(destination, bucket_url.as_deref()),
+                    "result_destination",
+                    "invalid_destination",
+                    &mut diagnostics,
+                )?
+            }
+            None => None,
+        };
+        let callback_url = match original.callback_url.as_deref() {
+            Some(url) => diagnose(self.webhooks.target(url), "callback_url", "invalid_callback", &mut diagnostics)?,
+            None => None,
+        };
+        let backend = canary::route(&self.config.canary, auth_context.tenant_id.as_deref());
+        let languages = self.client_for(backend).read().await.languages();
+        let language = languages.resolve(&request.language);
+        let language_recognized = language.is_some();
+        if let Some(version) = request.language_version.as_deref() {
+            let unsupported = match language {
+                None => Some(format!(
+                    "Language '{}' is not recognized, so no version can be pinned",
+                    request.language
+                )),
+                Some(info) if info.versions.is_empty() => Some(format!(
+                    "The execution service doesn't report versions of '{}' to pin",
+                    info.name
+                )),
+                Some(info) if !info.supports_version(version) => Some(format!(
+                    "Version '{}' of '{}' is not available; choose one of: {}",
+                    version,
+                    info.name,
+                    info.versions.join(", ")
+                )),
+                Some(_) => None,
+            };
+            if let Some(message) = unsupported {
+                diagnostics.push(Diagnostic::new("language_version", "unsupported_version", message));
+            }
+        }
+        if !language_recognized {
+            warnings.push(Warning::new(
+                "language_defaulted",
+                format!(
+                    "Language '{}' is not recognized; the execution service's default is used",
+                    request.language
+                ),
+            ));
+        }
+        Ok(PreparedSubmission {
+            original,
+            request,
+            warnings,
+            result_target,
+            callback_url,
+            backend,
+            language_recognized,
+            diagnostics,
+        })
+    }
+
+    async fn submit_execution(
+        &self,
+        auth_context: &AuthContext,
+        request: CreateExecutionRequest,
+        lineage: Option<Lineage>,
+    ) -> Result<ExecutionResponse, ApiError> {
+        if !self.admission.admits_priority(request.priority.unwrap_or_default()) {
+            return Err(ApiError::ServiceUnavailable);
+        }
+        let resubmitted_from = match lineage {
+            Some(Lineage::Resubmitted(id)) => Some(id),
+            _ => None,
+        };
+        let retried_from = match lineage {
+            Some(Lineage::Retried(id)) => Some(id),
+            _ => None,
+        };
+        let user_id = auth_context.user_id.clone();
+        let workspace_id = request.workspace_id.map(|id| id.to_string());
+        let PreparedSubmission {
+            original,
+            request,
+            warnings,
+            result_target,
+            callback_url,
+            backend,
+            diagnostics,
+            ..
+        } = server_timing::time(Phase::Validation, self.prepare_submission(auth_context, request)).await?;
+        if let Some(diagnostic) = diagnostics.into_iter().next() {
+            return Err(ApiError::BadRequest(diagnostic.message));
+        }
+        let group_turn = match &original.concurrency_group {
+            Some(group) => {
+                let scope = auth_context.principal();
+                Some(self.concurrency_groups.acquire(&scope, group).await?)
+            }
+            None => None,
+        };
+
+        // Send to execution service via gRPC
+        if !backend.is_primary() {
+            self.metrics.record_canary_execution();
+        }
+        let client = self.client_for(backend).read().await;
+        let mirrored = self
+            .shadow
+            .as_ref()
+            .filter(|shadow| shadow.should_mirror())
+            .map(|shadow| (shadow.clone(), user_id.clone(), workspace_id.clone(), request.clone()));
+        let mut execution = client.create_execution(user_id, workspace_id, request).await?;
+        execution.resubmitted_from = resubmitted_from;
+        execution.retried_from = retried_from;
+        if let Some((shadow, user_id, workspace_id, request)) = mirrored {
+            shadow.mirror(self.client_for(backend).clone(), execution.id, user_id, workspace_id, request);
+        }
+        
+        // Cache the response
+        self.cache_execution(&mut execution, Some(auth_context)).await;
+        if let Some(turn) = group_turn.filter(|_| !execution.status.is_terminal()) {
+            self.concurrency_groups.hold(execution.id, turn);
+        }
+        execution.tty = original.tty.unwrap_or(false);
+        execution.session_id = original.session_id.clone();
+        execution.group_id = original.group_id.clone();
+        execution.language_version = original.language_version.clone();
+        execution.metadata = original.metadata.clone();
+        execution.backend = backend;
+        let delivery_url = result_target.map(|target| target.url_for(execution.id));
+        execution.result_delivery = delivery_url.as_ref().map(delivery::pending);
+        let trace = TraceContext::current();
+        execution.trace_id = trace.as_ref().map(|trace| trace.trace_id.clone());
+        execution.trace_url = trace.as_ref().and_then(|trace| trace.trace_url.clone());
+        execution.code_sha256 = Some(sha256_hex(&original.code));
+        // A pre-signed URL is meant for one result, so resubmissions don't inherit it
+        let mut original = original;
+        if matches!(original.result_destination, Some(ResultDestination::PresignedUrl { .. })) {
+            original.result_destination = None;
+        }
+        // Without a record other replicas still learn the owner from upstream
+        if let Err(e) = self.records.insert(&execution, auth_context, &original).await {
+            warn!("Failed to record execution {} in the SQL store: {}", execution.id, e);
+        }
+        if let Some(cached) = self.executions.write().await.get_mut(&execution.id) {
+            cached.meta_mut().backend = backend;
+            cached.meta_mut().request = Some(original);
+            cached.meta_mut().resubmitted_from = resubmitted_from;
+            cached.meta_mut().retried_from = retried_from;
+            cached.meta_mut().result_delivery = execution.result_delivery.clone();
+            cached.meta_mut().trace = trace;
+            cached.meta_mut().code_sha256 = execution.code_sha256.clone();
+            cached.meta().withhold_output(&mut execution);
+        }
+        if let Some(url) = delivery_url {
+            self.result_deliveries.deliver(
+                self.client_for(backend).clone(),
+                self.executions.clone(),
+                execution.id,
+                url,
+            );
+        }
+        if let Some(url) = callback_url {
+            self.queue_webhook(backend, execution.id, url, auth_context.principal()).await;
+        }
+        execution.warnings = warnings;
+        
+        Ok(execution)
+    }
+
+    /// Send execution `id`'s completion webhook to `url` once it finishes,
+    /// signed for `principal`, through the outbox when there is one
+    async fn queue_webhook(&self, backend: Backend, id: Uuid, url: Url, principal: String) {
+        if let Some(outbox) = &self.outbox {
+            let expires_at = Utc::now()
+                + chrono::Duration::from_std(self.webhooks.config().result_timeout).unwrap_or_default();
+            let queued = outbox
+                .enqueue(
+                    OutboxKind::Webhook,
+                    &crate::webhook::idempotency_key(id),
+                    &id.to_string(),
+                    Some(url.as_str()),
+                    Some(&principal),
+                    expires_at,
+                )
+                .await;
+            match queued {
+                Ok(()) => return,
+                Err(e) => warn!("Failed to queue webhook of execution {}, sending it from memory: {}", id, e),
+            }
+        }
+        self.webhooks.notify(self.client_for(backend).clone(), id, url, principal);
+    }
+
+    /// Submit scheduled executions as they come due. Schedules in the SQL
+    /// store are run by the lease holder only, so each run is submitted once;
+    /// runs wait while the gateway is read-only
+    pub fn spawn_scheduler(self: &Arc<Self>) {
+        let lease = self.db.is_some().then(|| self.leader.campaign("scheduler"));
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(state.config.schedules.poll_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if state.inflight.is_draining() {
+                    break;
+                }
+                if lease.as_ref().is_some_and(|lease| !lease.is_leader()) || state.read_only.is_enabled() {
+                    continue;
+                }
+                let due = match state.schedules.due(Utc::now()).await {
+                    Ok(due) => due,
+                    Err(e) => {
+                        warn!("Failed to read due schedules: {}", e);
+                        continue;
+                    }
+                };
+                for schedule in due {
+                    if let Err(e) = state.run_schedule(&schedule).await {
+                        warn!("Failed to update schedule {}: {}", schedule.id, e);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Submit one run of `schedule` as its owner, moving it on to its next run first
+    async fn run_schedule(&self, schedule: &Schedule) -> Result<()> {
+        let now = Utc::now();
+        if !self.schedules.claim(schedule, now, schedule.following(now)).await? {
+            return Ok(());
+        }
+        let outcome = match self.create_execution(&schedule.owner(), schedule.request().clone()).await {
+            Ok(execution) => {
+                info!("Schedule {} submitted execution {}", schedule.id, execution.id);
+                Ok(execution.id)
+            }
+            Err(e) => {
+                warn!("Schedule {} failed to submit its execution: {}", schedule.id, e);
+                Err(e.to_string())
+            }
+        };
+        self.schedules.record_run(schedule.id, outcome).await
+    }
+
+    /// Drive running pipelines through their steps, and drop finished ones
+    /// past retention. Pipelines in the SQL store are driven by the lease
+    /// holder only, so each step is submitted once, and a new holder picks up
+    /// those a previous one left running; no new steps start while the
+    /// gateway is read-only
+    pub fn spawn_pipeline_runner(self: &Arc<Self>) {
+        let lease = self.db.is_some().then(|| self.leader.campaign("pipelines"));
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(state.config.pipelines.poll_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = state.pipelines.created() => {}
+                }
+                if state.inflight.is_draining() {
+                    break;
+                }
+                if lease.as_ref().is_some_and(|lease| !lease.is_leader()) || state.read_only.is_enabled() {
+                    continue;
+                }
+                let cutoff = Utc::now() - chrono::Duration::from_std(state.config.pipelines.retention).unwrap_or_default();
+                match state.pipelines.sweep(cutoff).await {
+                    Ok(0) => {}
+                    Ok(swept) => info!("Dropped {} finished pipelines", swept),
+                    Err(e) => warn!("Failed to sweep pipelines: {}", e),
+                }
+                let running = match state.pipelines.running().await {
+                    Ok(running) => running,
+                    Err(e) => {
+                        warn!("Failed to read running pipelines: {}", e);
+                        continue;
+                    }
+                };
+                for id in running {
+                    if !state.pipelines.start_driving(id) {
+                        continue;
+                    }
+                    let state = state.clone();
+                    let lease = lease.clone();
+                    tokio::spawn(async move {
+                        pipeline::drive(&state, id, lease.as_ref()).await;
+                        state.pipelines.stop_driving(id);
+                    });
+                }
+            }
+        });
+    }
 
-        let execution_client = ExecutionClient::new(&execution_service_url).await?;
+    /// Deliver outbox entries as they come due. Only the lease holder
+    /// delivers, so replicas don't race each other for the same entries
+    pub fn spawn_outbox_dispatcher(self: &Arc<Self>) {
+        let Some(outbox) = &self.outbox else {
+            return;
+        };
+        info!("Delivering webhooks and metering records through the SQL outbox");
+        let lease = self.leader.campaign("delivery-outbox");
+        let state = self.clone();
+        let poll_interval = outbox.config().poll_interval;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(poll_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut last_sweep: Option<std::time::Instant> = None;
+            loop {
+                interval.tick().await;
+                if state.inflight.is_draining() {
+                    break;
+                }
+                if !lease.is_leader() {
+                    continue;
+                }
+                let Some(outbox) = &state.outbox else {
+                    break;
+                };
+                if last_sweep.is_none_or(|at| at.elapsed() >= std::time::Duration::from_secs(60)) {
+                    match outbox.sweep().await {
+                        Ok(0) => {}
+                        Ok(swept) => info!("Dropped {} finished outbox entries", swept),
+                        Err(e) => warn!("Failed to sweep the delivery outbox: {}", e),
+                    }
+                    last_sweep = Some(std::time::Instant::now());
+                }
+                let entries = match outbox.due().await {
+                    Ok(entries) => entries,
+                    Err(e) => {
+                        warn!("Failed to read the delivery outbox: {}", e);
+                        continue;
+                    }
+                };
+                for entry in entries {
+                    if let Err(e) = state.dispatch(outbox, &entry).await {
+                        warn!("Failed to update outbox entry {}: {}", entry.idempotency_key, e);
+                    }
+                }
+            }
+        });
+    }
 
-        Ok(Self {
-            execution_client: Arc::new(RwLock::new(execution_client)),
-            executions: Arc::new(RwLock::new(HashMap::new())),
-        })
+    /// Attempt one outbox entry, recording the outcome on it
+    async fn dispatch(&self, outbox: &Outbox, entry: &OutboxEntry) -> Result<()> {
+        match entry.kind {
+            OutboxKind::Metering => {
+                metering::record_serialized(entry.payload.as_deref().unwrap_or_default());
+                outbox.mark_delivered(entry).await
+            }
+            OutboxKind::Webhook => {
+                if Utc::now() >= entry.expires_at {
+                    self.webhooks.count(false);
+                    warn!("Gave up on webhook {}: execution did not finish in time", entry.idempotency_key);
+                    return outbox.abandon(entry, "execution did not finish in time").await;
+                }
+                let (Ok(id), Some(Ok(url)), Some(principal)) = (
+                    entry.execution_id.parse::<Uuid>(),
+                    entry.target.as_deref().map(Url::parse),
+                    entry.payload.as_deref(),
+                ) else {
+                    return outbox.abandon(entry, "malformed entry").await;
+                };
+                let execution = match self.get_execution(id).await {
+                    Ok(execution) if !execution.status.is_terminal() => {
+                        return outbox.defer(entry, self.webhooks.config().poll_interval).await;
+                    }
+                    Ok(execution) => execution,
+                    Err(ApiError::NotFound) => return outbox.abandon(entry, "execution not found").await,
+                    Err(e) => {
+                        outbox.record_failure(entry, &format!("fetching execution: {}", e), true).await?;
+                        return Ok(());
+                    }
+                };
+                let body = serde_json::to_vec(&execution)?;
+                match self.webhooks.attempt(&url, &body, &entry.idempotency_key, principal).await {
+                    Ok(()) => {
+                        self.webhooks.count(true);
+                        info!("Sent completion webhook of execution {} to {}", id, delivery::location(&url));
+                        outbox.mark_delivered(entry).await
+                    }
+                    Err(failure) => {
+                        warn!(
+                            "Webhook attempt {} to {} failed: {}",
+                            entry.attempts + 1,
+                            delivery::location(&url),
+                            failure.message
+                        );
+                        if outbox.record_failure(entry, &failure.message, failure.retryable).await? {
+                            self.webhooks.count(false);
+                        }
+                        Ok(())
+                    }
+                }
+            }
+        }
     }
 
-    pub async fn create_execution(
+    /// Create a new execution from a stored one's original request with
+    /// `overrides` applied, linked back to the original
+    pub async fn resubmit_execution(
         &self,
-        request: CreateExecutionRequest,
+        auth_context: &AuthContext,
+        id: Uuid,
+        overrides: ResubmitOverrides,
     ) -> Result<ExecutionResponse, ApiError> {
-        // TODO: Get user_id from auth context
-        let user_id = "test-user".to_string();
-        let workspace_id = request.workspace_id.map(|id| id.to_string());
-        
-        // Send to execution service via gRPC
-        let mut client = self.execution_client.write().await;
-        let execution = client.create_execution(user_id, workspace_id, request).await?;
-        
-        // Cache the response
-        {
-            let mut executions = self.executions.write().await;
-            executions.insert(execution.id, execution.clone());
+        let original = self
+            .update_owned(auth_context, id, ADMIN_SCOPE, |cached| cached.meta().request.clone())
+            .await?
+            .ok_or_else(|| {
+                ApiError::BadRequest(format!(
+                    "The original request for execution {} is not available for resubmission",
+                    id
+                ))
+            })?;
+
+        self.submit_execution(
+            auth_context,
+            original.with_overrides(overrides),
+            Some(Lineage::Resubmitted(id)),
+        )
+        .await
+    }
+
+    /// Run a failed or timed-out execution again exactly as it was first
+    /// requested, as a new execution linked back to the original
+    pub async fn retry_execution(&self, auth_context: &AuthContext, id: Uuid) -> Result<ExecutionResponse, ApiError> {
+        let (status, original) = self
+            .update_owned(auth_context, id, ADMIN_SCOPE, |cached| {
+                (cached.meta().reported_status(cached.status()), cached.meta().request.clone())
+            })
+            .await?;
+        if !matches!(status, ExecutionStatus::Failed | ExecutionStatus::Timeout) {
+            return Err(ApiError::Conflict(format!(
+                "Execution {} is {:?}; only failed or timed-out executions can be retried",
+                id, status
+            )));
         }
-        
-        Ok(execution)
+        let original = original.ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "The original request for execution {} is not available for retry",
+                id
+            ))
+        })?;
+
+        self.submit_execution(auth_context, original, Some(Lineage::Retried(id)))
+            .await
     }
 
     pub async fn get_execution(&self, id: Uuid) -> Result<ExecutionResponse, ApiError> {
+        if self.is_purged(id) {
+            return Err(ApiError::NotFound);
+        }
         // Try cache first
         {
             let executions = self.executions.read().await;
-            if let Some(execution) = executions.get(&id) {
-                // If it's still pending/running, fetch latest from service
-                if execution.status == ExecutionStatus::Pending || execution.status == ExecutionStatus::Running {
-                    // Continue to fetch from service
-                } else {
-                    return Ok(execution.clone());
+            if let Some(cached) = executions.get(&id) {
+                if cached.meta().is_deleted() {
+                    return Err(ApiError::NotFound);
+                }
+                // If it's not final yet, fetch latest from service, unless
+                // the execution service is pushing updates and one arrived recently.
+                // Entries another replica invalidated always go upstream.
+                if !cached.is_stale() && (cached.is_terminal() || self.push_update_is_fresh(cached)) {
+                    match cached.unpack() {
+                        Ok(execution) => return Ok(execution),
+                        // Refetched below, which replaces the unreadable output
+                        Err(e) => warn!("{}", e),
+                    }
                 }
             }
         }
         
         // Fetch from execution service via gRPC
-        let mut client = self.execution_client.write().await;
-        let execution = client.get_execution(id).await?;
+        let mut execution = self.fetch_execution(id).await?;
         
-        // Update cache
-        {
-            let mut executions = self.executions.write().await;
-            executions.insert(execution.id, execution.clone());
+        // Update cache, unless the execution was deleted while the fetch was in flight
+        self.cache_execution(&mut execution, None).await;
+        if self.is_deleted(id).await {
+            return Err(ApiError::NotFound);
         }
         
         Ok(execution)
     }
 
+    /// Execution `id` for a client to display, and whether it was served
+    /// stale. Finished executions are answered from the cache and refreshed
+    /// in the background once old; unfinished ones fall back to the cached
+    /// copy when upstream takes longer than the read budget
+    pub async fn read_execution(self: &Arc<Self>, id: Uuid) -> Result<(ExecutionResponse, bool), ApiError> {
+        let stale_reads = &self.config.stale_reads;
+        let fallback = {
+            let executions = self.executions.read().await;
+            match executions.get(&id) {
+                Some(cached) if !self.is_purged(id) && !cached.meta().is_deleted() => {
+                    match cached.unpack() {
+                        Ok(execution) if cached.is_terminal() && !cached.is_stale() => {
+                            if stale_reads.revalidate_after.is_some_and(|after| cached.age() >= after) {
+                                self.revalidate_execution(id);
+                            }
+                            return Ok((execution, false));
+                        }
+                        Ok(_) => Some(cached.clone()),
+                        // Nothing to fall back on; refetched from upstream
+                        Err(e) => {
+                            warn!("{}", e);
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            }
+        };
+
+        let (Some(budget), Some(fallback)) = (stale_reads.upstream_budget, fallback) else {
+            return Ok((self.get_execution(id).await?, false));
+        };
+        // Fetched in its own task, so the cache is still brought up to date
+        // after the budget runs out
+        let state = self.clone();
+        let fetch = tokio::spawn(async move { state.get_execution(id).await });
+        match tokio::time::timeout(budget, fetch).await {
+            // Upstream being unreachable is treated like it being slow
+            Ok(Ok(Err(ApiError::ServiceUnavailable))) | Err(_) => {
+                self.metrics.record_stale_read();
+                Ok((fallback.unpack()?, true))
+            }
+            Ok(fetched) => {
+                let execution = fetched.map_err(|e| ApiError::Internal(e.into()))??;
+                Ok((execution, false))
+            }
+        }
+    }
+
+    /// Refresh a finished execution's cache entry in the background, unless
+    /// a fetch of it is already in flight
+    fn revalidate_execution(self: &Arc<Self>, id: Uuid) {
+        if self.execution_fetches.lock().unwrap().contains_key(&id) {
+            return;
+        }
+        let state = self.clone();
+        tokio::spawn(async move {
+            match state.fetch_execution(id).await {
+                Ok(mut execution) => {
+                    if !state.is_purged(id) {
+                        state.cache_execution(&mut execution, None).await;
+                        state.metrics.record_revalidation();
+                    }
+                }
+                Err(e) => warn!("Failed to revalidate cached execution {}: {}", id, e),
+            }
+        });
+    }
+
+    /// Version of execution `id`'s cache entry while reads are served from it
+    /// without going upstream: finished, not invalidated and not deleted
+    pub async fn settled_version(&self, id: Uuid) -> Option<u64> {
+        let executions = self.executions.read().await;
+        let cached = executions.get(&id)?;
+        (cached.is_terminal() && !cached.is_stale() && !cached.meta().is_deleted()).then(|| cached.version())
+    }
+
+    fn push_update_is_fresh(&self, cached: &CachedExecution) -> bool {
+        let callbacks = &self.config.callbacks;
+        callbacks.secret.is_some() && cached.age() < callbacks.poll_fallback_after
+    }
+
+    /// Wait until an execution finishes or `deadline` passes, returning the
+    /// latest snapshot either way; an unfinished one carries any output so far
+    pub async fn wait_for_completion(
+        &self,
+        id: Uuid,
+        deadline: tokio::time::Instant,
+    ) -> Result<ExecutionResponse, ApiError> {
+        // Subscribe before the first read so a transition in between isn't missed
+        let mut transitions = self.transitions.subscribe();
+
+        loop {
+            let execution = self.get_execution(id).await?;
+            let finished = execution.status.is_terminal();
+            if finished || tokio::time::Instant::now() >= deadline {
+                return Ok(execution);
+            }
+
+            let poll_at = (tokio::time::Instant::now() + self.config.sync_wait.poll_interval).min(deadline);
+            let transition = async {
+                loop {
+                    match transitions.recv().await {
+                        Ok(event) if event.execution_id != id => {}
+                        // Having lagged, this execution's change may be among those missed
+                        Ok(_) | Err(RecvError::Lagged(_)) => return,
+                        Err(RecvError::Closed) => std::future::pending().await,
+                    }
+                }
+            };
+            tokio::select! {
+                _ = transition => {}
+                _ = tokio::time::sleep_until(poll_at) => {}
+            }
+        }
+    }
+
+    /// Apply a state change pushed by the execution service, so readers see it
+    /// without another upstream fetch
+    pub async fn apply_update(&self, id: Uuid, update: ExecutionUpdate) -> Result<(), ApiError> {
+        let existing = self
+            .executions
+            .read()
+            .await
+            .get(&id)
+            .and_then(|cached| cached.unpack().ok());
+        let finished = update.status.is_terminal();
+
+        let mut execution = ExecutionResponse {
+            id,
+            status: update.status,
+            created_at: existing.as_ref().map_or_else(Utc::now, |e| e.created_at),
+            started_at: update
+                .started_at
+                .or_else(|| existing.as_ref().and_then(|e| e.started_at)),
+            completed_at: match update.completed_at {
+                Some(completed_at) => Some(completed_at),
+                None if finished => Some(Utc::now()),
+                None => None,
+            },
+            result: update.result.or_else(|| existing.and_then(|e| e.result)),
+            pinned: false,
+            output_limit_exceeded: false,
+            tty: false,
+            session_id: None,
+            group_id: None,
+            backend: Backend::Primary,
+            resubmitted_from: None,
+            retried_from: None,
+            cancellation: None,
+            annotations: Default::default(),
+            warnings: Vec::new(),
+            result_delivery: None,
+            trace_id: None,
+            trace_url: None,
+            code_sha256: None,
+            language_version: None,
+            metadata: Default::default(),
+            owner: None,
+        };
+        self.cache_execution(&mut execution, None).await;
+
+        // Callbacks reach a single replica; the others refetch on next read
+        self.invalidate(id, false).await;
+        Ok(())
+    }
+
+    /// Tell the other replicas execution `id` changed, so they refetch it or,
+    /// once `purged`, drop it
+    async fn invalidate(&self, id: Uuid, purged: bool) {
+        let invalidation = CacheInvalidation {
+            execution_id: id,
+            origin: self.instance_id,
+            purged,
+        };
+        self.publish(CACHE_INVALIDATION_TOPIC, &invalidation).await;
+    }
+
+    /// Force the next read of execution `id` upstream
+    async fn mark_stale(&self, id: Uuid) {
+        if let Some(cached) = self.executions.write().await.get_mut(&id) {
+            cached.mark_stale();
+        }
+    }
+
+    async fn is_deleted(&self, id: Uuid) -> bool {
+        self.executions
+            .read()
+            .await
+            .get(&id)
+            .is_some_and(|cached| cached.meta().is_deleted())
+    }
+
+    /// Apply `update` to an execution the caller may modify: their own, or any
+    /// with `scope` or the admin scope. Soft-deleted executions and executions
+    /// the caller can't modify are reported as not found.
+    async fn update_owned<T>(
+        &self,
+        auth_context: &AuthContext,
+        id: Uuid,
+        scope: &str,
+        update: impl FnOnce(&mut CachedExecution) -> T,
+    ) -> Result<T, ApiError> {
+        // Make sure there's an entry to update, fetching it if it isn't cached
+        self.get_execution(id).await?;
+
+        let mut executions = self.executions.write().await;
+        let cached = executions
+            .get_mut(&id)
+            .filter(|cached| {
+                !cached.meta().is_deleted()
+                    && (cached.meta().is_owned_by(&auth_context.user_id)
+                        || auth_context.has_scope(scope)
+                        || auth_context.has_scope(ADMIN_SCOPE))
+            })
+            .ok_or(ApiError::NotFound)?;
+        Ok(update(cached))
+    }
+
+    /// Execution `id` if the caller may see it: their own, or any with
+    /// `scope` or the admin scope; otherwise not found
+    pub async fn get_owned_execution(
+        &self,
+        auth_context: &AuthContext,
+        id: Uuid,
+        scope: &str,
+    ) -> Result<ExecutionResponse, ApiError> {
+        self.update_owned(auth_context, id, scope, |cached| cached.unpack()).await?
+    }
+
+    /// Remove an execution from the caller's history. This is a soft delete:
+    /// the execution stays recoverable by an admin until the purge window passes.
+    pub async fn delete_execution(&self, auth_context: &AuthContext, id: Uuid) -> Result<(), ApiError> {
+        let deleted_at = chrono::Utc::now();
+        self.update_owned(auth_context, id, ADMIN_SCOPE, |_| ()).await?;
+        // Stored first, so a failure leaves the execution visible everywhere
+        self.records
+            .set_deleted(id, Some(deleted_at))
+            .await
+            .map_err(ApiError::Internal)?;
+        if let Some(cached) = self.executions.write().await.get_mut(&id) {
+            cached.meta_mut().deleted_at = Some(deleted_at);
+        }
+        self.invalidate(id, false).await;
+
+        let id = id.to_string();
+        audit::record(
+            AuditEvent::new("execution.delete", &auth_context.user_id, AuditOutcome::Allowed)
+                .subject(&id)
+                .tenant(auth_context.tenant_id.as_deref()),
+        );
+        Ok(())
+    }
+
+    /// Remove a finished execution and everything held for it at once: its
+    /// code and output in the cache and SQL store, buffered logs, archived
+    /// payloads, artifacts and the execution service's copy. Unlike a soft
+    /// delete this can't be undone, so only the execution's known owner or an
+    /// admin may purge it; executions already soft-deleted can still be purged
+    pub async fn purge_execution(&self, auth_context: &AuthContext, id: Uuid) -> Result<(), ApiError> {
+        if let Err(e) = self.get_execution(id).await {
+            // Soft-deleted executions read as not found, wherever they were deleted
+            if !matches!(e, ApiError::NotFound) || !self.is_deleted(id).await {
+                return Err(e);
+            }
+        }
+        let (backend, files) = {
+            let executions = self.executions.read().await;
+            let cached = executions
+                .get(&id)
+                .filter(|cached| {
+                    cached.meta().is_owned_by(&auth_context.user_id) || auth_context.has_scope(ADMIN_SCOPE)
+                })
+                .ok_or(ApiError::NotFound)?;
+            if !cached.is_terminal() {
+                return Err(ApiError::Conflict(format!(
+                    "Execution {} is still running; cancel it before purging",
+                    id
+                )));
+            }
+            let files = cached.unpack()?.result.map(|result| result.files_created).unwrap_or_default();
+            (cached.meta().backend, files)
+        };
+
+        // Remote copies go first, so a failure leaves the execution in place to purge again
+        if let Some(store) = &self.artifact_store {
+            store.delete(id, &files).await?;
+        }
+        let reason = format!("Purged by {}", auth_context.user_id);
+        if !self.client_for(backend).read().await.delete_execution(id, &reason).await? {
+            info!(execution_id = %id, "Execution service can't delete executions; hiding it instead");
+        }
+        if let Some(pool) = &self.db {
+            sqlx::query("DELETE FROM executions WHERE id = $1::uuid")
+                .bind(id.to_string())
+                .execute(pool)
+                .await
+                .map_err(|e| ApiError::Internal(e.into()))?;
+        }
+
+        self.timelines.forget(id).await?;
+        self.forget_execution(id).await;
+        self.invalidate(id, true).await;
+
+        let id = id.to_string();
+        audit::record(
+            AuditEvent::new("execution.purge", &auth_context.user_id, AuditOutcome::Allowed)
+                .subject(&id)
+                .tenant(auth_context.tenant_id.as_deref()),
+        );
+        Ok(())
+    }
+
+    /// Drop everything this replica holds for a purged execution
+    async fn forget_execution(&self, id: Uuid) {
+        self.purged.lock().unwrap().insert(id, Utc::now());
+        self.executions.write().await.remove(&id);
+        self.output_buffers.remove(id);
+        self.concurrency_groups.release(id);
+        if let Some(cache) = &self.grpc_cache {
+            cache.remove(id);
+        }
+        self.payload_archive.forget_execution(id).await;
+    }
+
+    fn is_purged(&self, id: Uuid) -> bool {
+        self.purged.lock().unwrap().contains_key(&id)
+    }
+
+    /// Input for an unfinished execution the caller may modify
+    pub async fn open_stdin(&self, auth_context: &AuthContext, id: Uuid) -> Result<StdinWriter, ApiError> {
+        let backend = self
+            .update_owned(auth_context, id, ADMIN_SCOPE, |cached| {
+                if cached.is_terminal() {
+                    return Err(ApiError::BadRequest(format!("Execution {} has already finished", id)));
+                }
+                Ok(cached.meta().backend)
+            })
+            .await??;
+        Ok(StdinWriter {
+            client: self.client_for(backend).clone(),
+            id,
+        })
+    }
+
+    /// Stop an execution that hasn't finished on its backend at the caller's request
+    pub async fn cancel_execution(
+        &self,
+        auth_context: &AuthContext,
+        id: Uuid,
+    ) -> Result<ExecutionResponse, ApiError> {
+        let confirmed_owner = self
+            .update_owned(auth_context, id, ADMIN_SCOPE, |cached| {
+                if !cached.meta().reported_status(cached.status()).is_cancellable() {
+                    return Err(ApiError::BadRequest(
+                        "Only executions that haven't finished or started cancelling can be cancelled".to_string(),
+                    ));
+                }
+                Ok(cached.meta().owner.as_deref() == Some(auth_context.user_id.as_str()))
+            })
+            .await??;
+
+        // Executions reached through a scope, including those with no known
+        // owner, are cancelled as an admin
+        let cause = if confirmed_owner && auth_context.impersonated_by.is_none() {
+            CancelCause::User
+        } else {
+            CancelCause::Admin
+        };
+        let reason = format!("Cancelled by {}", auth_context.user_id);
+        self.cancel_upstream(id, Cancellation::new(cause, Some(&auth_context.user_id), reason))
+            .await?;
+        self.get_execution(id).await
+    }
+
+    /// Pin or unpin an execution, controlling whether retention cleanup may remove it
+    pub async fn set_pinned(
+        &self,
+        auth_context: &AuthContext,
+        id: Uuid,
+        pinned: bool,
+    ) -> Result<ExecutionResponse, ApiError> {
+        self.update_owned(auth_context, id, ADMIN_SCOPE, |_| ()).await?;
+        self.records
+            .set_pinned(id, pinned)
+            .await
+            .map_err(ApiError::Internal)?;
+        let execution = self
+            .executions
+            .write()
+            .await
+            .get_mut(&id)
+            .map(|cached| {
+                cached.meta_mut().pinned = pinned;
+                cached.unpack()
+            })
+            .ok_or(ApiError::NotFound)??;
+        self.invalidate(id, false).await;
+        Ok(execution)
+    }
+
+    /// Merge the caller's annotation on a finished execution, starting from
+    /// the stored annotations so every replica sees the same ones
+    pub async fn annotate_execution(
+        &self,
+        auth_context: &AuthContext,
+        id: Uuid,
+        patch: AnnotationPatch,
+    ) -> Result<ExecutionResponse, ApiError> {
+        patch.validate().map_err(ApiError::BadRequest)?;
+
+        let stored = self.records.annotations(id).await.map_err(ApiError::Internal)?;
+        let previous = self
+            .update_owned(auth_context, id, GRADER_SCOPE, |cached| {
+                if !cached.is_terminal() {
+                    return Err(ApiError::BadRequest(
+                        "Only finished executions can be annotated".to_string(),
+                    ));
+                }
+                let annotations = stored.as_ref().unwrap_or(&cached.meta().annotations);
+                Ok(annotations.get(&auth_context.user_id).cloned())
+            })
+            .await??;
+        let mut annotation = previous.unwrap_or_else(|| Annotation {
+            note: None,
+            score: None,
+            data: None,
+            updated_at: chrono::Utc::now(),
+        });
+        patch.apply(&mut annotation);
+
+        // Stored first, so a failure leaves the previous annotation everywhere
+        self.records
+            .annotate(id, &auth_context.user_id, &annotation)
+            .await
+            .map_err(ApiError::Internal)?;
+        let execution = self
+            .update_owned(auth_context, id, GRADER_SCOPE, |cached| {
+                let meta = cached.meta_mut();
+                if let Some(stored) = stored {
+                    meta.annotations = stored;
+                }
+                meta.annotations.insert(auth_context.user_id.clone(), annotation);
+                cached.unpack()
+            })
+            .await??;
+        self.invalidate(id, false).await;
+        Ok(execution)
+    }
+
+    /// The caller's executions matching `filter`, newest first and up to its
+    /// limit. With the SQL store, executions submitted through any replica
+    /// are read a page at a time as the stream is polled; ones not cached are
+    /// fetched from the execution service without being cached, so a long
+    /// listing doesn't fill the cache. Without it, the cache is listed
+    pub fn matching_executions(
+        self: &Arc<Self>,
+        auth_context: &AuthContext,
+        filter: ExecutionFilter,
+    ) -> BoxStream<'static, ExecutionResponse> {
+        let limit = filter.limit;
+        let filter = Arc::new(filter);
+        let user_id = auth_context.user_id.clone();
+        if !self.records.is_stored() {
+            let state = self.clone();
+            return futures::stream::once(async move { state.matching_cached(&user_id, &filter).await })
+                .flat_map(futures::stream::iter)
+                .boxed();
+        }
+
+        let page_size = STORED_PAGE_SIZE.min(limit.max(1));
+        let pages = {
+            let (state, user_id, filter) = (self.clone(), user_id.clone(), filter.clone());
+            // None once the last page was read
+            futures::stream::unfold(Some(None), move |after| {
+                let (state, user_id, filter) = (state.clone(), user_id.clone(), filter.clone());
+                async move {
+                    let after = after?;
+                    match state.records.matching(&user_id, &filter, after, page_size).await {
+                        Ok(page) => {
+                            let next = (page.len() == page_size).then(|| page.last().copied());
+                            Some((page, next))
+                        }
+                        Err(e) => {
+                            warn!("Failed to list executions from the SQL store: {}", e);
+                            None
+                        }
+                    }
+                }
+            })
+        };
+        let state = self.clone();
+        pages
+            .flat_map(futures::stream::iter)
+            .map(move |(_, id)| {
+                let (state, user_id, filter) = (state.clone(), user_id.clone(), filter.clone());
+                async move { state.listed_execution(&user_id, &filter, id).await }
+            })
+            .buffered(STORED_FETCH_CONCURRENCY)
+            .filter_map(futures::future::ready)
+            .take(limit)
+            .boxed()
+    }
+
+    /// Cached executions `user_id` owns matching `filter`, newest first and
+    /// up to its limit
+    async fn matching_cached(&self, user_id: &str, filter: &ExecutionFilter) -> Vec<ExecutionResponse> {
+        let executions = self.executions.read().await;
+        let mut matching: Vec<&CachedExecution> = executions
+            .iter()
+            .filter(|(id, cached)| self.is_listed(**id, cached, user_id, filter))
+            .map(|(_, cached)| cached)
+            .collect();
+        matching.sort_by_key(|cached| std::cmp::Reverse(cached.created_at()));
+        matching
+            .into_iter()
+            .take(filter.limit)
+            .filter_map(|cached| cached.unpack().inspect_err(|e| warn!("{}", e)).ok())
+            .collect()
+    }
+
+    /// Whether cached execution `id` belongs in a listing of `user_id`'s
+    /// executions matching `filter`
+    fn is_listed(&self, id: Uuid, cached: &CachedExecution, user_id: &str, filter: &ExecutionFilter) -> bool {
+        !self.is_purged(id) && filter.lists(cached, user_id)
+    }
+
+    /// Stored execution `id` for a listing of `user_id`'s executions matching
+    /// `filter`, from the cache when it's there and otherwise as the execution
+    /// service reports it with its stored state overlaid; None if it no
+    /// longer matches or can't be read
+    async fn listed_execution(&self, user_id: &str, filter: &ExecutionFilter, id: Uuid) -> Option<ExecutionResponse> {
+        if self.is_purged(id) {
+            return None;
+        }
+        let cached = self.executions.read().await.get(&id).map(|cached| {
+            let listed = self.is_listed(id, cached, user_id, filter);
+            listed.then(|| cached.unpack().inspect_err(|e| warn!("{}", e)).ok()).flatten()
+        });
+        if let Some(cached) = cached {
+            return cached;
+        }
+
+        // Tags live in the cached request, so an uncached execution can't match them
+        if !filter.tags.is_empty() {
+            return None;
+        }
+        let mut execution = self
+            .fetch_execution(id)
+            .await
+            .inspect_err(|e| warn!("Failed to load stored execution {}: {}", id, e))
+            .ok()?;
+        let record = self
+            .records
+            .load(id)
+            .await
+            .inspect_err(|e| warn!("Failed to load stored state of execution {}: {}", id, e))
+            .ok()
+            .flatten()?;
+        let mut meta = ExecutionMeta::default();
+        record.apply_to(&mut meta);
+        meta.apply_to(&mut execution);
+        let listed = !meta.is_deleted()
+            && meta.is_owned_by(user_id)
+            && filter.status.as_ref().is_none_or(|status| execution.status == *status);
+        listed.then_some(execution)
+    }
+
+    /// How many of the caller's executions may match `filter`; with the SQL
+    /// store an upper bound, as status and tags are checked while listing
+    pub async fn count_matching_executions(&self, auth_context: &AuthContext, filter: &ExecutionFilter) -> usize {
+        if !self.records.is_stored() {
+            let executions = self.executions.read().await;
+            let matching = executions
+                .iter()
+                .filter(|(id, cached)| self.is_listed(**id, cached, &auth_context.user_id, filter))
+                .count();
+            return matching.min(filter.limit);
+        }
+        match self.records.count_matching(&auth_context.user_id, filter).await {
+            Ok(count) => usize::try_from(count).unwrap_or(usize::MAX),
+            Err(e) => {
+                warn!("Failed to count executions in the SQL store: {}", e);
+                0
+            }
+        }
+    }
+
+    /// One page of the caller's executions from the execution service, with
+    /// gateway-owned fields filled in from the cache. Executions soft-deleted
+    /// here are left out, so a page may come back short; canary executions
+    /// aren't listed since only the primary backend is asked
+    pub async fn list_executions(
+        &self,
+        auth_context: &AuthContext,
+        query: &UpstreamListQuery,
+    ) -> Result<ExecutionPage, ApiError> {
+        let mut page = self
+            .execution_client
+            .read()
+            .await
+            .list_executions(&auth_context.user_id, query)
+            .await?;
+        let executions = self.executions.read().await;
+        let purged = self.purged.lock().unwrap().clone();
+        page.executions.retain_mut(|execution| {
+            if purged.contains_key(&execution.id) {
+                return false;
+            }
+            match executions.get(&execution.id) {
+                Some(cached) if cached.meta().is_deleted() => return false,
+                Some(cached) => cached.meta().apply_to(execution),
+                None => {}
+            }
+            // Cancelling is overlaid by the gateway, so upstream's filter can't tell it from running
+            query.status.as_ref().is_none_or(|status| execution.status == *status)
+        });
+        Ok(page)
+    }
+
+    /// The caller's executions matching `filter` among those the gateway
+    /// holds, newest first
+    pub async fn list_stored_executions(
+        self: &Arc<Self>,
+        auth_context: &AuthContext,
+        filter: &ExecutionFilter,
+    ) -> Vec<ExecutionResponse> {
+        self.matching_executions(auth_context, filter.clone()).collect().await
+    }
+
+    /// Restore a soft-deleted execution that hasn't been purged yet, whichever
+    /// replica deleted it
+    pub async fn undelete_execution(&self, id: Uuid) -> Result<ExecutionResponse, ApiError> {
+        let restored_stored = self
+            .records
+            .set_deleted(id, None)
+            .await
+            .map_err(ApiError::Internal)?;
+        let restored_cached = self
+            .executions
+            .write()
+            .await
+            .get_mut(&id)
+            .filter(|cached| cached.meta().is_deleted())
+            .map(|cached| cached.meta_mut().deleted_at = None)
+            .is_some();
+        if !restored_stored && !restored_cached {
+            return Err(ApiError::NotFound);
+        }
+        self.invalidate(id, false).await;
+        self.get_execution(id).await
+    }
+
+    /// Cached executions matching `request` that `eligible` accepts, oldest
+    /// first and up to its limit, with how many matched the filter at all
+    async fn bulk_candidates(
+        &self,
+        request: &BulkRequest,
+        eligible: impl Fn(&CachedExecution) -> bool,
+    ) -> (usize, Vec<BulkOutcome>) {
+        let now = Utc::now();
+        let executions = self.executions.read().await;
+        let mut matching: Vec<(&Uuid, &CachedExecution)> = executions
+            .iter()
+            .filter(|(_, cached)| {
+                let status = cached.meta().reported_status(cached.status());
+                request.filter.matches(cached.meta(), &status, cached.created_at(), now)
+            })
+            .collect();
+        let matched = matching.len();
+        matching.retain(|(_, cached)| eligible(cached));
+        matching.sort_by_key(|(_, cached)| cached.created_at());
+        let candidates = matching
+            .into_iter()
+            .take(request.limit())
+            .map(|(id, cached)| BulkOutcome {
+                execution_id: *id,
+                status: cached.meta().reported_status(cached.status()),
+                tenant_id: cached.meta().tenant_id.clone(),
+                requeued_as: None,
+                error: None,
+            })
+            .collect();
+        (matched, candidates)
+    }
+
+    /// Cancel the cached executions matching `request` that can still be
+    /// cancelled. Every replica acts on its own cache, so an operator runs
+    /// this against each replica.
+    pub async fn bulk_cancel(&self, actor: &AuthContext, request: &BulkRequest) -> BulkReport {
+        let (matched, candidates) = self
+            .bulk_candidates(request, |cached| {
+                cached.meta().reported_status(cached.status()).is_cancellable()
+            })
+            .await;
+        if request.dry_run {
+            return BulkReport::new(true, matched, candidates);
+        }
+
+        let cancellation = Cancellation::new(
+            CancelCause::Admin,
+            Some(&actor.user_id),
+            format!("Bulk cancelled by {}", actor.user_id),
+        );
+        let outcomes = futures::stream::iter(candidates)
+            .map(|mut outcome| {
+                let cancellation = cancellation.clone();
+                async move {
+                    if let Err(e) = self.cancel_upstream(outcome.execution_id, cancellation).await {
+                        outcome.error = Some(e.to_string());
+                    }
+                    outcome
+                }
+            })
+            .buffer_unordered(BULK_CONCURRENCY)
+            .collect()
+            .await;
+        BulkReport::new(false, matched, outcomes)
+    }
+
+    /// Resubmit the cached executions matching `request` from their original
+    /// requests on behalf of their owners, cancelling unfinished ones first.
+    /// Executions without a stored request or owner can't be requeued. Every
+    /// replica acts on its own cache.
+    pub async fn bulk_requeue(&self, actor: &AuthContext, request: &BulkRequest) -> BulkReport {
+        let (matched, candidates) = self
+            .bulk_candidates(request, |cached| {
+                cached.meta().request.is_some() && cached.meta().owner.is_some()
+            })
+            .await;
+        if request.dry_run {
+            return BulkReport::new(true, matched, candidates);
+        }
+
+        let outcomes = futures::stream::iter(candidates)
+            .map(|mut outcome| async move {
+                match self.requeue(actor, outcome.execution_id).await {
+                    Ok(requeued_as) => {
+                        outcome.requeued_as = Some(requeued_as);
+                        let id = outcome.execution_id.to_string();
+                        audit::record(
+                            AuditEvent::new("execution.requeue", &actor.user_id, AuditOutcome::Allowed)
+                                .subject(&id)
+                                .tenant(outcome.tenant_id.as_deref()),
+                        );
+                    }
+                    Err(e) => outcome.error = Some(e.to_string()),
+                }
+                outcome
+            })
+            .buffer_unordered(BULK_CONCURRENCY)
+            .collect()
+            .await;
+        BulkReport::new(false, matched, outcomes)
+    }
+
+    /// Resubmit one execution as its owner, returning the new execution's ID
+    async fn requeue(&self, actor: &AuthContext, id: Uuid) -> Result<Uuid, ApiError> {
+        let (owner, request, cancellable) = {
+            let executions = self.executions.read().await;
+            let cached = executions.get(&id).ok_or(ApiError::NotFound)?;
+            let meta = cached.meta();
+            let (Some(user_id), Some(request)) = (meta.owner.clone(), meta.request.clone()) else {
+                return Err(ApiError::BadRequest(format!(
+                    "Execution {} has no stored request to requeue",
+                    id
+                )));
+            };
+            let owner = AuthContext {
+                user_id,
+                tenant_id: meta.tenant_id.clone(),
+                token: String::new(),
+                scopes: Vec::new(),
+                impersonated_by: Some(actor.user_id.clone()),
+            };
+            (owner, request, meta.reported_status(cached.status()).is_cancellable())
+        };
+        if cancellable {
+            let reason = format!("Requeued by {}", actor.user_id);
+            self.cancel_upstream(id, Cancellation::new(CancelCause::Admin, Some(&actor.user_id), reason))
+                .await?;
+        }
+        let execution = self.submit_execution(&owner, request, Some(Lineage::Resubmitted(id))).await?;
+        Ok(execution.id)
+    }
+
+    /// Fetch an execution upstream, joining any fetch already in flight for the same ID
+    async fn fetch_execution(&self, id: Uuid) -> Result<ExecutionResponse, ApiError> {
+        let backend = self.backend_of(id).await;
+        let fetch = {
+            let mut fetches = self.execution_fetches.lock().unwrap();
+            fetches
+                .entry(id)
+                .or_insert_with(|| {
+                    let client = self.client_for(backend).clone();
+                    async move { Arc::new(client.read().await.get_execution(id).await) }
+                        .boxed()
+                        .shared()
+                })
+                .clone()
+        };
+
+        let result = fetch.await;
+
+        // Clear the completed fetch so the next poll interval goes upstream again
+        {
+            let mut fetches = self.execution_fetches.lock().unwrap();
+            if fetches.get(&id).is_some_and(|f| f.peek().is_some()) {
+                fetches.remove(&id);
+            }
+        }
+
+        match &*result {
+            Ok(execution) => Ok(execution.clone()),
+            Err(e) => Err(e.duplicate()),
+        }
+    }
+
+    /// Output of an execution as it's delivered, each event paired with the
+    /// offsets delivered so far; ends after the execution finishes or the
+    /// upstream stream breaks
+    pub async fn stream_output(
+        self: &Arc<Self>,
+        id: Uuid,
+        start: OutputStart,
+    ) -> Result<BoxStream<'static, Result<(OutputEvent, Offsets), ApiError>>, ApiError> {
+        let (offsets, subscription) = self.subscribe_output(id, start).await?;
+        let (tx, rx) = mpsc::channel(OUTPUT_CHANNEL_DEPTH);
+        tokio::spawn(self.clone().forward_output(id, offsets, subscription, tx));
+        Ok(tokio_stream::wrappers::ReceiverStream::new(rx).boxed())
+    }
+
+    /// Buffered output followed by live output while the execution runs, or
+    /// its stored output once it has finished, with the offsets it starts after
+    async fn subscribe_output(
+        self: &Arc<Self>,
+        id: Uuid,
+        start: OutputStart,
+    ) -> Result<(Offsets, OutputSubscription), ApiError> {
+        if let Some(subscription) = self.output_buffers.replay(id, start) {
+            return subscription;
+        }
+
+        let execution = self.get_execution(id).await?;
+        if execution.status.is_terminal() {
+            return Ok(OutputSubscription::completed(&execution, start, self.output_buffers.config()));
+        }
+
+        if self.output_buffers.open(id, execution.status.clone()) {
+            let state = self.clone();
+            tokio::spawn(async move { state.pump_output(id).await });
+        }
+        self.output_buffers
+            .replay(id, start)
+            .unwrap_or(Err(ApiError::ServiceUnavailable))
+    }
+
+    /// Send output to one subscriber, resubscribing from what it has already
+    /// received whenever it falls behind the live output
+    async fn forward_output(
+        self: Arc<Self>,
+        id: Uuid,
+        mut offsets: Offsets,
+        mut subscription: OutputSubscription,
+        tx: OutputSender,
+    ) {
+        loop {
+            for event in subscription.backlog.drain(..) {
+                if !deliver_output(&tx, &mut offsets, event).await {
+                    return;
+                }
+            }
+            let Some(mut live) = subscription.live.take() else {
+                return;
+            };
+            loop {
+                match live.recv().await {
+                    Ok(event) => {
+                        if !deliver_output(&tx, &mut offsets, event).await {
+                            return;
+                        }
+                    }
+                    Err(RecvError::Lagged(_)) => break,
+                    Err(RecvError::Closed) => {
+                        let event = OutputEvent::Interrupted("Output stream ended".to_string());
+                        deliver_output(&tx, &mut offsets, event).await;
+                        return;
+                    }
+                }
+            }
+            subscription = match self.subscribe_output(id, OutputStart::From(offsets)).await {
+                Ok((_, subscription)) => subscription,
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            };
+        }
+    }
+
+    /// Copy one upstream output stream into the execution's buffer, keeping the
+    /// buffer around for reconnecting clients once the stream ends
+    async fn pump_output(&self, id: Uuid) {
+        let tenant_id = self
+            .executions
+            .read()
+            .await
+            .get(&id)
+            .and_then(|cached| cached.meta().tenant_id.clone());
+        let limit = self.tenant_settings.output_limit(tenant_id.as_deref()).await;
+        let mut output_bytes: u64 = 0;
+        let mut cancelled = false;
+
+        let backend = self.backend_of(id).await;
+        let stream = self.client_for(backend).read().await.stream_execution(id).await;
+        let finished = match stream {
+            Ok(mut stream) => loop {
+                match stream.next().await {
+                    // Output past the cap is dropped while the cancellation takes effect
+                    Some(Ok(UpstreamEvent::Output(_, _))) if cancelled => {}
+                    Some(Ok(UpstreamEvent::Output(output, data))) => {
+                        output_bytes += data.len() as u64;
+                        match limit {
+                            Some(limit) if output_bytes > limit => {
+                                cancelled = true;
+                                self.stop_for_output_limit(id, limit).await;
+                            }
+                            _ => self.output_buffers.append(id, output, data),
+                        }
+                    }
+                    Some(Ok(UpstreamEvent::Status(status))) => {
+                        if status.is_terminal() {
+                            break Some(status);
+                        }
+                        self.output_buffers.status(id, status);
+                    }
+                    Some(Err(e)) => {
+                        warn!("Output stream for execution {} failed: {}", id, e);
+                        break None;
+                    }
+                    None => break None,
+                }
+            },
+            Err(e) => {
+                warn!("Failed to open output stream for execution {}: {}", id, e);
+                None
+            }
+        };
+
+        match finished {
+            Some(status) => {
+                self.output_buffers.finish(id, OutputEvent::Finished(status));
+                tokio::time::sleep(self.output_buffers.linger()).await;
+            }
+            None => self.output_buffers.finish(
+                id,
+                OutputEvent::Interrupted("Output stream from the execution service ended".to_string()),
+            ),
+        }
+        // An interrupted buffer goes at once so the next subscriber reopens the stream
+        self.output_buffers.remove(id);
+    }
+
+    /// Cancel an execution whose output passed its tenant's cap and flag it
+    async fn stop_for_output_limit(&self, id: Uuid, limit: u64) {
+        warn!("Execution {} exceeded its {} byte output limit, cancelling", id, limit);
+        if let Some(cached) = self.executions.write().await.get_mut(&id) {
+            cached.meta_mut().output_limit_exceeded = true;
+        }
+        let reason = format!("Output exceeded the {} byte limit", limit);
+        if let Err(e) = self.cancel_upstream(id, Cancellation::new(CancelCause::Quota, None, reason)).await {
+            warn!("Failed to cancel execution {} over its output limit: {}", id, e);
+        }
+    }
+
     pub async fn get_execution_status(&self, id: Uuid) -> Result<ExecutionStatus, ApiError> {
         let execution = self.get_execution(id).await?;
         Ok(execution.status)
     }
-}
\ No newline at end of file
+
+    /// Statuses of several executions at once, fetching those not settled in
+    /// the cache concurrently. Executions the caller doesn't own, and deleted
+    /// ones, are not found unless the caller is an admin
+    pub async fn get_execution_statuses(
+        &self,
+        auth_context: &AuthContext,
+        ids: &[Uuid],
+    ) -> Vec<(Uuid, Result<ExecutionStatus, ApiError>)> {
+        futures::stream::iter(ids.iter().copied())
+            .map(|id| async move {
+                let execution = self.get_owned_execution(auth_context, id, ADMIN_SCOPE).await;
+                (id, execution.map(|execution| execution.status))
+            })
+            .buffer_unordered(STATUS_BATCH_CONCURRENCY)
+            .collect()
+            .await
+    }
+}
+/// Most executions one status query can ask about
+pub const MAX_STATUS_BATCH: usize = 100;
+
+/// Upstream fetches in flight for one status query
+const STATUS_BATCH_CONCURRENCY: usize = 16;
+
+/// Upstream fetches in flight reading uncached stored executions for a listing
+const STORED_FETCH_CONCURRENCY: usize = 16;
+
+/// Most executions read from the SQL store at a time for a listing
+const STORED_PAGE_SIZE: usize = 100;
+
+/// Output events queued ahead of a slow subscriber
+const OUTPUT_CHANNEL_DEPTH: usize = 16;
+
+/// Status changes buffered for local waiters before slow ones start missing them
+const TRANSITION_CHANNEL_DEPTH: usize = 1024;
+
+type OutputSender = mpsc::Sender<Result<(OutputEvent, Offsets), ApiError>>;
+
+/// Send one event unless the subscriber already has it, returning whether
+/// more may follow
+async fn deliver_output(tx: &OutputSender, offsets: &mut Offsets, event: OutputEvent) -> bool {
+    let event = match event {
+        OutputEvent::Output(chunk) => {
+            // Live output may overlap what a replay already delivered
+            let Some(chunk) = chunk.after(offsets) else {
+                return true;
+            };
+            offsets.advance(&chunk);
+            OutputEvent::Output(chunk)
+        }
+        event => event,
+    };
+    let more = matches!(event, OutputEvent::Output(_) | OutputEvent::Status(_));
+    tx.send(Ok((event, *offsets))).await.is_ok() && more
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StorageConfig;
+
+    fn filter() -> ExecutionFilter {
+        ExecutionFilter {
+            status: None,
+            pinned: None,
+            created_after: None,
+            created_before: None,
+            session_id: None,
+            group_id: None,
+            tags: Vec::new(),
+            limit: DEFAULT_LIST_LIMIT,
+        }
+    }
+
+    fn filter_with(change: impl FnOnce(&mut ExecutionFilter)) -> ExecutionFilter {
+        let mut filter = filter();
+        change(&mut filter);
+        filter
+    }
+
+    /// A running execution `owner` submitted in session `s-1` of group `g-1`,
+    /// tagged `team:infra`
+    fn cached(owner: Option<&str>) -> CachedExecution {
+        let config = StorageConfig {
+            compression_threshold_bytes: 64 * 1024,
+            compression_level: 3,
+        };
+        let mut execution = ExecutionResponse::new_pending();
+        execution.status = ExecutionStatus::Running;
+        let mut cached = CachedExecution::pack(execution, &config, &Metrics::new());
+        let request = serde_json::from_value(serde_json::json!({
+            "language": "python",
+            "code": "print(1)",
+            "session_id": "s-1",
+            "group_id": "g-1",
+            "metadata": { "team": "infra" },
+        }))
+        .unwrap();
+        let meta = cached.meta_mut();
+        meta.owner = owner.map(str::to_string);
+        meta.request = Some(request);
+        cached
+    }
+
+    #[test]
+    fn listings_only_include_the_callers_executions() {
+        assert!(filter().lists(&cached(Some("alice")), "alice"));
+        assert!(!filter().lists(&cached(Some("alice")), "bob"));
+        // Executions with no known owner are listed to no one
+        assert!(!filter().lists(&cached(None), "alice"));
+    }
+
+    #[test]
+    fn listings_leave_out_deleted_executions() {
+        let mut cached = cached(Some("alice"));
+        cached.meta_mut().deleted_at = Some(Utc::now());
+        assert!(!filter().lists(&cached, "alice"));
+    }
+
+    #[test]
+    fn listings_apply_the_filter_to_owned_executions() {
+        let cached = cached(Some("alice"));
+        let created_at = cached.created_at();
+        let second = chrono::Duration::seconds(1);
+
+        let matching = [
+            filter_with(|f| f.status = Some(ExecutionStatus::Running)),
+            filter_with(|f| f.pinned = Some(false)),
+            filter_with(|f| f.created_after = Some(created_at)),
+            filter_with(|f| f.created_before = Some(created_at + second)),
+            filter_with(|f| f.session_id = Some("s-1".to_string())),
+            filter_with(|f| f.group_id = Some("g-1".to_string())),
+            filter_with(|f| f.tags = vec![Tag::parse("team:infra").unwrap()]),
+        ];
+        for filter in &matching {
+            assert!(filter.lists(&cached, "alice"), "{:?}", filter);
+            assert!(!filter.lists(&cached, "bob"), "{:?}", filter);
+        }
+
+        let excluding = [
+            filter_with(|f| f.status = Some(ExecutionStatus::Completed)),
+            filter_with(|f| f.pinned = Some(true)),
+            filter_with(|f| f.created_after = Some(created_at + second)),
+            filter_with(|f| f.created_before = Some(created_at)),
+            filter_with(|f| f.session_id = Some("s-2".to_string())),
+            filter_with(|f| f.group_id = Some("g-2".to_string())),
+            filter_with(|f| f.tags = vec![Tag::parse("team:web").unwrap()]),
+        ];
+        for filter in &excluding {
+            assert!(!filter.lists(&cached, "alice"), "{:?}", filter);
+        }
+    }
+}
@@ -0,0 +1,482 @@
+use std::borrow::Cow;
+use std::io::{self, Cursor, Write};
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    body::{Body, Bytes},
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        FromRequestParts,
+    },
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use flate2::{write::GzEncoder, Compress, Compression, Decompress, FlushCompress, FlushDecompress};
+use futures::{Future, StreamExt};
+use hyper::upgrade::{OnUpgrade, Upgraded};
+use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::debug;
+use tungstenite::protocol::frame::{
+    coding::{Control, Data, OpCode},
+    FrameHeader,
+};
+
+use crate::config::StreamCompressionConfig;
+use crate::response::accepts_encoding;
+use crate::state::AppState;
+
+/// Bytes a sync-flushed deflate block ends with; permessage-deflate leaves
+/// them off every message
+const DEFLATE_TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Largest message a client may send, after inflating
+const MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Gzip a streamed response for a client that accepts it, flushing after
+/// every chunk so each event still reaches the client as soon as it's sent
+pub fn gzip_stream(response: Response, request_headers: &HeaderMap, config: &StreamCompressionConfig) -> Response {
+    if !config.sse_gzip || !accepts_encoding(request_headers, "gzip") {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    parts.headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+    parts.headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+    parts.headers.remove(header::CONTENT_LENGTH);
+
+    let encoder = GzEncoder::new(Vec::new(), Compression::new(config.level));
+    let chunks = futures::stream::unfold(
+        (body.into_data_stream(), Some(encoder)),
+        |(mut body, encoder)| async move {
+            let mut encoder = encoder?;
+            match body.next().await {
+                Some(Ok(chunk)) => {
+                    let compressed = encoder
+                        .write_all(&chunk)
+                        .and_then(|()| encoder.flush())
+                        .map(|()| Bytes::from(std::mem::take(encoder.get_mut())));
+                    Some((compressed, (body, Some(encoder))))
+                }
+                Some(Err(e)) => Some((Err(io::Error::other(e)), (body, None))),
+                None => Some((encoder.finish().map(Bytes::from), (body, None))),
+            }
+        },
+    );
+    Response::from_parts(parts, Body::from_stream(chunks))
+}
+
+/// What the gateway agreed to in a permessage-deflate negotiation
+#[derive(Debug, Clone, Copy)]
+struct DeflateParams {
+    /// The client asked the gateway to compress every message on its own
+    server_no_context_takeover: bool,
+}
+
+impl DeflateParams {
+    /// The first permessage-deflate offer in `headers` the gateway can
+    /// accept; it always compresses with a full window, so offers that
+    /// shrink the server's window are declined
+    fn negotiate(headers: &HeaderMap) -> Option<Self> {
+        headers
+            .get_all(header::SEC_WEBSOCKET_EXTENSIONS)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .find_map(|offer| {
+                let mut params = offer.split(';').map(str::trim);
+                if params.next() != Some("permessage-deflate") {
+                    return None;
+                }
+                let mut negotiated = Self {
+                    server_no_context_takeover: false,
+                };
+                for param in params {
+                    let (name, value) = match param.split_once('=') {
+                        Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                        None => (param, None),
+                    };
+                    match (name, value) {
+                        ("server_no_context_takeover", None) => negotiated.server_no_context_takeover = true,
+                        ("client_no_context_takeover", None) | ("client_max_window_bits", _) => {}
+                        ("server_max_window_bits", Some("15")) => {}
+                        _ => return None,
+                    }
+                }
+                Some(negotiated)
+            })
+    }
+
+    /// `Sec-WebSocket-Extensions` value accepting the offer
+    fn response_header(&self) -> HeaderValue {
+        HeaderValue::from_static(if self.server_no_context_takeover {
+            "permessage-deflate; server_no_context_takeover"
+        } else {
+            "permessage-deflate"
+        })
+    }
+}
+
+/// WebSocket upgrade for interactive sessions: permessage-deflate when it's
+/// enabled and the client offers it, a plain socket otherwise
+pub struct SessionUpgrade(Upgrade);
+
+enum Upgrade {
+    Plain(WebSocketUpgrade),
+    Deflate {
+        key: HeaderValue,
+        on_upgrade: OnUpgrade,
+        params: DeflateParams,
+        level: u32,
+    },
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for SessionUpgrade {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let config = &state.config().stream_compression;
+        let params = config
+            .websocket_deflate
+            .then(|| DeflateParams::negotiate(&parts.headers))
+            .flatten();
+        // The plain upgrade validates the handshake and takes the connection;
+        // a deflate session takes it over from a clone instead
+        let on_upgrade = parts.extensions.get::<OnUpgrade>().cloned();
+        let key = parts.headers.get(header::SEC_WEBSOCKET_KEY).cloned();
+        let plain = WebSocketUpgrade::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        match (params, on_upgrade, key) {
+            (Some(params), Some(on_upgrade), Some(key)) => Ok(Self(Upgrade::Deflate {
+                key,
+                on_upgrade,
+                params,
+                level: config.level,
+            })),
+            _ => Ok(Self(Upgrade::Plain(plain))),
+        }
+    }
+}
+
+impl SessionUpgrade {
+    /// Finish the handshake and run `callback` with the socket once the
+    /// connection is upgraded
+    pub fn on_upgrade<C, Fut>(self, callback: C) -> Response
+    where
+        C: FnOnce(SessionSocket) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (key, on_upgrade, params, level) = match self.0 {
+            Upgrade::Plain(upgrade) => {
+                return upgrade.on_upgrade(move |socket| callback(SessionSocket::Plain(socket)));
+            }
+            Upgrade::Deflate {
+                key,
+                on_upgrade,
+                params,
+                level,
+            } => (key, on_upgrade, params, level),
+        };
+
+        tokio::spawn(async move {
+            match on_upgrade.await {
+                Ok(upgraded) => {
+                    let socket = DeflateSocket::new(TokioIo::new(upgraded), params, level);
+                    callback(SessionSocket::Deflate(socket)).await;
+                }
+                Err(e) => debug!("WebSocket upgrade failed: {}", e),
+            }
+        });
+
+        let accept = tungstenite::handshake::derive_accept_key(key.as_bytes());
+        let mut response = StatusCode::SWITCHING_PROTOCOLS.into_response();
+        let headers = response.headers_mut();
+        headers.insert(header::CONNECTION, HeaderValue::from_static("upgrade"));
+        headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+        if let Ok(accept) = HeaderValue::from_str(&accept) {
+            headers.insert(header::SEC_WEBSOCKET_ACCEPT, accept);
+        }
+        headers.insert(header::SEC_WEBSOCKET_EXTENSIONS, params.response_header());
+        response
+    }
+}
+
+/// An upgraded interactive session socket
+pub enum SessionSocket {
+    Plain(WebSocket),
+    Deflate(DeflateSocket),
+}
+
+impl SessionSocket {
+    /// The next message from the client, or `None` once the connection is
+    /// gone; safe to cancel
+    pub async fn recv(&mut self) -> Option<Result<Message, axum::Error>> {
+        match self {
+            Self::Plain(socket) => socket.recv().await,
+            Self::Deflate(socket) => socket.recv().await,
+        }
+    }
+
+    pub async fn send(&mut self, message: Message) -> Result<(), axum::Error> {
+        match self {
+            Self::Plain(socket) => socket.send(message).await,
+            Self::Deflate(socket) => socket.send(message).await,
+        }
+    }
+}
+
+/// A message being received in fragments, as (opcode, compressed, payload)
+type Fragments = (Data, bool, Vec<u8>);
+
+/// WebSocket speaking permessage-deflate, framed here because the socket
+/// axum provides rejects the compressed-message bit. Pings are answered
+/// without surfacing them
+pub struct DeflateSocket {
+    io: TokioIo<Upgraded>,
+    read_buf: Vec<u8>,
+    /// Encoded frames not yet written, so an interrupted write resumes
+    write_buf: Vec<u8>,
+    fragments: Option<Fragments>,
+    compress: Compress,
+    decompress: Decompress,
+    params: DeflateParams,
+    closed: bool,
+}
+
+impl DeflateSocket {
+    fn new(io: TokioIo<Upgraded>, params: DeflateParams, level: u32) -> Self {
+        Self {
+            io,
+            read_buf: Vec::new(),
+            write_buf: Vec::new(),
+            fragments: None,
+            compress: Compress::new(Compression::new(level), false),
+            decompress: Decompress::new(false),
+            params,
+            closed: false,
+        }
+    }
+
+    async fn recv(&mut self) -> Option<Result<Message, axum::Error>> {
+        while !self.closed {
+            if let Err(e) = self.flush().await {
+                return Some(Err(e));
+            }
+            match self.next_frame() {
+                Ok(Some((header, payload))) => match self.handle_frame(header, payload) {
+                    Ok(Some(message)) => {
+                        if matches!(message, Message::Close(_)) {
+                            self.closed = true;
+                            let _ = self.flush().await;
+                        }
+                        return Some(Ok(message));
+                    }
+                    Ok(None) => continue,
+                    Err(e) => return Some(Err(e)),
+                },
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+            match self.io.read_buf(&mut self.read_buf).await {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(axum::Error::new(e))),
+            }
+        }
+        None
+    }
+
+    async fn send(&mut self, message: Message) -> Result<(), axum::Error> {
+        let (opcode, payload) = match message {
+            Message::Text(text) => (OpCode::Data(Data::Text), self.deflate(text.as_bytes())?),
+            Message::Binary(data) => (OpCode::Data(Data::Binary), self.deflate(&data)?),
+            Message::Ping(data) => (OpCode::Control(Control::Ping), data),
+            Message::Pong(data) => (OpCode::Control(Control::Pong), data),
+            Message::Close(frame) => {
+                let payload = frame
+                    .map(|frame| [&frame.code.to_be_bytes()[..], frame.reason.as_bytes()].concat())
+                    .unwrap_or_default();
+                (OpCode::Control(Control::Close), payload)
+            }
+        };
+        self.queue(opcode, &payload);
+        self.flush().await
+    }
+
+    /// Frame `payload` for writing; data frames always carry compressed payloads
+    fn queue(&mut self, opcode: OpCode, payload: &[u8]) {
+        let header = FrameHeader {
+            rsv1: matches!(opcode, OpCode::Data(_)),
+            opcode,
+            ..FrameHeader::default()
+        };
+        let _ = header.format(payload.len() as u64, &mut self.write_buf);
+        self.write_buf.extend_from_slice(payload);
+    }
+
+    async fn flush(&mut self) -> Result<(), axum::Error> {
+        if self.write_buf.is_empty() {
+            return Ok(());
+        }
+        while !self.write_buf.is_empty() {
+            let written = self.io.write(&self.write_buf).await.map_err(axum::Error::new)?;
+            if written == 0 {
+                return Err(axum::Error::new(io::Error::from(io::ErrorKind::WriteZero)));
+            }
+            self.write_buf.drain(..written);
+        }
+        self.io.flush().await.map_err(axum::Error::new)
+    }
+
+    /// The next complete frame in the read buffer, unmasked
+    fn next_frame(&mut self) -> Result<Option<(FrameHeader, Vec<u8>)>, axum::Error> {
+        let mut cursor = Cursor::new(&self.read_buf);
+        let Some((header, length)) = FrameHeader::parse(&mut cursor).map_err(axum::Error::new)? else {
+            return Ok(None);
+        };
+        if length > MAX_MESSAGE_BYTES as u64 {
+            return Err(protocol_error("Message too large"));
+        }
+        let start = cursor.position() as usize;
+        let end = start + length as usize;
+        if self.read_buf.len() < end {
+            return Ok(None);
+        }
+        let mut payload = self.read_buf[start..end].to_vec();
+        self.read_buf.drain(..end);
+        let Some(mask) = header.mask else {
+            return Err(protocol_error("Client frames must be masked"));
+        };
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+        Ok(Some((header, payload)))
+    }
+
+    /// Apply one frame, returning the message it completes, if any
+    fn handle_frame(&mut self, header: FrameHeader, payload: Vec<u8>) -> Result<Option<Message>, axum::Error> {
+        if header.rsv2 || header.rsv3 {
+            return Err(protocol_error("Reserved bits set"));
+        }
+        match header.opcode {
+            OpCode::Control(control) => {
+                if header.rsv1 || !header.is_final {
+                    return Err(protocol_error("Invalid control frame"));
+                }
+                match control {
+                    Control::Ping => {
+                        self.queue(OpCode::Control(Control::Pong), &payload);
+                        Ok(None)
+                    }
+                    Control::Pong => Ok(None),
+                    Control::Close => {
+                        let frame = (payload.len() >= 2).then(|| CloseFrame {
+                            code: u16::from_be_bytes([payload[0], payload[1]]),
+                            reason: Cow::Owned(String::from_utf8_lossy(&payload[2..]).into_owned()),
+                        });
+                        // Echo the close before the connection goes away
+                        self.queue(OpCode::Control(Control::Close), &payload[..payload.len().min(2)]);
+                        Ok(Some(Message::Close(frame)))
+                    }
+                    Control::Reserved(_) => Err(protocol_error("Unknown opcode")),
+                }
+            }
+            OpCode::Data(Data::Continue) => {
+                let Some((data, compressed, mut buffered)) = self.fragments.take() else {
+                    return Err(protocol_error("Continuation without a message"));
+                };
+                if header.rsv1 {
+                    return Err(protocol_error("Compression bit on a continuation frame"));
+                }
+                if buffered.len() + payload.len() > MAX_MESSAGE_BYTES {
+                    return Err(protocol_error("Message too large"));
+                }
+                buffered.extend_from_slice(&payload);
+                if header.is_final {
+                    self.finish_message(data, compressed, buffered).map(Some)
+                } else {
+                    self.fragments = Some((data, compressed, buffered));
+                    Ok(None)
+                }
+            }
+            OpCode::Data(data @ (Data::Text | Data::Binary)) => {
+                if self.fragments.is_some() {
+                    return Err(protocol_error("New message before the last one finished"));
+                }
+                if header.is_final {
+                    self.finish_message(data, header.rsv1, payload).map(Some)
+                } else {
+                    self.fragments = Some((data, header.rsv1, payload));
+                    Ok(None)
+                }
+            }
+            OpCode::Data(Data::Reserved(_)) => Err(protocol_error("Unknown opcode")),
+        }
+    }
+
+    fn finish_message(&mut self, data: Data, compressed: bool, payload: Vec<u8>) -> Result<Message, axum::Error> {
+        let payload = if compressed { self.inflate(payload)? } else { payload };
+        match data {
+            Data::Text => String::from_utf8(payload)
+                .map(Message::Text)
+                .map_err(|_| protocol_error("Text message is not UTF-8")),
+            _ => Ok(Message::Binary(payload)),
+        }
+    }
+
+    /// Compress one outgoing message
+    fn deflate(&mut self, data: &[u8]) -> Result<Vec<u8>, axum::Error> {
+        let mut out = Vec::with_capacity(data.len() / 2 + 64);
+        let start = self.compress.total_in();
+        loop {
+            let consumed = (self.compress.total_in() - start) as usize;
+            self.compress
+                .compress_vec(&data[consumed..], &mut out, FlushCompress::Sync)
+                .map_err(axum::Error::new)?;
+            // The flush is complete once all input is in and output had room to spare
+            if (self.compress.total_in() - start) as usize == data.len() && out.len() < out.capacity() {
+                break;
+            }
+            out.reserve(out.capacity().max(64));
+        }
+        if out.ends_with(&DEFLATE_TAIL) {
+            out.truncate(out.len() - DEFLATE_TAIL.len());
+        }
+        if self.params.server_no_context_takeover {
+            self.compress.reset();
+        }
+        Ok(out)
+    }
+
+    /// Decompress one incoming message, refusing to inflate past the size limit
+    fn inflate(&mut self, mut data: Vec<u8>) -> Result<Vec<u8>, axum::Error> {
+        data.extend_from_slice(&DEFLATE_TAIL);
+        let mut out = Vec::with_capacity((data.len() * 4).min(MAX_MESSAGE_BYTES));
+        let start = self.decompress.total_in();
+        loop {
+            let progress = (self.decompress.total_in(), self.decompress.total_out());
+            let consumed = (self.decompress.total_in() - start) as usize;
+            self.decompress
+                .decompress_vec(&data[consumed..], &mut out, FlushDecompress::Sync)
+                .map_err(axum::Error::new)?;
+            if (self.decompress.total_in() - start) as usize == data.len() && out.len() < out.capacity() {
+                break;
+            }
+            if out.len() >= MAX_MESSAGE_BYTES {
+                return Err(protocol_error("Message too large"));
+            }
+            if progress == (self.decompress.total_in(), self.decompress.total_out()) && out.len() < out.capacity() {
+                return Err(protocol_error("Corrupt compressed message"));
+            }
+            out.reserve(out.capacity().max(1024));
+        }
+        Ok(out)
+    }
+}
+
+fn protocol_error(message: &'static str) -> axum::Error {
+    axum::Error::new(io::Error::new(io::ErrorKind::InvalidData, message))
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::{
+    extract::{Extension, Path, State},
+    middleware,
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::postgres::PgPool;
+use sqlx::Row;
+use tokio::sync::RwLock;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::auth::{self, AuthContext, AuthInterceptor, ADMIN_SCOPE};
+use crate::error::ApiError;
+use crate::execution::{ExecutionResponse, ExecutionStatus};
+use crate::state::AppState;
+
+/// Timeline routes, authenticated
+pub fn routes(auth_interceptor: AuthInterceptor) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/executions/:id/events", get(execution_events))
+        .route_layer(middleware::from_fn_with_state(auth_interceptor, auth::require_auth))
+}
+
+/// A status an execution entered, and when
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineEvent {
+    /// `created`, `started`, or the status entered
+    pub event: &'static str,
+    pub status: ExecutionStatus,
+    pub at: DateTime<Utc>,
+}
+
+impl TimelineEvent {
+    fn new(status: ExecutionStatus, at: DateTime<Utc>) -> Self {
+        let event = match status {
+            ExecutionStatus::Pending => "created",
+            ExecutionStatus::Running => "started",
+            ref status => status.as_str(),
+        };
+        Self { event, status, at }
+    }
+}
+
+/// An execution's status transitions, with the time spent waiting for and
+/// running on an executor
+#[derive(Debug, Serialize)]
+pub struct Timeline {
+    pub execution_id: Uuid,
+    pub events: Vec<TimelineEvent>,
+    /// From creation until the executor started running it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_time_ms: Option<i64>,
+    /// From start until it reached a final status
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_time_ms: Option<i64>,
+}
+
+impl Timeline {
+    fn new(execution_id: Uuid, events: Vec<TimelineEvent>) -> Self {
+        let at = |matches: fn(&ExecutionStatus) -> bool| {
+            events.iter().find(|event| matches(&event.status)).map(|event| event.at)
+        };
+        let created = at(|status| *status == ExecutionStatus::Pending);
+        let started = at(|status| *status == ExecutionStatus::Running);
+        let finished = at(ExecutionStatus::is_terminal);
+        let elapsed = |from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>| {
+            Some((to? - from?).num_milliseconds().max(0))
+        };
+        Self {
+            execution_id,
+            queue_time_ms: elapsed(created, started),
+            run_time_ms: elapsed(started, finished),
+            events,
+        }
+    }
+}
+
+/// Status transitions of executions, kept in the SQL store when one is
+/// configured so every replica sees the same timeline, and in memory otherwise.
+/// Each status is recorded once per execution, at the time the execution
+/// service reports when it has one
+pub struct ExecutionTimelines {
+    events: RwLock<HashMap<Uuid, Vec<TimelineEvent>>>,
+    pool: Option<PgPool>,
+}
+
+impl ExecutionTimelines {
+    pub fn new(pool: Option<PgPool>) -> Self {
+        Self {
+            events: RwLock::new(HashMap::new()),
+            pool,
+        }
+    }
+
+    /// Record the status `execution` moved to. An execution first seen past
+    /// pending also gets its creation, so queue time is known
+    pub async fn record(&self, execution: &ExecutionResponse, previous: Option<&ExecutionStatus>) {
+        let mut recorded = Vec::new();
+        if previous.is_none() && execution.status != ExecutionStatus::Pending {
+            recorded.push(TimelineEvent::new(ExecutionStatus::Pending, execution.created_at));
+        }
+        let at = match execution.status {
+            ExecutionStatus::Pending => Some(execution.created_at),
+            ExecutionStatus::Running => execution.started_at,
+            ref status if status.is_terminal() => execution.completed_at,
+            _ => None,
+        };
+        recorded.push(TimelineEvent::new(execution.status.clone(), at.unwrap_or_else(Utc::now)));
+
+        if let Some(pool) = &self.pool {
+            for event in &recorded {
+                let inserted = sqlx::query(
+                    "INSERT INTO execution_events (execution_id, status, occurred_at) \
+                     VALUES ($1::uuid, $2, $3) \
+                     ON CONFLICT (execution_id, status) DO NOTHING",
+                )
+                .bind(execution.id.to_string())
+                .bind(event.status.as_str())
+                .bind(event.at)
+                .execute(pool)
+                .await;
+                if let Err(e) = inserted {
+                    warn!("Failed to record {} event of execution {}: {}", event.event, execution.id, e);
+                }
+            }
+            return;
+        }
+
+        let mut events = self.events.write().await;
+        let timeline = events.entry(execution.id).or_default();
+        for event in recorded {
+            if !timeline.iter().any(|seen| seen.status == event.status) {
+                timeline.push(event);
+            }
+        }
+        timeline.sort_by_key(|event| event.at);
+    }
+
+    /// Transitions of one execution, oldest first
+    pub async fn timeline(&self, execution_id: Uuid) -> Result<Timeline> {
+        let Some(pool) = &self.pool else {
+            let events = self.events.read().await.get(&execution_id).cloned().unwrap_or_default();
+            return Ok(Timeline::new(execution_id, events));
+        };
+        let rows = sqlx::query(
+            "SELECT status, occurred_at FROM execution_events \
+             WHERE execution_id = $1::uuid \
+             ORDER BY occurred_at, id",
+        )
+        .bind(execution_id.to_string())
+        .fetch_all(pool)
+        .await?;
+        let mut events = Vec::with_capacity(rows.len());
+        for row in rows {
+            let status: String = row.try_get("status")?;
+            let Some(status) = ExecutionStatus::parse(&status) else {
+                warn!("Skipping event of execution {} with unknown status '{}'", execution_id, status);
+                continue;
+            };
+            events.push(TimelineEvent::new(status, row.try_get("occurred_at")?));
+        }
+        Ok(Timeline::new(execution_id, events))
+    }
+
+    /// Drop the timelines of executions `keep` rejects
+    pub async fn retain(&self, keep: impl Fn(&Uuid) -> bool) {
+        self.events.write().await.retain(|id, _| keep(id));
+    }
+
+    /// Drop one execution's timeline for good
+    pub async fn forget(&self, execution_id: Uuid) -> Result<()> {
+        self.events.write().await.remove(&execution_id);
+        if let Some(pool) = &self.pool {
+            sqlx::query("DELETE FROM execution_events WHERE execution_id = $1::uuid")
+                .bind(execution_id.to_string())
+                .execute(pool)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Status transitions of an execution with their timestamps, separating
+/// time spent queued from time spent running. Only the execution's owner
+/// or an admin may see them
+async fn execution_events(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Timeline>, ApiError> {
+    state.get_owned_execution(&auth_context, id, ADMIN_SCOPE).await?;
+    let timeline = state.timelines().timeline(id).await?;
+    Ok(Json(timeline))
+}
@@ -0,0 +1,98 @@
+use axum::{
+    extract::{Request, State},
+    http::{header::HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::TracingConfig;
+use crate::state::AppState;
+
+/// W3C trace context header, read from callers and sent upstream
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+tokio::task_local! {
+    static REQUEST_TRACE: TraceContext;
+}
+
+/// The distributed trace a REST request belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    /// 32 lowercase hex digits
+    pub trace_id: String,
+    /// Where the trace can be viewed, from the configured template
+    pub trace_url: Option<String>,
+    sampled: bool,
+}
+
+impl TraceContext {
+    /// Continue the trace in a `traceparent` header, or start a new one
+    fn new(traceparent: Option<&str>, config: &TracingConfig) -> Self {
+        let (trace_id, sampled) = traceparent
+            .and_then(parse_traceparent)
+            .unwrap_or_else(|| (Uuid::new_v4().simple().to_string(), true));
+        let trace_url = config
+            .viewer_url_template
+            .as_ref()
+            .map(|template| template.replace("{trace_id}", &trace_id));
+        Self {
+            trace_id,
+            trace_url,
+            sampled,
+        }
+    }
+
+    /// Trace of the request being handled, when tracing is enabled
+    pub fn current() -> Option<TraceContext> {
+        REQUEST_TRACE.try_with(Clone::clone).ok()
+    }
+
+    /// A `traceparent` for a call made on this trace's behalf, with a fresh span ID
+    pub fn child_traceparent(&self) -> String {
+        let span_id = &Uuid::new_v4().simple().to_string()[..16];
+        format!("00-{}-{}-{}", self.trace_id, span_id, if self.sampled { "01" } else { "00" })
+    }
+}
+
+/// Trace ID and sampled flag of a version-00 `traceparent`
+fn parse_traceparent(value: &str) -> Option<(String, bool)> {
+    let mut parts = value.trim().split('-');
+    let (version, trace_id, span_id, flags) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    let hex = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+    let valid = version == "00"
+        && parts.next().is_none()
+        && hex(trace_id, 32)
+        && hex(span_id, 16)
+        && hex(flags, 2)
+        && trace_id.bytes().any(|b| b != b'0')
+        && span_id.bytes().any(|b| b != b'0');
+    if !valid {
+        return None;
+    }
+    let flags = u8::from_str_radix(flags, 16).ok()?;
+    Some((trace_id.to_string(), flags & 1 == 1))
+}
+
+/// Join the caller's trace, or start one, for the rest of the request, and
+/// echo it in the response's `traceparent`
+pub async fn propagate(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let config = &state.config().tracing;
+    if !config.enabled {
+        return next.run(request).await;
+    }
+    let traceparent = request
+        .headers()
+        .get(TRACEPARENT_HEADER)
+        .and_then(|value| value.to_str().ok());
+    let trace = TraceContext::new(traceparent, config);
+    let echoed = HeaderValue::from_str(&trace.child_traceparent()).ok();
+    let mut response = REQUEST_TRACE.scope(trace, next.run(request)).await;
+    if let Some(value) = echoed {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(TRACEPARENT_HEADER), value);
+    }
+    response
+}
@@ -0,0 +1,261 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::auth::{self, AuthContext, AuthInterceptor};
+use crate::error::ApiError;
+use crate::ids;
+use crate::state::AppState;
+
+/// Upload routes, authenticated; users only ever see their own uploads.
+/// Content is streamed to disk under its own size cap rather than the
+/// inline request body limit, so these must be mounted outside that limit
+pub fn routes(auth_interceptor: AuthInterceptor) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/uploads", post(create_upload))
+        .route("/v1/uploads/:id", get(get_upload).put(put_upload_content))
+        .route_layer(middleware::from_fn_with_state(auth_interceptor, auth::require_auth))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CreateUploadRequest {
+    /// Size the client intends to send, so oversized uploads are refused up front
+    size_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadState {
+    /// Created, waiting for its content
+    Pending,
+    /// Content is being received
+    Receiving,
+    /// Content received; can be referenced by executions
+    Complete,
+}
+
+/// A payload sent separately from the execution that uses it, so large code
+/// isn't bound by the inline request body limit
+#[derive(Debug, Clone, Serialize)]
+pub struct Upload {
+    pub id: Uuid,
+    pub state: UploadState,
+    pub size_bytes: Option<u64>,
+    /// Hex SHA-256 of the content, once received
+    pub sha256: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Where to `PUT` the content
+    pub upload_url: String,
+    #[serde(skip)]
+    owner: String,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+/// Uploads awaiting content or use by an execution
+#[derive(Default)]
+pub struct Uploads {
+    uploads: RwLock<HashMap<Uuid, Upload>>,
+}
+
+impl Uploads {
+    /// An upload owned by `user_id`; other users' uploads are reported as not found
+    pub async fn get(&self, id: Uuid, user_id: &str) -> Option<Upload> {
+        self.uploads
+            .read()
+            .await
+            .get(&id)
+            .filter(|upload| upload.owner == user_id)
+            .cloned()
+    }
+
+    /// Code from a complete upload owned by `user_id`
+    pub async fn read_code(&self, id: Uuid, user_id: &str) -> Result<String, ApiError> {
+        let upload = self.get(id, user_id).await.ok_or_else(|| {
+            ApiError::BadRequest(format!("Upload {} does not exist or has expired", id))
+        })?;
+        if upload.state != UploadState::Complete {
+            return Err(ApiError::BadRequest(format!("Upload {} has no content yet", id)));
+        }
+        let content = tokio::fs::read(&upload.path)
+            .await
+            .map_err(|e| ApiError::Internal(e.into()))?;
+        String::from_utf8(content)
+            .map_err(|_| ApiError::BadRequest(format!("Upload {} is not valid UTF-8 code", id)))
+    }
+
+    async fn insert(&self, upload: Upload) {
+        self.uploads.write().await.insert(upload.id, upload);
+    }
+
+    /// Claim a pending upload for writing, so concurrent `PUT`s can't interleave
+    async fn begin_write(&self, id: Uuid, user_id: &str) -> Result<Upload, ApiError> {
+        let mut uploads = self.uploads.write().await;
+        let upload = uploads
+            .get_mut(&id)
+            .filter(|upload| upload.owner == user_id)
+            .ok_or(ApiError::NotFound)?;
+        if upload.state != UploadState::Pending {
+            return Err(ApiError::BadRequest(format!("Upload {} already has content", id)));
+        }
+        upload.state = UploadState::Receiving;
+        Ok(upload.clone())
+    }
+
+    async fn finish_write(&self, id: Uuid, outcome: Option<(u64, String)>) -> Option<Upload> {
+        let mut uploads = self.uploads.write().await;
+        let upload = uploads.get_mut(&id)?;
+        match outcome {
+            Some((size_bytes, sha256)) => {
+                upload.state = UploadState::Complete;
+                upload.size_bytes = Some(size_bytes);
+                upload.sha256 = Some(sha256);
+            }
+            // A failed write can be retried
+            None => upload.state = UploadState::Pending,
+        }
+        Some(upload.clone())
+    }
+
+    async fn remove(&self, id: Uuid) -> Option<Upload> {
+        self.uploads.write().await.remove(&id)
+    }
+}
+
+async fn create_upload(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    body: Option<Json<CreateUploadRequest>>,
+) -> Result<Response, ApiError> {
+    let config = &state.config().uploads;
+    let Json(request) = body.unwrap_or_default();
+    if request.size_bytes.is_some_and(|size| size > config.max_bytes) {
+        return Err(ApiError::BadRequest(format!(
+            "Uploads are limited to {} bytes",
+            config.max_bytes
+        )));
+    }
+
+    let id = ids::generate();
+    let created_at = Utc::now();
+    let upload = Upload {
+        id,
+        state: UploadState::Pending,
+        size_bytes: request.size_bytes,
+        sha256: None,
+        created_at,
+        expires_at: created_at + chrono::Duration::from_std(config.ttl).unwrap_or_default(),
+        upload_url: format!("/v1/uploads/{}", id),
+        owner: auth_context.user_id,
+        path: config.dir.join(format!("{}.upload", id)),
+    };
+    state.uploads().insert(upload.clone()).await;
+
+    // Uploads are single-use scratch space; drop them once they expire
+    let ttl = config.ttl;
+    let expiring = state.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(ttl).await;
+        if let Some(upload) = expiring.uploads().remove(id).await {
+            if let Err(e) = tokio::fs::remove_file(&upload.path).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to remove upload file {}: {}", upload.path.display(), e);
+                }
+            }
+        }
+    });
+
+    let location = upload.upload_url.clone();
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(upload)).into_response())
+}
+
+async fn get_upload(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Upload>, ApiError> {
+    let upload = state
+        .uploads()
+        .get(id, &auth_context.user_id)
+        .await
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(upload))
+}
+
+/// Receive an upload's content, streaming it to disk
+async fn put_upload_content(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+    body: Body,
+) -> Result<Json<Upload>, ApiError> {
+    let upload = state.uploads().begin_write(id, &auth_context.user_id).await?;
+    let max_bytes = upload
+        .size_bytes
+        .unwrap_or(u64::MAX)
+        .min(state.config().uploads.max_bytes);
+
+    let outcome = write_content(body, &upload.path, max_bytes).await;
+    if outcome.is_err() {
+        let _ = tokio::fs::remove_file(&upload.path).await;
+    }
+    let upload = state
+        .uploads()
+        .finish_write(id, outcome.as_ref().ok().cloned())
+        .await
+        .ok_or(ApiError::NotFound)?;
+    outcome?;
+    Ok(Json(upload))
+}
+
+/// Write `body` to `path`, returning its size and hex SHA-256
+async fn write_content(body: Body, path: &std::path::Path, max_bytes: u64) -> Result<(u64, String), ApiError> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| ApiError::Internal(e.into()))?;
+    }
+    let file = tokio::fs::File::create(path)
+        .await
+        .map_err(|e| ApiError::Internal(e.into()))?;
+    let mut writer = tokio::io::BufWriter::new(file);
+
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    let mut chunks = body.into_data_stream();
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.map_err(|_| ApiError::BadRequest("Failed to read upload body".to_string()))?;
+        size += chunk.len() as u64;
+        if size > max_bytes {
+            return Err(ApiError::BadRequest(format!(
+                "Upload exceeds its {} byte limit",
+                max_bytes
+            )));
+        }
+        hasher.update(&chunk);
+        writer
+            .write_all(&chunk)
+            .await
+            .map_err(|e| ApiError::Internal(e.into()))?;
+    }
+    writer.flush().await.map_err(|e| ApiError::Internal(e.into()))?;
+    Ok((size, hex::encode(hasher.finalize())))
+}
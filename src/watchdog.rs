@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::WatchdogConfig;
+use crate::execution::ExecutionStatus;
+
+/// An execution that has sat in a non-terminal status past its threshold.
+/// Published on the watchdog alerts topic when first flagged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StuckExecution {
+    pub execution_id: Uuid,
+    pub status: ExecutionStatus,
+    pub owner: Option<String>,
+    pub tenant_id: Option<String>,
+    /// When the execution entered its current status, as far as the gateway knows
+    pub since: DateTime<Utc>,
+    pub stuck_for_secs: u64,
+    pub flagged_at: DateTime<Utc>,
+    /// Cancelled upstream by the watchdog
+    pub cancelled: bool,
+}
+
+#[derive(Default)]
+struct WatchdogCounters {
+    flagged: AtomicU64,
+    cancelled: AtomicU64,
+    cancel_failures: AtomicU64,
+}
+
+/// Executions currently flagged as stuck, for operators and metrics
+pub struct Watchdog {
+    config: WatchdogConfig,
+    flagged: Mutex<HashMap<Uuid, StuckExecution>>,
+    counters: WatchdogCounters,
+}
+
+impl Watchdog {
+    pub fn new(config: WatchdogConfig) -> Self {
+        Self {
+            config,
+            flagged: Mutex::new(HashMap::new()),
+            counters: WatchdogCounters::default(),
+        }
+    }
+
+    /// How long an execution may stay in `status` before it counts as stuck;
+    /// None for final statuses
+    pub fn threshold(&self, status: &ExecutionStatus) -> Option<std::time::Duration> {
+        match status {
+            ExecutionStatus::Pending | ExecutionStatus::Queued | ExecutionStatus::Preparing => {
+                Some(self.config.pending_threshold)
+            }
+            ExecutionStatus::Running | ExecutionStatus::Cancelling => Some(self.config.running_threshold),
+            _ => None,
+        }
+    }
+
+    /// Record `stuck`, returning whether it's newly flagged and should be alerted on
+    pub fn flag(&self, stuck: StuckExecution) -> bool {
+        let mut flagged = self.flagged.lock().unwrap();
+        match flagged.get_mut(&stuck.execution_id) {
+            // Keep when it was first flagged; the rest is refreshed every sweep
+            Some(existing) if existing.status == stuck.status => {
+                existing.stuck_for_secs = stuck.stuck_for_secs;
+                false
+            }
+            _ => {
+                self.counters.flagged.fetch_add(1, Ordering::Relaxed);
+                flagged.insert(stuck.execution_id, stuck);
+                true
+            }
+        }
+    }
+
+    /// Note the outcome of cancelling a flagged execution
+    pub fn record_cancel(&self, id: Uuid, cancelled: bool) {
+        if !cancelled {
+            self.counters.cancel_failures.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.counters.cancelled.fetch_add(1, Ordering::Relaxed);
+        if let Some(stuck) = self.flagged.lock().unwrap().get_mut(&id) {
+            stuck.cancelled = true;
+        }
+    }
+
+    /// Forget executions that are no longer stuck
+    pub fn retain(&self, mut still_stuck: impl FnMut(&Uuid) -> bool) {
+        self.flagged.lock().unwrap().retain(|id, _| still_stuck(id));
+    }
+
+    /// Flagged executions, longest stuck first
+    pub fn stuck(&self) -> Vec<StuckExecution> {
+        let mut stuck: Vec<StuckExecution> = self.flagged.lock().unwrap().values().cloned().collect();
+        stuck.sort_by_key(|s| std::cmp::Reverse(s.stuck_for_secs));
+        stuck
+    }
+
+    /// Watchdog counters and the current stuck count in Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            ("syla_gateway_watchdog_flagged_total", &self.counters.flagged),
+            ("syla_gateway_watchdog_cancelled_total", &self.counters.cancelled),
+            ("syla_gateway_watchdog_cancel_failures_total", &self.counters.cancel_failures),
+        ];
+        for (name, counter) in counters {
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, counter.load(Ordering::Relaxed));
+        }
+        let _ = writeln!(out, "# TYPE syla_gateway_watchdog_stuck_executions gauge");
+        let _ = writeln!(
+            out,
+            "syla_gateway_watchdog_stuck_executions {}",
+            self.flagged.lock().unwrap().len()
+        );
+        out
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use axum::{extract::State, middleware, routing::get, Extension, Json, Router};
+use hmac::{Hmac, Mac};
+use reqwest::{header, StatusCode, Url};
+use serde::Serialize;
+use sha2::Sha256;
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::audit::{self, AuditEvent, AuditOutcome};
+use crate::auth::{self, AuthContext, AuthInterceptor, ADMIN_SCOPE, SETTINGS_SCOPE};
+use crate::callbacks::{SIGNATURE_HEADER, SIGNATURE_PREFIX, TIMESTAMP_HEADER};
+use crate::clients::execution::{self, ExecutionClient};
+use crate::config::WebhookConfig;
+use crate::delivery;
+use crate::error::ApiError;
+use crate::state::AppState;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header carrying a key unique to the execution, identical on every retry
+/// and redelivery, so receivers can drop duplicates
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Webhook secret routes, authenticated and scoped to the caller's tenant,
+/// or to the caller outside tenants
+pub fn routes(auth_interceptor: AuthInterceptor) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/settings/webhooks", get(get_signing_secret))
+        .route_layer(middleware::from_fn_with_state(auth_interceptor, auth::require_auth))
+}
+
+/// Secret the caller's webhooks are signed with
+#[derive(Debug, Serialize)]
+pub struct WebhookSecret {
+    pub signing_secret: String,
+}
+
+/// A tenant's secret is shared by all its users, so only those who manage
+/// its settings may read it
+async fn get_signing_secret(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<WebhookSecret>, ApiError> {
+    let event = AuditEvent::new("settings.webhooks.read_secret", &auth_context.user_id, AuditOutcome::Allowed)
+        .tenant(auth_context.tenant_id.as_deref());
+    if auth_context.tenant_id.is_some()
+        && !auth_context.has_scope(SETTINGS_SCOPE)
+        && !auth_context.has_scope(ADMIN_SCOPE)
+    {
+        audit::record(AuditEvent {
+            outcome: AuditOutcome::Denied,
+            ..event
+        });
+        return Err(ApiError::Forbidden(format!("Requires the {} scope", SETTINGS_SCOPE)));
+    }
+    let signing_secret = state
+        .webhooks()
+        .signing_secret(&auth_context.principal())
+        .ok_or_else(|| ApiError::BadRequest("Execution webhooks are not enabled".to_string()))?;
+    audit::record(event);
+    Ok(Json(WebhookSecret { signing_secret }))
+}
+
+/// Idempotency key of execution `id`'s completion webhook
+pub fn idempotency_key(id: Uuid) -> String {
+    format!("webhook:{}", id)
+}
+
+/// Why a webhook request failed, and whether trying again could help
+#[derive(Debug)]
+pub struct WebhookFailure {
+    pub message: String,
+    pub retryable: bool,
+}
+
+#[derive(Default)]
+struct WebhookCounters {
+    delivered: AtomicU64,
+    failed: AtomicU64,
+}
+
+/// POSTs finished executions to the `callback_url` they were submitted with,
+/// so clients needn't poll. Bodies are signed like the execution service's
+/// callbacks to the gateway: `x-syla-signature: sha256=<hex HMAC>` over
+/// `<timestamp>.<body>`, with the timestamp in `x-syla-timestamp`. Each
+/// tenant, or user outside tenants, has its own signing secret, so one
+/// can't forge webhooks to another
+pub struct Webhooks {
+    config: WebhookConfig,
+    /// Whether the deployment's surface includes webhooks
+    enabled: bool,
+    counters: WebhookCounters,
+}
+
+impl Webhooks {
+    pub fn new(config: &WebhookConfig, enabled: bool) -> Self {
+        Self {
+            config: config.clone(),
+            enabled,
+            counters: WebhookCounters::default(),
+        }
+    }
+
+    /// Check a callback URL before an execution is submitted
+    pub fn target(&self, callback_url: &str) -> Result<Url, ApiError> {
+        if !self.enabled || self.config.secret.is_none() {
+            return Err(ApiError::BadRequest("Execution webhooks are not enabled".to_string()));
+        }
+        delivery::parse_external_url(callback_url, self.config.allow_http, "Callback URL")
+    }
+
+    /// Secret `principal`'s webhooks are signed with. It's derived from the
+    /// configured master key, so every replica signs with the same one
+    /// without storing it
+    pub fn signing_secret(&self, principal: &str) -> Option<String> {
+        let master = self.config.secret.as_deref()?;
+        let mut mac = HmacSha256::new_from_slice(master.as_bytes()).ok()?;
+        mac.update(b"webhook-signing:");
+        mac.update(principal.as_bytes());
+        Some(hex::encode(mac.finalize().into_bytes()))
+    }
+
+    /// Wait in the background for execution `id` to finish on `client`, then
+    /// POST it to `url`, signed for `principal`. Lost if the gateway restarts
+    /// first; the delivery outbox is used instead when a SQL store is configured
+    pub fn notify(
+        self: &Arc<Self>,
+        client: Arc<RwLock<ExecutionClient>>,
+        id: Uuid,
+        url: Url,
+        principal: String,
+    ) {
+        let webhooks = self.clone();
+        tokio::spawn(async move {
+            let config = &webhooks.config;
+            let deadline = Instant::now() + config.result_timeout;
+            let outcome = match execution::wait_for_result(&client, id, config.poll_interval, deadline).await {
+                Ok(execution) => match serde_json::to_vec(&execution) {
+                    Ok(body) => webhooks.post(&url, body, &idempotency_key(id), &principal).await,
+                    Err(e) => Err(e.into()),
+                },
+                Err(e) => Err(e),
+            };
+            webhooks.count(outcome.is_ok());
+            match outcome {
+                Ok(()) => info!("Sent completion webhook of execution {} to {}", id, delivery::location(&url)),
+                Err(e) => {
+                    warn!(
+                        "Failed to send completion webhook of execution {} to {}: {}",
+                        id,
+                        delivery::location(&url),
+                        e
+                    );
+                }
+            }
+        });
+    }
+
+    /// POST `body` to `url`, retrying transient failures
+    async fn post(&self, url: &Url, body: Vec<u8>, idempotency_key: &str, principal: &str) -> Result<()> {
+        let mut backoff = Duration::from_secs(1);
+        let mut attempt = 1;
+        loop {
+            let failure = match self.attempt(url, &body, idempotency_key, principal).await {
+                Ok(()) => return Ok(()),
+                Err(failure) if !failure.retryable => anyhow::bail!(failure.message),
+                Err(failure) => failure.message,
+            };
+            if attempt >= self.config.attempts {
+                anyhow::bail!("{} after {} attempts", failure, attempt);
+            }
+            warn!("Webhook attempt {} to {} failed: {}", attempt, delivery::location(url), failure);
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+
+    /// POST `body` to `url` once, signed for `principal` as of now
+    pub async fn attempt(
+        &self,
+        url: &Url,
+        body: &[u8],
+        idempotency_key: &str,
+        principal: &str,
+    ) -> Result<(), WebhookFailure> {
+        let fail = |message: String, retryable: bool| WebhookFailure { message, retryable };
+        let secret = self
+            .signing_secret(principal)
+            .ok_or_else(|| fail("no webhook signing secret".to_string(), false))?;
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).map_err(|e| fail(e.to_string(), false))?;
+        mac.update(timestamp.as_bytes());
+        mac.update(b".");
+        mac.update(body);
+        let signature = format!("{}{}", SIGNATURE_PREFIX, hex::encode(mac.finalize().into_bytes()));
+        let http = delivery::pinned_client(url, self.config.request_timeout)
+            .await
+            .map_err(|e| fail(e.to_string(), !e.is::<delivery::NonPublicAddress>()))?;
+        let response = http
+            .post(url.clone())
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(TIMESTAMP_HEADER, timestamp)
+            .header(SIGNATURE_HEADER, signature)
+            .header(IDEMPOTENCY_KEY_HEADER, idempotency_key)
+            .body(body.to_vec())
+            .send()
+            .await;
+        // Errors carry the URL, whose query may hold a token; leave it out
+        match response {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response)
+                if !response.status().is_server_error() && response.status() != StatusCode::TOO_MANY_REQUESTS =>
+            {
+                Err(fail(format!("callback URL rejected the webhook with {}", response.status()), false))
+            }
+            Ok(response) => Err(fail(format!("callback URL responded with {}", response.status()), true)),
+            Err(e) => Err(fail(e.without_url().to_string(), true)),
+        }
+    }
+
+    /// Count a webhook as sent or given up on
+    pub fn count(&self, delivered: bool) {
+        let counter = if delivered { &self.counters.delivered } else { &self.counters.failed };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn config(&self) -> &WebhookConfig {
+        &self.config
+    }
+
+    /// Webhook outcomes in Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            ("syla_gateway_webhooks_total", &self.counters.delivered),
+            ("syla_gateway_webhook_failures_total", &self.counters.failed),
+        ];
+        for (name, counter) in counters {
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, counter.load(Ordering::Relaxed));
+        }
+        out
+    }
+}
@@ -0,0 +1,215 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::get,
+    Extension, Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::audit::{self, AuditEvent, AuditOutcome};
+use crate::auth::{self, AuthContext, AuthInterceptor, ADMIN_SCOPE};
+use crate::clients::workspace::WorkspaceClient;
+use crate::error::ApiError;
+use crate::proto;
+use crate::state::{AppState, DEFAULT_LIST_LIMIT, MAX_LIST_LIMIT};
+
+/// Workspace routes, authenticated; backed by the workspace service
+pub fn routes(auth_interceptor: AuthInterceptor) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/workspaces", get(list_workspaces).post(create_workspace))
+        .route(
+            "/v1/workspaces/:id",
+            get(get_workspace).patch(update_workspace).delete(delete_workspace),
+        )
+        .route_layer(middleware::from_fn_with_state(auth_interceptor, auth::require_auth))
+}
+
+/// How long a workspace lives and who can use it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceType {
+    #[default]
+    Ephemeral,
+    Session,
+    Persistent,
+    Collaborative,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceStatus {
+    Pending,
+    Active,
+    Suspended,
+    Terminated,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Workspace {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub description: String,
+    #[serde(rename = "type")]
+    pub kind: WorkspaceType,
+    pub status: WorkspaceStatus,
+    /// Environment variables set for executions in the workspace
+    pub environment: HashMap<String, String>,
+    pub allowed_packages: Vec<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub metadata: HashMap<String, String>,
+    /// Full upstream configuration, so updates keep the parts REST doesn't expose
+    #[serde(skip)]
+    pub(crate) config: proto::WorkspaceConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateWorkspaceRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, rename = "type")]
+    pub kind: WorkspaceType,
+    #[serde(default)]
+    pub environment: HashMap<String, String>,
+    #[serde(default)]
+    pub allowed_packages: Vec<String>,
+    /// Lifetime before the workspace service removes it; its default when unset
+    pub ttl_seconds: Option<u64>,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+/// Fields to change on a workspace; unset fields are left as they are
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateWorkspaceRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub environment: Option<HashMap<String, String>>,
+    pub allowed_packages: Option<Vec<String>>,
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListWorkspacesQuery {
+    #[serde(rename = "type")]
+    kind: Option<WorkspaceType>,
+    status: Option<WorkspaceStatus>,
+    limit: Option<usize>,
+    page_token: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DeleteWorkspaceQuery {
+    /// Delete even if executions are still running in the workspace
+    #[serde(default)]
+    force: bool,
+}
+
+/// A page of workspaces from the workspace service
+#[derive(Debug, Serialize)]
+pub struct WorkspacePage {
+    pub workspaces: Vec<Workspace>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
+}
+
+/// The workspace client, when the workspaces surface is enabled
+fn client(state: &AppState) -> Result<&WorkspaceClient, ApiError> {
+    state.workspace_client().ok_or(ApiError::NotFound)
+}
+
+/// A workspace the caller owns, or any workspace for admins; others are reported as not found
+async fn owned_workspace(
+    client: &WorkspaceClient,
+    auth_context: &AuthContext,
+    id: &str,
+) -> Result<Workspace, ApiError> {
+    let workspace = client.get_workspace(auth_context, id).await?;
+    if workspace.user_id != auth_context.user_id && !auth_context.has_scope(ADMIN_SCOPE) {
+        return Err(ApiError::NotFound);
+    }
+    Ok(workspace)
+}
+
+async fn create_workspace(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Json(request): Json<CreateWorkspaceRequest>,
+) -> Result<Response, ApiError> {
+    if request.name.trim().is_empty() {
+        return Err(ApiError::BadRequest("Workspace name must not be empty".to_string()));
+    }
+    let workspace = client(&state)?.create_workspace(&auth_context, request).await?;
+    audit::record(
+        AuditEvent::new("workspace.create", &auth_context.user_id, AuditOutcome::Allowed)
+            .subject(&workspace.id)
+            .tenant(auth_context.tenant_id.as_deref()),
+    );
+    let location = format!("/v1/workspaces/{}", workspace.id);
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(workspace)).into_response())
+}
+
+async fn list_workspaces(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Query(query): Query<ListWorkspacesQuery>,
+) -> Result<Json<WorkspacePage>, ApiError> {
+    let page_size = query.limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT) as u32;
+    let mut page = client(&state)?
+        .list_workspaces(&auth_context, query.kind, query.status, page_size, query.page_token)
+        .await?;
+    page.workspaces.retain(|workspace| workspace.user_id == auth_context.user_id);
+    Ok(Json(page))
+}
+
+async fn get_workspace(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<Workspace>, ApiError> {
+    let workspace = owned_workspace(client(&state)?, &auth_context, &id).await?;
+    Ok(Json(workspace))
+}
+
+async fn update_workspace(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+    Json(update): Json<UpdateWorkspaceRequest>,
+) -> Result<Json<Workspace>, ApiError> {
+    let client = client(&state)?;
+    let current = owned_workspace(client, &auth_context, &id).await?;
+    let workspace = client.update_workspace(&auth_context, current, update).await?;
+    audit::record(
+        AuditEvent::new("workspace.update", &auth_context.user_id, AuditOutcome::Allowed)
+            .subject(&id)
+            .tenant(auth_context.tenant_id.as_deref()),
+    );
+    Ok(Json(workspace))
+}
+
+async fn delete_workspace(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+    Query(query): Query<DeleteWorkspaceQuery>,
+) -> Result<StatusCode, ApiError> {
+    let client = client(&state)?;
+    owned_workspace(client, &auth_context, &id).await?;
+    client.delete_workspace(&auth_context, &id, query.force).await?;
+    audit::record(
+        AuditEvent::new("workspace.delete", &auth_context.user_id, AuditOutcome::Allowed)
+            .subject(&id)
+            .tenant(auth_context.tenant_id.as_deref()),
+    );
+    Ok(StatusCode::NO_CONTENT)
+}